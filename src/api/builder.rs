@@ -0,0 +1,222 @@
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use std::sync::Arc;
+use std::time::Duration;
+
+use super::{GeminiClient, Provider, DEFAULT_BASE_URL};
+use crate::config::{ExifConfig, RoutingConfig, WatermarkConfig};
+use crate::core::{BananaError, PersonGeneration};
+use crate::fixtures;
+
+/// How transient failures (connection errors, 5xx responses) are retried
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Retries after the first attempt (0 disables retrying)
+    pub max_retries: u32,
+    /// Delay before the first retry; doubles on each further retry
+    pub initial_backoff: Duration,
+    /// Upper bound on the delay between retries
+    pub max_backoff: Duration,
+}
+
+impl RetryPolicy {
+    /// Never retry
+    pub const fn none() -> Self {
+        Self::new(0)
+    }
+
+    /// Retry up to `max_retries` times, backing off from 1s up to 30s
+    pub const fn new(max_retries: u32) -> Self {
+        Self {
+            max_retries,
+            initial_backoff: Duration::from_secs(1),
+            max_backoff: Duration::from_secs(30),
+        }
+    }
+
+    /// Delay before retry number `attempt` (starting at 0)
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        self.initial_backoff
+            .saturating_mul(2u32.saturating_pow(attempt))
+            .min(self.max_backoff)
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self::none()
+    }
+}
+
+/// Builds a `GeminiClient` without a `Config`.
+///
+/// ```ignore
+/// let client = GeminiClient::builder()
+///     .api_key(key)
+///     .timeout(Duration::from_secs(120))
+///     .retry_policy(RetryPolicy::new(3))
+///     .build()?;
+/// ```
+#[derive(Debug, Clone)]
+pub struct GeminiClientBuilder {
+    api_key: Option<String>,
+    base_url: String,
+    headers: Vec<(String, String)>,
+    user_project: Option<String>,
+    exif: ExifConfig,
+    srgb: bool,
+    retain_data: bool,
+    watermark: Option<WatermarkConfig>,
+    timeout: Option<Duration>,
+    timeout_fallback: Option<String>,
+    person_generation: Option<PersonGeneration>,
+    routing: RoutingConfig,
+    retry_policy: RetryPolicy,
+    provider: Option<Arc<dyn Provider>>,
+}
+
+impl Default for GeminiClientBuilder {
+    fn default() -> Self {
+        Self {
+            api_key: None,
+            base_url: DEFAULT_BASE_URL.to_string(),
+            headers: Vec::new(),
+            user_project: None,
+            exif: ExifConfig::default(),
+            srgb: false,
+            retain_data: false,
+            watermark: None,
+            timeout: None,
+            timeout_fallback: None,
+            person_generation: None,
+            routing: RoutingConfig::default(),
+            retry_policy: RetryPolicy::none(),
+            provider: None,
+        }
+    }
+}
+
+impl GeminiClientBuilder {
+    /// API key (required unless replaying fixtures)
+    pub fn api_key(mut self, key: impl Into<String>) -> Self {
+        self.api_key = Some(key.into());
+        self
+    }
+
+    /// API root, e.g. a gateway or `banana mock-server`
+    pub fn base_url(mut self, url: impl Into<String>) -> Self {
+        self.base_url = url.into().trim_end_matches('/').to_string();
+        self
+    }
+
+    /// Extra header sent with every request (validated in `build`)
+    pub fn header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+
+    /// Google Cloud project to bill (sent as `x-goog-user-project`)
+    pub fn user_project(mut self, project: impl Into<String>) -> Self {
+        self.user_project = Some(project.into());
+        self
+    }
+
+    /// Attribution written into downloaded images
+    pub fn exif(mut self, exif: ExifConfig) -> Self {
+        self.exif = exif;
+        self
+    }
+
+    /// Whether to convert downloaded images to sRGB and embed an sRGB profile (default false)
+    pub fn srgb(mut self, srgb: bool) -> Self {
+        self.srgb = srgb;
+        self
+    }
+
+    /// Whether to keep base64 data on the job after saving each image (default false)
+    pub fn retain_data(mut self, retain: bool) -> Self {
+        self.retain_data = retain;
+        self
+    }
+
+    /// Watermark composited onto downloaded images
+    pub fn watermark(mut self, watermark: WatermarkConfig) -> Self {
+        self.watermark = Some(watermark);
+        self
+    }
+
+    /// Give up on a generate request after this long
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Model to retry a timed-out job on, once (see `GeminiClient::generate_job`)
+    pub fn timeout_fallback(mut self, model: impl Into<String>) -> Self {
+        self.timeout_fallback = Some(model.into());
+        self
+    }
+
+    /// Person generation policy for jobs that don't set one
+    pub fn person_generation(mut self, policy: PersonGeneration) -> Self {
+        self.person_generation = Some(policy);
+        self
+    }
+
+    /// Rules for picking a model for jobs whose model is "auto"
+    pub fn routing(mut self, routing: RoutingConfig) -> Self {
+        self.routing = routing;
+        self
+    }
+
+    /// How transient failures are retried (default: not at all)
+    pub fn retry_policy(mut self, policy: RetryPolicy) -> Self {
+        self.retry_policy = policy;
+        self
+    }
+
+    /// Send generate requests to another backend instead of the Gemini API
+    pub fn provider(mut self, provider: impl Provider + 'static) -> Self {
+        self.provider = Some(Arc::new(provider));
+        self
+    }
+
+    pub fn build(self) -> Result<GeminiClient, BananaError> {
+        // Replayed requests never reach the API, and other providers bring
+        // their own credentials, so no key is needed then
+        let api_key = self
+            .api_key
+            .or_else(|| fixtures::is_replaying().then(|| "replay".to_string()))
+            .or_else(|| self.provider.is_some().then(String::new))
+            .ok_or(BananaError::MissingApiKey)?;
+
+        let mut headers = HeaderMap::new();
+        for (name, value) in &self.headers {
+            let name = HeaderName::from_bytes(name.as_bytes())
+                .map_err(|_| BananaError::ConfigError(format!("Invalid header name: {}", name)))?;
+            let value = HeaderValue::from_str(value)
+                .map_err(|_| BananaError::ConfigError(format!("Invalid value for header {}", name)))?;
+            headers.insert(name, value);
+        }
+        if let Some(project) = &self.user_project {
+            let value = HeaderValue::from_str(project)
+                .map_err(|_| BananaError::ConfigError(format!("Invalid user project: {}", project)))?;
+            headers.insert("x-goog-user-project", value);
+        }
+
+        Ok(GeminiClient {
+            api_key,
+            base_url: self.base_url,
+            headers,
+            exif: self.exif,
+            srgb: self.srgb,
+            retain_data: self.retain_data,
+            watermark: self.watermark,
+            job_timeout: self.timeout,
+            timeout_fallback: self.timeout_fallback,
+            person_generation: self.person_generation,
+            routing: self.routing,
+            retry_policy: self.retry_policy,
+            provider: self.provider,
+        })
+    }
+}
@@ -1,57 +1,293 @@
+mod builder;
+pub(crate) mod openai;
+mod provider;
+pub(crate) mod replicate;
 mod types;
 
-use anyhow::{Context, Result};
 use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use chrono::Utc;
+use futures_util::future;
+use futures_util::stream::{self, StreamExt};
+use reqwest::header::HeaderMap;
 use std::path::Path;
-use tokio::fs;
-
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::{fs, time};
+use tokio::io::AsyncWriteExt;
+
+pub use builder::{GeminiClientBuilder, RetryPolicy};
+pub use openai::OpenAiProvider;
+pub use provider::Provider;
+pub use replicate::ReplicateProvider;
 pub use types::*;
 
-use crate::config::Config;
-use crate::core::{BananaError, GenerateParams, Job, JobStatus};
+use crate::config::{Config, ExifConfig, ProviderKind, RoutingConfig, WatermarkConfig, AUTO_MODEL};
+use crate::core::{prompt, usage, BananaError, GenerateParams, Job, PersonGeneration, Result, TokenUsage};
+use crate::db::Database;
+use crate::fixtures::{self, Fixture, FixtureMode};
+use crate::heartbeat;
 use crate::http_client::HTTP_CLIENT;
+use crate::imaging;
+use crate::metadata::{self, ImageMetadata};
+use crate::platform;
+use crate::quota::{self, QuotaState};
+use crate::thumbnails;
+
+/// Maximum number of images decoded and written at once
+const MAX_CONCURRENT_DOWNLOADS: usize = 4;
+
+/// Public Gemini API root
+pub const DEFAULT_BASE_URL: &str = "https://generativelanguage.googleapis.com/v1beta";
+
+/// Largest request body the API accepts for inline data
+pub const MAX_REQUEST_BYTES: usize = 20 * 1024 * 1024;
+
+/// Finish reasons meaning a safety or policy filter stopped generation
+const SAFETY_FINISH_REASONS: &[&str] = &[
+    "SAFETY",
+    "IMAGE_SAFETY",
+    "PROHIBITED_CONTENT",
+    "IMAGE_PROHIBITED_CONTENT",
+    "BLOCKLIST",
+    "SPII",
+];
+
+/// Base64 bytes decoded per chunk (must be a multiple of 4)
+const DECODE_CHUNK_SIZE: usize = 256 * 1024;
 
 /// Gemini API client
 pub struct GeminiClient {
     api_key: String,
     base_url: String,
+    headers: HeaderMap,
+    exif: ExifConfig,
+    srgb: bool,
+    retain_data: bool,
+    watermark: Option<WatermarkConfig>,
+    job_timeout: Option<Duration>,
+    timeout_fallback: Option<String>,
+    /// Policy for jobs that don't set their own (`defaults.people`)
+    person_generation: Option<PersonGeneration>,
+    routing: RoutingConfig,
+    retry_policy: RetryPolicy,
+    /// Backend that serves generate requests instead of the Gemini API
+    provider: Option<Arc<dyn Provider>>,
 }
 
 impl GeminiClient {
+    /// Start building a client without a `Config`
+    pub fn builder() -> GeminiClientBuilder {
+        GeminiClientBuilder::default()
+    }
+
     /// Create a new client from config
     pub fn from_config(config: &Config) -> Result<Self, BananaError> {
-        let api_key = config
-            .api_key()
-            .ok_or(BananaError::MissingApiKey)?
-            .to_string();
-
-        Ok(Self {
-            api_key,
-            base_url: config.api.base_url.clone(),
-        })
+        let mut builder = Self::builder()
+            .base_url(&config.api.base_url)
+            .exif(config.output.exif.clone())
+            .srgb(config.output.srgb)
+            .retain_data(config.output.retain_data)
+            .routing(config.routing.clone())
+            .retry_policy(RetryPolicy::new(config.api.max_retries));
+        if let Some(key) = config.api_key() {
+            builder = builder.api_key(key);
+        }
+        for (name, value) in &config.api.headers {
+            builder = builder.header(name, value);
+        }
+        if let Some(project) = &config.api.user_project {
+            builder = builder.user_project(project);
+        }
+        if config.output.watermark.path.is_some() {
+            builder = builder.watermark(config.output.watermark.clone());
+        }
+        if config.api.job_timeout_secs > 0 {
+            builder = builder.timeout(Duration::from_secs(config.api.job_timeout_secs));
+        }
+        if let Some(model) = &config.api.timeout_fallback_model {
+            builder = builder.timeout_fallback(config.resolve_model(model));
+        }
+        if let Some(policy) = config.defaults.people {
+            builder = builder.person_generation(policy);
+        }
+        match config.api.provider {
+            ProviderKind::Gemini => {}
+            ProviderKind::Replicate => {
+                let token = config.replicate_token().ok_or_else(|| {
+                    BananaError::ConfigError(
+                        "Replicate API token not configured. Set REPLICATE_API_TOKEN or run: banana config set replicate.token <token>"
+                            .to_string(),
+                    )
+                })?;
+                builder = builder.provider(ReplicateProvider::new(
+                    token,
+                    &config.replicate.base_url,
+                    RetryPolicy::new(config.api.max_retries),
+                ));
+            }
+            ProviderKind::OpenAi => {
+                let key = config.openai_key().ok_or_else(|| {
+                    BananaError::ConfigError(
+                        "OpenAI API key not configured. Set OPENAI_API_KEY or run: banana config set openai.key <key>"
+                            .to_string(),
+                    )
+                })?;
+                builder = builder.provider(OpenAiProvider::new(
+                    key,
+                    &config.openai.base_url,
+                    RetryPolicy::new(config.api.max_retries),
+                ));
+            }
+        }
+        builder.build()
+    }
+
+    /// Skip the configured watermark for images downloaded by this client
+    pub fn without_watermark(mut self) -> Self {
+        self.watermark = None;
+        self
+    }
+
+    /// Keep base64 data on the job after download, regardless of `output.retain_data`
+    pub fn with_retained_data(mut self) -> Self {
+        self.retain_data = true;
+        self
     }
 
-    /// Generate images from a prompt
+    /// Name and API root of the backend this client sends requests to
+    pub fn backend(&self) -> (&'static str, &str) {
+        match &self.provider {
+            Some(provider) => (provider.name(), provider.base_url()),
+            None => ("gemini", &self.base_url),
+        }
+    }
+
+    /// The model and the reason for it, if `params` asks for "auto"
+    fn route(&self, params: &GenerateParams) -> Option<(String, String)> {
+        (params.model == AUTO_MODEL).then(|| self.routing.route(params))
+    }
+
+    /// Generate images from a prompt, giving up after `api.job_timeout_secs`
     pub async fn generate(&self, params: &GenerateParams) -> Result<GenerateResponse> {
-        let url = format!(
-            "{}/models/{}:generateContent?key={}",
-            self.base_url, params.model, self.api_key
+        let routed;
+        let params = match self.route(params) {
+            Some((model, _)) => {
+                routed = GenerateParams { model, ..params.clone() };
+                &routed
+            }
+            None => params,
+        };
+        match self.job_timeout {
+            Some(limit) => time::timeout(limit, self.send_generate(params))
+                .await
+                .map_err(|_| BananaError::Timeout(limit.as_secs()))?,
+            None => self.send_generate(params).await,
+        }
+    }
+
+    /// Switch a job on the "auto" model to the model `[routing]` picks,
+    /// recording the reason on the job
+    pub fn route_job(&self, job: &mut Job) {
+        if let Some((model, reason)) = self.route(&job.params) {
+            tracing::info!("Routing {} to {} ({})", job.id, model, reason);
+            job.params.model = model.clone();
+            job.model = model;
+            job.route = Some(reason);
+        }
+    }
+
+    /// Run a job's request with heartbeats.
+    ///
+    /// The job is routed first (see `route_job`), and a job without a person
+    /// generation policy gets `defaults.people` if its model takes one. If it
+    /// times out and `api.timeout_fallback_model` is set, the job is switched
+    /// to that model and retried once.
+    pub async fn generate_job(&self, job: &mut Job, db: &Database) -> Result<GenerateResponse> {
+        self.route_job(job);
+        if let (None, Some(people)) = (job.params.person_generation, self.person_generation) {
+            job.params.person_generation = Some(people);
+            if job.params.ignored_params().contains(&"people") {
+                tracing::warn!(
+                    "defaults.people = {} is not applied to {}: {} has no person generation setting",
+                    people.as_str(),
+                    job.id,
+                    job.params.model
+                );
+                job.params.person_generation = None;
+            }
+        }
+        let (provider, base_url) = self.backend();
+        job.provider = Some(provider.to_string());
+        job.base_url = Some(base_url.to_string());
+        db.update_job(job)?;
+
+        let result = heartbeat::run(db, &job.id, self.generate(&job.params)).await;
+
+        let timed_out = match &result {
+            Err(BananaError::Timeout(secs)) => Some(*secs),
+            _ => None,
+        };
+        let fallback = match (timed_out, &self.timeout_fallback) {
+            (Some(_), Some(model)) if *model != job.params.model => model.clone(),
+            _ => return result,
+        };
+
+        tracing::warn!(
+            "{} timed out on {} after {}s; retrying on {}",
+            job.id,
+            job.params.model,
+            timed_out.unwrap_or_default(),
+            fallback
         );
+        job.params.model = fallback.clone();
+        job.model = fallback;
+        db.update_job(job)?;
+
+        heartbeat::run(db, &job.id, self.generate(&job.params)).await
+    }
+
+    async fn send_generate(&self, params: &GenerateParams) -> Result<GenerateResponse> {
+        if let Some(provider) = &self.provider {
+            tracing::debug!("Sending generate request to {} for: {}", provider.name(), params.model);
+            return provider.generate(params).await;
+        }
 
+        // Gemini image models return one image per request, so several
+        // images are several requests (with consecutive seeds, if seeded)
+        if params.num_images <= 1 || params.ignored_params().contains(&"num-images") {
+            return self.send_gemini(params).await;
+        }
+        let requests: Vec<GenerateParams> = (0..params.num_images)
+            .map(|i| GenerateParams {
+                num_images: 1,
+                seed: params.seed.map(|seed| seed.wrapping_add(i64::from(i))),
+                ..params.clone()
+            })
+            .collect();
+        let responses = future::try_join_all(requests.iter().map(|params| self.send_gemini(params))).await?;
+        Ok(merge_responses(responses))
+    }
+
+    /// Send one generateContent request
+    async fn send_gemini(&self, params: &GenerateParams) -> Result<GenerateResponse> {
         let request = self.build_generate_request(params);
+        let body = serde_json::to_vec(&request)?;
+        if body.len() > MAX_REQUEST_BYTES {
+            return Err(BananaError::PayloadTooLarge {
+                what: format!("the request body ({})", largest_inline_image(params)),
+                size: body.len(),
+                limit: MAX_REQUEST_BYTES,
+            });
+        }
 
-        tracing::debug!("Sending generate request to: {}", url);
+        tracing::debug!("Sending generate request for: {}", params.model);
         tracing::debug!("Request body: {}", serde_json::to_string_pretty(&request)?);
 
-        let response = HTTP_CLIENT
-            .post(&url)
-            .json(&request)
-            .send()
-            .await
-            .context("Failed to send request to Gemini API")?;
-
-        let status = response.status();
-        let body = response.text().await?;
+        let raw = self.post(&format!("{}:generateContent", params.model), body).await?;
+        let status = reqwest::StatusCode::from_u16(raw.status)
+            .map_err(|_| BananaError::InvalidResponse(format!("Invalid HTTP status {}", raw.status)))?;
+        let retry_after_header = raw.retry_after;
+        let body = raw.body;
 
         tracing::debug!("Response status: {}", status);
         tracing::debug!("Response body: {}", body);
@@ -63,21 +299,163 @@ impl GeminiClient {
                         code: status.as_u16() as i32,
                         message: body.clone(),
                         status: status.to_string(),
+                        details: Vec::new(),
                     },
                 });
-            return Err(BananaError::ApiError {
-                message: error.error.message,
-                source: None,
+
+            let mut err = api_error(status, &error.error, &params.model);
+            if let BananaError::RateLimited { retry_after, .. } = &mut err {
+                *retry_after = retry_after_header.or(*retry_after);
+                record_quota(&params.model, &error.error, *retry_after);
             }
-            .into());
+            return Err(err);
         }
 
-        let response: GenerateResponse = serde_json::from_str(&body)
-            .context("Failed to parse Gemini API response")?;
+        let response: GenerateResponse = serde_json::from_str(&body).map_err(|e| {
+            BananaError::InvalidResponse(format!("Failed to parse Gemini API response: {}", e))
+        })?;
+
+        quota::clear();
 
         Ok(response)
     }
 
+    /// POST a JSON body to an API method (e.g. "gemini-2.5-flash-image:countTokens").
+    ///
+    /// With `--replay` the response comes from a fixture instead of the
+    /// network; with `--record` every response is also saved as one.
+    async fn post(&self, method: &str, body: Vec<u8>) -> Result<Fixture> {
+        let record_to = match fixtures::mode() {
+            Some(FixtureMode::Replay(dir)) => return fixtures::load(dir, method, &body),
+            Some(FixtureMode::Record(dir)) => Some(fixtures::path_for(dir, method, &body)),
+            None => None,
+        };
+
+        let url = format!("{}/models/{}?key={}", self.base_url, method, self.api_key);
+        let reply = provider::send(method, self.retry_policy, || {
+            HTTP_CLIENT
+                .post(&url)
+                .headers(self.headers.clone())
+                .header(reqwest::header::CONTENT_TYPE, "application/json")
+                .body(body.clone())
+        })
+        .await?;
+        let fixture = Fixture {
+            method: method.to_string(),
+            status: reply.status,
+            retry_after: reply.retry_after,
+            body: reply.body,
+        };
+
+        if let Some(path) = record_to {
+            fixtures::save(&path, &fixture)?;
+        }
+
+        Ok(fixture)
+    }
+
+    /// Count the tokens in a prompt using the countTokens endpoint
+    pub async fn count_tokens(&self, model: &str, prompt: &str) -> Result<u32> {
+        let request = CountTokensRequest {
+            contents: vec![Content {
+                parts: vec![ContentPart::Text {
+                    text: prompt.to_string(),
+                }],
+                role: None,
+            }],
+        };
+
+        let raw = self
+            .post(&format!("{}:countTokens", model), serde_json::to_vec(&request)?)
+            .await?;
+        let status = reqwest::StatusCode::from_u16(raw.status)
+            .map_err(|_| BananaError::InvalidResponse(format!("Invalid HTTP status {}", raw.status)))?;
+        let body = raw.body;
+
+        tracing::debug!("countTokens status: {}", status);
+
+        if !status.is_success() {
+            let error = serde_json::from_str::<ApiErrorResponse>(&body)
+                .map(|e| e.error)
+                .unwrap_or_else(|_| ApiError {
+                    code: status.as_u16() as i32,
+                    message: body,
+                    status: status.to_string(),
+                    details: Vec::new(),
+                });
+            return Err(api_error(status, &error, model));
+        }
+
+        let response: CountTokensResponse = serde_json::from_str(&body).map_err(|e| {
+            BananaError::InvalidResponse(format!("Failed to parse countTokens response: {}", e))
+        })?;
+
+        Ok(response.total_tokens)
+    }
+
+    /// Names of the image models the API currently offers
+    pub async fn list_models(&self) -> Result<Vec<String>> {
+        let url = format!("{}/models?pageSize=1000&key={}", self.base_url, self.api_key);
+        let response = HTTP_CLIENT.get(&url).headers(self.headers.clone()).send().await?;
+        let status = response.status();
+        let body = response.text().await?;
+
+        if !status.is_success() {
+            let error = serde_json::from_str::<ApiErrorResponse>(&body)
+                .map(|e| e.error)
+                .unwrap_or_else(|_| ApiError {
+                    code: status.as_u16() as i32,
+                    message: body,
+                    status: status.to_string(),
+                    details: Vec::new(),
+                });
+            return Err(api_error(status, &error, "models"));
+        }
+
+        let response: ListModelsResponse = serde_json::from_str(&body).map_err(|e| {
+            BananaError::InvalidResponse(format!("Failed to parse models response: {}", e))
+        })?;
+
+        Ok(response
+            .models
+            .into_iter()
+            .filter(|m| {
+                m.supported_generation_methods
+                    .iter()
+                    .any(|method| method == "generateContent" || method == "predict")
+            })
+            .map(|m| m.name.strip_prefix("models/").unwrap_or(&m.name).to_string())
+            .filter(|name| name.contains("image"))
+            .collect())
+    }
+
+    /// Fail fast if the prompt exceeds the model's input limit.
+    ///
+    /// Prompts shorter (in bytes) than the limit can't exceed it, so the
+    /// extra request is only made for long prompts. If counting fails the
+    /// check is skipped and the generate request decides.
+    pub async fn preflight(&self, params: &GenerateParams) -> Result<()> {
+        let Some(limit) = usage::input_token_limit(&params.model) else {
+            return Ok(());
+        };
+        if params.prompt.len() <= limit as usize {
+            return Ok(());
+        }
+
+        match self.count_tokens(&params.model, &params.prompt).await {
+            Ok(tokens) if tokens > limit => Err(BananaError::PromptTooLong {
+                model: params.model.clone(),
+                tokens,
+                limit,
+            }),
+            Ok(_) => Ok(()),
+            Err(e) => {
+                tracing::debug!("Skipping prompt length preflight: {}", e);
+                Ok(())
+            }
+        }
+    }
+
     /// Build the API request body
     fn build_generate_request(&self, params: &GenerateParams) -> GenerateRequest {
         let mut parts = vec![ContentPart::Text {
@@ -97,17 +475,59 @@ impl GeminiClient {
             );
         }
 
-        GenerateRequest {
-            contents: vec![Content {
-                parts,
-                role: None,
-            }],
-            generation_config: Some(GenerationConfig {
-                response_modalities: Some(vec!["TEXT".to_string(), "IMAGE".to_string()]),
-                image_config: Some(ImageConfig {
-                    aspect_ratio: Some(params.aspect_ratio.clone()),
-                }),
+        // Earlier turns go first; the prompt is then the latest user turn
+        let mut contents: Vec<Content> = params
+            .context
+            .iter()
+            .map(|turn| {
+                let mut parts = Vec::new();
+                if let Some((data, mime_type)) = &turn.image {
+                    parts.push(ContentPart::InlineData {
+                        inlineData: InlineData {
+                            mime_type: mime_type.clone(),
+                            data: data.clone(),
+                        },
+                    });
+                }
+                if let Some(text) = &turn.text {
+                    parts.push(ContentPart::Text { text: text.clone() });
+                }
+                Content {
+                    parts,
+                    role: Some(turn.role.clone()),
+                }
+            })
+            .collect();
+        let role = (!contents.is_empty()).then(|| "user".to_string());
+        contents.push(Content { parts, role });
+
+        let ignored = params.ignored_params();
+        let mut generation_config = GenerationConfig {
+            response_modalities: Some(params.response_modalities()),
+            image_config: Some(ImageConfig {
+                aspect_ratio: Some(params.aspect_ratio.clone()),
+                image_size: params.supports_size().then(|| params.size.clone()),
+                person_generation: params
+                    .person_generation
+                    .filter(|_| !ignored.contains(&"people"))
+                    .map(|policy| policy.api_value().to_string()),
+                extra: Default::default(),
             }),
+            seed: params.seed.filter(|_| !ignored.contains(&"seed")),
+            temperature: params.temperature.filter(|_| !ignored.contains(&"temperature")),
+            top_p: params.top_p.filter(|_| !ignored.contains(&"top-p")),
+            top_k: params.top_k.filter(|_| !ignored.contains(&"top-k")),
+            extra: Default::default(),
+        };
+        if !ignored.contains(&"gen-config") {
+            for (key, value) in &params.generation_config {
+                generation_config.set(key, value.clone());
+            }
+        }
+
+        GenerateRequest {
+            contents,
+            generation_config: Some(generation_config),
             safety_settings: None,
         }
     }
@@ -116,6 +536,31 @@ impl GeminiClient {
     pub fn process_response(&self, job: &mut Job, response: GenerateResponse) -> Result<()> {
         let mut image_index = 0u8;
 
+        if let Some(usage) = &response.usage_metadata {
+            job.usage = Some(TokenUsage {
+                prompt_tokens: usage.prompt_token_count,
+                candidates_tokens: usage.candidates_token_count,
+                total_tokens: usage.total_token_count,
+            });
+        }
+
+        // A blocked prompt usually comes back with no candidates, only feedback
+        let no_candidates = response.candidates.as_deref().unwrap_or_default().is_empty();
+        if let Some(feedback) = &response.prompt_feedback {
+            if feedback.block_reason.is_some() || no_candidates {
+                let reason = feedback.block_reason.clone().unwrap_or_else(|| "BLOCKED".to_string());
+                let categories = flagged_categories(feedback.safety_ratings.as_deref());
+                let message = "The prompt was blocked by the API".to_string();
+                tracing::warn!("Prompt blocked: {} {:?}", reason, categories);
+                job.set_failed(&message);
+                return Err(BananaError::SafetyBlocked {
+                    reason,
+                    categories,
+                    message,
+                });
+            }
+        }
+
         for candidate in response.candidates.unwrap_or_default() {
             // Check for refusal/recitation before processing content
             if let Some(reason) = &candidate.finish_reason {
@@ -126,9 +571,15 @@ impl GeminiClient {
                         .unwrap_or("Image generation was refused by the API");
                     tracing::warn!("Generation refused: {} - {}", reason, message);
                     job.set_failed(message);
-                    return Err(
-                        BananaError::GenerationFailed(message.to_string()).into()
-                    );
+                    return Err(if SAFETY_FINISH_REASONS.contains(&reason.as_str()) {
+                        BananaError::SafetyBlocked {
+                            reason: reason.clone(),
+                            categories: flagged_categories(candidate.safety_ratings.as_deref()),
+                            message: message.to_string(),
+                        }
+                    } else {
+                        BananaError::GenerationFailed(message.to_string())
+                    });
                 }
             }
 
@@ -149,7 +600,7 @@ impl GeminiClient {
 
         if job.images.is_empty() {
             job.set_failed("No images generated");
-            return Err(BananaError::GenerationFailed("No images in response".to_string()).into());
+            return Err(BananaError::GenerationFailed("No images in response".to_string()));
         }
 
         job.set_completed();
@@ -158,51 +609,327 @@ impl GeminiClient {
 
     /// Download images from job to disk
     pub async fn download_images(&self, job: &mut Job, output_dir: &Path) -> Result<Vec<String>> {
-        fs::create_dir_all(output_dir).await?;
-
-        let mut paths = Vec::new();
+        self.download_images_with_progress(job, output_dir, |_| {}).await
+    }
 
-        for image in &mut job.images {
-            if let Some(data) = &image.data {
-                let ext = match image.mime_type.as_str() {
-                    "image/png" => "png",
-                    "image/jpeg" => "jpg",
-                    "image/webp" => "webp",
-                    _ => "png",
-                };
+    /// Download images from job to disk, reporting per-image progress.
+    ///
+    /// Images are decoded and written in chunks (never holding a whole decoded
+    /// image in memory) and up to `MAX_CONCURRENT_DOWNLOADS` run in parallel.
+    pub async fn download_images_with_progress(
+        &self,
+        job: &mut Job,
+        output_dir: &Path,
+        on_progress: impl Fn(DownloadProgress),
+    ) -> Result<Vec<String>> {
+        fs::create_dir_all(output_dir).await?;
 
-                let filename = format!("{}_{}.{}", job.id, image.index, ext);
+        let job_id = job.id.clone();
+        let filenames = reserve_filenames(job, output_dir)?;
+        let image_metadata = &ImageMetadata::for_job(job);
+        let exif = &self.exif;
+        let watermark = &self.watermark;
+        let srgb = self.srgb;
+        let retain_data = self.retain_data;
+        let on_progress = &on_progress;
+
+        let results: Vec<Result<(u8, String)>> = stream::iter(
+            job.images.iter_mut().filter(|image| image.data.is_some()).zip(filenames),
+        )
+        .map(|(image, filename)| {
+            let job_id = job_id.clone();
+            async move {
                 let path = output_dir.join(&filename);
 
-                let bytes = BASE64
-                    .decode(data)
-                    .context("Failed to decode base64 image")?;
+                let data = image.data.as_deref().unwrap_or_default();
+                write_base64_file(&path, data, |written, total| {
+                    on_progress(DownloadProgress {
+                        index: image.index,
+                        written,
+                        total,
+                    })
+                })
+                .await?;
+
+                // Recorded canonical so later lookups by file path match
+                // regardless of the working directory
+                let recorded = platform::canonicalize(&path).unwrap_or_else(|_| path.clone());
+                let path_str = platform::display_path(&recorded);
+                image.path = Some(path_str.clone());
+                if !retain_data {
+                    image.data = None; // Clear base64 data after saving
+                }
 
-                fs::write(&path, &bytes).await?;
+                tracing::info!("Saved image to: {}", path.display());
 
-                image.path = Some(path.to_string_lossy().to_string());
-                image.data = None; // Clear base64 data after saving
-                paths.push(path.to_string_lossy().to_string());
+                // Post-process locally: watermark first (it re-encodes the
+                // image), then embed generation metadata and pre-build the
+                // preview thumbnail so viewers never decode the full image
+                let (thumb_job_id, index, source) = (job_id.clone(), image.index, path.clone());
+                let (image_metadata, exif) = (image_metadata.clone(), exif.clone());
+                let watermark = watermark.clone();
+                let (watermarked, embedded, thumb, dimensions) = tokio::task::spawn_blocking(move || {
+                    let watermarked = match &watermark {
+                        Some(config) => imaging::apply_watermark(&source, config),
+                        None => Ok(()),
+                    };
+                    (
+                        watermarked,
+                        metadata::embed(&source, &image_metadata, &exif, srgb),
+                        thumbnails::ensure(&thumb_job_id, index, &source),
+                        image::image_dimensions(&source),
+                    )
+                })
+                .await
+                .map_err(|e| BananaError::ImageError(format!("Image post-processing panicked: {}", e)))?;
+                if let Err(e) = watermarked {
+                    tracing::warn!("Failed to apply watermark: {}", e);
+                }
+                if let Err(e) = embedded {
+                    tracing::warn!("Failed to embed metadata: {}", e);
+                }
+                if let Err(e) = thumb {
+                    tracing::warn!("Failed to create thumbnail: {}", e);
+                }
+                match dimensions {
+                    Ok((width, height)) => {
+                        image.width = Some(width);
+                        image.height = Some(height);
+                    }
+                    Err(e) => tracing::warn!("Failed to read image dimensions: {}", e),
+                }
 
-                tracing::info!("Saved image to: {}", path.display());
+                Ok((image.index, path_str))
             }
+        })
+        .buffer_unordered(MAX_CONCURRENT_DOWNLOADS)
+        .collect()
+        .await;
+
+        // Return paths in image order regardless of completion order
+        let mut saved = results.into_iter().collect::<Result<Vec<_>>>()?;
+        saved.sort_by_key(|(index, _)| *index);
+        Ok(saved.into_iter().map(|(_, path)| path).collect())
+    }
+}
+
+/// Map an API error response to the matching `BananaError`.
+///
+/// The HTTP status decides first, then the gRPC status string, so gateways
+/// that rewrite one of them still map correctly.
+fn api_error(status: reqwest::StatusCode, error: &ApiError, model: &str) -> BananaError {
+    // Variants append their own guidance after the message
+    let message = error.message.trim_end_matches('.').to_string();
+    let reason = error
+        .detail("ErrorInfo")
+        .and_then(|d| d.get("reason"))
+        .and_then(|r| r.as_str());
+
+    match (status.as_u16(), error.status.as_str()) {
+        // An invalid key is reported as a plain 400
+        _ if reason == Some("API_KEY_INVALID") => BananaError::Unauthorized { message },
+        (401 | 403, _) | (_, "UNAUTHENTICATED" | "PERMISSION_DENIED") => BananaError::Unauthorized { message },
+        (429, _) | (_, "RESOURCE_EXHAUSTED") => BananaError::RateLimited {
+            message,
+            retry_after: retry_delay(error),
+        },
+        (404, _) | (_, "NOT_FOUND") => BananaError::ModelNotFound {
+            model: model.to_string(),
+            message,
+        },
+        (500..=599, _) | (_, "UNAVAILABLE" | "INTERNAL" | "DEADLINE_EXCEEDED") => {
+            BananaError::ServiceUnavailable { message }
+        }
+        (400, _) | (_, "INVALID_ARGUMENT") => BananaError::InvalidParameter(message),
+        _ => BananaError::ApiError { message },
+    }
+}
+
+/// Seconds until a quota resets, from a RetryInfo detail
+fn retry_delay(error: &ApiError) -> Option<u64> {
+    error
+        .detail("RetryInfo")
+        .and_then(|d| d.get("retryDelay"))
+        .and_then(|d| d.as_str())
+        .and_then(quota::parse_retry_delay)
+}
+
+/// Remember a rate limit so other invocations (and the TUI) can show it
+fn record_quota(model: &str, error: &ApiError, retry_after: Option<u64>) {
+    let quota_id = error
+        .detail("QuotaFailure")
+        .and_then(|d| d.pointer("/violations/0/quotaId"))
+        .and_then(|q| q.as_str())
+        .map(String::from);
+
+    let state = QuotaState {
+        limited_at: Utc::now(),
+        resets_at: retry_after.map(|s| Utc::now() + chrono::Duration::seconds(s as i64)),
+        quota_id,
+        model: model.to_string(),
+    };
+    if let Err(e) = quota::save(&state) {
+        tracing::debug!("Failed to save quota state: {}", e);
+    }
+}
+
+/// Combine the responses of a job's per-image requests into one
+fn merge_responses(responses: Vec<GenerateResponse>) -> GenerateResponse {
+    let sum = |a: Option<i32>, b: Option<i32>| match (a, b) {
+        (None, None) => None,
+        (a, b) => Some(a.unwrap_or(0) + b.unwrap_or(0)),
+    };
+
+    let mut merged = GenerateResponse {
+        candidates: None,
+        prompt_feedback: None,
+        usage_metadata: None,
+    };
+    for response in responses {
+        if let Some(candidates) = response.candidates {
+            merged.candidates.get_or_insert_with(Vec::new).extend(candidates);
         }
+        merged.prompt_feedback = merged.prompt_feedback.or(response.prompt_feedback);
+        merged.usage_metadata = match (merged.usage_metadata, response.usage_metadata) {
+            (Some(a), Some(b)) => Some(UsageMetadata {
+                prompt_token_count: sum(a.prompt_token_count, b.prompt_token_count),
+                candidates_token_count: sum(a.candidates_token_count, b.candidates_token_count),
+                total_token_count: sum(a.total_token_count, b.total_token_count),
+            }),
+            (a, b) => a.or(b),
+        };
+    }
+    merged
+}
+
+/// Reserve file names for a job's not-yet-downloaded images.
+///
+/// Single images keep the `{job_id}_{index}` name. Multi-image jobs use a
+/// prompt slug (plus the seed, if set) so the files are recognizable. Each
+/// name is claimed by creating the (empty) file, so concurrent jobs never
+/// pick the same one; a name that is already taken gets a numeric suffix.
+fn reserve_filenames(job: &Job, output_dir: &Path) -> Result<Vec<String>> {
+    let multi = job.images.len() > 1 || job.params.num_images > 1;
+    let slug = prompt::slug(&job.params.prompt, 40);
+
+    job.images
+        .iter()
+        .filter(|image| image.data.is_some())
+        .map(|image| {
+            let ext = match image.mime_type.as_str() {
+                "image/png" => "png",
+                "image/jpeg" => "jpg",
+                "image/webp" => "webp",
+                _ => "png",
+            };
+            let stem = match (multi, job.params.seed) {
+                (false, _) => format!("{}_{}", job.id, image.index),
+                (true, Some(seed)) => format!("{}_{}_{}", slug, seed, image.index),
+                (true, None) => format!("{}_{}", slug, image.index),
+            };
+
+            let mut filename = format!("{}.{}", stem, ext);
+            let mut n = 2;
+            loop {
+                let reserved = std::fs::OpenOptions::new()
+                    .write(true)
+                    .create_new(true)
+                    .open(output_dir.join(&filename));
+                match reserved {
+                    Ok(_) => return Ok(filename),
+                    Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {
+                        filename = format!("{}-{}.{}", stem, n, ext);
+                        n += 1;
+                    }
+                    Err(e) => return Err(e.into()),
+                }
+            }
+        })
+        .collect()
+}
 
-        Ok(paths)
+/// Progress of a single image being written to disk
+#[derive(Debug, Clone, Copy)]
+pub struct DownloadProgress {
+    /// Image index within the job
+    pub index: u8,
+    /// Decoded bytes written so far
+    pub written: u64,
+    /// Approximate decoded size in bytes
+    pub total: u64,
+}
+
+/// Decode base64 data in chunks and stream it to a file
+async fn write_base64_file(path: &Path, data: &str, on_chunk: impl Fn(u64, u64)) -> Result<()> {
+    let file = fs::File::create(path).await?;
+    let mut writer = tokio::io::BufWriter::new(file);
+    let total = (data.len() / 4 * 3) as u64;
+    let mut written = 0u64;
+    let mut buf = Vec::with_capacity(DECODE_CHUNK_SIZE / 4 * 3);
+
+    // Chunk length is a multiple of 4 so every chunk is independently decodable
+    for chunk in data.as_bytes().chunks(DECODE_CHUNK_SIZE) {
+        buf.clear();
+        BASE64
+            .decode_vec(chunk, &mut buf)
+            .map_err(|e| BananaError::ImageError(format!("Failed to decode base64 image: {}", e)))?;
+        writer.write_all(&buf).await?;
+        written += buf.len() as u64;
+        on_chunk(written, total);
     }
+
+    writer.flush().await?;
+    Ok(())
 }
 
-/// Load an image file and encode as base64
+/// Describe the biggest inline image in a request, for size errors
+fn largest_inline_image(params: &GenerateParams) -> String {
+    let reference = params
+        .reference_image
+        .as_ref()
+        .map(|data| ("reference image".to_string(), data.len()));
+    let context = params.context.iter().enumerate().filter_map(|(i, turn)| {
+        let (data, _) = turn.image.as_ref()?;
+        Some((format!("context image {}", i + 1), data.len()))
+    });
+
+    match reference.into_iter().chain(context).max_by_key(|(_, len)| *len) {
+        Some((name, len)) => format!("largest part: {}, {:.1} MB", name, len as f64 / (1024.0 * 1024.0)),
+        None => "no images attached".to_string(),
+    }
+}
+
+/// Load an image file and encode as base64.
+///
+/// The mime type comes from the file's magic bytes; the extension is only a
+/// fallback for formats that can't be sniffed.
 pub async fn load_image_base64(path: &Path) -> Result<(String, String)> {
     let data = fs::read(path).await?;
     let base64_data = BASE64.encode(&data);
 
-    let mime_type = match path.extension().and_then(|e| e.to_str()) {
-        Some("png") => "image/png",
-        Some("jpg") | Some("jpeg") => "image/jpeg",
-        Some("webp") => "image/webp",
-        Some("gif") => "image/gif",
-        _ => "image/png",
+    let extension = path.extension().and_then(|e| e.to_str()).map(str::to_lowercase);
+    let from_extension = match extension.as_deref() {
+        Some("png") => Some("image/png"),
+        Some("jpg") | Some("jpeg") => Some("image/jpeg"),
+        Some("webp") => Some("image/webp"),
+        Some("gif") => Some("image/gif"),
+        _ => None,
+    };
+    let sniffed = image::guess_format(&data).ok().map(|format| format.to_mime_type());
+
+    let mime_type = match (sniffed, from_extension) {
+        (Some(sniffed), Some(claimed)) if sniffed != claimed => {
+            tracing::warn!(
+                "{} has a .{} extension but contains {}; using the detected type",
+                path.display(),
+                extension.as_deref().unwrap_or_default(),
+                sniffed
+            );
+            sniffed
+        }
+        (Some(sniffed), _) => sniffed,
+        (None, Some(claimed)) => claimed,
+        (None, None) => "image/png",
     };
 
     Ok((base64_data, mime_type.to_string()))
@@ -0,0 +1,250 @@
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use futures_util::future::BoxFuture;
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use super::provider::{self, Provider};
+use super::{GenerateResponse, InlineData, RetryPolicy, UsageMetadata};
+use crate::core::{BananaError, GenerateParams, Result};
+use crate::fixtures;
+use crate::http_client::HTTP_CLIENT;
+use crate::imaging;
+
+/// Public OpenAI API root
+pub const DEFAULT_BASE_URL: &str = "https://api.openai.com/v1";
+
+/// Multipart boundary for edit requests (the prompt is checked not to contain it)
+const BOUNDARY: &str = "----banana-cli-7f3a9c1e5b2d";
+
+/// OpenAI image models (gpt-image-1, dall-e-3, dall-e-2)
+#[derive(Debug)]
+pub struct OpenAiProvider {
+    api_key: String,
+    base_url: String,
+    retry_policy: RetryPolicy,
+}
+
+/// Response of the generations and edits endpoints
+#[derive(Debug, Deserialize)]
+struct ImagesResponse {
+    #[serde(default)]
+    data: Vec<ImageData>,
+    usage: Option<ImagesUsage>,
+}
+
+/// One generated image, inline or (for DALL·E without `b64_json`) by URL
+#[derive(Debug, Deserialize)]
+struct ImageData {
+    b64_json: Option<String>,
+    url: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ImagesUsage {
+    input_tokens: Option<i32>,
+    output_tokens: Option<i32>,
+    total_tokens: Option<i32>,
+}
+
+impl OpenAiProvider {
+    pub fn new(api_key: impl Into<String>, base_url: impl Into<String>, retry_policy: RetryPolicy) -> Self {
+        Self {
+            api_key: api_key.into(),
+            base_url: base_url.into().trim_end_matches('/').to_string(),
+            retry_policy,
+        }
+    }
+
+    async fn run(&self, params: &GenerateParams) -> Result<GenerateResponse> {
+        if fixtures::is_replaying() {
+            return Err(BananaError::Fixture(
+                "--replay only covers Gemini API requests, not OpenAI".to_string(),
+            ));
+        }
+
+        let reply = match (&params.reference_image, &params.reference_mime_type) {
+            (Some(data), Some(mime_type)) => {
+                let body = edit_body(params, data, mime_type)?;
+                let url = format!("{}/images/edits", self.base_url);
+                provider::send("OpenAI", self.retry_policy, || {
+                    HTTP_CLIENT
+                        .post(&url)
+                        .bearer_auth(&self.api_key)
+                        .header(
+                            reqwest::header::CONTENT_TYPE,
+                            format!("multipart/form-data; boundary={}", BOUNDARY),
+                        )
+                        .body(body.clone())
+                })
+                .await?
+            }
+            _ => {
+                let url = format!("{}/images/generations", self.base_url);
+                let body = generate_body(params);
+                provider::send("OpenAI", self.retry_policy, || {
+                    HTTP_CLIENT.post(&url).bearer_auth(&self.api_key).json(&body)
+                })
+                .await?
+            }
+        };
+
+        if !(200..300).contains(&reply.status) {
+            return Err(api_error(reply.status, &reply.body, &params.model, reply.retry_after));
+        }
+        let response: ImagesResponse = serde_json::from_str(&reply.body).map_err(|e| {
+            BananaError::InvalidResponse(format!("Failed to parse OpenAI response: {}", e))
+        })?;
+
+        let mut images = Vec::new();
+        for image in response.data {
+            match (image.b64_json, image.url) {
+                (Some(data), _) => images.push(InlineData {
+                    mime_type: "image/png".to_string(),
+                    data,
+                }),
+                (None, Some(url)) => images.push(provider::fetch_image(&url).await?),
+                (None, None) => {}
+            }
+        }
+
+        let mut generated = provider::image_response(images);
+        generated.usage_metadata = response.usage.map(|usage| UsageMetadata {
+            prompt_token_count: usage.input_tokens,
+            candidates_token_count: usage.output_tokens,
+            total_token_count: usage.total_tokens,
+        });
+        Ok(generated)
+    }
+}
+
+impl Provider for OpenAiProvider {
+    fn name(&self) -> &'static str {
+        "openai"
+    }
+
+    fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    fn generate<'a>(&'a self, params: &'a GenerateParams) -> BoxFuture<'a, Result<GenerateResponse>> {
+        Box::pin(self.run(params))
+    }
+}
+
+/// JSON body for `images/generations`
+fn generate_body(params: &GenerateParams) -> Value {
+    let mut body = json!({
+        "model": params.model,
+        "prompt": params.prompt,
+        "n": params.num_images,
+        "size": image_size(&params.model, &params.aspect_ratio),
+    });
+    // gpt-image models always answer inline and reject the field
+    if params.model.starts_with("dall-e") {
+        body["response_format"] = json!("b64_json");
+    }
+    body
+}
+
+/// Multipart body for `images/edits`, with the reference image as a file part
+fn edit_body(params: &GenerateParams, data: &str, mime_type: &str) -> Result<Vec<u8>> {
+    if params.prompt.contains(BOUNDARY) {
+        return Err(BananaError::InvalidParameter("Prompt contains the multipart boundary".to_string()));
+    }
+    let image = BASE64
+        .decode(data)
+        .map_err(|e| BananaError::ImageError(format!("Failed to decode reference image: {}", e)))?;
+    let extension = mime_type.strip_prefix("image/").unwrap_or("png");
+
+    let mut body = Vec::new();
+    let n = params.num_images.to_string();
+    let size = image_size(&params.model, &params.aspect_ratio);
+    for (name, value) in [
+        ("model", params.model.as_str()),
+        ("prompt", params.prompt.as_str()),
+        ("n", n.as_str()),
+        ("size", size),
+    ] {
+        body.extend_from_slice(
+            format!("--{}\r\nContent-Disposition: form-data; name=\"{}\"\r\n\r\n{}\r\n", BOUNDARY, name, value)
+                .as_bytes(),
+        );
+    }
+    if params.model.starts_with("dall-e") {
+        body.extend_from_slice(
+            format!("--{}\r\nContent-Disposition: form-data; name=\"response_format\"\r\n\r\nb64_json\r\n", BOUNDARY)
+                .as_bytes(),
+        );
+    }
+    body.extend_from_slice(
+        format!(
+            "--{}\r\nContent-Disposition: form-data; name=\"image\"; filename=\"image.{}\"\r\nContent-Type: {}\r\n\r\n",
+            BOUNDARY, extension, mime_type
+        )
+        .as_bytes(),
+    );
+    body.extend_from_slice(&image);
+    body.extend_from_slice(format!("\r\n--{}--\r\n", BOUNDARY).as_bytes());
+    Ok(body)
+}
+
+/// The model's output size closest to the requested aspect ratio.
+///
+/// OpenAI models offer a fixed set of sizes instead of a ratio and a
+/// resolution, so only the ratio's orientation carries over; `1K`/`2K`/`4K`
+/// have no equivalent.
+fn image_size(model: &str, aspect_ratio: &str) -> &'static str {
+    let shape = match imaging::parse_ratio(aspect_ratio).unwrap_or(1.0) {
+        ratio if ratio > 1.2 => "landscape",
+        ratio if ratio < 1.0 / 1.2 => "portrait",
+        _ => "square",
+    };
+    match (model, shape) {
+        ("dall-e-2", _) => "1024x1024",
+        ("dall-e-3", "landscape") => "1792x1024",
+        ("dall-e-3", "portrait") => "1024x1792",
+        (_, "landscape") => "1536x1024",
+        (_, "portrait") => "1024x1536",
+        _ => "1024x1024",
+    }
+}
+
+/// Map an error response (`{"error": {"message", "code"}}`) to the matching `BananaError`
+fn api_error(status: u16, body: &str, model: &str, retry_after: Option<u64>) -> BananaError {
+    let error = serde_json::from_str::<Value>(body).ok().and_then(|v| v.get("error").cloned());
+    let message = error
+        .as_ref()
+        .and_then(|e| e.get("message"))
+        .and_then(Value::as_str)
+        .unwrap_or(body)
+        .trim_end_matches('.')
+        .to_string();
+    let code = error
+        .as_ref()
+        .and_then(|e| e.get("code"))
+        .and_then(Value::as_str)
+        .unwrap_or_default();
+
+    match (status, code) {
+        (_, "content_policy_violation" | "moderation_blocked") => BananaError::SafetyBlocked {
+            reason: code.to_string(),
+            categories: Vec::new(),
+            message,
+        },
+        (401 | 403, _) => BananaError::ApiError {
+            message: format!(
+                "OpenAI key rejected: {}. Check OPENAI_API_KEY or run: banana config set openai.key <key>",
+                message
+            ),
+        },
+        (404, _) | (_, "model_not_found") => BananaError::ModelNotFound {
+            model: model.to_string(),
+            message,
+        },
+        (429, _) => BananaError::RateLimited { message, retry_after },
+        (400, _) => BananaError::InvalidParameter(message),
+        _ => BananaError::ApiError {
+            message: format!("OpenAI returned HTTP {}: {}", status, message),
+        },
+    }
+}
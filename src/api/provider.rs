@@ -0,0 +1,121 @@
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use futures_util::future::BoxFuture;
+use tokio::time;
+
+use super::types::{Candidate, Content, ContentPart, GenerateResponse, InlineData};
+use super::RetryPolicy;
+use crate::core::{BananaError, GenerateParams, Result};
+use crate::http_client::HTTP_CLIENT;
+
+/// An image backend other than the Gemini API.
+///
+/// A provider turns `GenerateParams` into its own request and its reply into
+/// a `GenerateResponse`, so jobs, downloads, and post-processing work the
+/// same whichever backend produced the images.
+pub trait Provider: std::fmt::Debug + Send + Sync {
+    /// Name used in config and on jobs, e.g. "replicate"
+    fn name(&self) -> &'static str;
+
+    /// API root requests go to, recorded on jobs next to the name
+    fn base_url(&self) -> &str;
+
+    /// Run one generate (or edit, if the params carry a reference image) request
+    fn generate<'a>(&'a self, params: &'a GenerateParams) -> BoxFuture<'a, Result<GenerateResponse>>;
+}
+
+/// A provider's HTTP response, read in full
+pub(super) struct Reply {
+    pub status: u16,
+    /// Seconds from a `Retry-After` header
+    pub retry_after: Option<u64>,
+    pub body: String,
+}
+
+/// Send the request `build` creates, rebuilding and retrying it after
+/// connection errors and 5xx responses as `policy` allows
+pub(super) async fn send(
+    name: &str,
+    policy: RetryPolicy,
+    build: impl Fn() -> reqwest::RequestBuilder,
+) -> Result<Reply> {
+    let mut attempt = 0;
+    let response = loop {
+        let result = build().send().await;
+        let transient = match &result {
+            Ok(response) => response.status().is_server_error(),
+            Err(_) => true,
+        };
+        if !transient || attempt >= policy.max_retries {
+            break result?;
+        }
+
+        let delay = policy.backoff(attempt);
+        attempt += 1;
+        tracing::warn!(
+            "{} request failed; retrying in {}s ({}/{})",
+            name,
+            delay.as_secs(),
+            attempt,
+            policy.max_retries
+        );
+        time::sleep(delay).await;
+    };
+
+    let status = response.status().as_u16();
+    let retry_after = response
+        .headers()
+        .get(reqwest::header::RETRY_AFTER)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.trim().parse::<u64>().ok());
+    let body = response.text().await?;
+    tracing::debug!("{} response {}: {}", name, status, body);
+
+    Ok(Reply { status, retry_after, body })
+}
+
+/// A response holding the given images as a single candidate
+pub(super) fn image_response(images: Vec<InlineData>) -> GenerateResponse {
+    let parts = images
+        .into_iter()
+        .map(|image| ContentPart::InlineData { inlineData: image })
+        .collect();
+    GenerateResponse {
+        candidates: Some(vec![Candidate {
+            content: Some(Content { parts, role: None }),
+            finish_reason: Some("STOP".to_string()),
+            finish_message: None,
+            safety_ratings: None,
+        }]),
+        prompt_feedback: None,
+        usage_metadata: None,
+    }
+}
+
+/// Fetch an image a provider returned by URL, as inline data
+pub(super) async fn fetch_image(url: &str) -> Result<InlineData> {
+    let response = HTTP_CLIENT.get(url).send().await?;
+    if !response.status().is_success() {
+        return Err(BananaError::InvalidResponse(format!(
+            "Failed to fetch {}: HTTP {}",
+            url,
+            response.status()
+        )));
+    }
+    let header_type = response
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .filter(|v| v.starts_with("image/"))
+        .map(String::from);
+    let bytes = response.bytes().await?;
+
+    let mime_type = image::guess_format(&bytes)
+        .ok()
+        .map(|format| format.to_mime_type().to_string())
+        .or(header_type)
+        .unwrap_or_else(|| "image/png".to_string());
+    Ok(InlineData {
+        mime_type,
+        data: BASE64.encode(&bytes),
+    })
+}
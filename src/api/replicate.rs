@@ -0,0 +1,198 @@
+use futures_util::future::BoxFuture;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::time::Duration;
+use tokio::time;
+
+use super::provider::{self, Provider};
+use super::{GenerateResponse, RetryPolicy};
+use crate::core::{BananaError, GenerateParams, Result};
+use crate::fixtures;
+use crate::http_client::HTTP_CLIENT;
+
+/// Public Replicate API root
+pub const DEFAULT_BASE_URL: &str = "https://api.replicate.com/v1";
+
+/// Seconds the API may hold the create request open before replying with
+/// a prediction that is still running
+const PREFER_WAIT_SECS: u64 = 60;
+
+/// Delay between status checks of a running prediction
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Models hosted on Replicate, addressed by `owner/name` or `owner/name:version`
+#[derive(Debug)]
+pub struct ReplicateProvider {
+    token: String,
+    base_url: String,
+    retry_policy: RetryPolicy,
+}
+
+/// A prediction as returned by the create and get endpoints
+#[derive(Debug, Deserialize)]
+struct Prediction {
+    id: String,
+    status: String,
+    /// A single URL or a list of them, depending on the model
+    #[serde(default)]
+    output: Value,
+    #[serde(default)]
+    error: Value,
+}
+
+impl ReplicateProvider {
+    pub fn new(token: impl Into<String>, base_url: impl Into<String>, retry_policy: RetryPolicy) -> Self {
+        Self {
+            token: token.into(),
+            base_url: base_url.into().trim_end_matches('/').to_string(),
+            retry_policy,
+        }
+    }
+
+    async fn run(&self, params: &GenerateParams) -> Result<GenerateResponse> {
+        if fixtures::is_replaying() {
+            return Err(BananaError::Fixture(
+                "--replay only covers Gemini API requests, not Replicate".to_string(),
+            ));
+        }
+
+        // Versioned slugs go to the generic endpoint, the rest to the model's own
+        let (url, body) = match params.model.split_once(':') {
+            Some((_, version)) => (
+                format!("{}/predictions", self.base_url),
+                json!({ "version": version, "input": input(params) }),
+            ),
+            None => (
+                format!("{}/models/{}/predictions", self.base_url, params.model),
+                json!({ "input": input(params) }),
+            ),
+        };
+        tracing::debug!("Creating Replicate prediction for: {}", params.model);
+
+        let mut prediction = self.request(Some(&body), &url, &params.model).await?;
+        while matches!(prediction.status.as_str(), "starting" | "processing") {
+            time::sleep(POLL_INTERVAL).await;
+            let url = format!("{}/predictions/{}", self.base_url, prediction.id);
+            prediction = self.request(None, &url, &params.model).await?;
+        }
+
+        match prediction.status.as_str() {
+            "succeeded" => {}
+            "canceled" => {
+                return Err(BananaError::GenerationFailed("The prediction was canceled".to_string()))
+            }
+            _ => {
+                let message = match &prediction.error {
+                    Value::String(message) => message.trim_end_matches('.').to_string(),
+                    Value::Null => format!("Prediction {}", prediction.status),
+                    other => other.to_string(),
+                };
+                // Replicate's own content filter reports as a plain failure
+                return Err(if message.contains("NSFW") {
+                    BananaError::SafetyBlocked {
+                        reason: "NSFW".to_string(),
+                        categories: Vec::new(),
+                        message,
+                    }
+                } else {
+                    BananaError::GenerationFailed(message)
+                });
+            }
+        }
+
+        let urls: Vec<&str> = match &prediction.output {
+            Value::String(url) => vec![url.as_str()],
+            Value::Array(items) => items.iter().filter_map(Value::as_str).collect(),
+            _ => Vec::new(),
+        };
+        let mut images = Vec::new();
+        for url in urls {
+            images.push(provider::fetch_image(url).await?);
+        }
+        Ok(provider::image_response(images))
+    }
+
+    /// POST `body` (or GET, without one), retrying transient failures
+    async fn request(&self, body: Option<&Value>, url: &str, model: &str) -> Result<Prediction> {
+        let reply = provider::send("Replicate", self.retry_policy, || {
+            let request = match body {
+                Some(body) => HTTP_CLIENT
+                    .post(url)
+                    .header("Prefer", format!("wait={}", PREFER_WAIT_SECS))
+                    .json(body),
+                None => HTTP_CLIENT.get(url),
+            };
+            request.bearer_auth(&self.token)
+        })
+        .await?;
+
+        if !(200..300).contains(&reply.status) {
+            return Err(api_error(reply.status, &reply.body, model, reply.retry_after));
+        }
+        serde_json::from_str(&reply.body).map_err(|e| {
+            BananaError::InvalidResponse(format!("Failed to parse Replicate response: {}", e))
+        })
+    }
+}
+
+impl Provider for ReplicateProvider {
+    fn name(&self) -> &'static str {
+        "replicate"
+    }
+
+    fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    fn generate<'a>(&'a self, params: &'a GenerateParams) -> BoxFuture<'a, Result<GenerateResponse>> {
+        Box::pin(self.run(params))
+    }
+}
+
+/// Model input: the common fields most text-to-image models on Replicate accept
+fn input(params: &GenerateParams) -> Value {
+    let mut input = json!({
+        "prompt": params.prompt,
+        "aspect_ratio": params.aspect_ratio,
+    });
+    if params.num_images > 1 {
+        input["num_outputs"] = json!(params.num_images);
+    }
+    if let Some(seed) = params.seed {
+        input["seed"] = json!(seed);
+    }
+    if let Some(negative) = &params.negative_prompt {
+        input["negative_prompt"] = json!(negative);
+    }
+    if let (Some(data), Some(mime_type)) = (&params.reference_image, &params.reference_mime_type) {
+        input["image"] = json!(format!("data:{};base64,{}", mime_type, data));
+    }
+    input
+}
+
+/// Map an error response (`{"detail": ...}`) to the matching `BananaError`
+fn api_error(status: u16, body: &str, model: &str, retry_after: Option<u64>) -> BananaError {
+    let message = serde_json::from_str::<Value>(body)
+        .ok()
+        .and_then(|v| v.get("detail").and_then(Value::as_str).map(String::from))
+        .unwrap_or_else(|| body.to_string());
+    let message = message.trim_end_matches('.').to_string();
+
+    match status {
+        401 | 403 => BananaError::ApiError {
+            message: format!(
+                "Replicate token rejected: {}. Check REPLICATE_API_TOKEN or run: banana config set replicate.token <token>",
+                message
+            ),
+        },
+        404 => BananaError::ModelNotFound {
+            model: model.to_string(),
+            message,
+        },
+        429 => BananaError::RateLimited { message, retry_after },
+        400 | 422 => BananaError::InvalidParameter(message),
+        _ => BananaError::ApiError {
+            message: format!("Replicate returned HTTP {}: {}", status, message),
+        },
+    }
+}
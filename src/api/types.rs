@@ -1,4 +1,5 @@
 use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
 
 /// Request body for generateContent endpoint
 #[derive(Debug, Serialize)]
@@ -47,6 +48,38 @@ pub struct GenerationConfig {
     pub response_modalities: Option<Vec<String>>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub image_config: Option<ImageConfig>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub seed: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub top_p: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub top_k: Option<u32>,
+    /// Fields without a dedicated flag (`--gen-config`)
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+impl GenerationConfig {
+    /// Set a field by its snake_case name, replacing the typed field of the
+    /// same name; dots reach into nested objects (`image_config.person_generation`)
+    pub fn set(&mut self, key: &str, value: Value) {
+        if let (Some(("image_config", field)), Some(image_config)) = (key.split_once('.'), &mut self.image_config) {
+            image_config.set(field, value);
+            return;
+        }
+        match key {
+            "response_modalities" => self.response_modalities = None,
+            "image_config" => self.image_config = None,
+            "seed" => self.seed = None,
+            "temperature" => self.temperature = None,
+            "top_p" => self.top_p = None,
+            "top_k" => self.top_k = None,
+            _ => {}
+        }
+        insert_field(&mut self.extra, key, value);
+    }
 }
 
 /// Image-specific configuration
@@ -54,6 +87,44 @@ pub struct GenerationConfig {
 pub struct ImageConfig {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub aspect_ratio: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub image_size: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub person_generation: Option<String>,
+    /// Fields without a dedicated flag (`--gen-config image_config.<field>`)
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+impl ImageConfig {
+    /// Set a field by its snake_case name, replacing the typed field of the same name
+    pub fn set(&mut self, key: &str, value: Value) {
+        match key {
+            "aspect_ratio" => self.aspect_ratio = None,
+            "image_size" => self.image_size = None,
+            "person_generation" => self.person_generation = None,
+            _ => {}
+        }
+        insert_field(&mut self.extra, key, value);
+    }
+}
+
+/// Insert `value` at a dotted `key`, creating (or replacing non-object) parents as needed
+fn insert_field(map: &mut Map<String, Value>, key: &str, value: Value) {
+    match key.split_once('.') {
+        Some((head, rest)) => {
+            let parent = map.entry(head).or_insert_with(|| Value::Object(Map::new()));
+            if !parent.is_object() {
+                *parent = Value::Object(Map::new());
+            }
+            if let Value::Object(parent) = parent {
+                insert_field(parent, rest, value);
+            }
+        }
+        None => {
+            map.insert(key.to_string(), value);
+        }
+    }
 }
 
 /// Safety settings
@@ -65,6 +136,7 @@ pub struct SafetySetting {
 
 /// Response from generateContent endpoint
 #[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct GenerateResponse {
     pub candidates: Option<Vec<Candidate>>,
     pub prompt_feedback: Option<PromptFeedback>,
@@ -83,6 +155,7 @@ pub struct Candidate {
 
 /// Feedback about the prompt
 #[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct PromptFeedback {
     pub block_reason: Option<String>,
     pub safety_ratings: Option<Vec<SafetyRating>>,
@@ -92,17 +165,78 @@ pub struct PromptFeedback {
 #[derive(Debug, Deserialize)]
 pub struct SafetyRating {
     pub category: String,
+    #[serde(default)]
     pub probability: String,
+    /// Whether this category caused the block
+    #[serde(default)]
+    pub blocked: bool,
+}
+
+impl SafetyRating {
+    /// Whether this category plausibly triggered a block
+    pub fn is_flagged(&self) -> bool {
+        self.blocked || matches!(self.probability.as_str(), "MEDIUM" | "HIGH")
+    }
+
+    /// Readable category name ("HARM_CATEGORY_DANGEROUS_CONTENT" -> "dangerous content")
+    pub fn category_name(&self) -> String {
+        self.category
+            .trim_start_matches("HARM_CATEGORY_")
+            .replace('_', " ")
+            .to_lowercase()
+    }
+}
+
+/// Names of the flagged categories among `ratings`
+pub fn flagged_categories(ratings: Option<&[SafetyRating]>) -> Vec<String> {
+    ratings
+        .unwrap_or_default()
+        .iter()
+        .filter(|rating| rating.is_flagged())
+        .map(SafetyRating::category_name)
+        .collect()
 }
 
 /// Token usage metadata
 #[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct UsageMetadata {
     pub prompt_token_count: Option<i32>,
     pub candidates_token_count: Option<i32>,
     pub total_token_count: Option<i32>,
 }
 
+/// Request body for countTokens endpoint
+#[derive(Debug, Serialize)]
+pub struct CountTokensRequest {
+    pub contents: Vec<Content>,
+}
+
+/// Response from countTokens endpoint
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CountTokensResponse {
+    #[serde(default)]
+    pub total_tokens: u32,
+}
+
+/// Response from the models list endpoint
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ListModelsResponse {
+    #[serde(default)]
+    pub models: Vec<ModelInfo>,
+}
+
+/// One model from the models list, e.g. `models/gemini-2.5-flash-image`
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ModelInfo {
+    pub name: String,
+    #[serde(default)]
+    pub supported_generation_methods: Vec<String>,
+}
+
 /// Error response from API
 #[derive(Debug, Deserialize)]
 pub struct ApiErrorResponse {
@@ -114,5 +248,20 @@ pub struct ApiErrorResponse {
 pub struct ApiError {
     pub code: i32,
     pub message: String,
+    #[serde(default)]
     pub status: String,
+    /// Structured details (google.rpc.RetryInfo, QuotaFailure, ...)
+    #[serde(default)]
+    pub details: Vec<serde_json::Value>,
+}
+
+impl ApiError {
+    /// The detail whose `@type` ends with `type_suffix` (e.g. "RetryInfo")
+    pub fn detail(&self, type_suffix: &str) -> Option<&serde_json::Value> {
+        self.details.iter().find(|d| {
+            d.get("@type")
+                .and_then(|t| t.as_str())
+                .is_some_and(|t| t.ends_with(type_suffix))
+        })
+    }
 }
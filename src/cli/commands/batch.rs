@@ -0,0 +1,304 @@
+use anyhow::{Context, Result};
+use clap::{Args, Subcommand};
+use colored::Colorize;
+use futures_util::stream::{self, StreamExt};
+use serde::Serialize;
+use std::path::Path;
+
+use crate::api::GeminiClient;
+use crate::cli::output::{self, Report};
+use crate::cli::progress::BatchProgress;
+use crate::cli::OutputFormat;
+use crate::config::Config;
+use crate::core::{Batch, BatchMember, GenerateParams, Job};
+use crate::db::Database;
+use crate::imaging;
+
+#[derive(Args)]
+pub struct BatchArgs {
+    #[command(subcommand)]
+    pub command: BatchCommand,
+}
+
+#[derive(Subcommand)]
+pub enum BatchCommand {
+    /// Re-run the failed and unfinished jobs of a batch, keeping completed ones
+    Resume {
+        /// Batch ID (printed by sweep and edit-batch)
+        batch_id: String,
+
+        /// Maximum number of jobs running at once
+        #[arg(short = 'j', long, default_value = "3", value_parser = clap::value_parser!(u8).range(1..=16))]
+        concurrency: u8,
+
+        /// Output format (text, json, quiet, jsonl, template)
+        #[arg(short, long, value_enum, default_value_t = OutputFormat::Text)]
+        format: OutputFormat,
+    },
+}
+
+/// Outcome of one member after a resume
+#[derive(Debug, Serialize)]
+pub struct ResumeItem {
+    #[serde(flatten)]
+    pub member: BatchMember,
+    pub job_id: Option<String>,
+    /// Whether the member was re-run (false if it had already completed)
+    pub resumed: bool,
+    pub success: bool,
+    pub paths: Vec<String>,
+    pub error: Option<String>,
+}
+
+/// `provider_flag` is set when `--provider` was given, which takes precedence
+/// over the backend recorded on the batch's jobs
+pub async fn run(args: BatchArgs, config: &Config, db: &Database, provider_flag: bool) -> Result<()> {
+    match args.command {
+        BatchCommand::Resume { batch_id, concurrency, format } => {
+            resume(&batch_id, concurrency, format, config, db, provider_flag).await
+        }
+    }
+}
+
+async fn resume(
+    batch_id: &str,
+    concurrency: u8,
+    format: OutputFormat,
+    config: &Config,
+    db: &Database,
+    provider_flag: bool,
+) -> Result<()> {
+    output::check(format)?;
+
+    let batch = db
+        .get_batch(batch_id)?
+        .with_context(|| format!("Batch not found: {}", batch_id))?;
+    let jobs = db.list_batch_jobs(&batch.id)?;
+
+    // Pair each member with its job, preferring a completed one if it was run twice
+    let mut items = Vec::new();
+    let mut pending = Vec::new();
+    for (index, member) in batch.members.iter().enumerate() {
+        let mut matching = jobs.iter().filter(|job| member.matches(job));
+        let job = matching
+            .clone()
+            .find(|job| job.status.is_success())
+            .or_else(|| matching.next_back())
+            .cloned();
+
+        match job {
+            Some(job) if job.status.is_success() => items.push((index, ResumeItem {
+                member: member.clone(),
+                job_id: Some(job.id.clone()),
+                resumed: false,
+                success: true,
+                paths: job.images.iter().filter_map(|img| img.path.clone()).collect(),
+                error: None,
+            })),
+            job => pending.push((index, member, job)),
+        }
+    }
+
+    if pending.is_empty() {
+        if format == OutputFormat::Text {
+            println!(
+                "{} All {} job(s) of batch {} already completed",
+                "✓".green(),
+                batch.members.len(),
+                batch.id.yellow()
+            );
+        }
+        return Ok(());
+    }
+
+    // Send the re-runs to the backend the batch ran on, not today's default
+    let recorded = jobs
+        .iter()
+        .find_map(|job| job.provider.as_deref().map(|provider| (provider, job.base_url.as_deref())));
+    let config = match recorded {
+        Some((provider, base_url)) if !provider_flag => config.for_backend(provider, base_url),
+        _ => config.clone(),
+    };
+
+    let mut client = GeminiClient::from_config(&config)?;
+    if !batch.watermark {
+        client = client.without_watermark();
+    }
+
+    let pb = if format == OutputFormat::Text {
+        println!(
+            "{} {} of {} job(s) from {} {}",
+            "Resuming".cyan().bold(),
+            pending.len(),
+            batch.members.len(),
+            batch.kind.as_str(),
+            batch.id.yellow()
+        );
+        Some(BatchProgress::start(pending.len() as u64, batch.prompt.clone()))
+    } else {
+        None
+    };
+
+    let resumed: Vec<(usize, ResumeItem)> = stream::iter(pending)
+        .map(|(index, member, job)| {
+            let client = &client;
+            let batch = &batch;
+            let pb = &pb;
+            async move {
+                let result = run_member(client, batch, member, job, db).await;
+                if let Some(pb) = pb {
+                    pb.inc();
+                }
+                let item = match result {
+                    Ok(job) => ResumeItem {
+                        member: member.clone(),
+                        job_id: Some(job.id.clone()),
+                        resumed: true,
+                        success: true,
+                        paths: job.images.iter().filter_map(|img| img.path.clone()).collect(),
+                        error: None,
+                    },
+                    Err((job_id, e)) => ResumeItem {
+                        member: member.clone(),
+                        job_id,
+                        resumed: true,
+                        success: false,
+                        paths: Vec::new(),
+                        error: Some(e.to_string()),
+                    },
+                };
+                (index, item)
+            }
+        })
+        .buffer_unordered(concurrency as usize)
+        .collect()
+        .await;
+
+    if let Some(pb) = &pb {
+        pb.finish();
+    }
+
+    // Report in batch order
+    items.extend(resumed);
+    items.sort_by_key(|(index, _)| *index);
+    let items: Vec<ResumeItem> = items.into_iter().map(|(_, item)| item).collect();
+
+    let failed = items.iter().filter(|item| !item.success).count();
+
+    let report = Report::new(&items)?
+        .quiet(items.iter().flat_map(|item| &item.paths))
+        .text(|| print_summary(&items));
+    output::write(format, report)?;
+
+    if failed > 0 {
+        anyhow::bail!("{} of {} job(s) still failed", failed, items.len());
+    }
+
+    Ok(())
+}
+
+/// Run one member of a batch, re-using its earlier (failed or unfinished) job if any
+pub(super) async fn run_member(
+    client: &GeminiClient,
+    batch: &Batch,
+    member: &BatchMember,
+    existing: Option<Job>,
+    db: &Database,
+) -> std::result::Result<Job, (Option<String>, anyhow::Error)> {
+    let mut job = match existing {
+        Some(job) => job,
+        None => {
+            let mut job = new_member_job(batch, member).await.map_err(|e| (None, e))?;
+            db.insert_new_job(&mut job).map_err(|e| (None, e.into()))?;
+            job
+        }
+    };
+    let job_id = Some(job.id.clone());
+
+    job.images.clear();
+    job.usage = None;
+    job.set_running(0);
+    db.update_job(&job).map_err(|e| (job_id.clone(), e.into()))?;
+
+    let result = async {
+        let response = client.generate_job(&mut job, db).await?;
+        client.process_response(&mut job, response)?;
+        if let Some(dir) = &batch.output_dir {
+            client.download_images(&mut job, Path::new(dir)).await?;
+        }
+        Ok::<(), anyhow::Error>(())
+    }
+    .await;
+
+    if let Err(e) = &result {
+        job.set_failed(e.to_string());
+    }
+    db.update_job(&job).map_err(|e| (job_id.clone(), e.into()))?;
+
+    match result {
+        Ok(()) => Ok(job),
+        Err(e) => Err((job_id, e)),
+    }
+}
+
+/// Build a fresh job for a member, loading its source image for edits
+async fn new_member_job(batch: &Batch, member: &BatchMember) -> Result<Job> {
+    let params = GenerateParams::new(&batch.prompt)
+        .with_aspect_ratio(&member.aspect_ratio)
+        .with_size(&member.size)
+        .with_model(&member.model);
+
+    let mut job = match &member.source_image {
+        Some(source) => {
+            let (base64_data, mime_type) =
+                imaging::load_for_edit(Path::new(source), &member.aspect_ratio, batch.fit, None).await?;
+            Job::new_edit(params.with_reference_image(base64_data, mime_type), source.clone())
+        }
+        None => Job::new_generate(params),
+    };
+    job.project = batch.project.clone();
+    job.batch_id = Some(batch.id.clone());
+    Ok(job)
+}
+
+/// Print a table of per-member results
+fn print_summary(items: &[ResumeItem]) {
+    println!(
+        "{:<40} {:<12} {:<10} {}",
+        "MEMBER".bold(),
+        "JOB".bold(),
+        "STATUS".bold(),
+        "RESULT".bold()
+    );
+    println!("{}", "-".repeat(90));
+
+    for item in items {
+        let status = match (item.resumed, item.success) {
+            (false, _) => "kept".dimmed().to_string(),
+            (true, true) => "completed".green().to_string(),
+            (true, false) => "failed".red().to_string(),
+        };
+        let result = if item.success {
+            item.paths.first().cloned().unwrap_or_else(|| "(not downloaded)".to_string())
+        } else {
+            item.error.clone().unwrap_or_default()
+        };
+
+        println!(
+            "{:<40} {:<12} {:<10} {}",
+            item.member.label(),
+            item.job_id.as_deref().unwrap_or("-"),
+            status,
+            result
+        );
+    }
+
+    let succeeded = items.iter().filter(|item| item.success).count();
+    println!();
+    println!(
+        "{} {} succeeded, {} failed",
+        "Summary:".cyan().bold(),
+        succeeded.to_string().green(),
+        (items.len() - succeeded).to_string().red()
+    );
+}
@@ -0,0 +1,229 @@
+use anyhow::Result;
+use clap::builder::PossibleValuesParser;
+use clap::Args;
+use colored::Colorize;
+use serde::Serialize;
+use std::time::Instant;
+
+use crate::api::GeminiClient;
+use crate::cli::output::{self, Report};
+use crate::cli::progress::BatchProgress;
+use crate::cli::OutputFormat;
+use crate::config::Config;
+use crate::core::{GenerateParams, Job};
+use crate::db::Database;
+
+/// Small, cheap prompt used unless `--prompt` is given
+const BENCH_PROMPT: &str = "a single yellow banana on a plain white background";
+
+#[derive(Args)]
+pub struct BenchArgs {
+    /// Models or aliases to benchmark, comma-separated (default: every known Gemini model)
+    #[arg(short, long = "model", value_delimiter = ',')]
+    pub models: Vec<String>,
+
+    /// Requests per model
+    #[arg(short = 'n', long, default_value = "3", value_parser = clap::value_parser!(u32).range(1..=50))]
+    pub runs: u32,
+
+    /// Prompt to send instead of the built-in one
+    #[arg(long)]
+    pub prompt: Option<String>,
+
+    /// Image size to request
+    #[arg(short, long, default_value = "1K", value_parser = PossibleValuesParser::new(Config::sizes()))]
+    pub size: String,
+
+    /// Output format (text, json, quiet, jsonl, template)
+    #[arg(short, long, value_enum, default_value_t = OutputFormat::Text)]
+    pub format: OutputFormat,
+}
+
+/// Results for one model
+#[derive(Debug, Serialize)]
+pub struct BenchRow {
+    pub model: String,
+    pub runs: u32,
+    pub failures: u32,
+    pub failure_rate: f64,
+    /// Latency percentiles of successful runs, in seconds
+    pub p50_secs: Option<f64>,
+    pub p90_secs: Option<f64>,
+    pub max_secs: Option<f64>,
+    /// Average estimated cost of a successful run
+    pub cost_per_run_usd: Option<f64>,
+    pub total_cost_usd: f64,
+    /// Most recent error message, if any run failed
+    pub last_error: Option<String>,
+}
+
+pub async fn run(args: BenchArgs, config: &Config, db: &Database) -> Result<()> {
+    output::check(args.format)?;
+
+    let models: Vec<String> = if args.models.is_empty() {
+        // Imagen models are served by `predict`, not the generateContent
+        // endpoint bench requests go to
+        Config::models()
+            .iter()
+            .filter(|m| !m.starts_with("imagen-"))
+            .map(|m| m.to_string())
+            .collect()
+    } else {
+        args.models.iter().map(|model| config.resolve_model(model)).collect()
+    };
+    let prompt = args.prompt.as_deref().unwrap_or(BENCH_PROMPT);
+
+    // Latency should reflect the model, not our own post-processing
+    let client = GeminiClient::from_config(config)?.without_watermark();
+
+    let pb = if args.format == OutputFormat::Text {
+        println!(
+            "{} {} model(s), {} run(s) each",
+            "Benchmarking".cyan().bold(),
+            models.len(),
+            args.runs
+        );
+        Some(BatchProgress::start(models.len() as u64 * args.runs as u64, String::new()))
+    } else {
+        None
+    };
+
+    // Runs are sequential so they don't compete for the same quota
+    let mut rows = Vec::new();
+    for model in &models {
+        if let Some(pb) = &pb {
+            pb.set_message(model.clone());
+        }
+
+        let params = GenerateParams::new(prompt)
+            .with_aspect_ratio("1:1")
+            .with_size(&args.size)
+            .with_model(model);
+
+        let mut latencies = Vec::new();
+        let mut costs = Vec::new();
+        let mut last_error = None;
+        for _ in 0..args.runs {
+            let started = Instant::now();
+            match run_once(&client, params.clone(), db).await {
+                Ok(job) => {
+                    latencies.push(started.elapsed().as_secs_f64());
+                    costs.extend(job.estimated_cost());
+                }
+                Err(e) => last_error = Some(e.to_string()),
+            }
+            if let Some(pb) = &pb {
+                pb.inc();
+            }
+        }
+
+        let failures = args.runs - latencies.len() as u32;
+        latencies.sort_by(|a, b| a.total_cmp(b));
+        let total_cost_usd = costs.iter().fold(0.0, |sum, cost| sum + cost);
+        rows.push(BenchRow {
+            model: model.clone(),
+            runs: args.runs,
+            failures,
+            failure_rate: failures as f64 / args.runs as f64,
+            p50_secs: percentile(&latencies, 50),
+            p90_secs: percentile(&latencies, 90),
+            max_secs: latencies.last().copied(),
+            cost_per_run_usd: (!costs.is_empty()).then(|| total_cost_usd / costs.len() as f64),
+            total_cost_usd,
+            last_error,
+        });
+    }
+
+    if let Some(pb) = &pb {
+        pb.finish();
+    }
+
+    let report = Report::new(&rows)?
+        .quiet(rows.iter().map(|row| &row.model))
+        .text(|| print_table(&rows));
+    output::write(args.format, report)
+}
+
+/// Generate once without downloading, recording the job so spend reports include it
+async fn run_once(client: &GeminiClient, params: GenerateParams, db: &Database) -> Result<Job> {
+    let mut job = Job::new_generate(params);
+    job.project = Some("bench".to_string());
+    db.insert_new_job(&mut job)?;
+
+    job.set_running(0);
+    db.update_job(&job)?;
+
+    let result = match client.generate_job(&mut job, db).await {
+        Ok(response) => client.process_response(&mut job, response),
+        Err(e) => Err(e),
+    };
+    if let Err(e) = &result {
+        job.set_failed(e.to_string());
+    }
+    db.update_job(&job)?;
+
+    result?;
+    Ok(job)
+}
+
+/// Nearest-rank percentile of sorted samples
+fn percentile(sorted: &[f64], pct: usize) -> Option<f64> {
+    if sorted.is_empty() {
+        return None;
+    }
+    let rank = (pct * sorted.len()).div_ceil(100).max(1);
+    Some(sorted[rank - 1])
+}
+
+fn print_table(rows: &[BenchRow]) {
+    println!(
+        "{:<28} {:>5} {:>7} {:>7} {:>7} {:>7} {:>10} {:>10}",
+        "MODEL".bold(),
+        "RUNS".bold(),
+        "FAILED".bold(),
+        "P50".bold(),
+        "P90".bold(),
+        "MAX".bold(),
+        "COST/RUN".bold(),
+        "TOTAL".bold()
+    );
+    println!("{}", "-".repeat(90));
+
+    let secs = |v: Option<f64>| v.map(|s| format!("{:.1}s", s)).unwrap_or_else(|| "-".to_string());
+    for row in rows {
+        let failed = format!("{:.0}%", row.failure_rate * 100.0);
+        let failed = if row.failures > 0 { failed.red() } else { failed.normal() };
+        println!(
+            "{:<28} {:>5} {:>7} {:>7} {:>7} {:>7} {:>10} {:>10}",
+            row.model,
+            row.runs,
+            failed,
+            secs(row.p50_secs),
+            secs(row.p90_secs),
+            secs(row.max_secs),
+            row.cost_per_run_usd.map(|c| format!("${:.4}", c)).unwrap_or_else(|| "-".to_string()),
+            format!("${:.4}", row.total_cost_usd)
+        );
+    }
+
+    let errors: Vec<&BenchRow> = rows.iter().filter(|row| row.last_error.is_some()).collect();
+    if !errors.is_empty() {
+        println!();
+        for row in errors {
+            println!(
+                "{} {}: {}",
+                "✗".red(),
+                row.model,
+                row.last_error.as_deref().unwrap_or_default()
+            );
+        }
+    }
+
+    let total: f64 = rows.iter().map(|row| row.total_cost_usd).sum();
+    println!();
+    println!("{} ${:.4} (estimated)", "Total spend:".cyan().bold(), total);
+    println!(
+        "{}",
+        "Bench jobs are tagged with project \"bench\": banana stats spend --group-by project".dimmed()
+    );
+}
@@ -1,32 +1,77 @@
-use anyhow::Result;
+use anyhow::{Context, Result};
 use clap::{Args, Subcommand};
 use colored::Colorize;
+use serde::Serialize;
+use serde_json::Value;
+use std::path::{Path, PathBuf};
 
-use crate::config::Config;
+use super::version;
+use crate::cli::output::{self, Report};
+use crate::cli::OutputFormat;
+use crate::config::{assignments, key_info, Config, KeySource, ProviderKind, ValueKind, AUTO_MODEL, KEYS, MASK};
+use crate::i18n::t;
 
 #[derive(Args)]
 pub struct ConfigArgs {
     #[command(subcommand)]
     pub command: Option<ConfigCommand>,
+
+    /// Output format without a subcommand (text, json, quiet, jsonl, template)
+    #[arg(short, long, value_enum, default_value_t = OutputFormat::Text)]
+    pub format: OutputFormat,
 }
 
 #[derive(Subcommand)]
 pub enum ConfigCommand {
     /// Show all configuration values
-    Show,
+    Show {
+        /// Mask secrets and personal values and add environment info, for bug reports
+        #[arg(long)]
+        redacted: bool,
+
+        /// Show secrets (API key, header values) instead of masking them
+        #[arg(long, conflicts_with = "redacted")]
+        reveal: bool,
+
+        /// Output format (text, json, quiet, jsonl, template)
+        #[arg(short, long, value_enum, default_value_t = OutputFormat::Text)]
+        format: OutputFormat,
+    },
 
     /// Get a specific configuration value
     Get {
         /// Config key (e.g., api.key, defaults.aspect_ratio)
         key: String,
+
+        /// Show secrets instead of masking them
+        #[arg(long)]
+        reveal: bool,
+
+        /// Output format (text, json, quiet, jsonl, template)
+        #[arg(short, long, value_enum, default_value_t = OutputFormat::Text)]
+        format: OutputFormat,
     },
 
-    /// Set a configuration value
+    /// Set configuration values (`KEY VALUE`, or any number of `KEY=VALUE`)
     Set {
-        /// Config key (e.g., api.key, defaults.aspect_ratio)
-        key: String,
-        /// Value to set
-        value: String,
+        /// `KEY VALUE`, or `KEY=VALUE` pairs (e.g., api.model=x defaults.size=2K)
+        #[arg(value_name = "KEY=VALUE", required_unless_present = "from_file")]
+        pairs: Vec<String>,
+
+        /// Also apply every value in a TOML file ("-" reads stdin)
+        #[arg(long, value_name = "FILE")]
+        from_file: Option<PathBuf>,
+
+        /// Output format (text, json, quiet, jsonl, template)
+        #[arg(short, long, value_enum, default_value_t = OutputFormat::Text)]
+        format: OutputFormat,
+    },
+
+    /// List every config key with its type, default, and current source
+    Keys {
+        /// Output format (text, json, quiet, jsonl, template)
+        #[arg(short, long, value_enum, default_value_t = OutputFormat::Text)]
+        format: OutputFormat,
     },
 
     /// Show the config file path
@@ -42,67 +87,465 @@ pub enum ConfigCommand {
 
 pub fn run(args: ConfigArgs, config: &mut Config) -> Result<()> {
     match args.command {
-        Some(ConfigCommand::Show) | None => show_config(config),
-        Some(ConfigCommand::Get { key }) => get_config(&key, config),
-        Some(ConfigCommand::Set { key, value }) => set_config(&key, &value, config),
+        Some(ConfigCommand::Show { redacted: true, format, .. }) => show_redacted(config, format),
+        Some(ConfigCommand::Show { reveal, format, .. }) => show(config, reveal, format),
+        None => show(config, false, args.format),
+        Some(ConfigCommand::Get { key, reveal, format }) => get_config(&key, reveal, format, config),
+        Some(ConfigCommand::Set { pairs, from_file, format }) => {
+            set_config(&pairs, from_file.as_deref(), format, config)
+        }
+        Some(ConfigCommand::Keys { format }) => list_keys(config, format),
         Some(ConfigCommand::Path) => show_path(config),
         Some(ConfigCommand::Reset { force }) => reset_config(force, config),
     }
 }
 
-fn show_config(config: &Config) -> Result<()> {
+fn show(config: &Config, reveal: bool, format: OutputFormat) -> Result<()> {
+    let report = Report::new(&config_value(config, false, reveal)?)?.text(|| show_config(config, reveal));
+    output::write(format, report)
+}
+
+fn show_config(config: &Config, reveal: bool) {
     println!("{}", "Configuration".cyan().bold());
     println!("{}", "=".repeat(50));
     println!();
 
     println!("[{}]", "api".yellow());
-    println!("  {} = {}", "key".bold(), config.get("api.key").unwrap_or_else(|| "(not set)".dimmed().to_string()));
+    let key = if reveal { config.get_revealed("api.key") } else { config.get("api.key") };
+    println!("  {} = {}", "key".bold(), key.unwrap_or_else(|| "(not set)".dimmed().to_string()));
     println!("  {} = {}", "model".bold(), config.api.model);
     println!("  {} = {}", "base_url".bold(), config.api.base_url);
+    if let Some(project) = &config.api.user_project {
+        println!("  {} = {}", "user_project".bold(), project);
+    }
+    println!("  {} = {}", "job_timeout_secs".bold(), config.api.job_timeout_secs);
+    if let Some(model) = &config.api.timeout_fallback_model {
+        println!("  {} = {}", "timeout_fallback_model".bold(), model);
+    }
+    println!("  {} = {}", "max_retries".bold(), config.api.max_retries);
+    println!("  {} = {}", "provider".bold(), config.api.provider.as_str());
     println!();
 
+    if !config.api.headers.is_empty() {
+        println!("[{}]", "api.headers".yellow());
+        for (name, value) in &config.api.headers {
+            println!("  {} = {}", name.bold(), if reveal { value.as_str() } else { MASK });
+        }
+        println!();
+    }
+
     println!("[{}]", "defaults".yellow());
     println!("  {} = {}", "aspect_ratio".bold(), config.defaults.aspect_ratio);
     println!("  {} = {}", "size".bold(), config.defaults.size);
     println!();
 
+    if config.api.provider == ProviderKind::Replicate || config.replicate.token.is_some() {
+        println!("[{}]", "replicate".yellow());
+        let token = if reveal { config.get_revealed("replicate.token") } else { config.get("replicate.token") };
+        println!("  {} = {}", "token".bold(), token.unwrap_or_else(|| "(not set)".dimmed().to_string()));
+        println!("  {} = {}", "model".bold(), config.replicate.model);
+        println!("  {} = {}", "base_url".bold(), config.replicate.base_url);
+        println!();
+    }
+
+    if config.api.provider == ProviderKind::OpenAi || config.openai.key.is_some() {
+        println!("[{}]", "openai".yellow());
+        let key = if reveal { config.get_revealed("openai.key") } else { config.get("openai.key") };
+        println!("  {} = {}", "key".bold(), key.unwrap_or_else(|| "(not set)".dimmed().to_string()));
+        println!("  {} = {}", "model".bold(), config.openai.model);
+        println!("  {} = {}", "base_url".bold(), config.openai.base_url);
+        println!();
+    }
+
+    if config.api.model == AUTO_MODEL {
+        println!("[{}]", "routing".yellow());
+        println!("  {} = {}", "draft_model".bold(), config.routing.draft_model);
+        println!("  {} = {}", "quality_model".bold(), config.routing.quality_model);
+        println!("  {} = {}", "quality_sizes".bold(), config.routing.quality_sizes.join(","));
+        println!("  {} = {}", "quality_edits".bold(), config.routing.quality_edits);
+        println!();
+    }
+
+    if !config.models.aliases.is_empty() {
+        println!("[{}]", "models.aliases".yellow());
+        for (alias, model) in &config.models.aliases {
+            println!("  {} = {}", alias.bold(), model);
+        }
+        println!();
+    }
+
+    for (model, overrides) in &config.model_defaults {
+        println!("[{}]", format!("model-defaults.\"{}\"", model).yellow());
+        if let Some(aspect_ratio) = &overrides.aspect_ratio {
+            println!("  {} = {}", "aspect_ratio".bold(), aspect_ratio);
+        }
+        if let Some(size) = &overrides.size {
+            println!("  {} = {}", "size".bold(), size);
+        }
+        println!();
+    }
+
     println!("[{}]", "output".yellow());
     println!("  {} = {}", "directory".bold(), config.output.directory);
     println!("  {} = {}", "auto_download".bold(), config.output.auto_download);
     println!("  {} = {}", "display".bold(), config.output.display.as_str());
+    println!("  {} = {}", "srgb".bold(), config.output.srgb);
+    println!("  {} = {}", "retain_data".bold(), config.output.retain_data);
+    println!("  {} = {}", "date_format".bold(), config.output.date_format);
     println!();
 
+    if !config.output.exif.is_empty() {
+        println!("[{}]", "output.exif".yellow());
+        let exif = &config.output.exif;
+        for (name, value) in [("artist", &exif.artist), ("copyright", &exif.copyright), ("software", &exif.software)] {
+            if let Some(value) = value {
+                println!("  {} = {}", name.bold(), value);
+            }
+        }
+        println!();
+    }
+
+    if let Some(path) = &config.output.watermark.path {
+        let watermark = &config.output.watermark;
+        println!("[{}]", "output.watermark".yellow());
+        println!("  {} = {}", "path".bold(), path);
+        println!("  {} = {}", "position".bold(), watermark.position.as_str());
+        println!("  {} = {}", "opacity".bold(), watermark.opacity);
+        println!("  {} = {}", "scale".bold(), watermark.scale);
+        println!();
+    }
+
     println!("[{}]", "tui".yellow());
     println!("  {} = {}", "show_images".bold(), config.tui.show_images);
     println!("  {} = {}", "theme".bold(), config.tui.theme);
+    println!("  {} = {}", "group_by_day".bold(), config.tui.group_by_day);
+    println!("  {} = {}", "inline_height".bold(), config.tui.inline_height);
+    println!();
+
+    println!("[{}]", "ui".yellow());
+    println!("  {} = {}", "language".bold(), config.ui.language);
+    println!("  {} = {}", "animations".bold(), config.ui.animations);
+    println!();
+
+    println!("[{}]", "history".yellow());
+    println!("  {} = {}", "trash_days".bold(), config.history.trash_days);
+    println!("  {} = {}", "tmp_hours".bold(), config.history.tmp_hours);
+    println!(
+        "  {} = {}",
+        "auto_prune_failed_after".bold(),
+        config
+            .history
+            .auto_prune_failed_after
+            .clone()
+            .unwrap_or_else(|| "(not set)".dimmed().to_string())
+    );
+    println!("  {} = {}", "max_jobs".bold(), config.history.max_jobs);
+    println!("  {} = {}", "max_disk_mb".bold(), config.history.max_disk_mb);
+    println!();
+
+    println!("[{}]", "hooks".yellow());
+    println!(
+        "  {} = {}",
+        "upscaler".bold(),
+        config.hooks.upscaler.clone().unwrap_or_else(|| "(not set)".dimmed().to_string())
+    );
     println!();
 
     println!("{}", format!("Config file: {}", config.config_path.display()).dimmed());
 
-    Ok(())
 }
 
-fn get_config(key: &str, config: &Config) -> Result<()> {
-    match config.get(key) {
+/// Shareable config snapshot for bug reports
+#[derive(Debug, Serialize)]
+struct RedactedSnapshot {
+    environment: Environment,
+    config: Value,
+}
+
+#[derive(Debug, Serialize)]
+struct Environment {
+    version: &'static str,
+    os: &'static str,
+    arch: &'static str,
+    terminal: Option<String>,
+    image_protocols: Vec<&'static str>,
+}
+
+fn show_redacted(config: &Config, format: OutputFormat) -> Result<()> {
+    let info = version::collect(config)?;
+    let snapshot = RedactedSnapshot {
+        environment: Environment {
+            version: info.version,
+            os: info.os,
+            arch: info.arch,
+            terminal: std::env::var("TERM_PROGRAM")
+                .or_else(|_| std::env::var("TERM"))
+                .ok(),
+            image_protocols: info.features.image_protocols,
+        },
+        config: config_value(config, true, false)?,
+    };
+
+    // Plain text so it can be pasted into an issue as-is
+    let toml = toml::to_string_pretty(&snapshot.config)?;
+    let env = &snapshot.environment;
+    let report = Report::new(&snapshot)?.text(|| {
+        println!("banana {} ({}/{})", env.version, env.os, env.arch);
+        println!("terminal: {}", env.terminal.as_deref().unwrap_or("unknown"));
+        println!("image protocols: {}", env.image_protocols.join(", "));
+        println!();
+        print!("{}", toml);
+    });
+    output::write(format, report)
+}
+
+/// The config as JSON with the API key and header values masked (unless `reveal`).
+///
+/// `redacted` also masks personal values and shortens paths under the home
+/// directory to `~`.
+fn config_value(config: &Config, redacted: bool, reveal: bool) -> Result<Value> {
+    // Round-trip through TOML so unset values are dropped and f32s stay short
+    let table: toml::Table = toml::from_str(&toml::to_string(config)?)?;
+    let mut value = serde_json::to_value(table)?;
+
+    if let Some(api) = value.get_mut("api").filter(|_| !reveal) {
+        mask(api, "key");
+        if let Some(Value::Object(headers)) = api.get_mut("headers") {
+            for header in headers.values_mut() {
+                *header = Value::from(MASK);
+            }
+        }
+        if redacted {
+            mask(api, "user_project");
+        }
+    }
+    if let Some(replicate) = value.get_mut("replicate").filter(|_| !reveal) {
+        mask(replicate, "token");
+    }
+    if let Some(openai) = value.get_mut("openai").filter(|_| !reveal) {
+        mask(openai, "key");
+    }
+
+    if redacted {
+        if let Some(exif) = value.pointer_mut("/output/exif") {
+            mask(exif, "artist");
+            mask(exif, "copyright");
+        }
+        if let Some(home) = directories::BaseDirs::new().map(|d| d.home_dir().display().to_string()) {
+            shorten_home(&mut value, &home);
+        }
+    }
+
+    Ok(value)
+}
+
+fn mask(table: &mut Value, field: &str) {
+    if let Some(value) = table.get_mut(field).filter(|v| !v.is_null()) {
+        *value = Value::from(MASK);
+    }
+}
+
+fn shorten_home(value: &mut Value, home: &str) {
+    match value {
+        Value::String(s) => {
+            if let Some(rest) = s.strip_prefix(home) {
+                *s = format!("~{}", rest);
+            }
+        }
+        Value::Object(map) => map.values_mut().for_each(|v| shorten_home(v, home)),
+        Value::Array(items) => items.iter_mut().for_each(|v| shorten_home(v, home)),
+        _ => {}
+    }
+}
+
+/// `config get` output for every format but text
+#[derive(Debug, Serialize)]
+struct KeyValue<'a> {
+    key: &'a str,
+    /// Typed per the key's kind; null when unset
+    value: Value,
+    source: KeySource,
+}
+
+fn get_config(key: &str, reveal: bool, format: OutputFormat, config: &Config) -> Result<()> {
+    let value = if reveal { config.get_revealed(key) } else { config.get(key) };
+    let known = value.is_some() || key_info(key).is_some();
+
+    if !known && format != OutputFormat::Text {
+        anyhow::bail!("Unknown config key '{}'", key);
+    }
+
+    let key_value = KeyValue {
+        key,
+        value: value.as_deref().map_or(Value::Null, |v| typed_value(key, v)),
+        source: config.sources().get(key),
+    };
+    let report = Report::new(&key_value)?.quiet(value.as_deref()).text(|| match &value {
         Some(value) => println!("{}", value),
+        None if known => {}
         None => {
-            eprintln!("{}: Unknown config key '{}'", "Error".red().bold(), key);
+            eprintln!("{}: Unknown config key '{}'", t("Error").red().bold(), key);
             eprintln!();
             eprintln!("Available keys:");
             for k in Config::keys() {
                 eprintln!("  {}", k);
             }
         }
+    });
+    output::write(format, report)
+}
+
+/// A config value as JSON of its key's type (strings for masked or unregistered keys)
+fn typed_value(key: &str, value: &str) -> Value {
+    let typed = match key_info(key).map(|info| info.kind) {
+        Some(ValueKind::Bool) => value.parse::<bool>().ok().map(Value::from),
+        Some(ValueKind::Integer) => value.parse::<i64>().ok().map(Value::from),
+        Some(ValueKind::Float) => value.parse::<f64>().ok().map(Value::from),
+        _ => None,
+    };
+    typed.unwrap_or_else(|| Value::from(value))
+}
+
+fn set_config(pairs: &[String], from_file: Option<&Path>, format: OutputFormat, config: &mut Config) -> Result<()> {
+    output::check(format)?;
+    let mut changes = match from_file {
+        Some(path) => {
+            let content = if path == Path::new("-") {
+                std::io::read_to_string(std::io::stdin()).context("Failed to read stdin")?
+            } else {
+                std::fs::read_to_string(path)
+                    .with_context(|| format!("Failed to read {}", path.display()))?
+            };
+            let table: toml::Table = content.parse().context("Invalid TOML")?;
+            assignments(&table)?
+        }
+        None => Vec::new(),
+    };
+    changes.extend(parse_pairs(pairs)?);
+
+    // Apply everything before saving so one bad value changes nothing, on
+    // top of the file as it is now in case another process just saved it
+    config.update(|config| {
+        for (key, value) in &changes {
+            config.set(key, value).with_context(|| format!("Failed to set {}", key))?;
+        }
+        Ok(())
+    })?;
+
+    // Report values as `config get` would, masking secrets
+    let sources = config.sources();
+    let values: Vec<KeyValue> = changes
+        .iter()
+        .map(|(key, _)| KeyValue {
+            key,
+            value: config.get(key).as_deref().map_or(Value::Null, |v| typed_value(key, v)),
+            source: sources.get(key),
+        })
+        .collect();
+    let report = Report::new(&values)?
+        .quiet(changes.iter().map(|(key, _)| key))
+        .text(|| {
+            for (key, value) in &changes {
+                println!("{} Set {} = {}", "✓".green(), key.cyan(), value);
+            }
+        });
+    output::write(format, report)
+}
+
+/// `KEY VALUE` (the classic form) or any number of `KEY=VALUE` pairs
+fn parse_pairs(pairs: &[String]) -> Result<Vec<(String, String)>> {
+    if let [key, value] = pairs {
+        if !key.contains('=') {
+            return Ok(vec![(key.clone(), value.clone())]);
+        }
     }
-    Ok(())
+    pairs
+        .iter()
+        .map(|pair| match pair.split_once('=') {
+            Some((key, value)) => Ok((key.trim().to_string(), value.to_string())),
+            None => anyhow::bail!("Expected KEY=VALUE, got '{}'", pair),
+        })
+        .collect()
 }
 
-fn set_config(key: &str, value: &str, config: &mut Config) -> Result<()> {
-    config.set(key, value)?;
-    config.save()?;
+/// A row of `config keys`
+#[derive(Debug, Serialize)]
+struct KeyRow {
+    key: &'static str,
+    #[serde(rename = "type")]
+    kind: &'static str,
+    description: &'static str,
+    #[serde(skip_serializing_if = "<[_]>::is_empty")]
+    options: &'static [&'static str],
+    default: Option<String>,
+    value: Option<String>,
+    source: KeySource,
+}
+
+fn list_keys(config: &Config, format: OutputFormat) -> Result<()> {
+    let defaults = Config::default();
+    let sources = config.sources();
+    let rows: Vec<KeyRow> = KEYS
+        .iter()
+        .map(|info| KeyRow {
+            key: info.key,
+            kind: info.kind.as_str(),
+            description: info.description,
+            options: info.options,
+            default: defaults.get(info.key),
+            value: config.get(info.key),
+            source: sources.get(info.key),
+        })
+        .collect();
+
+    let report = Report::new(&rows)?
+        .quiet(rows.iter().map(|row| row.key))
+        .text(|| print_keys(&rows));
+    output::write(format, report)
+}
+
+/// Print the key table with each key's description
+fn print_keys(rows: &[KeyRow]) {
+    println!(
+        "{:<28} {:<8} {:<28} {}",
+        "KEY".bold(),
+        "TYPE".bold(),
+        "DEFAULT".bold(),
+        "SOURCE".bold()
+    );
+    for row in rows {
+        let source = match row.source {
+            KeySource::Default => row.source.as_str().dimmed(),
+            _ => row.source.as_str().green(),
+        };
+        println!(
+            "{:<28} {:<8} {:<28} {}",
+            row.key.cyan(),
+            row.kind,
+            row.default.as_deref().unwrap_or("-"),
+            source
+        );
+
+        let mut description = row.description.to_string();
+        if !row.options.is_empty() {
+            description.push_str(&format!(" [{}]", row.options.join(", ")));
+        }
+        println!("  {}", description.dimmed());
+    }
+    println!();
+    println!(
+        "{}",
+        "api.headers.<name> sets an extra request header (empty value removes it)".dimmed()
+    );
+    println!(
+        "{}",
+        "models.aliases.<alias> maps a short name to a model (empty value removes it)".dimmed()
+    );
+    println!(
+        "{}",
+        "model-defaults.<model>.aspect_ratio|size override [defaults] for one model".dimmed()
+    );
 
-    println!("{} Set {} = {}", "✓".green(), key.cyan(), value);
-    Ok(())
 }
 
 fn show_path(config: &Config) -> Result<()> {
@@ -114,7 +557,7 @@ fn reset_config(force: bool, config: &mut Config) -> Result<()> {
     if !force {
         eprintln!(
             "{}: This will reset all configuration to defaults. Use --force to confirm.",
-            "Warning".yellow().bold()
+            t("Warning").yellow().bold()
         );
         return Ok(());
     }
@@ -0,0 +1,182 @@
+use anyhow::{Context, Result};
+use clap::Args;
+use colored::Colorize;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+use crate::core::{BananaError, Job};
+use crate::db::Database;
+use crate::i18n::t;
+use crate::platform;
+
+#[derive(Args)]
+pub struct CpArgs {
+    /// Job IDs to copy (omit with --all)
+    #[arg(required_unless_present = "all")]
+    pub job_ids: Vec<String>,
+
+    /// Copy every job
+    #[arg(long, conflicts_with = "job_ids")]
+    pub all: bool,
+
+    /// Source data directory or database file (defaults to this machine's)
+    #[arg(long)]
+    pub from: Option<PathBuf>,
+
+    /// Destination data directory or database file (defaults to this machine's)
+    #[arg(long)]
+    pub to: Option<PathBuf>,
+
+    /// Where to copy image files (defaults to `images/` next to the destination database)
+    #[arg(long)]
+    pub images_dir: Option<PathBuf>,
+
+    /// Overwrite jobs that already exist in the destination
+    #[arg(short, long)]
+    pub force: bool,
+}
+
+pub fn run(args: CpArgs) -> Result<()> {
+    // Checked before resolving, which creates missing data directories
+    if let Some(from) = &args.from {
+        if !from.exists() {
+            anyhow::bail!("Source not found: {}", from.display());
+        }
+    }
+    let from_path = resolve_db_path(args.from.as_deref())?;
+    let to_path = resolve_db_path(args.to.as_deref())?;
+
+    if !from_path.exists() {
+        anyhow::bail!("Source database not found: {}", from_path.display());
+    }
+    if platform::canonicalize(&from_path).ok() == platform::canonicalize(&to_path).ok() {
+        anyhow::bail!("Source and destination are the same database");
+    }
+
+    let source = Database::open_at(&from_path)?;
+    let dest = Database::open_at(&to_path)?;
+
+    let images_dir = match args.images_dir {
+        Some(dir) => dir,
+        None => to_path
+            .parent()
+            .map(|p| p.join("images"))
+            .context("Invalid destination path")?,
+    };
+
+    let jobs = if args.all {
+        source.list_jobs(u32::MAX, None)?
+    } else {
+        let mut jobs = Vec::new();
+        for id in &args.job_ids {
+            match source.get_job(id)? {
+                Some(job) => jobs.push(job),
+                None => eprintln!("{}: Job '{}' not found in source", t("Warning").yellow().bold(), id),
+            }
+        }
+        jobs
+    };
+
+    let mut copied = 0;
+    let mut skipped = 0;
+
+    for mut job in jobs {
+        let existing = dest.get_job(&job.id)?;
+        if existing.is_some() && !args.force {
+            skipped += 1;
+            continue;
+        }
+        // A trashed job is invisible to get_job but still holds its ID
+        if existing.is_none() && dest.is_trashed(&job.id)? {
+            return Err(BananaError::JobInTrash(job.id).into());
+        }
+
+        copy_images(&mut job, &images_dir, existing.as_ref())?;
+
+        if existing.is_some() {
+            dest.update_job(&job)?;
+        } else {
+            dest.insert_job(&job)?;
+        }
+        copied += 1;
+    }
+
+    println!(
+        "{} Copied {} job(s) to {}",
+        "✓".green(),
+        copied,
+        platform::display_path(&to_path)
+    );
+    if skipped > 0 {
+        println!(
+            "{}",
+            format!("Skipped {} job(s) already present. Use --force to overwrite.", skipped).dimmed()
+        );
+    }
+
+    Ok(())
+}
+
+/// Accept either a data directory (containing jobs.db) or a database file
+fn resolve_db_path(path: Option<&Path>) -> Result<PathBuf> {
+    match path {
+        None => Ok(Database::db_path()?),
+        Some(p) if p.is_dir() => Ok(p.join("jobs.db")),
+        Some(p) if p.extension().is_some() => Ok(p.to_path_buf()),
+        Some(p) => {
+            std::fs::create_dir_all(p)?;
+            Ok(p.join("jobs.db"))
+        }
+    }
+}
+
+/// Copy a job's image files and rewrite their paths to the new location.
+///
+/// Existing files are never overwritten, except those of the destination
+/// job being replaced (`replaced`); other clashes get a numbered suffix.
+fn copy_images(job: &mut Job, images_dir: &Path, replaced: Option<&Job>) -> Result<()> {
+    let replaceable: HashSet<PathBuf> = replaced
+        .iter()
+        .flat_map(|job| &job.images)
+        .filter_map(|image| image.path.as_deref().map(PathBuf::from))
+        .collect();
+
+    for image in &mut job.images {
+        let Some(path) = &image.path else {
+            continue;
+        };
+
+        let src = PathBuf::from(path);
+        if !src.exists() {
+            tracing::warn!("Image file missing, keeping original path: {}", path);
+            continue;
+        }
+
+        std::fs::create_dir_all(images_dir)?;
+        let dst = free_path(images_dir, &src, &replaceable)?;
+        // Copying a file onto itself would truncate it
+        if platform::canonicalize(&src).ok() == platform::canonicalize(&dst).ok() {
+            continue;
+        }
+        std::fs::copy(&src, &dst)
+            .with_context(|| format!("Failed to copy {}", src.display()))?;
+
+        image.path = Some(platform::display_path(&dst));
+    }
+    Ok(())
+}
+
+/// A path in `dir` for a copy of `src` that doesn't clobber an existing file
+/// (unless it is one of `replaceable`), adding `-2`, `-3`, ... to the name
+fn free_path(dir: &Path, src: &Path, replaceable: &HashSet<PathBuf>) -> Result<PathBuf> {
+    let stem = src.file_stem().context("Invalid image path")?.to_string_lossy();
+    let ext = src.extension().map(|e| format!(".{}", e.to_string_lossy())).unwrap_or_default();
+
+    let mut dst = dir.join(format!("{}{}", stem, ext));
+    let mut n = 2;
+    while dst.exists() && !replaceable.contains(&dst) {
+        dst = dir.join(format!("{}-{}{}", stem, n, ext));
+        n += 1;
+    }
+    Ok(dst)
+}
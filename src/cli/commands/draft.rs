@@ -0,0 +1,331 @@
+use anyhow::{Context, Result};
+use clap::builder::PossibleValuesParser;
+use clap::Args;
+use colored::Colorize;
+use futures_util::stream::{self, StreamExt};
+use serde::Serialize;
+use std::io::{self, IsTerminal, Write};
+use std::path::{Path, PathBuf};
+
+use super::pipeline;
+use crate::api::GeminiClient;
+use crate::cli::output::{self, Report};
+use crate::cli::progress::{BatchProgress, Progress};
+use crate::cli::OutputFormat;
+use crate::config::{Config, DisplayMode};
+use crate::core::job::JobWithArtifacts;
+use crate::core::{prompt, GenerateParams, Job};
+use crate::db::Database;
+use crate::i18n::t;
+use crate::imaging;
+use crate::platform;
+
+/// Drafts are rendered at the cheapest size
+const DRAFT_SIZE: &str = "1K";
+
+/// Instruction sent with the chosen draft, ahead of the original prompt
+const REFINE_INSTRUCTION: &str = "Re-render this draft at full quality. Keep its composition, \
+framing, and subjects exactly; improve detail, lighting, and finish.";
+
+#[derive(Args)]
+pub struct DraftArgs {
+    /// The prompt describing the image to generate
+    #[arg(required = true)]
+    pub prompt: String,
+
+    /// Number of drafts to generate
+    #[arg(short = 'n', long, default_value = "4", value_parser = clap::value_parser!(u8).range(1..=8))]
+    pub count: u8,
+
+    /// Aspect ratio (1:1, 2:3, 3:2, 3:4, 4:3, 4:5, 5:4, 9:16, 16:9, 21:9)
+    #[arg(short, long, alias = "ar", value_parser = PossibleValuesParser::new(Config::aspect_ratios()))]
+    pub aspect_ratio: Option<String>,
+
+    /// Model for the drafts, or an alias (default: routing.draft_model)
+    #[arg(long)]
+    pub draft_model: Option<String>,
+
+    /// Pick one draft and re-render it at high quality as a linked job
+    #[arg(long)]
+    pub refine_best: bool,
+
+    /// Draft to refine (1-based) instead of asking
+    #[arg(long, requires = "refine_best")]
+    pub pick: Option<usize>,
+
+    /// Model for the refined render, or an alias (default: routing.quality_model)
+    #[arg(short, long)]
+    pub model: Option<String>,
+
+    /// Size of the refined render
+    #[arg(long, default_value = "4K", value_parser = PossibleValuesParser::new(["2K", "4K"]))]
+    pub to: String,
+
+    /// Output directory for downloaded images
+    #[arg(short, long)]
+    pub output: Option<PathBuf>,
+
+    /// Maximum number of drafts generating at once
+    #[arg(short = 'j', long, default_value = "4", value_parser = clap::value_parser!(u8).range(1..=16))]
+    pub concurrency: u8,
+
+    /// Substitute {KEY} placeholders in the prompt (repeatable)
+    #[arg(long = "var", value_name = "KEY=VALUE", value_parser = prompt::parse_var)]
+    pub vars: Vec<(String, String)>,
+
+    /// Project to attribute these jobs to (for spend reports)
+    #[arg(long)]
+    pub project: Option<String>,
+
+    /// Skip the configured watermark
+    #[arg(long)]
+    pub no_watermark: bool,
+
+    /// Output format (text, json, quiet, jsonl, template)
+    #[arg(short, long, value_enum, default_value_t = OutputFormat::Text)]
+    pub format: OutputFormat,
+}
+
+/// Drafts and, with `--refine-best`, the refined render
+#[derive(Debug, Serialize)]
+struct DraftResult<'a> {
+    drafts: Vec<JobWithArtifacts<'a>>,
+    /// 1-based index of the refined draft
+    picked: Option<usize>,
+    refined: Option<JobWithArtifacts<'a>>,
+}
+
+pub async fn run(args: DraftArgs, config: &Config, db: &Database) -> Result<()> {
+    output::check(args.format)?;
+
+    if let Some(pick) = args.pick.filter(|pick| !(1..=args.count as usize).contains(pick)) {
+        anyhow::bail!("--pick {} is out of range; there will be {} draft(s)", pick, args.count);
+    }
+
+    let prompt = prompt::render(&args.prompt, &args.vars)?;
+    let draft_model = config.resolve_model(args.draft_model.as_deref().unwrap_or(&config.routing.draft_model));
+    let quality_model = config.resolve_model(args.model.as_deref().unwrap_or(&config.routing.quality_model));
+    let aspect_ratio = args
+        .aspect_ratio
+        .clone()
+        .unwrap_or_else(|| config.defaults_for(&quality_model).aspect_ratio);
+
+    let mut client = GeminiClient::from_config(config)?;
+    if args.no_watermark {
+        client = client.without_watermark();
+    }
+
+    let draft_params = GenerateParams::new(&prompt)
+        .with_aspect_ratio(&aspect_ratio)
+        .with_size(DRAFT_SIZE)
+        .with_model(&draft_model);
+    client.preflight(&draft_params).await?;
+
+    let output_dir = args
+        .output
+        .clone()
+        .unwrap_or_else(|| config.output.dir());
+
+    let pb = if args.format == OutputFormat::Text {
+        println!("{} {} draft(s) with {}", "Drafting".cyan().bold(), args.count, draft_model);
+        Some(BatchProgress::start(args.count as u64, prompt.clone()))
+    } else {
+        None
+    };
+
+    let mut drafts: Vec<(usize, Job)> = stream::iter(0..args.count as usize)
+        .map(|index| {
+            let mut job = Job::new_generate(draft_params.clone());
+            job.project = args.project.clone();
+            let client = &client;
+            let output_dir = &output_dir;
+            let pb = &pb;
+            async move {
+                if let Err(e) = run_job(client, &mut job, output_dir, db).await {
+                    job.set_failed(e.to_string());
+                    if let Err(e) = db.update_job(&job) {
+                        tracing::warn!("Failed to record {}: {}", job.id, e);
+                    }
+                }
+                if let Some(pb) = pb {
+                    pb.inc();
+                }
+                (index, job)
+            }
+        })
+        .buffer_unordered(args.concurrency as usize)
+        .collect()
+        .await;
+    drafts.sort_by_key(|(index, _)| *index);
+    let drafts: Vec<Job> = drafts.into_iter().map(|(_, job)| job).collect();
+
+    if let Some(pb) = &pb {
+        pb.finish();
+    }
+    if args.format == OutputFormat::Text {
+        print_drafts(&drafts, config);
+    }
+
+    if !drafts.iter().any(|job| job.status.is_success()) {
+        anyhow::bail!("All {} draft(s) failed", drafts.len());
+    }
+
+    let mut picked = None;
+    let mut refined = None;
+    if args.refine_best {
+        let index = match args.pick {
+            Some(index) => index,
+            None => ask_pick(&drafts)?,
+        };
+        let draft = &drafts[index - 1];
+        if !draft.status.is_success() {
+            anyhow::bail!("Draft {} failed; pick one of the finished drafts", index);
+        }
+
+        let mut job = refine_job(draft, &quality_model, &args.to, args.project.clone()).await?;
+        let progress = Progress::start(
+            args.format,
+            false,
+            &job.id,
+            format!("Refining draft {} with {} at {}...", index, quality_model, args.to),
+        );
+        if let Err(e) = run_job(&client, &mut job, &output_dir, db).await {
+            job.set_failed(e.to_string());
+            db.update_job(&job)?;
+            progress.fail("Refinement failed", &e.to_string());
+            let report = Report::new(&job.with_artifacts())?.text(|| eprintln!("{}: {}", t("Error").red().bold(), e));
+            output::write(args.format, report)?;
+            return Err(e);
+        }
+        progress.finish(format!("Refined draft {}", index));
+        picked = Some(index);
+        refined = Some(job);
+    }
+
+    let result = DraftResult {
+        drafts: drafts.iter().map(Job::with_artifacts).collect(),
+        picked,
+        refined: refined.as_ref().map(Job::with_artifacts),
+    };
+    // Quiet output lists the refined image, or every draft without one
+    let jobs = match &refined {
+        Some(job) => std::slice::from_ref(job),
+        None => drafts.as_slice(),
+    };
+    let report = Report::new(&result)?
+        .records(result.drafts.iter().chain(&result.refined))?
+        .quiet(jobs.iter().flat_map(|job| &job.images).filter_map(|img| img.path.as_ref()))
+        .text(|| match &refined {
+            Some(job) => print_refined(job, config),
+            None => println!(
+                "{}",
+                "Add --refine-best to pick a draft and render it at full quality".dimmed()
+            ),
+        });
+    output::write(args.format, report)?;
+
+    Ok(())
+}
+
+/// Run a job to completion and download its images
+async fn run_job(client: &GeminiClient, job: &mut Job, output_dir: &Path, db: &Database) -> Result<()> {
+    db.insert_new_job(job)?;
+    job.set_running(0);
+    db.update_job(job)?;
+
+    let response = client.generate_job(job, db).await?;
+    client.process_response(job, response)?;
+    client.download_images(job, output_dir).await?;
+    db.update_job(job)?;
+    Ok(())
+}
+
+/// The high-quality job for `draft`, using its image as the composition reference
+async fn refine_job(draft: &Job, model: &str, size: &str, project: Option<String>) -> Result<Job> {
+    let path = draft
+        .primary_image()
+        .and_then(|img| img.path.clone())
+        .with_context(|| format!("Draft '{}' has no downloaded image", draft.id))?;
+    let (base64_data, mime_type) = imaging::load_reference(Path::new(&path)).await?;
+
+    let params = GenerateParams::new(format!("{}\n\n{}", REFINE_INSTRUCTION, draft.params.prompt))
+        .with_aspect_ratio(&draft.params.aspect_ratio)
+        .with_size(size)
+        .with_model(model)
+        .with_reference_image(base64_data, mime_type);
+
+    let mut job = Job::new_edit(params, path);
+    job.parent_id = Some(draft.id.clone());
+    job.project = project;
+    Ok(job)
+}
+
+/// Ask which draft to refine
+fn ask_pick(drafts: &[Job]) -> Result<usize> {
+    if !io::stdin().is_terminal() {
+        anyhow::bail!("Pass --pick <N> to choose a draft when not running in a terminal");
+    }
+
+    let mut line = String::new();
+    loop {
+        eprint!("{} [1-{}] ", "Refine which draft?".bold(), drafts.len());
+        io::stderr().flush()?;
+
+        line.clear();
+        if io::stdin().read_line(&mut line)? == 0 {
+            anyhow::bail!("Input closed");
+        }
+        match line.trim().parse::<usize>() {
+            Ok(index) if drafts.get(index.wrapping_sub(1)).is_some_and(|job| job.status.is_success()) => {
+                return Ok(index)
+            }
+            _ => eprintln!("{}", "Enter the number of a finished draft".dimmed()),
+        }
+    }
+}
+
+/// List the drafts by number, previewing each as the display mode allows
+fn print_drafts(drafts: &[Job], config: &Config) {
+    println!();
+    for (i, job) in drafts.iter().enumerate() {
+        let label = format!("[{}]", i + 1).yellow().bold();
+        let path = job.primary_image().and_then(|img| img.path.as_deref());
+        match (job.status.is_success(), path) {
+            (true, Some(path)) => {
+                println!("{} {} {}", label, path, job.id.dimmed());
+                match config.output.display {
+                    DisplayMode::Terminal => pipeline::display_image_terminal(path),
+                    DisplayMode::Viewer => {
+                        if let Err(e) = platform::open_in_viewer(Path::new(path)) {
+                            tracing::warn!("{}", e);
+                        }
+                    }
+                    DisplayMode::None => {}
+                }
+            }
+            _ => println!("{} {} {}", label, "✗".red(), job.status.to_string().red()),
+        }
+    }
+    println!();
+}
+
+/// Print the refined job
+fn print_refined(job: &Job, config: &Config) {
+    println!();
+    println!("{}: {}", "Job ID".cyan().bold(), job.id);
+    if let Some(parent) = &job.parent_id {
+        println!("{}: {}", "Draft Job".cyan().bold(), parent);
+    }
+    println!("{}: {}", "Model".cyan().bold(), job.model);
+    println!("{}: {}", "Size".cyan().bold(), job.params.size);
+    println!();
+    println!("{}:", "Refined Image".cyan().bold());
+    let paths: Vec<&str> = job.images.iter().filter_map(|img| img.path.as_deref()).collect();
+    for path in &paths {
+        println!("  {}", path);
+    }
+    if let (Some(first), DisplayMode::Terminal) = (paths.first(), config.output.display) {
+        println!();
+        pipeline::display_image_terminal(first);
+    }
+}
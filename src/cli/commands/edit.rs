@@ -1,206 +1,358 @@
 use anyhow::{Context, Result};
+use clap::builder::PossibleValuesParser;
 use clap::Args;
 use colored::Colorize;
-use indicatif::{ProgressBar, ProgressStyle};
-use std::path::PathBuf;
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
 use std::time::Duration;
 
-use crate::api::{load_image_base64, GeminiClient};
-use crate::config::Config;
-use crate::core::GenerateParams;
+use super::pipeline::{self, JobOptions};
+use crate::cli::destination::OutputFlags;
+use crate::cli::OutputFormat;
+use crate::config::{Config, ConfigWatcher};
+use crate::core::{params, prompt, ContextTurn, FitMode, GenerateParams, Modality, PersonGeneration};
 use crate::core::Job;
 use crate::db::Database;
+use crate::i18n::t;
+use crate::imaging;
+use crate::platform;
+
+/// How often the watch directory is scanned for new images
+const WATCH_POLL_INTERVAL: Duration = Duration::from_secs(2);
 
 #[derive(Args)]
 pub struct EditArgs {
-    /// Path to the image to edit
-    #[arg(required = true)]
-    pub image: PathBuf,
+    /// Path to the image to edit (omit when using --watch)
+    #[arg(required_unless_present = "watch")]
+    pub image: Option<PathBuf>,
 
     /// The edit instruction (e.g., "make the sky blue", "add a hat")
-    #[arg(required = true)]
-    pub prompt: String,
+    pub prompt: Option<String>,
+
+    /// Watch a directory and apply the edit to every new image dropped into it
+    #[arg(long, value_name = "DIR")]
+    pub watch: Option<PathBuf>,
+
+    /// Send the source job's prompt and image as prior turns (when editing a banana output)
+    #[arg(long)]
+    pub with_context: bool,
 
     /// Aspect ratio for the output
-    #[arg(short, long, alias = "ar")]
+    #[arg(short, long, alias = "ar", value_parser = PossibleValuesParser::new(Config::aspect_ratios()))]
     pub aspect_ratio: Option<String>,
 
+    /// Reshape the source to the output aspect ratio before upload (crop, pad, stretch)
+    #[arg(long, value_enum)]
+    pub fit: Option<FitMode>,
+
+    /// Frame of an animated GIF/WebP source to edit (0 = first)
+    #[arg(long, value_name = "N")]
+    pub frame: Option<usize>,
+
     /// Image size (1K, 2K, 4K)
-    #[arg(short, long)]
+    #[arg(short, long, value_parser = PossibleValuesParser::new(Config::sizes()))]
     pub size: Option<String>,
 
-    /// Model to use
+    /// Model to use, an alias such as pro or flash, or "auto" (see routing.*)
     #[arg(short, long)]
     pub model: Option<String>,
 
-    /// Output directory for edited images
-    #[arg(short, long)]
+    /// Output directory for edited images, or - to write the image to stdout
+    #[arg(short, long, conflicts_with = "no_download")]
     pub output: Option<PathBuf>,
 
     /// Don't download images automatically
     #[arg(long)]
     pub no_download: bool,
 
-    /// Output format (text, json, quiet)
-    #[arg(short, long, default_value = "text")]
-    pub format: String,
-}
+    /// Save into a fresh temporary directory that is deleted later (history.tmp_hours)
+    #[arg(long, conflicts_with_all = ["output", "no_download"])]
+    pub tmp: bool,
 
-pub async fn run(args: EditArgs, config: &Config, db: &Database) -> Result<()> {
-    // Load the source image
-    let image_path = args.image.canonicalize()
-        .context("Image file not found")?;
+    /// Open the first edited image in the default viewer
+    #[arg(long, conflicts_with = "no_download")]
+    pub open: bool,
 
-    let (base64_data, mime_type) = load_image_base64(&image_path).await
-        .context("Failed to load image file")?;
+    /// Substitute {KEY} placeholders in the prompt (repeatable)
+    #[arg(long = "var", value_name = "KEY=VALUE", value_parser = prompt::parse_var)]
+    pub vars: Vec<(String, String)>,
 
-    // Build parameters with reference image
-    let params = GenerateParams::new(&args.prompt)
-        .with_aspect_ratio(args.aspect_ratio.as_deref().unwrap_or(&config.defaults.aspect_ratio))
-        .with_size(args.size.as_deref().unwrap_or(&config.defaults.size))
-        .with_model(args.model.as_deref().unwrap_or(&config.api.model))
-        .with_reference_image(base64_data, mime_type);
+    /// Sampling temperature, 0.0-2.0; lower follows the prompt more closely (Gemini models only)
+    #[arg(long, value_parser = params::parse_temperature)]
+    pub temperature: Option<f64>,
 
-    // Create job
-    let mut job = Job::new_edit(params, image_path.to_string_lossy().to_string());
+    /// Only sample from tokens within this probability mass, 0.0-1.0 (Gemini models only)
+    #[arg(long, value_parser = params::parse_top_p)]
+    pub top_p: Option<f64>,
 
-    // Save to database
-    db.insert_job(&job)?;
+    /// Only sample from the K most likely tokens (Gemini models only)
+    #[arg(long, value_parser = clap::value_parser!(u32).range(1..))]
+    pub top_k: Option<u32>,
 
-    // Create API client
-    let client = GeminiClient::from_config(config)?;
+    /// Whether the images may show people (default: defaults.people, else the API's default)
+    #[arg(long, value_enum, value_name = "POLICY")]
+    pub people: Option<PersonGeneration>,
 
-    // Show progress
-    let pb = if args.format == "text" {
-        let pb = ProgressBar::new_spinner();
-        pb.set_style(
-            ProgressStyle::default_spinner()
-                .template("{spinner:.yellow} {msg}")
-                .unwrap(),
-        );
-        pb.set_message(format!("Editing image: {}...", job.prompt_preview(40)));
-        pb.enable_steady_tick(Duration::from_millis(100));
-        Some(pb)
-    } else {
-        None
-    };
+    /// Response modalities to ask for, comma-separated (image, or text,image; default both)
+    #[arg(long, value_enum, value_delimiter = ',', value_name = "LIST")]
+    pub modalities: Vec<Modality>,
 
-    // Set job as running
-    job.set_running(0);
-    db.update_job(&job)?;
+    /// Set a generationConfig field the API takes but no flag covers, e.g.
+    /// media_resolution=MEDIA_RESOLUTION_HIGH or thinking_config.include_thoughts=false (repeatable)
+    #[arg(long, value_name = "KEY=VALUE", value_parser = params::parse_gen_config)]
+    pub gen_config: Vec<(String, Value)>,
 
-    // Generate edited image
-    match client.generate(&job.params).await {
-        Ok(response) => {
-            if let Err(e) = client.process_response(&mut job, response) {
-                job.set_failed(e.to_string());
-                db.update_job(&job)?;
+    /// Project to attribute this job to (for spend reports)
+    #[arg(long)]
+    pub project: Option<String>,
 
-                if let Some(pb) = pb {
-                    pb.finish_with_message(format!("{} Edit failed", "✗".red()));
-                }
+    /// Skip the configured watermark
+    #[arg(long)]
+    pub no_watermark: bool,
 
-                if args.format == "json" {
-                    println!("{}", serde_json::to_string_pretty(&job)?);
-                } else if args.format != "quiet" {
-                    eprintln!("{}: {}", "Error".red().bold(), e);
-                }
-                return Err(e);
-            }
+    /// Keep the base64 image data in the job record after download
+    #[arg(long)]
+    pub keep_data: bool,
+
+    /// With --format json or quiet, report progress as JSON lines on stderr
+    #[arg(long)]
+    pub json_progress: bool,
+
+    /// Output format (text, json, quiet, jsonl, template)
+    #[arg(short, long, value_enum, default_value_t = OutputFormat::Text)]
+    pub format: OutputFormat,
+}
+
+pub async fn run(args: EditArgs, config: &Config, db: &Database) -> Result<()> {
+    params::check_modalities(&args.modalities)?;
+    if let Some(dir) = &args.watch {
+        if args.output.as_deref() == Some(Path::new("-")) {
+            anyhow::bail!("--output - writes a single image; it can't be combined with --watch");
         }
-        Err(e) => {
-            job.set_failed(e.to_string());
-            db.update_job(&job)?;
+        // With --watch the only positional is the instruction, which clap
+        // assigns to the first slot
+        let prompt = match (&args.prompt, &args.image) {
+            (Some(prompt), None) => prompt.clone(),
+            (None, Some(prompt)) => prompt.to_string_lossy().to_string(),
+            _ => anyhow::bail!("Usage: banana edit --watch <dir> \"<instruction>\""),
+        };
+        let prompt = prompt::render(&prompt, &args.vars)?;
+        return watch_folder(dir, &prompt, &args, config, db).await;
+    }
 
-            if let Some(pb) = pb {
-                pb.finish_with_message(format!("{} Edit failed", "✗".red()));
-            }
+    let image = args.image.clone().context("Missing image path")?;
+    let prompt = args.prompt.as_deref().context("Missing edit instruction")?;
+    let prompt = prompt::render(prompt, &args.vars)?;
+    edit_image(&image, &prompt, &args, config, db).await
+}
+
+/// Edit a single image file
+async fn edit_image(
+    image: &Path,
+    prompt: &str,
+    args: &EditArgs,
+    config: &Config,
+    db: &Database,
+) -> Result<()> {
+    // Load the source image
+    let image_path = platform::canonicalize(image)
+        .context("Image file not found")?;
 
-            if args.format == "json" {
-                println!("{}", serde_json::to_string_pretty(&job)?);
-            } else if args.format != "quiet" {
-                eprintln!("{}: {}", "Error".red().bold(), e);
+    let model = config.model(args.model.as_deref());
+    let defaults = config.defaults_for(&model);
+    let aspect_ratio = args.aspect_ratio.as_deref().unwrap_or(&defaults.aspect_ratio);
+
+    // An explicit ratio that doesn't match the source comes back distorted
+    if args.fit.is_none() && args.format == OutputFormat::Text {
+        if let (Some(requested), Ok(source)) = (
+            args.aspect_ratio.as_deref().and_then(imaging::parse_ratio),
+            imaging::image_ratio(&image_path),
+        ) {
+            if imaging::ratios_differ(source, requested) {
+                eprintln!(
+                    "{}",
+                    "Source aspect ratio differs from the output; pass --fit crop|pad|stretch to avoid distortion".dimmed()
+                );
             }
-            return Err(e);
         }
     }
 
-    // Download images
+    let (base64_data, mime_type) = imaging::load_for_edit(&image_path, aspect_ratio, args.fit, args.frame).await?;
+
+    let mut params = GenerateParams::new(prompt)
+        .with_aspect_ratio(aspect_ratio)
+        .with_size(args.size.as_deref().unwrap_or(&defaults.size))
+        .with_model(model)
+        .with_sampling(args.temperature, args.top_p, args.top_k)
+        .with_modalities(&args.modalities)
+        .with_generation_config(args.gen_config.clone());
+    if let Some(people) = args.people {
+        params = params.with_person_generation(people);
+    }
+
+    let source_job = if args.with_context {
+        let source_job = db.find_job_by_image_path(&image_path)?;
+        if source_job.is_none() && args.format == OutputFormat::Text {
+            eprintln!(
+                "{}: no banana job produced this image; editing without context",
+                t("Warning").yellow().bold()
+            );
+        }
+        source_job
+    } else {
+        None
+    };
+
+    // With context the source image is the model's previous reply, otherwise
+    // it's sent as a reference image alongside the instruction
+    let params = match &source_job {
+        Some(source) => params.with_context(context_turns(source, base64_data, mime_type)),
+        None => params.with_reference_image(base64_data, mime_type),
+    };
+
+    // Create job
+    let mut job = Job::new_edit(params, platform::display_path(&image_path));
+    job.project = args.project.clone();
+    job.parent_id = source_job.map(|source| source.id);
+
+    let options = JobOptions {
+        output: OutputFlags {
+            output: args.output.as_deref(),
+            no_download: args.no_download,
+            tmp: args.tmp,
+            open: args.open,
+        },
+        format: args.format,
+        json_progress: args.json_progress,
+        no_watermark: args.no_watermark,
+        keep_data: args.keep_data,
+    };
+    pipeline::run_job(job, options, config, db).await
+}
+
+/// Rebuild the conversation that produced `source`: its prompt (with any
+/// reference image) as the user turn and its output image as the model turn
+pub(super) fn context_turns(source: &Job, image_data: String, image_mime: String) -> Vec<ContextTurn> {
+    let reference = source
+        .params
+        .reference_image
+        .clone()
+        .zip(source.params.reference_mime_type.clone());
+
+    vec![
+        ContextTurn {
+            role: "user".to_string(),
+            text: Some(source.params.prompt.clone()),
+            image: reference,
+        },
+        ContextTurn {
+            role: "model".to_string(),
+            text: None,
+            image: Some((image_data, image_mime)),
+        },
+    ]
+}
+
+/// Watch a directory and edit every new image that appears in it
+async fn watch_folder(
+    dir: &Path,
+    prompt: &str,
+    args: &EditArgs,
+    config: &Config,
+    db: &Database,
+) -> Result<()> {
+    let dir = platform::canonicalize(dir).context("Watch directory not found")?;
+
+    // Only images dropped in after startup are processed
+    let mut seen: HashSet<PathBuf> = list_images(&dir)?.into_iter().collect();
+    // Files still being written: path -> last observed size
+    let mut pending: HashMap<PathBuf, u64> = HashMap::new();
+
+    // Writing results into the watched folder would re-trigger edits forever
     let output_dir = args
         .output
-        .unwrap_or_else(|| PathBuf::from(&config.output.directory));
+        .clone()
+        .unwrap_or_else(|| config.output.dir());
+    if platform::canonicalize(&output_dir).ok().as_deref() == Some(dir.as_path()) {
+        anyhow::bail!("Output directory must differ from the watched directory");
+    }
 
-    if !args.no_download && config.output.auto_download {
-        let paths = client.download_images(&mut job, &output_dir).await?;
+    if args.format == OutputFormat::Text {
+        println!(
+            "{}: {} (Ctrl+C to stop)",
+            "Watching".cyan().bold(),
+            platform::display_path(&dir)
+        );
+        println!("{}: {}", "Output".cyan().bold(), platform::display_path(&output_dir));
+    }
 
-        if let Some(pb) = &pb {
-            pb.finish_with_message(format!(
-                "{} Edited image saved",
-                "✓".green()
-            ));
+    // Edits to config.toml (say, a new default model) apply to the next image
+    let mut config = config.clone();
+    let mut config_watcher = ConfigWatcher::new(&config)
+        .map_err(|e| tracing::warn!("Config changes won't be picked up live: {:#}", e))
+        .ok();
+
+    // One listener for the whole watch, so a Ctrl+C pressed while an edit
+    // runs is still seen at the next poll
+    let ctrl_c = tokio::signal::ctrl_c();
+    tokio::pin!(ctrl_c);
+    let mut interval = tokio::time::interval(WATCH_POLL_INTERVAL);
+    loop {
+        tokio::select! {
+            _ = &mut ctrl_c => break,
+            _ = interval.tick() => {}
         }
 
-        // Display based on format
-        match args.format.as_str() {
-            "json" => {
-                println!("{}", serde_json::to_string_pretty(&job)?);
-            }
-            "quiet" => {
-                for path in &paths {
-                    println!("{}", path);
+        if config_watcher.as_mut().is_some_and(ConfigWatcher::changed) {
+            match config.reload() {
+                Ok(()) if args.format == OutputFormat::Text => {
+                    println!("{}: config.toml", "Reloaded".cyan().bold());
                 }
+                Ok(()) => {}
+                Err(e) => tracing::warn!("Failed to reload config: {:#}", e),
             }
-            _ => {
-                println!();
-                println!("{}: {}", "Job ID".cyan().bold(), job.id);
-                println!("{}: {}", "Source".cyan().bold(), image_path.display());
-                println!("{}: {}", "Edit".cyan().bold(), job.params.prompt);
-                println!("{}: {}", "Model".cyan().bold(), job.model);
-                println!("{}: {}", "Status".cyan().bold(), "completed".green());
-                println!();
-                println!("{}:", "Edited Image".cyan().bold());
-                for path in &paths {
-                    println!("  {}", path);
-                }
+        }
 
-                // Try to display image in terminal
-                if config.output.display == crate::config::DisplayMode::Terminal {
-                    if let Some(first_path) = paths.first() {
-                        println!();
-                        display_image_terminal(first_path);
-                    }
-                }
+        for path in list_images(&dir)? {
+            if seen.contains(&path) {
+                continue;
             }
-        }
-    } else {
-        if let Some(pb) = &pb {
-            pb.finish_with_message(format!(
-                "{} Edit complete (not downloaded)",
-                "✓".green()
-            ));
-        }
 
-        if args.format == "json" {
-            println!("{}", serde_json::to_string_pretty(&job)?);
+            // Wait until the file size is stable across two polls so we don't
+            // upload a partially copied image
+            let size = std::fs::metadata(&path).map(|m| m.len()).unwrap_or(0);
+            match pending.insert(path.clone(), size) {
+                Some(previous) if previous == size && size > 0 => {}
+                _ => continue,
+            }
+
+            pending.remove(&path);
+            seen.insert(path.clone());
+
+            if let Err(e) = edit_image(&path, prompt, args, &config, db).await {
+                tracing::warn!("Failed to edit {}: {}", path.display(), e);
+            }
         }
     }
 
-    // Update database
-    db.update_job(&job)?;
-
     Ok(())
 }
 
-/// Display an image in the terminal using viuer
-fn display_image_terminal(path: &str) {
-    let conf = viuer::Config {
-        width: Some(80),
-        height: Some(30),
-        absolute_offset: false,
-        ..Default::default()
-    };
-
-    if let Err(e) = viuer::print_from_file(path, &conf) {
-        tracing::debug!("Failed to display image in terminal: {}", e);
+/// List image files directly inside a directory
+fn list_images(dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut images = Vec::new();
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        let is_image = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(|e| matches!(e.to_lowercase().as_str(), "png" | "jpg" | "jpeg" | "webp" | "gif"))
+            .unwrap_or(false);
+        if path.is_file() && is_image {
+            images.push(path);
+        }
     }
+    images.sort();
+    Ok(images)
 }
@@ -0,0 +1,260 @@
+use anyhow::{Context, Result};
+use clap::builder::PossibleValuesParser;
+use clap::Args;
+use colored::Colorize;
+use futures_util::stream::{self, StreamExt};
+use serde::Serialize;
+use std::path::PathBuf;
+
+use super::batch;
+use crate::api::GeminiClient;
+use crate::cli::output::{self, Report};
+use crate::cli::progress::BatchProgress;
+use crate::cli::OutputFormat;
+use crate::config::Config;
+use crate::core::{prompt, Batch, BatchKind, BatchMember, FitMode, GenerateParams};
+use crate::db::Database;
+use crate::platform;
+
+#[derive(Args)]
+pub struct EditBatchArgs {
+    /// Glob pattern matching the images to edit (quote it to avoid shell expansion)
+    #[arg(required = true)]
+    pub pattern: String,
+
+    /// The edit instruction applied to every file
+    #[arg(required = true)]
+    pub prompt: String,
+
+    /// Aspect ratio for the output
+    #[arg(short, long, alias = "ar", value_parser = PossibleValuesParser::new(Config::aspect_ratios()))]
+    pub aspect_ratio: Option<String>,
+
+    /// Reshape the source to the output aspect ratio before upload (crop, pad, stretch)
+    #[arg(long, value_enum)]
+    pub fit: Option<FitMode>,
+
+    /// Image size (1K, 2K, 4K)
+    #[arg(short, long, value_parser = PossibleValuesParser::new(Config::sizes()))]
+    pub size: Option<String>,
+
+    /// Model to use, an alias such as pro or flash, or "auto" (see routing.*)
+    #[arg(short, long)]
+    pub model: Option<String>,
+
+    /// Output directory for edited images
+    #[arg(short, long)]
+    pub output: Option<PathBuf>,
+
+    /// Maximum number of edits running at once
+    #[arg(short = 'j', long, default_value = "3", value_parser = clap::value_parser!(u8).range(1..=16))]
+    pub concurrency: u8,
+
+    /// Don't download images automatically
+    #[arg(long)]
+    pub no_download: bool,
+
+    /// Substitute {KEY} placeholders in the prompt (repeatable)
+    #[arg(long = "var", value_name = "KEY=VALUE", value_parser = prompt::parse_var)]
+    pub vars: Vec<(String, String)>,
+
+    /// Project to attribute this job to (for spend reports)
+    #[arg(long)]
+    pub project: Option<String>,
+
+    /// Skip the configured watermark
+    #[arg(long)]
+    pub no_watermark: bool,
+
+    /// Output format (text, json, quiet, jsonl, template)
+    #[arg(short, long, value_enum, default_value_t = OutputFormat::Text)]
+    pub format: OutputFormat,
+}
+
+/// Result of editing one file in the batch
+#[derive(Debug, Serialize)]
+pub struct BatchItem {
+    pub file: String,
+    pub job_id: Option<String>,
+    pub success: bool,
+    pub paths: Vec<String>,
+    pub error: Option<String>,
+}
+
+pub async fn run(mut args: EditBatchArgs, config: &Config, db: &Database) -> Result<()> {
+    output::check(args.format)?;
+
+    args.prompt = prompt::render(&args.prompt, &args.vars)?;
+
+    let files = expand_pattern(&args.pattern)?;
+    if files.is_empty() {
+        anyhow::bail!("No files match '{}'", args.pattern);
+    }
+
+    let mut client = GeminiClient::from_config(config)?;
+    if args.no_watermark {
+        client = client.without_watermark();
+    }
+
+    // Every file shares the instruction, so check its length once up front
+    let model = config.model(args.model.as_deref());
+    let preflight = GenerateParams::new(&args.prompt).with_model(&model);
+    client.preflight(&preflight).await?;
+
+    let defaults = config.defaults_for(&model);
+    let members = files
+        .iter()
+        .map(|file| BatchMember {
+            model: model.to_string(),
+            aspect_ratio: args.aspect_ratio.clone().unwrap_or_else(|| defaults.aspect_ratio.clone()),
+            size: args.size.clone().unwrap_or_else(|| defaults.size.clone()),
+            source_image: Some(platform::display_path(
+                &platform::canonicalize(file).unwrap_or_else(|_| file.clone()),
+            )),
+        })
+        .collect();
+
+    let mut batch = Batch::new(BatchKind::EditBatch, &args.prompt, members);
+    let output_dir = args
+        .output
+        .clone()
+        .unwrap_or_else(|| config.output.dir());
+    if !args.no_download && config.output.auto_download {
+        // Absolute so `batch resume` downloads to the same place from any directory
+        batch.output_dir = Some(platform::display_path(&std::env::current_dir()?.join(&output_dir)));
+    }
+    batch.fit = args.fit;
+    batch.project = args.project.clone();
+    batch.watermark = !args.no_watermark;
+    db.insert_batch(&batch)?;
+
+    let pb = if args.format == OutputFormat::Text {
+        Some(BatchProgress::start(files.len() as u64, format!("Editing: {}", args.prompt)))
+    } else {
+        None
+    };
+
+    let mut items: Vec<BatchItem> = stream::iter(files.iter().zip(&batch.members))
+        .map(|(file, member)| {
+            let client = &client;
+            let batch = &batch;
+            let pb = &pb;
+            async move {
+                let result = batch::run_member(client, batch, member, None, db).await;
+                if let Some(pb) = pb {
+                    pb.inc();
+                }
+                let file = platform::display_path(file);
+                match result {
+                    Ok(job) => BatchItem {
+                        file,
+                        job_id: Some(job.id.clone()),
+                        success: true,
+                        paths: job.images.iter().filter_map(|img| img.path.clone()).collect(),
+                        error: None,
+                    },
+                    Err((job_id, e)) => BatchItem {
+                        file,
+                        job_id,
+                        success: false,
+                        paths: Vec::new(),
+                        error: Some(e.to_string()),
+                    },
+                }
+            }
+        })
+        .buffer_unordered(args.concurrency as usize)
+        .collect()
+        .await;
+
+    // Report in input order regardless of completion order
+    items.sort_by(|a, b| a.file.cmp(&b.file));
+
+    if let Some(pb) = &pb {
+        pb.finish();
+    }
+
+    let failed = items.iter().filter(|item| !item.success).count();
+
+    let report = Report::new(&items)?
+        .quiet(items.iter().flat_map(|item| &item.paths))
+        .text(|| print_summary(&items));
+    output::write(args.format, report)?;
+
+    if failed > 0 && args.format == OutputFormat::Text {
+        println!(
+            "{}",
+            format!("Re-run the failed edits with: banana batch resume {}", batch.id).dimmed()
+        );
+    }
+
+    if failed > 0 {
+        anyhow::bail!("{} of {} edit(s) failed", failed, items.len());
+    }
+
+    Ok(())
+}
+
+/// Expand a glob pattern into a sorted list of image files
+fn expand_pattern(pattern: &str) -> Result<Vec<PathBuf>> {
+    let mut files: Vec<PathBuf> = glob::glob(pattern)
+        .with_context(|| format!("Invalid glob pattern '{}'", pattern))?
+        .filter_map(|entry| entry.ok())
+        .filter(|path| path.is_file())
+        .collect();
+    files.sort();
+    Ok(files)
+}
+
+/// Print a table of per-file results
+fn print_summary(items: &[BatchItem]) {
+    println!(
+        "{:<40} {:<12} {:<10} {}",
+        "FILE".bold(),
+        "JOB".bold(),
+        "STATUS".bold(),
+        "RESULT".bold()
+    );
+    println!("{}", "-".repeat(90));
+
+    for item in items {
+        let status = if item.success {
+            "completed".green().to_string()
+        } else {
+            "failed".red().to_string()
+        };
+        let result = if item.success {
+            item.paths.first().cloned().unwrap_or_else(|| "(not downloaded)".to_string())
+        } else {
+            item.error.clone().unwrap_or_default()
+        };
+
+        println!(
+            "{:<40} {:<12} {:<10} {}",
+            truncate_start(&item.file, 40),
+            item.job_id.as_deref().unwrap_or("-"),
+            status,
+            result
+        );
+    }
+
+    let succeeded = items.iter().filter(|item| item.success).count();
+    println!();
+    println!(
+        "{} {} succeeded, {} failed",
+        "Summary:".cyan().bold(),
+        succeeded.to_string().green(),
+        (items.len() - succeeded).to_string().red()
+    );
+}
+
+/// Keep the end of a long path, which is usually the distinguishing part
+fn truncate_start(s: &str, max_len: usize) -> String {
+    let count = s.chars().count();
+    if count <= max_len {
+        s.to_string()
+    } else {
+        let tail: String = s.chars().skip(count - (max_len - 3)).collect();
+        format!("...{}", tail)
+    }
+}
@@ -1,13 +1,14 @@
 use anyhow::Result;
+use clap::builder::PossibleValuesParser;
 use clap::Args;
-use colored::Colorize;
-use indicatif::{ProgressBar, ProgressStyle};
+use serde_json::Value;
 use std::path::PathBuf;
-use std::time::Duration;
 
-use crate::api::GeminiClient;
+use super::pipeline::{self, JobOptions};
+use crate::cli::destination::OutputFlags;
+use crate::cli::OutputFormat;
 use crate::config::Config;
-use crate::core::GenerateParams;
+use crate::core::{params, prompt, GenerateParams, Modality, PersonGeneration};
 use crate::core::Job;
 use crate::db::Database;
 
@@ -18,179 +19,130 @@ pub struct GenerateArgs {
     pub prompt: String,
 
     /// Aspect ratio (1:1, 2:3, 3:2, 3:4, 4:3, 4:5, 5:4, 9:16, 16:9, 21:9)
-    #[arg(short, long, alias = "ar")]
+    #[arg(short, long, alias = "ar", value_parser = PossibleValuesParser::new(Config::aspect_ratios()))]
     pub aspect_ratio: Option<String>,
 
     /// Image size (1K, 2K, 4K - 4K only for Gemini 3 Pro)
-    #[arg(short, long)]
+    #[arg(short, long, value_parser = PossibleValuesParser::new(Config::sizes()))]
     pub size: Option<String>,
 
-    /// Model to use
+    /// Model to use, an alias such as pro or flash, or "auto" (see routing.*)
     #[arg(short, long)]
     pub model: Option<String>,
 
-    /// Output directory for downloaded images
-    #[arg(short, long)]
+    /// Seed for more repeatable results (Gemini models only)
+    #[arg(long)]
+    pub seed: Option<i64>,
+
+    /// Number of images to request (1-4)
+    #[arg(short = 'n', long, alias = "count", value_parser = clap::value_parser!(u8).range(1..=4))]
+    pub num_images: Option<u8>,
+
+    /// Output directory for downloaded images, or - to write the image to stdout
+    #[arg(short, long, conflicts_with = "no_download")]
     pub output: Option<PathBuf>,
 
     /// Don't download images automatically
     #[arg(long)]
     pub no_download: bool,
 
-    /// Output format (text, json, quiet)
-    #[arg(short, long, default_value = "text")]
-    pub format: String,
-}
+    /// Save into a fresh temporary directory that is deleted later (history.tmp_hours)
+    #[arg(long, conflicts_with_all = ["output", "no_download"])]
+    pub tmp: bool,
 
-pub async fn run(args: GenerateArgs, config: &Config, db: &Database) -> Result<()> {
-    // Build parameters
-    let params = GenerateParams::new(&args.prompt)
-        .with_aspect_ratio(args.aspect_ratio.as_deref().unwrap_or(&config.defaults.aspect_ratio))
-        .with_size(args.size.as_deref().unwrap_or(&config.defaults.size))
-        .with_model(args.model.as_deref().unwrap_or(&config.api.model));
+    /// Open the first generated image in the default viewer
+    #[arg(long, conflicts_with = "no_download")]
+    pub open: bool,
 
-    // Create job
-    let mut job = Job::new_generate(params);
+    /// Substitute {KEY} placeholders in the prompt (repeatable)
+    #[arg(long = "var", value_name = "KEY=VALUE", value_parser = prompt::parse_var)]
+    pub vars: Vec<(String, String)>,
 
-    // Save to database
-    db.insert_job(&job)?;
-
-    // Create API client
-    let client = GeminiClient::from_config(config)?;
-
-    // Show progress
-    let pb = if args.format == "text" {
-        let pb = ProgressBar::new_spinner();
-        pb.set_style(
-            ProgressStyle::default_spinner()
-                .template("{spinner:.yellow} {msg}")
-                .unwrap(),
-        );
-        pb.set_message(format!("Generating image: {}...", job.prompt_preview(40)));
-        pb.enable_steady_tick(Duration::from_millis(100));
-        Some(pb)
-    } else {
-        None
-    };
+    /// Sampling temperature, 0.0-2.0; lower follows the prompt more closely (Gemini models only)
+    #[arg(long, value_parser = params::parse_temperature)]
+    pub temperature: Option<f64>,
 
-    // Set job as running
-    job.set_running(0);
-    db.update_job(&job)?;
-
-    // Generate
-    match client.generate(&job.params).await {
-        Ok(response) => {
-            if let Err(e) = client.process_response(&mut job, response) {
-                job.set_failed(e.to_string());
-                db.update_job(&job)?;
-
-                if let Some(pb) = pb {
-                    pb.finish_with_message(format!("{} Generation failed", "✗".red()));
-                }
-
-                if args.format == "json" {
-                    println!("{}", serde_json::to_string_pretty(&job)?);
-                } else if args.format != "quiet" {
-                    eprintln!("{}: {}", "Error".red().bold(), e);
-                }
-                return Err(e);
-            }
-        }
-        Err(e) => {
-            job.set_failed(e.to_string());
-            db.update_job(&job)?;
-
-            if let Some(pb) = pb {
-                pb.finish_with_message(format!("{} Generation failed", "✗".red()));
-            }
-
-            if args.format == "json" {
-                println!("{}", serde_json::to_string_pretty(&job)?);
-            } else if args.format != "quiet" {
-                eprintln!("{}: {}", "Error".red().bold(), e);
-            }
-            return Err(e);
-        }
-    }
+    /// Only sample from tokens within this probability mass, 0.0-1.0 (Gemini models only)
+    #[arg(long, value_parser = params::parse_top_p)]
+    pub top_p: Option<f64>,
 
-    // Download images
-    let output_dir = args
-        .output
-        .unwrap_or_else(|| PathBuf::from(&config.output.directory));
-
-    if !args.no_download && config.output.auto_download {
-        let paths = client.download_images(&mut job, &output_dir).await?;
-
-        if let Some(pb) = &pb {
-            pb.finish_with_message(format!(
-                "{} Generated {} image(s)",
-                "✓".green(),
-                paths.len()
-            ));
-        }
-
-        // Display based on format
-        match args.format.as_str() {
-            "json" => {
-                println!("{}", serde_json::to_string_pretty(&job)?);
-            }
-            "quiet" => {
-                for path in &paths {
-                    println!("{}", path);
-                }
-            }
-            _ => {
-                println!();
-                println!("{}: {}", "Job ID".cyan().bold(), job.id);
-                println!("{}: {}", "Prompt".cyan().bold(), job.params.prompt);
-                println!("{}: {}", "Model".cyan().bold(), job.model);
-                println!("{}: {}", "Aspect Ratio".cyan().bold(), job.params.aspect_ratio);
-                println!("{}: {}", "Status".cyan().bold(), "completed".green());
-                println!();
-                println!("{}:", "Generated Images".cyan().bold());
-                for path in &paths {
-                    println!("  {}", path);
-                }
-
-                // Try to display image in terminal
-                if config.output.display == crate::config::DisplayMode::Terminal {
-                    if let Some(first_path) = paths.first() {
-                        println!();
-                        display_image_terminal(first_path);
-                    }
-                }
-            }
-        }
-    } else {
-        if let Some(pb) = &pb {
-            pb.finish_with_message(format!(
-                "{} Generated {} image(s) (not downloaded)",
-                "✓".green(),
-                job.images.len()
-            ));
-        }
-
-        if args.format == "json" {
-            println!("{}", serde_json::to_string_pretty(&job)?);
-        }
-    }
+    /// Only sample from the K most likely tokens (Gemini models only)
+    #[arg(long, value_parser = clap::value_parser!(u32).range(1..))]
+    pub top_k: Option<u32>,
+
+    /// Whether the images may show people (default: defaults.people, else the API's default)
+    #[arg(long, value_enum, value_name = "POLICY")]
+    pub people: Option<PersonGeneration>,
+
+    /// Response modalities to ask for, comma-separated (image, or text,image; default both)
+    #[arg(long, value_enum, value_delimiter = ',', value_name = "LIST")]
+    pub modalities: Vec<Modality>,
+
+    /// Set a generationConfig field the API takes but no flag covers, e.g.
+    /// media_resolution=MEDIA_RESOLUTION_HIGH or thinking_config.include_thoughts=false (repeatable)
+    #[arg(long, value_name = "KEY=VALUE", value_parser = params::parse_gen_config)]
+    pub gen_config: Vec<(String, Value)>,
 
-    // Update database
-    db.update_job(&job)?;
+    /// Project to attribute this job to (for spend reports)
+    #[arg(long)]
+    pub project: Option<String>,
+
+    /// Skip the configured watermark
+    #[arg(long)]
+    pub no_watermark: bool,
+
+    /// Keep the base64 image data in the job record after download
+    #[arg(long)]
+    pub keep_data: bool,
+
+    /// With --format json or quiet, report progress as JSON lines on stderr
+    #[arg(long)]
+    pub json_progress: bool,
 
-    Ok(())
+    /// Output format (text, json, quiet, jsonl, template)
+    #[arg(short, long, value_enum, default_value_t = OutputFormat::Text)]
+    pub format: OutputFormat,
 }
 
-/// Display an image in the terminal using viuer
-fn display_image_terminal(path: &str) {
-    let conf = viuer::Config {
-        width: Some(80),
-        height: Some(30),
-        absolute_offset: false,
-        ..Default::default()
-    };
+pub async fn run(args: GenerateArgs, config: &Config, db: &Database) -> Result<()> {
+    params::check_modalities(&args.modalities)?;
+    let prompt = prompt::render(&args.prompt, &args.vars)?;
 
-    if let Err(e) = viuer::print_from_file(path, &conf) {
-        tracing::debug!("Failed to display image in terminal: {}", e);
+    // Build parameters
+    let model = config.model(args.model.as_deref());
+    let defaults = config.defaults_for(&model);
+    let mut params = GenerateParams::new(prompt)
+        .with_aspect_ratio(args.aspect_ratio.as_deref().unwrap_or(&defaults.aspect_ratio))
+        .with_size(args.size.as_deref().unwrap_or(&defaults.size))
+        .with_model(model)
+        .with_sampling(args.temperature, args.top_p, args.top_k)
+        .with_modalities(&args.modalities)
+        .with_generation_config(args.gen_config);
+    if let Some(seed) = args.seed {
+        params = params.with_seed(seed);
+    }
+    if let Some(num_images) = args.num_images {
+        params = params.with_num_images(num_images);
+    }
+    if let Some(people) = args.people {
+        params = params.with_person_generation(people);
     }
+
+    // Create job
+    let mut job = Job::new_generate(params);
+    job.project = args.project.clone();
+
+    let options = JobOptions {
+        output: OutputFlags {
+            output: args.output.as_deref(),
+            no_download: args.no_download,
+            tmp: args.tmp,
+            open: args.open,
+        },
+        format: args.format,
+        json_progress: args.json_progress,
+        no_watermark: args.no_watermark,
+        keep_data: args.keep_data,
+    };
+    pipeline::run_job(job, options, config, db).await
 }
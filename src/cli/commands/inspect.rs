@@ -0,0 +1,81 @@
+use anyhow::{Context, Result};
+use clap::Args;
+use colored::Colorize;
+use std::path::PathBuf;
+
+use crate::cli::commands::jobs;
+use crate::cli::output::{self, Report};
+use crate::cli::OutputFormat;
+use crate::config::Config;
+use crate::core::Job;
+use crate::db::Database;
+use crate::metadata::{self, ImageMetadata};
+use crate::platform;
+
+#[derive(Args)]
+pub struct InspectArgs {
+    /// Image file to inspect
+    #[arg(required = true)]
+    pub image: PathBuf,
+
+    /// Output format (text, json, quiet, jsonl, template)
+    #[arg(short, long, value_enum, default_value_t = OutputFormat::Text)]
+    pub format: OutputFormat,
+}
+
+pub fn run(args: InspectArgs, config: &Config, db: &Database) -> Result<()> {
+    let path = platform::canonicalize(&args.image).context("Image file not found")?;
+    let embedded = metadata::read(&path)?;
+
+    // Prefer the embedded job ID; fall back to files recorded in the history
+    let job = match &embedded {
+        Some(meta) => db.get_job(&meta.job_id)?,
+        None => db.find_job_by_image_path(&path)?,
+    };
+
+    let file = platform::display_path(&path);
+    let inspection = serde_json::json!({
+        "file": file,
+        "metadata": embedded,
+        "job": job,
+    });
+    let report = Report::new(&inspection)?
+        .quiet(embedded.as_ref().map(|m| &m.job_id).or(job.as_ref().map(|j| &j.id)))
+        .text(|| print_inspection(&file, embedded.as_ref(), job.as_ref(), config));
+    output::write(args.format, report)?;
+
+    Ok(())
+}
+
+/// Print what is known about an image file
+fn print_inspection(file: &str, embedded: Option<&ImageMetadata>, job: Option<&Job>, config: &Config) {
+    println!("{}: {}", "File".cyan().bold(), file);
+
+    match embedded {
+        Some(meta) => {
+            println!();
+            println!("{}:", "Embedded Metadata".cyan().bold());
+            println!("  Job ID: {}", meta.job_id);
+            println!("  Model: {}", meta.model);
+            println!("  Aspect Ratio: {}", meta.aspect_ratio);
+            println!("  Size: {}", meta.size);
+            if let Some(seed) = meta.seed {
+                println!("  Seed: {}", seed);
+            }
+            println!("  Prompt: {}", meta.prompt);
+            println!("  Generator: {}", meta.generator);
+        }
+        None => {
+            println!("{}", "No banana metadata embedded in this file.".dimmed());
+        }
+    }
+
+    match job {
+        Some(job) => jobs::print_job(job, config),
+        None if embedded.is_some() => {
+            println!();
+            println!("{}", "The job is no longer in the history.".dimmed());
+        }
+        None => {}
+    }
+}
@@ -1,8 +1,30 @@
-use anyhow::Result;
-use clap::{Args, Subcommand};
+use anyhow::{Context, Result};
+use chrono::{DateTime, Duration, Local, Utc};
+use clap::builder::PossibleValuesParser;
+use clap::{Args, Subcommand, ValueEnum};
 use colored::Colorize;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::Duration as StdDuration;
 
-use crate::db::Database;
+use super::edit;
+use super::pipeline::{self, JobOptions};
+use crate::cli::destination::OutputFlags;
+use crate::cli::output::{self, Report};
+use crate::cli::table::Table;
+use crate::cli::OutputFormat;
+use crate::config::Config;
+use crate::core::{BananaError, Job, JobAction, JobAttachment, Modality};
+use crate::db::{Database, JobFilter};
+use crate::history;
+use crate::i18n::t;
+use crate::imaging;
+use crate::platform;
+use crate::thumbnails;
+
+/// Longest prompt shown in job tables (narrower terminals cut it further)
+const PROMPT_PREVIEW_LEN: usize = 60;
 
 #[derive(Args)]
 pub struct JobsArgs {
@@ -14,12 +36,84 @@ pub struct JobsArgs {
     pub limit: u32,
 
     /// Filter by status (queued, running, completed, failed, cancelled)
-    #[arg(short, long)]
+    #[arg(short, long, value_parser = PossibleValuesParser::new(["queued", "running", "completed", "failed", "cancelled"]))]
     pub status: Option<String>,
 
-    /// Output format (text, json)
-    #[arg(short, long, default_value = "text")]
-    pub format: String,
+    /// Only jobs from this batch (e.g. a sweep ID)
+    #[arg(short, long)]
+    pub batch: Option<String>,
+
+    /// Group the table under a heading per day (text output)
+    #[arg(long, value_enum)]
+    pub group_by: Option<JobGrouping>,
+
+    /// Output format (text, json, quiet, jsonl, template)
+    #[arg(short, long, value_enum, default_value_t = OutputFormat::Text)]
+    pub format: OutputFormat,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum JobGrouping {
+    Day,
+}
+
+/// Status counts and spend for the jobs in a listing
+#[derive(Debug, Default, Serialize)]
+pub struct JobsSummary {
+    pub total: usize,
+    pub completed: usize,
+    pub failed: usize,
+    pub running: usize,
+    pub stalled: usize,
+    pub queued: usize,
+    pub cancelled: usize,
+    /// Estimated cost of the listed jobs that have usage data
+    pub estimated_cost_usd: f64,
+}
+
+impl JobsSummary {
+    fn of(jobs: &[Job]) -> Self {
+        let mut summary = Self {
+            total: jobs.len(),
+            ..Self::default()
+        };
+        for job in jobs {
+            match job.status_label() {
+                "completed" => summary.completed += 1,
+                "failed" => summary.failed += 1,
+                "running" => summary.running += 1,
+                "stalled" => summary.stalled += 1,
+                "queued" => summary.queued += 1,
+                "cancelled" => summary.cancelled += 1,
+                _ => {}
+            }
+            summary.estimated_cost_usd += job.estimated_cost().unwrap_or(0.0);
+        }
+        summary
+    }
+
+    /// One-line summary such as "12 jobs: 10 completed, 2 failed · $0.4680 estimated"
+    fn line(&self) -> String {
+        let counts: Vec<String> = [
+            (self.completed, "completed"),
+            (self.failed, "failed"),
+            (self.running, "running"),
+            (self.stalled, "stalled"),
+            (self.queued, "queued"),
+            (self.cancelled, "cancelled"),
+        ]
+        .iter()
+        .filter(|(count, _)| *count > 0)
+        .map(|(count, label)| format!("{} {}", count, label))
+        .collect();
+
+        format!(
+            "{} job(s): {} · ${:.4} estimated",
+            self.total,
+            counts.join(", "),
+            self.estimated_cost_usd
+        )
+    }
 }
 
 #[derive(Subcommand)]
@@ -29,166 +123,871 @@ pub enum JobsCommand {
         /// Job ID
         job_id: String,
 
-        /// Output format (text, json)
-        #[arg(short, long, default_value = "text")]
-        format: String,
+        /// Output format (text, json, quiet, jsonl, template)
+        #[arg(short, long, value_enum, default_value_t = OutputFormat::Text)]
+        format: OutputFormat,
+    },
+
+    /// Compare the parameters of two jobs
+    Diff {
+        /// First job ID
+        a: String,
+
+        /// Second job ID
+        b: String,
+
+        /// Open both jobs' primary images in the default viewer
+        #[arg(long)]
+        open: bool,
+
+        /// Output format (text, json, quiet, jsonl, template)
+        #[arg(short, long, value_enum, default_value_t = OutputFormat::Text)]
+        format: OutputFormat,
     },
 
-    /// Delete a job from history
+    /// Run a job again with its stored parameters, as a new job whose parent is the original
+    Retry {
+        /// Job ID
+        job_id: String,
+
+        /// Output directory for the images, or - to write the image to stdout
+        #[arg(short, long, conflicts_with = "no_download")]
+        output: Option<PathBuf>,
+
+        /// Don't download images automatically
+        #[arg(long)]
+        no_download: bool,
+
+        /// Save into a fresh temporary directory that is deleted later (history.tmp_hours)
+        #[arg(long, conflicts_with_all = ["output", "no_download"])]
+        tmp: bool,
+
+        /// Open the first image in the default viewer
+        #[arg(long, conflicts_with = "no_download")]
+        open: bool,
+
+        /// With --format json or quiet, report progress as JSON lines on stderr
+        #[arg(long)]
+        json_progress: bool,
+
+        /// Output format (text, json, quiet, jsonl, template)
+        #[arg(short, long, value_enum, default_value_t = OutputFormat::Text)]
+        format: OutputFormat,
+    },
+
+    /// Link an external file (e.g. a retouched export) to a job
+    Attach {
+        /// Job ID
+        job_id: String,
+
+        /// File to attach
+        path: PathBuf,
+
+        /// Output format (text, json, quiet, jsonl, template)
+        #[arg(short, long, value_enum, default_value_t = OutputFormat::Text)]
+        format: OutputFormat,
+    },
+
+    /// Move a job to the trash
     Delete {
         /// Job ID
         job_id: String,
+
+        /// Output format (text, json, quiet, jsonl, template)
+        #[arg(short, long, value_enum, default_value_t = OutputFormat::Text)]
+        format: OutputFormat,
+    },
+
+    /// Star a job so the history caps never evict it
+    Star {
+        /// Job ID
+        job_id: String,
+
+        /// Output format (text, json, quiet, jsonl, template)
+        #[arg(short, long, value_enum, default_value_t = OutputFormat::Text)]
+        format: OutputFormat,
+    },
+
+    /// Remove a job's star
+    Unstar {
+        /// Job ID
+        job_id: String,
+
+        /// Output format (text, json, quiet, jsonl, template)
+        #[arg(short, long, value_enum, default_value_t = OutputFormat::Text)]
+        format: OutputFormat,
+    },
+
+    /// Delete one image of a job, removing its file and its entry on the job
+    RmImage {
+        /// Job ID
+        job_id: String,
+
+        /// Image index (as listed by `jobs show`)
+        index: u8,
+
+        /// Output format (text, json, quiet, jsonl, template)
+        #[arg(short, long, value_enum, default_value_t = OutputFormat::Text)]
+        format: OutputFormat,
+    },
+
+    /// Restore a job from the trash
+    Restore {
+        /// Job ID
+        job_id: String,
+
+        /// Output format (text, json, quiet, jsonl, template)
+        #[arg(short, long, value_enum, default_value_t = OutputFormat::Text)]
+        format: OutputFormat,
+    },
+
+    /// Print job status changes as they happen (Ctrl+C to stop)
+    Watch {
+        /// Only jobs from this batch; stops once all of them have finished
+        #[arg(short, long)]
+        batch: Option<String>,
+
+        /// Seconds between checks
+        #[arg(short, long, default_value = "2", value_parser = clap::value_parser!(u64).range(1..))]
+        interval: u64,
+
+        /// Output format (text, json, quiet, jsonl, template; one event per line)
+        #[arg(short, long, value_enum, default_value_t = OutputFormat::Text)]
+        format: OutputFormat,
+    },
+
+    /// List jobs in the trash
+    Trash {
+        /// Output format (text, json, quiet, jsonl, template)
+        #[arg(short, long, value_enum, default_value_t = OutputFormat::Text)]
+        format: OutputFormat,
     },
 
-    /// Clear all jobs from history
+    /// Move all jobs to the trash
     Clear {
         /// Skip confirmation prompt
         #[arg(short, long)]
         force: bool,
+
+        /// Only clear jobs with this status
+        #[arg(short, long, value_parser = PossibleValuesParser::new(["queued", "running", "completed", "failed", "cancelled"]))]
+        status: Option<String>,
+
+        /// Only clear jobs older than this age (e.g. 12h, 7d, 2w)
+        #[arg(long, value_parser = history::parse_age)]
+        older_than: Option<Duration>,
+
+        /// Delete permanently instead of moving to the trash
+        #[arg(long)]
+        permanent: bool,
+
+        /// Also delete the jobs' image files (requires --permanent)
+        #[arg(long, requires = "permanent")]
+        delete_files: bool,
     },
 }
 
-pub fn run(args: JobsArgs, db: &Database) -> Result<()> {
+/// `provider_flag` is set when `--provider` was given, which takes precedence
+/// over the backend a retried job ran on
+pub async fn run(args: JobsArgs, config: &Config, db: &Database, provider_flag: bool) -> Result<()> {
     match args.command {
-        Some(JobsCommand::Show { job_id, format }) => show_job(&job_id, &format, db),
-        Some(JobsCommand::Delete { job_id }) => delete_job(&job_id, db),
-        Some(JobsCommand::Clear { force }) => clear_jobs(force, db),
-        None => list_jobs(args.limit, args.status.as_deref(), &args.format, db),
+        Some(JobsCommand::Show { job_id, format }) => show_job(&job_id, format, config, db),
+        Some(JobsCommand::Diff { a, b, open, format }) => diff_jobs(&a, &b, open, format, db),
+        Some(JobsCommand::Retry {
+            job_id,
+            output,
+            no_download,
+            tmp,
+            open,
+            json_progress,
+            format,
+        }) => {
+            let options = JobOptions {
+                output: OutputFlags {
+                    output: output.as_deref(),
+                    no_download,
+                    tmp,
+                    open,
+                },
+                format,
+                json_progress,
+                no_watermark: false,
+                keep_data: false,
+            };
+            retry_job(&job_id, options, provider_flag, config, db).await
+        }
+        Some(JobsCommand::Attach { job_id, path, format }) => attach_file(&job_id, &path, format, db),
+        Some(JobsCommand::Delete { job_id, format }) => delete_job(&job_id, format, db),
+        Some(JobsCommand::Star { job_id, format }) => set_starred(&job_id, true, format, db),
+        Some(JobsCommand::Unstar { job_id, format }) => set_starred(&job_id, false, format, db),
+        Some(JobsCommand::RmImage { job_id, index, format }) => remove_image(&job_id, index, format, db),
+        Some(JobsCommand::Restore { job_id, format }) => restore_job(&job_id, format, db),
+        Some(JobsCommand::Watch { batch, interval, format }) => {
+            watch_jobs(batch.as_deref(), StdDuration::from_secs(interval), format, db).await
+        }
+        Some(JobsCommand::Trash { format }) => list_trash(format, db, config),
+        Some(JobsCommand::Clear {
+            force,
+            status,
+            older_than,
+            permanent,
+            delete_files,
+        }) => {
+            let filter = JobFilter {
+                status,
+                created_before: older_than.map(history::cutoff).transpose()?,
+            };
+            clear_jobs(&filter, force, permanent, delete_files, db)
+        }
+        None => list_jobs(
+            args.limit,
+            args.status.as_deref(),
+            args.batch.as_deref(),
+            args.group_by,
+            args.format,
+            config,
+            db,
+        ),
     }
 }
 
-fn list_jobs(limit: u32, status: Option<&str>, format: &str, db: &Database) -> Result<()> {
-    let jobs = db.list_jobs(limit, status)?;
+fn list_jobs(
+    limit: u32,
+    status: Option<&str>,
+    batch: Option<&str>,
+    group_by: Option<JobGrouping>,
+    format: OutputFormat,
+    config: &Config,
+    db: &Database,
+) -> Result<()> {
+    let (jobs, total) = match batch {
+        Some(batch_id) => {
+            let jobs: Vec<Job> = db
+                .list_batch_jobs(batch_id)?
+                .into_iter()
+                .filter(|job| status.is_none_or(|s| job.status_name() == s))
+                .collect();
+            let total = jobs.len() as i64;
+            (jobs.into_iter().take(limit as usize).collect(), total)
+        }
+        None => (db.list_jobs(limit, status)?, db.count_jobs()?),
+    };
+
+    let summary = JobsSummary::of(&jobs);
+    let listing = serde_json::json!({
+        "jobs": jobs.iter().map(Job::with_artifacts).collect::<Vec<_>>(),
+        "summary": summary,
+    });
+    let report = Report::new(&listing)?
+        .records(jobs.iter().map(Job::with_artifacts))?
+        .quiet(jobs.iter().map(|job| &job.id))
+        .text(|| print_jobs(&jobs, &summary, total, limit, group_by, config));
+    output::write(format, report)
+}
 
+/// Print jobs as a table, followed by their summary
+fn print_jobs(
+    jobs: &[Job],
+    summary: &JobsSummary,
+    total: i64,
+    limit: u32,
+    group_by: Option<JobGrouping>,
+    config: &Config,
+) {
     if jobs.is_empty() {
-        if format == "json" {
-            println!("[]");
+        println!("{}", t("No jobs found.").dimmed());
+        return;
+    }
+
+    let dates = config.output.date_format();
+    let mut table = Table::new(&[t("ID"), t("ACTION"), t("STATUS"), t("PROMPT"), t("CREATED")]).flexible(3);
+    let mut current_day = None;
+    for job in jobs {
+        if group_by == Some(JobGrouping::Day) && current_day != Some(job.created_day()) {
+            current_day = Some(job.created_day());
+            table.add_separator(dates.day(job.created_day()));
+        }
+
+        let status_colored = match job.status_label() {
+            "completed" => "completed".green(),
+            "failed" => "failed".red(),
+            "running" => "running".yellow(),
+            "stalled" => "stalled".red(),
+            "queued" => "queued".blue(),
+            "cancelled" => "cancelled".dimmed(),
+            s => s.normal(),
+        };
+
+        let id = if job.starred {
+            format!("{} ★", job.id).yellow()
         } else {
-            println!("{}", "No jobs found.".dimmed());
+            job.id.normal()
+        };
+        table.add_row(vec![
+            id,
+            job.action.to_string().normal(),
+            status_colored,
+            job.prompt_preview(PROMPT_PREVIEW_LEN).normal(),
+            dates.datetime(&job.created_at).dimmed(),
+        ]);
+    }
+    table.print();
+
+    println!();
+    println!("{}", summary.line());
+    if total as u32 > limit {
+        println!(
+            "{}",
+            format!("Showing {} of {} jobs. Use --limit to see more.", limit, total).dimmed()
+        );
+    }
+}
+
+/// Most recent jobs checked by `jobs watch` (outside of a batch)
+const WATCH_WINDOW: u32 = 100;
+
+/// A job appearing or changing status, as printed by `jobs watch`
+#[derive(Debug, Serialize)]
+struct WatchEvent<'a> {
+    time: DateTime<Local>,
+    job_id: &'a str,
+    /// Previous status (absent for newly seen jobs)
+    from: Option<&'a str>,
+    to: &'a str,
+    prompt: &'a str,
+}
+
+async fn watch_jobs(
+    batch: Option<&str>,
+    interval: StdDuration,
+    format: OutputFormat,
+    db: &Database,
+) -> Result<()> {
+    if let Some(batch_id) = batch {
+        if db.get_batch(batch_id)?.is_none() {
+            return Err(BananaError::BatchNotFound(batch_id.to_string()).into());
         }
-        return Ok(());
     }
 
-    if format == "json" {
-        println!("{}", serde_json::to_string_pretty(&jobs)?);
-        return Ok(());
+    let load = || -> Result<Vec<Job>> {
+        Ok(match batch {
+            Some(batch_id) => db.list_batch_jobs(batch_id)?,
+            None => db.list_jobs(WATCH_WINDOW, None)?,
+        })
+    };
+
+    // Jobs that already exist are the baseline; only changes are reported
+    let mut jobs = load()?;
+    let mut seen: HashMap<String, &'static str> =
+        jobs.iter().map(|job| (job.id.clone(), job.status_label())).collect();
+
+    // Events are printed as they happen, so JSON is one event per line
+    let mut writer = output::writer(match format {
+        OutputFormat::Json => OutputFormat::Jsonl,
+        format => format,
+    })?;
+
+    if format == OutputFormat::Text {
+        let active = seen.values().filter(|s| matches!(**s, "running" | "queued")).count();
+        println!(
+            "{} {} ({} active, Ctrl+C to stop)",
+            "Watching".cyan().bold(),
+            batch.map(|id| format!("batch {}", id)).unwrap_or_else(|| "jobs".to_string()),
+            active
+        );
     }
 
-    // Table header
+    loop {
+        if let Some(batch_id) = batch {
+            if !jobs.is_empty() && jobs.iter().all(|job| job.status.is_terminal()) {
+                if format == OutputFormat::Text {
+                    println!("{} Batch {} finished", "✓".green(), batch_id);
+                }
+                return Ok(());
+            }
+        }
+
+        tokio::time::sleep(interval).await;
+
+        jobs = load()?;
+        // Report oldest first so events read in the order they happened
+        jobs.sort_by_key(|job| job.updated_at);
+        for job in &jobs {
+            let status = job.status_label();
+            let previous = seen.insert(job.id.clone(), status);
+            if previous == Some(status) {
+                continue;
+            }
+
+            let prompt = job.prompt_preview(PROMPT_PREVIEW_LEN);
+            let event = WatchEvent {
+                time: Local::now(),
+                job_id: &job.id,
+                from: previous,
+                to: status,
+                prompt: &prompt,
+            };
+            let report = Report::new(&event)?
+                .quiet([format!("{} {}", job.id, status)])
+                .text(|| print_watch_event(&event));
+            writer.write(report)?;
+        }
+    }
+}
+
+fn print_watch_event(event: &WatchEvent) {
+    let to = match event.to {
+        "completed" => event.to.green(),
+        "failed" | "stalled" => event.to.red(),
+        "running" => event.to.yellow(),
+        "queued" => event.to.blue(),
+        _ => event.to.normal(),
+    };
+    let change = match event.from {
+        Some(from) => format!("{} → {}", from, to),
+        None => format!("new, {}", to),
+    };
     println!(
-        "{:<12} {:<10} {:<12} {:<40} {}",
-        "ID".bold(),
-        "ACTION".bold(),
-        "STATUS".bold(),
-        "PROMPT".bold(),
-        "CREATED".bold()
+        "{}  {}  {}  {}",
+        event.time.format("%H:%M:%S").to_string().dimmed(),
+        event.job_id,
+        change,
+        event.prompt.dimmed()
     );
-    println!("{}", "-".repeat(90));
+}
 
-    for job in jobs {
-        let status_colored = match job.status_name() {
-            "completed" => "completed".green().to_string(),
-            "failed" => "failed".red().to_string(),
-            "running" => "running".yellow().to_string(),
-            "queued" => "queued".blue().to_string(),
-            "cancelled" => "cancelled".dimmed().to_string(),
-            s => s.to_string(),
-        };
+fn show_job(job_id: &str, format: OutputFormat, config: &Config, db: &Database) -> Result<()> {
+    let job = db.get_job(job_id)?;
 
-        let created = job.created_at.format("%Y-%m-%d %H:%M").to_string();
+    let report = Report::new(&job.as_ref().map(Job::with_artifacts))?
+        .quiet(job.iter().flat_map(|job| &job.images).filter_map(|img| img.path.as_ref()))
+        .text(|| match &job {
+            Some(job) => print_job(job, config),
+            None => eprintln!("{}: {}", t("Error").red().bold(), t("Job '{id}' not found").replace("{id}", job_id)),
+        });
+    output::write(format, report)
+}
 
+/// Print a job's full record in text form
+pub fn print_job(job: &Job, config: &Config) {
+    let dates = config.output.date_format();
+    println!();
+    if job.starred {
+        println!("{}: {} {}", t("Job ID").cyan().bold(), job.id, "★".yellow());
+    } else {
+        println!("{}: {}", t("Job ID").cyan().bold(), job.id);
+    }
+    println!("{}: {}", t("Action").cyan().bold(), job.action);
+    if job.is_stale() {
         println!(
-            "{:<12} {:<10} {:<12} {:<40} {}",
-            job.id,
-            job.action.to_string(),
-            status_colored,
-            job.prompt_preview(38),
-            created.dimmed()
+            "{}: {} {}",
+            t("Status").cyan().bold(),
+            job.status,
+            t("(stalled: no heartbeat since last update)").red()
         );
+    } else {
+        println!("{}: {}", t("Status").cyan().bold(), job.status);
+    }
+    match &job.route {
+        Some(route) => println!("{}: {} {}", t("Model").cyan().bold(), job.model, format!("(auto: {})", route).dimmed()),
+        None => println!("{}: {}", t("Model").cyan().bold(), job.model),
+    }
+    if let Some(provider) = &job.provider {
+        match &job.base_url {
+            Some(url) => println!("{}: {} ({})", t("Provider").cyan().bold(), provider, url),
+            None => println!("{}: {}", t("Provider").cyan().bold(), provider),
+        }
+    }
+    if let Some(batch_id) = &job.batch_id {
+        println!("{}: {}", t("Batch").cyan().bold(), batch_id);
+    }
+    println!("{}: {} UTC", t("Created").cyan().bold(), dates.datetime_secs(&job.created_at));
+    println!("{}: {} UTC", t("Updated").cyan().bold(), dates.datetime_secs(&job.updated_at));
+    println!();
+    println!("{}:", t("Prompt").cyan().bold());
+    println!("  {}", job.params.prompt);
+    println!();
+    println!("{}:", t("Parameters").cyan().bold());
+    println!("  {}: {}", t("Aspect Ratio"), job.params.aspect_ratio);
+    println!("  Size: {}", job.params.size);
+    if job.params.num_images > 1 {
+        println!("  Count: {}", job.params.num_images);
+    }
+    if let Some(seed) = job.params.seed {
+        println!("  Seed: {}", seed);
+    }
+    if let Some(temperature) = job.params.temperature {
+        println!("  Temperature: {}", temperature);
+    }
+    if let Some(top_p) = job.params.top_p {
+        println!("  Top-P: {}", top_p);
+    }
+    if let Some(top_k) = job.params.top_k {
+        println!("  Top-K: {}", top_k);
+    }
+    if let Some(people) = job.params.person_generation {
+        println!("  People: {}", people.as_str());
+    }
+    if !job.params.modalities.is_empty() {
+        println!("  Modalities: {}", modalities(&job.params.modalities));
+    }
+    if let Some(neg) = &job.params.negative_prompt {
+        println!("  Negative: {}", neg);
     }
 
-    let count = db.count_jobs()?;
-    if count as u32 > limit {
+    if !job.images.is_empty() {
         println!();
-        println!(
-            "{}",
-            format!("Showing {} of {} jobs. Use --limit to see more.", limit, count).dimmed()
-        );
+        println!("{}:", t("Images").cyan().bold());
+        for img in &job.images {
+            if let Some(path) = &img.path {
+                let primary = if img.primary { format!(" {}", t("(primary)")).yellow() } else { "".normal() };
+                match img.dimensions() {
+                    Some(dimensions) => println!(
+                        "  [{}] {} {}{}",
+                        img.index,
+                        path,
+                        format!("({})", dimensions).dimmed(),
+                        primary
+                    ),
+                    None => println!("  [{}] {}{}", img.index, path, primary),
+                }
+            } else {
+                println!("  [{}] {}", img.index, t("(base64 data, not downloaded)"));
+            }
+        }
     }
 
-    Ok(())
+    if !job.attachments.is_empty() {
+        println!();
+        println!("{}:", t("Attachments").cyan().bold());
+        for attachment in &job.attachments {
+            println!(
+                "  {} {}",
+                attachment.path,
+                format!("({})", dates.datetime(&attachment.added_at)).dimmed()
+            );
+        }
+    }
+
+    if let Some(parent) = &job.parent_id {
+        println!();
+        println!("{}: {}", t("Parent Job").cyan().bold(), parent);
+    }
 }
 
-fn show_job(job_id: &str, format: &str, db: &Database) -> Result<()> {
-    let job = db.get_job(job_id)?;
+/// A parameter that differs between two jobs
+#[derive(Debug, Serialize)]
+struct FieldChange {
+    field: &'static str,
+    a: Option<String>,
+    b: Option<String>,
+}
 
-    match job {
-        Some(job) => {
-            if format == "json" {
-                println!("{}", serde_json::to_string_pretty(&job)?);
+/// One run of words in a prompt diff
+#[derive(Debug, Serialize)]
+struct WordChange {
+    op: &'static str,
+    text: String,
+}
+
+/// Modalities as listed by `jobs show` and `jobs diff` ("text,image" when unset)
+fn modalities(modalities: &[Modality]) -> String {
+    match modalities {
+        [] => "text,image".to_string(),
+        modalities => modalities.iter().map(Modality::as_str).collect::<Vec<_>>().join(","),
+    }
+}
+
+fn diff_jobs(a_id: &str, b_id: &str, open: bool, format: OutputFormat, db: &Database) -> Result<()> {
+    let a = db
+        .get_job(a_id)?
+        .ok_or_else(|| BananaError::JobNotFound(a_id.to_string()))?;
+    let b = db
+        .get_job(b_id)?
+        .ok_or_else(|| BananaError::JobNotFound(b_id.to_string()))?;
+
+    let fields: [(&'static str, Option<String>, Option<String>); 14] = [
+        ("action", Some(a.action.to_string()), Some(b.action.to_string())),
+        ("provider", a.provider.clone(), b.provider.clone()),
+        ("base_url", a.base_url.clone(), b.base_url.clone()),
+        ("model", Some(a.model.clone()), Some(b.model.clone())),
+        ("aspect_ratio", Some(a.params.aspect_ratio.clone()), Some(b.params.aspect_ratio.clone())),
+        ("size", Some(a.params.size.clone()), Some(b.params.size.clone())),
+        ("seed", a.params.seed.map(|s| s.to_string()), b.params.seed.map(|s| s.to_string())),
+        ("temperature", a.params.temperature.map(|t| t.to_string()), b.params.temperature.map(|t| t.to_string())),
+        ("top_p", a.params.top_p.map(|p| p.to_string()), b.params.top_p.map(|p| p.to_string())),
+        ("top_k", a.params.top_k.map(|k| k.to_string()), b.params.top_k.map(|k| k.to_string())),
+        (
+            "person_generation",
+            a.params.person_generation.map(|p| p.as_str().to_string()),
+            b.params.person_generation.map(|p| p.as_str().to_string()),
+        ),
+        (
+            "modalities",
+            Some(modalities(&a.params.modalities)),
+            Some(modalities(&b.params.modalities)),
+        ),
+        ("negative_prompt", a.params.negative_prompt.clone(), b.params.negative_prompt.clone()),
+        ("status", Some(a.status_name().to_string()), Some(b.status_name().to_string())),
+    ];
+    let changes: Vec<FieldChange> = fields
+        .into_iter()
+        .filter(|(_, va, vb)| va != vb)
+        .map(|(field, a, b)| FieldChange { field, a, b })
+        .collect();
+    let prompt_diff = word_diff(&a.params.prompt, &b.params.prompt);
+
+    let comparison = serde_json::json!({
+        "a": a.id,
+        "b": b.id,
+        "changes": changes,
+        "prompt_changed": a.params.prompt != b.params.prompt,
+        "prompt_diff": prompt_diff,
+    });
+    let report = Report::new(&comparison)?
+        .quiet(changes.iter().map(|change| change.field))
+        .text(|| {
+            println!();
+            println!("{}: {}", "A".cyan().bold(), a.id);
+            println!("{}: {}", "B".cyan().bold(), b.id);
+            println!();
+
+            println!("{}:", t("Prompt").cyan().bold());
+            if a.params.prompt == b.params.prompt {
+                println!("  {}", "(unchanged)".dimmed());
             } else {
-                println!();
-                println!("{}: {}", "Job ID".cyan().bold(), job.id);
-                println!("{}: {}", "Action".cyan().bold(), job.action);
-                println!("{}: {}", "Status".cyan().bold(), job.status);
-                println!("{}: {}", "Model".cyan().bold(), job.model);
-                println!("{}: {}", "Created".cyan().bold(), job.created_at.format("%Y-%m-%d %H:%M:%S UTC"));
-                println!("{}: {}", "Updated".cyan().bold(), job.updated_at.format("%Y-%m-%d %H:%M:%S UTC"));
-                println!();
-                println!("{}:", "Prompt".cyan().bold());
-                println!("  {}", job.params.prompt);
-                println!();
-                println!("{}:", "Parameters".cyan().bold());
-                println!("  Aspect Ratio: {}", job.params.aspect_ratio);
-                println!("  Size: {}", job.params.size);
-                if let Some(seed) = job.params.seed {
-                    println!("  Seed: {}", seed);
-                }
-                if let Some(neg) = &job.params.negative_prompt {
-                    println!("  Negative: {}", neg);
-                }
+                let words: Vec<String> = prompt_diff
+                    .iter()
+                    .map(|change| match change.op {
+                        "delete" => format!("[-{}-]", change.text).red().to_string(),
+                        "insert" => format!("{{+{}+}}", change.text).green().to_string(),
+                        _ => change.text.clone(),
+                    })
+                    .collect();
+                println!("  {}", words.join(" "));
+            }
+            println!();
 
-                if !job.images.is_empty() {
-                    println!();
-                    println!("{}:", "Images".cyan().bold());
-                    for img in &job.images {
-                        if let Some(path) = &img.path {
-                            println!("  [{}] {}", img.index, path);
-                        } else {
-                            println!("  [{}] (base64 data, not downloaded)", img.index);
-                        }
-                    }
+            println!("{}:", t("Parameters").cyan().bold());
+            if changes.is_empty() {
+                println!("  {}", "(unchanged)".dimmed());
+            } else {
+                println!("  {:<16} {:<30} {:<30}", "FIELD", "A", "B");
+                for change in &changes {
+                    println!(
+                        "  {:<16} {:<30} {:<30}",
+                        change.field,
+                        change.a.as_deref().unwrap_or("-").red(),
+                        change.b.as_deref().unwrap_or("-").green()
+                    );
                 }
+            }
+        });
+    output::write(format, report)?;
 
-                if let Some(parent) = &job.parent_id {
-                    println!();
-                    println!("{}: {}", "Parent Job".cyan().bold(), parent);
+    if open {
+        for job in [&a, &b] {
+            match job.primary_image().and_then(|img| img.path.as_deref()) {
+                Some(path) => {
+                    if let Err(e) = platform::open_in_viewer(Path::new(path)) {
+                        tracing::warn!("{}", e);
+                    }
                 }
+                None => eprintln!(
+                    "{}: job '{}' has no downloaded images",
+                    t("Warning").yellow().bold(),
+                    job.id
+                ),
             }
         }
-        None => {
-            if format == "json" {
-                println!("null");
+    }
+
+    Ok(())
+}
+
+/// Word-level diff of two prompts (longest common subsequence)
+fn word_diff(a: &str, b: &str) -> Vec<WordChange> {
+    let a: Vec<&str> = a.split_whitespace().collect();
+    let b: Vec<&str> = b.split_whitespace().collect();
+
+    // lcs[i][j] = length of the LCS of a[i..] and b[j..]
+    let mut lcs = vec![vec![0usize; b.len() + 1]; a.len() + 1];
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            lcs[i][j] = if a[i] == b[j] {
+                lcs[i + 1][j + 1] + 1
             } else {
-                eprintln!("{}: Job '{}' not found", "Error".red().bold(), job_id);
-            }
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
         }
     }
 
-    Ok(())
+    let mut changes: Vec<WordChange> = Vec::new();
+    let mut push = |op: &'static str, word: &str| match changes.last_mut() {
+        Some(last) if last.op == op => {
+            last.text.push(' ');
+            last.text.push_str(word);
+        }
+        _ => changes.push(WordChange {
+            op,
+            text: word.to_string(),
+        }),
+    };
+
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() && j < b.len() {
+        if a[i] == b[j] {
+            push("equal", a[i]);
+            i += 1;
+            j += 1;
+        } else if lcs[i + 1][j] >= lcs[i][j + 1] {
+            push("delete", a[i]);
+            i += 1;
+        } else {
+            push("insert", b[j]);
+            j += 1;
+        }
+    }
+    for word in &a[i..] {
+        push("delete", word);
+    }
+    for word in &b[j..] {
+        push("insert", word);
+    }
+
+    changes
 }
 
-fn delete_job(job_id: &str, db: &Database) -> Result<()> {
-    if db.delete_job(job_id)? {
-        println!("{} Deleted job: {}", "✓".green(), job_id);
-    } else {
-        eprintln!("{}: Job '{}' not found", "Error".red().bold(), job_id);
+/// What a command changed on one job, as reported by `--format`
+#[derive(Debug, Serialize)]
+struct JobChange<'a> {
+    job_id: &'a str,
+    action: &'static str,
+    /// False if the job was already in the requested state
+    changed: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    index: Option<u8>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    path: Option<&'a str>,
+}
+
+impl<'a> JobChange<'a> {
+    fn new(job_id: &'a str, action: &'static str, changed: bool) -> Self {
+        Self {
+            job_id,
+            action,
+            changed,
+            index: None,
+            path: None,
+        }
     }
-    Ok(())
 }
 
-fn clear_jobs(force: bool, db: &Database) -> Result<()> {
-    let count = db.count_jobs()?;
+fn attach_file(job_id: &str, path: &Path, format: OutputFormat, db: &Database) -> Result<()> {
+    output::check(format)?;
+    let mut job = db
+        .get_job(job_id)?
+        .ok_or_else(|| BananaError::JobNotFound(job_id.to_string()))?;
+    let path = platform::display_path(&platform::canonicalize(path).context("File not found")?);
+
+    let attached = !job.attachments.iter().any(|a| a.path == path);
+    if attached {
+        job.attachments.push(JobAttachment {
+            path: path.clone(),
+            added_at: Utc::now(),
+        });
+        db.update_job(&job)?;
+    }
+
+    let change = JobChange {
+        path: Some(&path),
+        ..JobChange::new(&job.id, "attach", attached)
+    };
+    let report = Report::new(&change)?.quiet([&job.id]).text(|| {
+        if attached {
+            println!("{} Attached {} to {}", "✓".green(), path, job.id);
+        } else {
+            println!("{}", format!("Already attached to {}: {}", job.id, path).dimmed());
+        }
+    });
+    output::write(format, report)
+}
+
+fn delete_job(job_id: &str, format: OutputFormat, db: &Database) -> Result<()> {
+    output::check(format)?;
+    let deleted = db.delete_job(job_id)?;
+    if !deleted && format != OutputFormat::Text {
+        return Err(BananaError::JobNotFound(job_id.to_string()).into());
+    }
+
+    let report = Report::new(&JobChange::new(job_id, "delete", deleted))?.quiet([job_id]).text(|| {
+        if deleted {
+            println!("{} {}", "✓".green(), t("Moved job to trash: {id}").replace("{id}", job_id));
+            println!("{}", t("Undo with: banana jobs restore {id}").replace("{id}", job_id).dimmed());
+        } else {
+            eprintln!("{}: {}", t("Error").red().bold(), t("Job '{id}' not found").replace("{id}", job_id));
+        }
+    });
+    output::write(format, report)
+}
+
+fn set_starred(job_id: &str, starred: bool, format: OutputFormat, db: &Database) -> Result<()> {
+    output::check(format)?;
+    let mut job = db
+        .get_job(job_id)?
+        .ok_or_else(|| BananaError::JobNotFound(job_id.to_string()))?;
+
+    let changed = job.starred != starred;
+    job.starred = starred;
+    db.update_job(&job)?;
+
+    let action = if starred { "star" } else { "unstar" };
+    let report = Report::new(&JobChange::new(&job.id, action, changed))?.quiet([&job.id]).text(|| {
+        if starred {
+            println!("{} Starred {}", "✓".green(), job.id);
+        } else {
+            println!("{} Unstarred {}", "✓".green(), job.id);
+        }
+    });
+    output::write(format, report)
+}
+
+fn remove_image(job_id: &str, index: u8, format: OutputFormat, db: &Database) -> Result<()> {
+    output::check(format)?;
+    let mut job = db
+        .get_job(job_id)?
+        .ok_or_else(|| BananaError::JobNotFound(job_id.to_string()))?;
+
+    let Some(image) = history::delete_image(&mut job, index)? else {
+        let indexes: Vec<String> = job.images.iter().map(|img| img.index.to_string()).collect();
+        anyhow::bail!(
+            "Job '{}' has no image {} (images: {})",
+            job.id,
+            index,
+            if indexes.is_empty() { "none".to_string() } else { indexes.join(", ") }
+        );
+    };
+    db.update_job(&job)?;
+
+    let change = JobChange {
+        index: Some(index),
+        path: image.path.as_deref(),
+        ..JobChange::new(&job.id, "rm-image", true)
+    };
+    let report = Report::new(&change)?.quiet([&job.id]).text(|| {
+        match &image.path {
+            Some(path) => println!("{} Deleted image {} of {}: {}", "✓".green(), index, job.id, path),
+            None => println!("{} Deleted image {} of {}", "✓".green(), index, job.id),
+        }
+        if job.images.is_empty() {
+            println!("{}", format!("{} has no images left", job.id).dimmed());
+        }
+    });
+    output::write(format, report)
+}
+
+fn clear_jobs(filter: &JobFilter, force: bool, permanent: bool, delete_files: bool, db: &Database) -> Result<()> {
+    let count = db.count_matching(filter)?;
 
     if count == 0 {
         println!("{}", "No jobs to clear.".dimmed());
@@ -197,19 +996,145 @@ fn clear_jobs(force: bool, db: &Database) -> Result<()> {
 
     if !force {
         eprintln!(
-            "{}: This will delete {} job(s). Use --force to confirm.",
-            "Warning".yellow().bold(),
-            count
+            "{}: This will {} {} job(s){}. Use --force to confirm.",
+            t("Warning").yellow().bold(),
+            if permanent { "permanently delete" } else { "trash" },
+            count,
+            if delete_files { " and their image files" } else { "" }
         );
         return Ok(());
     }
 
-    // Delete all jobs by listing and deleting each
-    let jobs = db.list_jobs(count as u32 + 1, None)?;
-    for job in jobs {
-        db.delete_job(&job.id)?;
-    }
+    let jobs = db.clear_jobs(filter, permanent)?;
 
-    println!("{} Cleared {} job(s)", "✓".green(), count);
+    if permanent {
+        for job in &jobs {
+            if let Err(e) = thumbnails::remove_for_job(&job.id) {
+                tracing::warn!("Failed to remove thumbnails: {}", e);
+            }
+            if delete_files {
+                for path in job.images.iter().filter_map(|img| img.path.as_ref()) {
+                    if let Err(e) = std::fs::remove_file(path) {
+                        tracing::warn!("Failed to delete {}: {}", path, e);
+                    }
+                }
+            }
+        }
+        println!("{} Deleted {} job(s)", "✓".green(), jobs.len());
+    } else {
+        println!("{} Moved {} job(s) to trash", "✓".green(), jobs.len());
+    }
     Ok(())
 }
+
+fn restore_job(job_id: &str, format: OutputFormat, db: &Database) -> Result<()> {
+    output::check(format)?;
+    let restored = db.restore_job(job_id)?;
+    if !restored && format != OutputFormat::Text {
+        anyhow::bail!("Job '{}' is not in the trash", job_id);
+    }
+
+    let report = Report::new(&JobChange::new(job_id, "restore", restored))?.quiet([job_id]).text(|| {
+        if restored {
+            println!("{} {}", "✓".green(), t("Restored job: {id}").replace("{id}", job_id));
+        } else {
+            eprintln!(
+                "{}: {}",
+                t("Error").red().bold(),
+                t("Job '{id}' is not in the trash").replace("{id}", job_id)
+            );
+        }
+    });
+    output::write(format, report)
+}
+
+/// Submit a copy of a job's request as a new job with the original as its parent
+async fn retry_job(
+    job_id: &str,
+    options: JobOptions<'_>,
+    provider_flag: bool,
+    config: &Config,
+    db: &Database,
+) -> Result<()> {
+    let original = db
+        .get_job(job_id)?
+        .ok_or_else(|| BananaError::JobNotFound(job_id.to_string()))?;
+    if matches!(original.status_label(), "queued" | "running") {
+        anyhow::bail!("Job {} is still {}; retry it once it has finished", original.id, original.status_label());
+    }
+
+    let mut params = original.params.clone();
+    // Edits made with --with-context before their conversation was stored
+    // have neither a reference image nor context turns, so rebuild them
+    if let (JobAction::Edit { source_image }, None, true) =
+        (&original.action, &params.reference_image, params.context.is_empty())
+    {
+        let source = match &original.parent_id {
+            Some(parent_id) => db.get_job(parent_id)?,
+            None => None,
+        }
+        .with_context(|| format!("The job {} was edited from is gone; run banana edit again", original.id))?;
+        let (data, mime_type) =
+            imaging::load_for_edit(Path::new(source_image), &params.aspect_ratio, None, None).await?;
+        params = params.with_context(edit::context_turns(&source, data, mime_type));
+    }
+
+    let mut job = match &original.action {
+        JobAction::Generate => Job::new_generate(params),
+        JobAction::Edit { source_image } => Job::new_edit(params, source_image.clone()),
+        JobAction::Upscale { source_image } => Job::new_upscale(params, source_image.clone(), None),
+    };
+    job.parent_id = Some(original.id.clone());
+    job.project = original.project.clone();
+
+    // Send the retry to the backend the original ran on, not today's default
+    let config = match &original.provider {
+        Some(provider) if !provider_flag => config.for_backend(provider, original.base_url.as_deref()),
+        _ => config.clone(),
+    };
+
+    if options.format == OutputFormat::Text {
+        println!("{} {} ({})", "Retrying".cyan().bold(), original.id.yellow(), original.status_label());
+    }
+    pipeline::run_job(job, options, &config, db).await
+}
+
+fn list_trash(format: OutputFormat, db: &Database, config: &Config) -> Result<()> {
+    let trashed = db.list_deleted_jobs()?;
+
+    let entries: Vec<_> = trashed
+        .iter()
+        .map(|(job, deleted_at)| serde_json::json!({ "job": job, "deleted_at": deleted_at }))
+        .collect();
+    let report = Report::new(&entries)?
+        .quiet(trashed.iter().map(|(job, _)| &job.id))
+        .text(|| print_trash(&trashed, config));
+    output::write(format, report)
+}
+
+/// Print trashed jobs as a table
+fn print_trash(trashed: &[(Job, DateTime<Utc>)], config: &Config) {
+    if trashed.is_empty() {
+        println!("{}", t("Trash is empty.").dimmed());
+        return;
+    }
+
+    let dates = config.output.date_format();
+    let mut table = Table::new(&[t("ID"), t("ACTION"), t("PROMPT"), t("DELETED")]).flexible(2);
+    for (job, deleted_at) in trashed {
+        table.add_row(vec![
+            job.id.normal(),
+            job.action.to_string().normal(),
+            job.prompt_preview(PROMPT_PREVIEW_LEN).normal(),
+            dates.datetime(deleted_at).dimmed(),
+        ]);
+    }
+    table.print();
+    println!();
+    println!(
+        "{}",
+        t("Trashed jobs are purged after {n} days.")
+            .replace("{n}", &config.history.trash_days.to_string())
+            .dimmed()
+    );
+}
@@ -0,0 +1,178 @@
+use anyhow::{Context, Result};
+use clap::Args;
+use colored::Colorize;
+use image::{DynamicImage, Rgb, RgbImage};
+use serde_json::{json, Value};
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, TcpStream};
+
+use crate::imaging;
+
+#[derive(Args)]
+pub struct MockServerArgs {
+    /// Port to listen on (0 picks a free one)
+    #[arg(short, long, default_value = "8765")]
+    pub port: u16,
+
+    /// Images returned per generate request
+    #[arg(short = 'n', long, default_value = "1", value_parser = clap::value_parser!(u8).range(1..=4))]
+    pub images: u8,
+
+    /// Delay before each response, in milliseconds
+    #[arg(long, default_value = "0")]
+    pub delay_ms: u64,
+}
+
+/// Longest side of generated placeholder images
+const IMAGE_EDGE: u32 = 64;
+
+pub async fn run(args: MockServerArgs) -> Result<()> {
+    let listener = TcpListener::bind(("127.0.0.1", args.port))
+        .await
+        .with_context(|| format!("Failed to listen on port {}", args.port))?;
+    let url = format!("http://{}", listener.local_addr()?);
+
+    // The URL goes first and alone so scripts can read it
+    println!("{}", url);
+    eprintln!(
+        "{} Mock Gemini API listening. Point the CLI at it with:",
+        "✓".green()
+    );
+    eprintln!("  banana config set api.base_url {}", url);
+    eprintln!("{}", "Press Ctrl+C to stop.".dimmed());
+
+    let delay = Duration::from_millis(args.delay_ms);
+    loop {
+        let (stream, _) = listener.accept().await?;
+        let images = args.images;
+        tokio::spawn(async move {
+            if let Err(e) = serve(stream, images, delay).await {
+                tracing::debug!("Mock connection closed: {}", e);
+            }
+        });
+    }
+}
+
+/// Answer requests on one (keep-alive) connection until the client hangs up
+async fn serve(stream: TcpStream, images: u8, delay: Duration) -> Result<()> {
+    let mut reader = BufReader::new(stream);
+    loop {
+        let mut request_line = String::new();
+        if reader.read_line(&mut request_line).await? == 0 {
+            return Ok(());
+        }
+        let path = request_line.split_whitespace().nth(1).unwrap_or("/").to_string();
+
+        let mut content_length = 0;
+        loop {
+            let mut line = String::new();
+            reader.read_line(&mut line).await?;
+            let line = line.trim_end();
+            if line.is_empty() {
+                break;
+            }
+            if let Some((name, value)) = line.split_once(':') {
+                if name.eq_ignore_ascii_case("content-length") {
+                    content_length = value.trim().parse().unwrap_or(0);
+                }
+            }
+        }
+
+        let mut body = vec![0; content_length];
+        reader.read_exact(&mut body).await?;
+        let request: Value = serde_json::from_slice(&body).unwrap_or(Value::Null);
+
+        tokio::time::sleep(delay).await;
+
+        let (status, response) = respond(&path, &request, images);
+        let response = response.to_string();
+        let head = format!(
+            "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n",
+            status,
+            response.len()
+        );
+        let stream = reader.get_mut();
+        stream.write_all(head.as_bytes()).await?;
+        stream.write_all(response.as_bytes()).await?;
+        stream.flush().await?;
+    }
+}
+
+/// Status line and body for a request path
+fn respond(path: &str, request: &Value, images: u8) -> (&'static str, Value) {
+    let method = path.split('?').next().unwrap_or(path);
+    let prompt = prompt_text(request);
+
+    if method.ends_with(":countTokens") {
+        // Roughly four characters per token, like the real tokenizer on English
+        let tokens = prompt.len().div_ceil(4);
+        return ("200 OK", json!({ "totalTokens": tokens }));
+    }
+
+    if method.ends_with(":generateContent") {
+        let ratio = request
+            .pointer("/generation_config/image_config/aspect_ratio")
+            .or_else(|| request.pointer("/generationConfig/imageConfig/aspectRatio"))
+            .and_then(Value::as_str)
+            .unwrap_or("1:1");
+        let data = placeholder_png(&prompt, ratio);
+        let parts: Vec<Value> = (0..images)
+            .map(|_| json!({ "inlineData": { "mimeType": "image/png", "data": data } }))
+            .collect();
+        let prompt_tokens = prompt.len().div_ceil(4);
+        let candidate_tokens = 1290 * images as usize;
+        return (
+            "200 OK",
+            json!({
+                "candidates": [{ "content": { "parts": parts, "role": "model" }, "finishReason": "STOP" }],
+                "usageMetadata": {
+                    "promptTokenCount": prompt_tokens,
+                    "candidatesTokenCount": candidate_tokens,
+                    "totalTokenCount": prompt_tokens + candidate_tokens,
+                },
+            }),
+        );
+    }
+
+    (
+        "404 Not Found",
+        json!({ "error": { "code": 404, "message": format!("Unknown method: {}", method), "status": "NOT_FOUND" } }),
+    )
+}
+
+/// Text of the last text part in the request
+fn prompt_text(request: &Value) -> String {
+    request
+        .pointer("/contents")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(|content| content.get("parts")?.as_array())
+        .flatten()
+        .filter_map(|part| part.get("text")?.as_str())
+        .next_back()
+        .unwrap_or_default()
+        .to_string()
+}
+
+/// Solid-color PNG in the requested ratio, colored by the prompt so results are repeatable
+fn placeholder_png(prompt: &str, ratio: &str) -> String {
+    let (w, h) = ratio
+        .split_once(':')
+        .and_then(|(w, h)| Some((w.parse::<u32>().ok()?, h.parse::<u32>().ok()?)))
+        .filter(|(w, h)| *w > 0 && *h > 0)
+        .unwrap_or((1, 1));
+    let (width, height) = if w >= h {
+        (IMAGE_EDGE, (IMAGE_EDGE * h / w).max(1))
+    } else {
+        ((IMAGE_EDGE * w / h).max(1), IMAGE_EDGE)
+    };
+
+    let seed = prompt.bytes().fold(0u32, |hash, byte| hash.wrapping_mul(31).wrapping_add(byte as u32));
+    let color = Rgb([(seed >> 16) as u8, (seed >> 8) as u8, seed as u8]);
+    let img = DynamicImage::ImageRgb8(RgbImage::from_pixel(width, height, color));
+
+    // Encoding to memory only fails on allocation errors
+    imaging::encode_png(&img).map(|(data, _)| data).unwrap_or_default()
+}
@@ -1,4 +1,18 @@
+pub mod batch;
+pub mod bench;
 pub mod config;
+pub mod cp;
+pub mod draft;
 pub mod edit;
+pub mod edit_batch;
 pub mod generate;
+pub mod inspect;
 pub mod jobs;
+pub mod mock_server;
+pub mod pipeline;
+pub mod stats;
+pub mod sweep;
+pub mod tokens;
+pub mod upscale;
+pub mod version;
+pub mod wizard;
@@ -0,0 +1,226 @@
+use anyhow::Result;
+use colored::Colorize;
+use std::path::Path;
+
+use crate::api::GeminiClient;
+use crate::cli::destination::{self, Destination, OutputFlags};
+use crate::cli::progress::Progress;
+use crate::cli::output::{self, Report};
+use crate::cli::OutputFormat;
+use crate::config::{Config, DisplayMode};
+use crate::core::{Job, JobAction};
+use crate::db::Database;
+use crate::i18n::t;
+use crate::platform;
+
+/// Flags generate and edit share for running a single job
+pub struct JobOptions<'a> {
+    pub output: OutputFlags<'a>,
+    pub format: OutputFormat,
+    pub json_progress: bool,
+    pub no_watermark: bool,
+    pub keep_data: bool,
+}
+
+/// Messages that differ between generate and edit jobs
+struct Labels {
+    running: &'static str,
+    failed: &'static str,
+    saved: &'static str,
+    not_saved: &'static str,
+    images: &'static str,
+}
+
+impl Labels {
+    fn for_job(job: &Job) -> Self {
+        match job.action {
+            JobAction::Generate => Labels {
+                running: "Generating image: {prompt}...",
+                failed: "Generation failed",
+                saved: "Generated {n} image(s)",
+                not_saved: "Generated {n} image(s) (not downloaded)",
+                images: "Generated Images",
+            },
+            JobAction::Edit { .. } | JobAction::Upscale { .. } => Labels {
+                running: "Editing image: {prompt}...",
+                failed: "Edit failed",
+                saved: "Edited image saved",
+                not_saved: "Edit complete (not downloaded)",
+                images: "Edited Image",
+            },
+        }
+    }
+}
+
+/// Record `job`, run it, save its images, and report the result in `options.format`
+pub async fn run_job(mut job: Job, options: JobOptions<'_>, config: &Config, db: &Database) -> Result<()> {
+    options.output.check(job.params.num_images, options.format)?;
+    output::check(options.format)?;
+    let labels = Labels::for_job(&job);
+
+    // Create API client
+    let mut client = GeminiClient::from_config(config)?;
+    if options.no_watermark {
+        client = client.without_watermark();
+    }
+    if options.keep_data {
+        client = client.with_retained_data();
+    }
+
+    // Checks below apply to the model the job will really run on
+    client.route_job(&mut job);
+
+    // Say so up front rather than let a no-op flag look like it worked
+    if options.format == OutputFormat::Text {
+        for flag in job.params.ignored_params() {
+            eprintln!(
+                "{}: --{} is ignored by {}",
+                t("Warning").yellow().bold(),
+                flag,
+                job.params.model
+            );
+        }
+    }
+
+    // Reject over-long prompts before recording a job
+    client.preflight(&job.params).await?;
+
+    // Save to database
+    db.insert_new_job(&mut job)?;
+
+    // Show progress
+    let progress = Progress::start(
+        options.format,
+        options.json_progress,
+        &job.id,
+        t(labels.running).replace("{prompt}", &job.prompt_preview(40)),
+    );
+
+    // Set job as running
+    job.set_running(0);
+    db.update_job(&job)?;
+
+    let result = match client.generate_job(&mut job, db).await {
+        Ok(response) => client.process_response(&mut job, response),
+        Err(e) => Err(e),
+    };
+    if let Err(e) = result {
+        job.set_failed(e.to_string());
+        db.update_job(&job)?;
+
+        progress.fail(t(labels.failed), &e.to_string());
+
+        let report = Report::new(&job.with_artifacts())?.text(|| eprintln!("{}: {}", t("Error").red().bold(), e));
+        output::write(options.format, report)?;
+        return Err(e.into());
+    }
+
+    // Download images
+    let destination = Destination::resolve(options.output, config, &job.id)?;
+    if let Some(output_dir) = destination.dir() {
+        let paths = client
+            .download_images_with_progress(&mut job, output_dir, |saved| progress.saving(&saved))
+            .await?;
+
+        if options.output.open {
+            if let Some(first_path) = paths.first() {
+                if let Err(e) = platform::open_in_viewer(Path::new(first_path)) {
+                    tracing::warn!("{}", e);
+                }
+            }
+        }
+
+        progress.finish(t(labels.saved).replace("{n}", &paths.len().to_string()));
+
+        // Display based on format
+        if matches!(destination, Destination::Stdout(_)) {
+            destination::write_stdout(&paths)?;
+        } else {
+            let report = Report::new(&job.with_artifacts())?
+                .quiet(&paths)
+                .text(|| print_job(&job, &paths, &labels, &destination, &options, config));
+            output::write(options.format, report)?;
+        }
+    } else {
+        progress.finish(t(labels.not_saved).replace("{n}", &job.images.len().to_string()));
+
+        let report = Report::new(&job.with_artifacts())?.text(|| {});
+        output::write(options.format, report)?;
+    }
+
+    // Update database
+    db.update_job(&job)?;
+
+    Ok(())
+}
+
+/// Print a finished job's details and images as text
+fn print_job(
+    job: &Job,
+    paths: &[String],
+    labels: &Labels,
+    destination: &Destination,
+    options: &JobOptions,
+    config: &Config,
+) {
+    println!();
+    println!("{}: {}", t("Job ID").cyan().bold(), job.id);
+    match &job.action {
+        JobAction::Edit { source_image } | JobAction::Upscale { source_image } => {
+            println!("{}: {}", t("Source").cyan().bold(), source_image);
+            println!("{}: {}", t("Edit").cyan().bold(), job.params.prompt);
+            println!("{}: {}", t("Model").cyan().bold(), job.model);
+        }
+        JobAction::Generate => {
+            println!("{}: {}", t("Prompt").cyan().bold(), job.params.prompt);
+            println!("{}: {}", t("Model").cyan().bold(), job.model);
+            println!("{}: {}", t("Aspect Ratio").cyan().bold(), job.params.aspect_ratio);
+        }
+    }
+    println!("{}: {}", t("Status").cyan().bold(), t("completed").green());
+    println!();
+    println!("{}:", t(labels.images).cyan().bold());
+    for path in paths {
+        println!("  {}", path);
+    }
+    if let Destination::Temp(dir) = destination {
+        println!(
+            "{}: {} ({})",
+            t("Temporary").cyan().bold(),
+            platform::display_path(dir),
+            t("deleted after {n}h").replace("{n}", &config.history.tmp_hours.to_string())
+        );
+    }
+
+    // Display the images according to the configured mode (a viewer opens only the first)
+    if let Some(first_path) = paths.first() {
+        match config.output.display {
+            DisplayMode::Terminal => {
+                for path in paths {
+                    println!();
+                    display_image_terminal(path);
+                }
+            }
+            DisplayMode::Viewer if !options.output.open => {
+                if let Err(e) = platform::open_in_viewer(Path::new(first_path)) {
+                    tracing::warn!("{}", e);
+                }
+            }
+            DisplayMode::Viewer | DisplayMode::None => {}
+        }
+    }
+}
+
+/// Display an image in the terminal using viuer
+pub fn display_image_terminal(path: &str) {
+    let conf = viuer::Config {
+        width: Some(80),
+        height: Some(30),
+        absolute_offset: false,
+        ..Default::default()
+    };
+
+    if let Err(e) = viuer::print_from_file(path, &conf) {
+        tracing::debug!("Failed to display image in terminal: {}", e);
+    }
+}
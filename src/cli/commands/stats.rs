@@ -0,0 +1,199 @@
+use anyhow::Result;
+use chrono::{Duration, Local, NaiveDate, Utc};
+use clap::{Args, Subcommand, ValueEnum};
+use colored::Colorize;
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+use crate::cli::output::{self, Report};
+use crate::cli::OutputFormat;
+use crate::config::Config;
+use crate::db::Database;
+
+#[derive(Args)]
+pub struct StatsArgs {
+    #[command(subcommand)]
+    pub command: StatsCommand,
+}
+
+#[derive(Subcommand)]
+pub enum StatsCommand {
+    /// Estimated spend from stored usage metadata
+    Spend {
+        /// How to group the report
+        #[arg(short, long, value_enum, default_value_t = GroupBy::Day)]
+        group_by: GroupBy,
+
+        /// Only include jobs from the last N days
+        #[arg(short, long)]
+        days: Option<u32>,
+
+        /// Print CSV instead of a table
+        #[arg(long)]
+        csv: bool,
+
+        /// Output format (text, json, quiet, jsonl, template)
+        #[arg(short, long, value_enum, default_value_t = OutputFormat::Text)]
+        format: OutputFormat,
+    },
+}
+
+/// Grouping key for spend reports
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum GroupBy {
+    Day,
+    Model,
+    Project,
+}
+
+/// One row of the spend report
+#[derive(Debug, Default, Serialize)]
+pub struct SpendRow {
+    pub key: String,
+    pub jobs: u32,
+    pub images: u32,
+    pub tokens: i64,
+    pub cost_usd: f64,
+    /// Jobs whose cost couldn't be estimated (unknown model or no usage data)
+    pub unpriced_jobs: u32,
+}
+
+pub fn run(args: StatsArgs, config: &Config, db: &Database) -> Result<()> {
+    match args.command {
+        StatsCommand::Spend {
+            group_by,
+            days,
+            csv,
+            format,
+        } => spend(group_by, days, csv, format, config, db),
+    }
+}
+
+fn spend(
+    group_by: GroupBy,
+    days: Option<u32>,
+    csv: bool,
+    format: OutputFormat,
+    config: &Config,
+    db: &Database,
+) -> Result<()> {
+    let since = days.map(|d| Utc::now() - Duration::days(d as i64));
+    let jobs = db.list_jobs(u32::MAX, Some("completed"))?;
+
+    let mut groups: BTreeMap<String, SpendRow> = BTreeMap::new();
+    for job in jobs.iter().filter(|job| since.map(|s| job.created_at >= s).unwrap_or(true)) {
+        let key = match group_by {
+            GroupBy::Day => job.created_at.with_timezone(&Local).format("%Y-%m-%d").to_string(),
+            GroupBy::Model => job.model.clone(),
+            GroupBy::Project => job.project.clone().unwrap_or_else(|| "(none)".to_string()),
+        };
+
+        let row = groups.entry(key.clone()).or_insert_with(|| SpendRow {
+            key,
+            ..Default::default()
+        });
+        row.jobs += 1;
+        row.images += job.images.len() as u32;
+        row.tokens += job
+            .usage
+            .as_ref()
+            .and_then(|u| u.total_tokens)
+            .unwrap_or(0) as i64;
+        match job.estimated_cost() {
+            Some(cost) => row.cost_usd += cost,
+            None => row.unpriced_jobs += 1,
+        }
+    }
+
+    let rows: Vec<SpendRow> = groups.into_values().collect();
+    let total: f64 = rows.iter().map(|r| r.cost_usd).sum();
+
+    // Days are grouped (and sorted) by ISO date, then shown in the configured format
+    let dates = config.output.date_format();
+    let label = |key: &str| match (group_by, NaiveDate::parse_from_str(key, "%Y-%m-%d")) {
+        (GroupBy::Day, Ok(day)) => dates.date(day),
+        _ => key.to_string(),
+    };
+
+    if csv {
+        println!("{},jobs,images,tokens,cost_usd,unpriced_jobs", group_label(group_by));
+        for row in &rows {
+            println!(
+                "{},{},{},{},{:.4},{}",
+                csv_escape(&label(&row.key)),
+                row.jobs,
+                row.images,
+                row.tokens,
+                row.cost_usd,
+                row.unpriced_jobs
+            );
+        }
+        return Ok(());
+    }
+
+    let spend = serde_json::json!({
+        "group_by": group_label(group_by),
+        "rows": rows,
+        "total_cost_usd": total,
+    });
+    let report = Report::new(&spend)?
+        .records(&rows)?
+        .quiet(rows.iter().map(|row| label(&row.key)))
+        .text(|| {
+            if rows.is_empty() {
+                println!("{}", "No completed jobs found.".dimmed());
+                return;
+            }
+
+            println!(
+                "{:<30} {:>6} {:>7} {:>10} {:>10}",
+                group_label(group_by).to_uppercase().bold(),
+                "JOBS".bold(),
+                "IMAGES".bold(),
+                "TOKENS".bold(),
+                "COST".bold()
+            );
+            println!("{}", "-".repeat(67));
+
+            for row in &rows {
+                let marker = if row.unpriced_jobs > 0 { "*" } else { "" };
+                println!(
+                    "{:<30} {:>6} {:>7} {:>10} {:>10}",
+                    label(&row.key),
+                    row.jobs,
+                    row.images,
+                    row.tokens,
+                    format!("${:.2}{}", row.cost_usd, marker)
+                );
+            }
+
+            println!("{}", "-".repeat(67));
+            println!("{:<30} {:>36}", "Total".bold(), format!("${:.2}", total).green().bold());
+
+            if rows.iter().any(|r| r.unpriced_jobs > 0) {
+                println!();
+                println!(
+                    "{}",
+                    "* Some jobs have no usage data or an unknown model and are not included.".dimmed()
+                );
+            }
+        });
+    output::write(format, report)
+}
+
+fn group_label(group_by: GroupBy) -> &'static str {
+    match group_by {
+        GroupBy::Day => "day",
+        GroupBy::Model => "model",
+        GroupBy::Project => "project",
+    }
+}
+
+/// Quote a CSV field if it contains separators or quotes
+fn csv_escape(field: &str) -> String {
+    if field.contains([',', '"', '\n']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
@@ -0,0 +1,286 @@
+use anyhow::Result;
+use clap::builder::PossibleValuesParser;
+use clap::Args;
+use colored::Colorize;
+use futures_util::stream::{self, StreamExt};
+use serde::Serialize;
+use std::path::PathBuf;
+
+use super::batch;
+use crate::api::GeminiClient;
+use crate::cli::output::{self, Report};
+use crate::cli::progress::BatchProgress;
+use crate::cli::OutputFormat;
+use crate::config::Config;
+use crate::core::{prompt, Batch, BatchKind, BatchMember, GenerateParams};
+use crate::db::Database;
+use crate::platform;
+
+#[derive(Args)]
+pub struct SweepArgs {
+    /// The prompt shared by every variant
+    #[arg(required = true)]
+    pub prompt: String,
+
+    /// Aspect ratios to try, comma-separated (e.g. 1:1,16:9)
+    #[arg(short, long = "aspect-ratio", alias = "ar", value_delimiter = ',', value_parser = PossibleValuesParser::new(Config::aspect_ratios()))]
+    pub aspect_ratios: Vec<String>,
+
+    /// Image sizes to try, comma-separated (e.g. 1K,2K)
+    #[arg(short, long = "size", value_delimiter = ',', value_parser = PossibleValuesParser::new(Config::sizes()))]
+    pub sizes: Vec<String>,
+
+    /// Models or aliases to try, comma-separated
+    #[arg(short, long = "model", value_delimiter = ',')]
+    pub models: Vec<String>,
+
+    /// Output directory for downloaded images
+    #[arg(short, long)]
+    pub output: Option<PathBuf>,
+
+    /// Maximum number of generations running at once
+    #[arg(short = 'j', long, default_value = "3", value_parser = clap::value_parser!(u8).range(1..=16))]
+    pub concurrency: u8,
+
+    /// Don't download images automatically
+    #[arg(long)]
+    pub no_download: bool,
+
+    /// Substitute {KEY} placeholders in the prompt (repeatable)
+    #[arg(long = "var", value_name = "KEY=VALUE", value_parser = prompt::parse_var)]
+    pub vars: Vec<(String, String)>,
+
+    /// Project to attribute these jobs to (for spend reports)
+    #[arg(long)]
+    pub project: Option<String>,
+
+    /// Skip the configured watermark
+    #[arg(long)]
+    pub no_watermark: bool,
+
+    /// Output format (text, json, quiet, jsonl, template)
+    #[arg(short, long, value_enum, default_value_t = OutputFormat::Text)]
+    pub format: OutputFormat,
+}
+
+/// Result of one variant
+#[derive(Debug, Serialize)]
+pub struct SweepItem {
+    #[serde(flatten)]
+    pub variant: BatchMember,
+    pub job_id: Option<String>,
+    pub success: bool,
+    pub paths: Vec<String>,
+    pub error: Option<String>,
+}
+
+/// Summary of a whole sweep
+#[derive(Debug, Serialize)]
+pub struct SweepResult {
+    pub batch_id: String,
+    pub items: Vec<SweepItem>,
+}
+
+pub async fn run(args: SweepArgs, config: &Config, db: &Database) -> Result<()> {
+    output::check(args.format)?;
+
+    let prompt = prompt::render(&args.prompt, &args.vars)?;
+    let mut batch = Batch::new(BatchKind::Sweep, &prompt, expand(&args, config));
+
+    let mut client = GeminiClient::from_config(config)?;
+    if args.no_watermark {
+        client = client.without_watermark();
+    }
+
+    // Token limits differ per model, so check the prompt against each one
+    for model in models(&args, config) {
+        client.preflight(&GenerateParams::new(&prompt).with_model(model)).await?;
+    }
+
+    let output_dir = args
+        .output
+        .clone()
+        .unwrap_or_else(|| config.output.dir());
+    if !args.no_download && config.output.auto_download {
+        // Absolute so `batch resume` downloads to the same place from any directory
+        batch.output_dir = Some(platform::display_path(&std::env::current_dir()?.join(&output_dir)));
+    }
+    batch.project = args.project.clone();
+    batch.watermark = !args.no_watermark;
+    db.insert_batch(&batch)?;
+
+    let pb = if args.format == OutputFormat::Text {
+        println!(
+            "{} {} variant(s) as batch {}",
+            "Sweeping".cyan().bold(),
+            batch.members.len(),
+            batch.id.yellow()
+        );
+        Some(BatchProgress::start(batch.members.len() as u64, prompt.clone()))
+    } else {
+        None
+    };
+
+    let mut items: Vec<(usize, SweepItem)> = stream::iter(batch.members.iter().enumerate())
+        .map(|(index, member)| {
+            let client = &client;
+            let batch = &batch;
+            let pb = &pb;
+            async move {
+                let result = batch::run_member(client, batch, member, None, db).await;
+                let item = match result {
+                    Ok(job) => SweepItem {
+                        variant: member.clone(),
+                        job_id: Some(job.id.clone()),
+                        success: true,
+                        paths: job.images.iter().filter_map(|img| img.path.clone()).collect(),
+                        error: None,
+                    },
+                    Err((job_id, e)) => SweepItem {
+                        variant: member.clone(),
+                        job_id,
+                        success: false,
+                        paths: Vec::new(),
+                        error: Some(e.to_string()),
+                    },
+                };
+                if let Some(pb) = pb {
+                    let mark = if item.success { "✓".green() } else { "✗".red() };
+                    pb.println(format!(
+                        "{} {} {} {}",
+                        mark,
+                        item.variant.label(),
+                        item.job_id.as_deref().unwrap_or("-").dimmed(),
+                        item.error.as_deref().unwrap_or_default().red()
+                    ));
+                    pb.inc();
+                }
+                (index, item)
+            }
+        })
+        .buffer_unordered(args.concurrency as usize)
+        .collect()
+        .await;
+
+    // Report in matrix order regardless of completion order
+    items.sort_by_key(|(index, _)| *index);
+    let result = SweepResult {
+        batch_id: batch.id,
+        items: items.into_iter().map(|(_, item)| item).collect(),
+    };
+
+    if let Some(pb) = &pb {
+        pb.finish();
+    }
+
+    let failed = result.items.iter().filter(|item| !item.success).count();
+
+    let report = Report::new(&result)?
+        .records(&result.items)?
+        .quiet(result.items.iter().flat_map(|item| &item.paths))
+        .text(|| print_summary(&result));
+    output::write(args.format, report)?;
+
+    if failed > 0 {
+        anyhow::bail!("{} of {} variant(s) failed", failed, result.items.len());
+    }
+
+    Ok(())
+}
+
+/// Requested models with aliases resolved, or just the configured one
+fn models(args: &SweepArgs, config: &Config) -> Vec<String> {
+    if args.models.is_empty() {
+        vec![config.model(None)]
+    } else {
+        args.models.iter().map(|model| config.resolve_model(model)).collect()
+    }
+}
+
+/// Cross product of the requested models, ratios, and sizes.
+///
+/// An omitted dimension uses the (per-model) configured default.
+fn expand(args: &SweepArgs, config: &Config) -> Vec<BatchMember> {
+    let mut variants = Vec::new();
+    for model in models(args, config) {
+        let defaults = config.defaults_for(&model);
+        let ratios = if args.aspect_ratios.is_empty() {
+            vec![defaults.aspect_ratio.clone()]
+        } else {
+            args.aspect_ratios.clone()
+        };
+        let sizes = if args.sizes.is_empty() {
+            vec![defaults.size.clone()]
+        } else {
+            args.sizes.clone()
+        };
+
+        for aspect_ratio in &ratios {
+            for size in &sizes {
+                variants.push(BatchMember {
+                    model: model.clone(),
+                    aspect_ratio: aspect_ratio.clone(),
+                    size: size.clone(),
+                    source_image: None,
+                });
+            }
+        }
+    }
+    variants
+}
+
+/// Print a table of per-variant results
+fn print_summary(result: &SweepResult) {
+    println!(
+        "{:<6} {:<4} {:<28} {:<12} {:<10} {}",
+        "RATIO".bold(),
+        "SIZE".bold(),
+        "MODEL".bold(),
+        "JOB".bold(),
+        "STATUS".bold(),
+        "RESULT".bold()
+    );
+    println!("{}", "-".repeat(90));
+
+    for item in &result.items {
+        let status = if item.success {
+            "completed".green().to_string()
+        } else {
+            "failed".red().to_string()
+        };
+        let outcome = if item.success {
+            item.paths.first().cloned().unwrap_or_else(|| "(not downloaded)".to_string())
+        } else {
+            item.error.clone().unwrap_or_default()
+        };
+
+        println!(
+            "{:<6} {:<4} {:<28} {:<12} {:<10} {}",
+            item.variant.aspect_ratio,
+            item.variant.size,
+            item.variant.model,
+            item.job_id.as_deref().unwrap_or("-"),
+            status,
+            outcome
+        );
+    }
+
+    let succeeded = result.items.iter().filter(|item| item.success).count();
+    println!();
+    println!(
+        "{} {} succeeded, {} failed",
+        "Summary:".cyan().bold(),
+        succeeded.to_string().green(),
+        (result.items.len() - succeeded).to_string().red()
+    );
+    println!(
+        "{}",
+        format!("List this sweep again with: banana jobs --batch {}", result.batch_id).dimmed()
+    );
+    if succeeded < result.items.len() {
+        println!(
+            "{}",
+            format!("Re-run the failed variants with: banana batch resume {}", result.batch_id).dimmed()
+        );
+    }
+}
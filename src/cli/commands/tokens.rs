@@ -0,0 +1,75 @@
+use anyhow::Result;
+use clap::Args;
+use colored::Colorize;
+use serde::Serialize;
+
+use crate::api::GeminiClient;
+use crate::cli::output::{self, Report};
+use crate::cli::OutputFormat;
+use crate::config::Config;
+use crate::core::usage;
+use crate::i18n::t;
+
+#[derive(Args)]
+pub struct TokensArgs {
+    /// The prompt to count
+    #[arg(required = true)]
+    pub prompt: String,
+
+    /// Model (or alias) whose tokenizer and limit to use
+    #[arg(short, long)]
+    pub model: Option<String>,
+
+    /// Output format (text, json, quiet, jsonl, template)
+    #[arg(short, long, value_enum, default_value_t = OutputFormat::Text)]
+    pub format: OutputFormat,
+}
+
+/// Token count for a prompt against a model's input limit
+#[derive(Debug, Serialize)]
+pub struct TokenCount {
+    pub model: String,
+    pub tokens: u32,
+    pub limit: Option<u32>,
+    pub within_limit: bool,
+}
+
+pub async fn run(args: TokensArgs, config: &Config) -> Result<()> {
+    let model = config.model(args.model.as_deref());
+    let client = GeminiClient::from_config(config)?;
+
+    let tokens = client.count_tokens(&model, &args.prompt).await?;
+    let limit = usage::input_token_limit(&model);
+    let count = TokenCount {
+        model: model.to_string(),
+        tokens,
+        limit,
+        within_limit: limit.map(|l| tokens <= l).unwrap_or(true),
+    };
+
+    let report = Report::new(&count)?
+        .quiet([count.tokens])
+        .text(|| print_count(&count));
+    output::write(args.format, report)?;
+
+    Ok(())
+}
+
+/// Print the token count against the model's input limit
+fn print_count(count: &TokenCount) {
+    println!("{}: {}", "Model".cyan().bold(), count.model);
+    println!("{}: {}", "Tokens".cyan().bold(), count.tokens);
+    match count.limit {
+        Some(limit) => println!("{}: {}", "Limit".cyan().bold(), limit),
+        None => println!("{}: {}", "Limit".cyan().bold(), "unknown".dimmed()),
+    }
+
+    if !count.within_limit {
+        println!();
+        println!(
+            "{}: prompt exceeds the model limit by {} tokens",
+            t("Warning").yellow().bold(),
+            count.tokens - count.limit.unwrap_or(0)
+        );
+    }
+}
@@ -0,0 +1,378 @@
+use anyhow::{Context, Result};
+use clap::builder::PossibleValuesParser;
+use clap::Args;
+use colored::Colorize;
+use image::{imageops, GenericImageView, RgbaImage};
+use std::path::{Path, PathBuf};
+
+use crate::api::GeminiClient;
+use crate::cli::output::{self, Report};
+use crate::cli::progress::Progress;
+use crate::cli::OutputFormat;
+use crate::config::Config;
+use crate::core::{GenerateParams, Job, JobImage, TokenUsage};
+use crate::db::Database;
+use crate::heartbeat;
+use crate::i18n::t;
+use crate::imaging;
+use crate::platform;
+use crate::thumbnails;
+
+/// Model used for upscaling unless overridden (the only one supporting 4K)
+const UPSCALE_MODEL: &str = "gemini-3-pro-image-preview";
+
+/// Instruction sent alongside the source image
+const UPSCALE_PROMPT: &str = "Re-render this exact image at a higher resolution. \
+Preserve the composition, colors, and every detail; do not add, remove, or change anything.";
+
+/// Long edge a model without a size setting renders at
+const NATIVE_EDGE: u32 = 1024;
+
+/// Fraction of a tile's width and height re-rendered past each inner edge,
+/// so seams fall inside content the model saw on both sides
+const TILE_OVERLAP: f64 = 0.125;
+
+#[derive(Args)]
+pub struct UpscaleArgs {
+    /// Image file or job ID to upscale
+    #[arg(required = true)]
+    pub source: String,
+
+    /// Target size
+    #[arg(long, default_value = "4K", value_parser = PossibleValuesParser::new(["2K", "4K"]))]
+    pub to: String,
+
+    /// Model to use, or an alias (models without a size setting upscale tile by tile)
+    #[arg(short, long)]
+    pub model: Option<String>,
+
+    /// Use the local upscaler command from `hooks.upscaler` instead of the API
+    #[arg(long, conflicts_with = "model")]
+    pub external: bool,
+
+    /// Output directory for upscaled images
+    #[arg(short, long)]
+    pub output: Option<PathBuf>,
+
+    /// Skip the configured watermark
+    #[arg(long)]
+    pub no_watermark: bool,
+
+    /// Output format (text, json, quiet, jsonl, template)
+    #[arg(short, long, value_enum, default_value_t = OutputFormat::Text)]
+    pub format: OutputFormat,
+}
+
+/// Where the image to upscale comes from
+struct UpscaleSource {
+    path: PathBuf,
+    aspect_ratio: Option<String>,
+    parent_id: Option<String>,
+}
+
+pub async fn run(args: UpscaleArgs, config: &Config, db: &Database) -> Result<()> {
+    output::check(args.format)?;
+
+    let source = resolve_source(&args.source, db)?;
+
+    if args.external {
+        return run_external(args, source, config, db).await;
+    }
+
+    let image_path = platform::canonicalize(&source.path)
+        .context("Image file not found")?;
+    let (base64_data, mime_type) = imaging::load_reference(&image_path).await?;
+
+    let model = config.resolve_model(args.model.as_deref().unwrap_or(UPSCALE_MODEL));
+    let params = GenerateParams::new(UPSCALE_PROMPT)
+        .with_aspect_ratio(
+            source
+                .aspect_ratio
+                .clone()
+                .unwrap_or_else(|| config.defaults_for(&model).aspect_ratio),
+        )
+        .with_size(&args.to)
+        .with_model(model)
+        .with_reference_image(base64_data, mime_type);
+
+    let mut job = Job::new_upscale(params, platform::display_path(&image_path), source.parent_id);
+    db.insert_new_job(&mut job)?;
+
+    let mut client = GeminiClient::from_config(config)?;
+    if args.no_watermark {
+        client = client.without_watermark();
+    }
+
+    let progress = Progress::start(args.format, false, &job.id, format!("Upscaling to {}...", args.to));
+
+    job.set_running(0);
+    db.update_job(&job)?;
+
+    let result: Result<()> = async {
+        client.route_job(&mut job);
+        if job.params.supports_size() {
+            let response = client.generate_job(&mut job, db).await?;
+            client.process_response(&mut job, response)?;
+        } else {
+            upscale_tiled(&client, &mut job, db, &image_path).await?;
+        }
+        Ok(())
+    }
+    .await;
+
+    if let Err(e) = result {
+        job.set_failed(e.to_string());
+        db.update_job(&job)?;
+
+        progress.fail("Upscale failed", &e.to_string());
+
+        let report = Report::new(&job.with_artifacts())?.text(|| eprintln!("{}: {}", t("Error").red().bold(), e));
+        output::write(args.format, report)?;
+        return Err(e);
+    }
+
+    let output_dir = args
+        .output
+        .unwrap_or_else(|| config.output.dir());
+    let paths = client.download_images(&mut job, &output_dir).await?;
+    db.update_job(&job)?;
+
+    progress.finish("Upscaled image saved".to_string());
+
+    print_result(&job, &image_path, &paths, args.format)
+}
+
+/// Upscale on a model without a size setting.
+///
+/// The image is cut into a grid of tiles (2x2 for 2K, 4x4 for 4K), each
+/// tile is re-rendered at the model's native size, and the results are
+/// stitched into one image at the target size.
+async fn upscale_tiled(client: &GeminiClient, job: &mut Job, db: &Database, image_path: &Path) -> Result<()> {
+    let source = image::open(image_path).with_context(|| format!("Failed to decode {}", image_path.display()))?;
+    let grid: u32 = if job.params.size == "4K" { 4 } else { 2 };
+    let (w, h) = source.dimensions();
+    let scale = (grid * NATIVE_EDGE) as f64 / w.max(h) as f64;
+    let scaled = |v: u32| (v as f64 * scale).round() as u32;
+    let mut canvas = RgbaImage::new(scaled(w).max(1), scaled(h).max(1));
+
+    tracing::info!(
+        "{} has no size setting; upscaling {} in {}x{} tiles",
+        job.params.model,
+        job.id,
+        grid,
+        grid
+    );
+
+    let mut usage = TokenUsage::default();
+    for (i, (x0, y0, x1, y1)) in tiles(w, h, grid).into_iter().enumerate() {
+        // Widen the tile by the overlap, clamped to the image
+        let (mx, my) = (((x1 - x0) as f64 * TILE_OVERLAP) as u32, ((y1 - y0) as f64 * TILE_OVERLAP) as u32);
+        let (ex0, ey0) = (x0.saturating_sub(mx), y0.saturating_sub(my));
+        let (ex1, ey1) = ((x1 + mx).min(w), (y1 + my).min(h));
+
+        let (data, mime_type) = imaging::encode_png(&source.crop_imm(ex0, ey0, ex1 - ex0, ey1 - ey0))?;
+        let params = job.params.clone().with_reference_image(data, mime_type);
+        let response = heartbeat::run(db, &job.id, client.generate(&params)).await?;
+
+        let mut tile_job = job.clone();
+        tile_job.images.clear();
+        client.process_response(&mut tile_job, response)?;
+        let rendered = tile_job
+            .images
+            .first()
+            .and_then(|img| img.data.as_deref())
+            .context("No image in tile response")?;
+        if let Some(tile_usage) = tile_job.usage {
+            usage.add(&tile_usage);
+        }
+
+        // Scale the widened tile to its target size, then keep only the core
+        let rendered = imaging::decode_base64(rendered)?.resize_exact(
+            (scaled(ex1) - scaled(ex0)).max(1),
+            (scaled(ey1) - scaled(ey0)).max(1),
+            imageops::FilterType::Lanczos3,
+        );
+        let core = rendered.crop_imm(
+            scaled(x0) - scaled(ex0),
+            scaled(y0) - scaled(ey0),
+            scaled(x1) - scaled(x0),
+            scaled(y1) - scaled(y0),
+        );
+        imageops::replace(&mut canvas, &core.to_rgba8(), scaled(x0) as i64, scaled(y0) as i64);
+
+        job.set_running(((i + 1) * 100 / (grid * grid) as usize) as u8);
+        db.update_job(job)?;
+    }
+
+    let (data, mime_type) = imaging::encode_png(&image::DynamicImage::ImageRgba8(canvas))?;
+    job.images.clear();
+    job.add_image(0, data, mime_type);
+    job.usage = Some(usage);
+    job.set_completed();
+    Ok(())
+}
+
+/// Split a `w` x `h` image into a `grid` x `grid` set of tile bounds
+/// (x0, y0, x1, y1), row by row
+fn tiles(w: u32, h: u32, grid: u32) -> Vec<(u32, u32, u32, u32)> {
+    (0..grid)
+        .flat_map(|row| {
+            (0..grid).map(move |col| (col * w / grid, row * h / grid, (col + 1) * w / grid, (row + 1) * h / grid))
+        })
+        .collect()
+}
+
+/// Upscale by shelling out to the configured external upscaler
+async fn run_external(
+    args: UpscaleArgs,
+    source: UpscaleSource,
+    config: &Config,
+    db: &Database,
+) -> Result<()> {
+    let template = config.hooks.upscaler.as_deref().context(
+        "No external upscaler configured. Set one with: banana config set hooks.upscaler \"realesrgan -i {in} -o {out}\"",
+    )?;
+    let words = shell_words::split(template).context("Invalid hooks.upscaler command")?;
+    let program = words.first().context("hooks.upscaler is empty")?.clone();
+
+    let image_path = platform::canonicalize(&source.path)
+        .context("Image file not found")?;
+
+    let params = GenerateParams::new(template)
+        .with_aspect_ratio(source.aspect_ratio.as_deref().unwrap_or(&config.defaults.aspect_ratio))
+        .with_size(&args.to)
+        .with_model(format!("external:{}", program));
+
+    let mut job = Job::new_upscale(params, platform::display_path(&image_path), source.parent_id);
+    db.insert_new_job(&mut job)?;
+    job.set_running(0);
+    db.update_job(&job)?;
+
+    let output_dir = args
+        .output
+        .unwrap_or_else(|| config.output.dir());
+    std::fs::create_dir_all(&output_dir)?;
+
+    // Keep the source format; most upscalers pick the encoder from the extension
+    let ext = image_path
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or("png")
+        .to_lowercase();
+    let out_path = output_dir.join(format!("{}_0.{}", job.id, ext));
+
+    let in_str = image_path.to_string_lossy();
+    let out_str = out_path.to_string_lossy();
+    let argv: Vec<String> = words[1..]
+        .iter()
+        .map(|w| w.replace("{in}", &in_str).replace("{out}", &out_str))
+        .collect();
+
+    tracing::debug!("Running external upscaler: {} {:?}", program, argv);
+
+    let output = tokio::process::Command::new(&program)
+        .args(&argv)
+        .output()
+        .await;
+
+    let failure = match &output {
+        Err(e) => Some(format!("Failed to run '{}': {}", program, e)),
+        Ok(out) if !out.status.success() => Some(format!(
+            "'{}' exited with {}: {}",
+            program,
+            out.status,
+            String::from_utf8_lossy(&out.stderr).trim()
+        )),
+        Ok(_) if !out_path.exists() => Some(format!(
+            "'{}' did not write {}",
+            program,
+            out_path.display()
+        )),
+        Ok(_) => None,
+    };
+
+    if let Some(error) = failure {
+        job.set_failed(&error);
+        db.update_job(&job)?;
+
+        let report = Report::new(&job.with_artifacts())?.text(|| eprintln!("{}: {}", t("Error").red().bold(), error));
+        output::write(args.format, report)?;
+        anyhow::bail!(error);
+    }
+
+    let path = platform::display_path(&out_path);
+    let dimensions = image::image_dimensions(&out_path).ok();
+    job.images.push(JobImage {
+        index: 0,
+        data: None,
+        path: Some(path.clone()),
+        mime_type: mime_type_for_extension(&ext).to_string(),
+        width: dimensions.map(|(w, _)| w),
+        height: dimensions.map(|(_, h)| h),
+        primary: false,
+    });
+    if let Err(e) = thumbnails::ensure(&job.id, 0, &out_path) {
+        tracing::warn!("Failed to create thumbnail: {}", e);
+    }
+    job.set_completed();
+    db.update_job(&job)?;
+
+    print_result(&job, &image_path, &[path], args.format)
+}
+
+/// Print the finished upscale job
+fn print_result(job: &Job, image_path: &Path, paths: &[String], format: OutputFormat) -> Result<()> {
+    let report = Report::new(&job.with_artifacts())?.quiet(paths).text(|| {
+        println!();
+        println!("{}: {}", "Job ID".cyan().bold(), job.id);
+        if let Some(parent) = &job.parent_id {
+            println!("{}: {}", "Parent Job".cyan().bold(), parent);
+        }
+        println!("{}: {}", "Source".cyan().bold(), platform::display_path(image_path));
+        println!("{}: {}", "Size".cyan().bold(), job.params.size);
+        println!("{}: {}", "Model".cyan().bold(), job.model);
+        println!();
+        println!("{}:", "Upscaled Image".cyan().bold());
+        for path in paths {
+            println!("  {}", path);
+        }
+    });
+    output::write(format, report)
+}
+
+/// Guess a mime type from a file extension
+fn mime_type_for_extension(ext: &str) -> &'static str {
+    match ext {
+        "jpg" | "jpeg" => "image/jpeg",
+        "webp" => "image/webp",
+        "gif" => "image/gif",
+        _ => "image/png",
+    }
+}
+
+/// Resolve a job ID to its primary image, or treat the argument as a path
+fn resolve_source(source: &str, db: &Database) -> Result<UpscaleSource> {
+    if let Some(job) = db.get_job(source)? {
+        let path = job
+            .primary_image()
+            .and_then(|img| img.path.clone())
+            .with_context(|| format!("Job '{}' has no downloaded images", job.id))?;
+
+        return Ok(UpscaleSource {
+            path: PathBuf::from(path),
+            aspect_ratio: Some(job.params.aspect_ratio.clone()),
+            parent_id: Some(job.id),
+        });
+    }
+
+    let path = Path::new(source);
+    if !path.exists() {
+        anyhow::bail!("'{}' is neither a job ID nor an existing file", source);
+    }
+
+    Ok(UpscaleSource {
+        path: path.to_path_buf(),
+        aspect_ratio: None,
+        parent_id: None,
+    })
+}
@@ -0,0 +1,110 @@
+use anyhow::Result;
+use clap::Args;
+use colored::Colorize;
+use serde::Serialize;
+
+use crate::cli::output::{self, Report};
+use crate::cli::OutputFormat;
+use crate::config::{Config, ProviderKind};
+use crate::db::{Database, SCHEMA_VERSION};
+
+#[derive(Args)]
+pub struct VersionArgs {
+    /// Output as JSON (shorthand for --format json)
+    #[arg(long)]
+    pub json: bool,
+
+    /// Output format (text, json, quiet, jsonl, template)
+    #[arg(short, long, value_enum, default_value_t = OutputFormat::Text)]
+    pub format: OutputFormat,
+}
+
+/// Build and environment information for bug reports and agent bootstrapping
+#[derive(Debug, Serialize)]
+pub struct VersionInfo {
+    pub name: &'static str,
+    pub version: &'static str,
+    pub schema_version: u32,
+    pub os: &'static str,
+    pub arch: &'static str,
+    pub providers: Vec<&'static str>,
+    pub features: Features,
+    pub paths: Paths,
+}
+
+/// Runtime-detected capabilities
+#[derive(Debug, Serialize)]
+pub struct Features {
+    /// Terminal image protocols detected for the current terminal
+    pub image_protocols: Vec<&'static str>,
+    /// Whether API keys can be stored in the OS keyring
+    pub keyring: bool,
+}
+
+/// Locations of persisted state
+#[derive(Debug, Serialize)]
+pub struct Paths {
+    pub config: String,
+    pub database: String,
+}
+
+pub fn run(args: VersionArgs, config: &Config) -> Result<()> {
+    let info = collect(config)?;
+
+    let format = if args.json { OutputFormat::Json } else { args.format };
+    let report = Report::new(&info)?.quiet([info.version]).text(|| {
+        println!("{} {}", "banana".yellow().bold(), info.version);
+        println!();
+        println!("{}: {}", "Schema Version".cyan().bold(), info.schema_version);
+        println!("{}: {}/{}", "Platform".cyan().bold(), info.os, info.arch);
+        println!("{}: {}", "Providers".cyan().bold(), info.providers.join(", "));
+        println!(
+            "{}: {}",
+            "Image Protocols".cyan().bold(),
+            info.features.image_protocols.join(", ")
+        );
+        println!(
+            "{}: {}",
+            "Keyring".cyan().bold(),
+            if info.features.keyring { "available" } else { "unavailable" }
+        );
+        println!();
+        println!("{}: {}", "Config".cyan().bold(), info.paths.config);
+        println!("{}: {}", "Database".cyan().bold(), info.paths.database);
+    });
+    output::write(format, report)
+}
+
+/// Gather version information
+pub fn collect(config: &Config) -> Result<VersionInfo> {
+    Ok(VersionInfo {
+        name: env!("CARGO_PKG_NAME"),
+        version: env!("CARGO_PKG_VERSION"),
+        schema_version: SCHEMA_VERSION,
+        os: std::env::consts::OS,
+        arch: std::env::consts::ARCH,
+        providers: ProviderKind::ALL.iter().map(ProviderKind::as_str).collect(),
+        features: Features {
+            image_protocols: detect_image_protocols(),
+            keyring: false,
+        },
+        paths: Paths {
+            config: config.config_path.display().to_string(),
+            database: Database::db_path()?.display().to_string(),
+        },
+    })
+}
+
+/// Detect which terminal graphics protocols viuer can use
+fn detect_image_protocols() -> Vec<&'static str> {
+    let mut protocols = Vec::new();
+    if viuer::get_kitty_support() != viuer::KittySupport::None {
+        protocols.push("kitty");
+    }
+    if viuer::is_iterm_supported() {
+        protocols.push("iterm");
+    }
+    // Unicode half-blocks always work as a fallback
+    protocols.push("blocks");
+    protocols
+}
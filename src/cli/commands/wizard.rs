@@ -0,0 +1,192 @@
+use anyhow::Result;
+use clap::Args;
+use colored::Colorize;
+use std::io::{self, BufRead, IsTerminal, Write};
+use std::path::PathBuf;
+
+use crate::cli::commands::generate::{self, GenerateArgs};
+use crate::cli::OutputFormat;
+use crate::config::Config;
+use crate::db::Database;
+
+#[derive(Args)]
+pub struct WizardArgs {
+    /// Model to use, or an alias such as pro or flash
+    #[arg(short, long)]
+    pub model: Option<String>,
+
+    /// Output directory for downloaded images
+    #[arg(short, long)]
+    pub output: Option<PathBuf>,
+
+    /// Print the assembled prompt instead of generating
+    #[arg(long)]
+    pub dry_run: bool,
+}
+
+/// One guided question; an empty answer skips optional ones
+struct Question {
+    label: &'static str,
+    hint: &'static str,
+    required: bool,
+}
+
+const QUESTIONS: [Question; 4] = [
+    Question {
+        label: "Subject",
+        hint: "what should the image show? e.g. a red fox sitting on a mossy rock",
+        required: true,
+    },
+    Question {
+        label: "Style",
+        hint: "e.g. watercolor, 35mm photo, flat vector illustration",
+        required: false,
+    },
+    Question {
+        label: "Lighting",
+        hint: "e.g. soft golden-hour light, neon rim light, overcast",
+        required: false,
+    },
+    Question {
+        label: "Composition",
+        hint: "e.g. close-up, wide shot with lots of negative space, centered",
+        required: false,
+    },
+];
+
+pub async fn run(args: WizardArgs, config: &Config, db: &Database) -> Result<()> {
+    if !io::stdin().is_terminal() {
+        anyhow::bail!("banana wizard is interactive; use banana generate in scripts");
+    }
+
+    let stdin = io::stdin();
+    let mut input = stdin.lock();
+
+    println!("{}", "🍌 Prompt wizard".yellow().bold());
+    println!("{}", "Answer a few questions; press Enter to skip optional ones.".dimmed());
+
+    let defaults = config.defaults_for(&config.model(args.model.as_deref()));
+
+    let (prompt, aspect_ratio) = loop {
+        println!();
+
+        let mut parts = Vec::new();
+        for question in &QUESTIONS {
+            if let Some(answer) = ask(&mut input, question)? {
+                parts.push(answer);
+            }
+        }
+        let aspect_ratio = ask_aspect_ratio(&mut input, &defaults.aspect_ratio)?;
+        let prompt = parts.join(", ");
+
+        println!();
+        println!("{}:", "Prompt".cyan().bold());
+        println!("  {}", prompt);
+        println!("{}: {}", "Aspect Ratio".cyan().bold(), aspect_ratio);
+        println!();
+
+        if args.dry_run {
+            return Ok(());
+        }
+
+        match read_line(&mut input, &format!("{} [Y/n/r=restart] ", "Generate?".bold()))?
+            .to_lowercase()
+            .as_str()
+        {
+            "" | "y" | "yes" => break (prompt, aspect_ratio),
+            "r" | "restart" => continue,
+            _ => {
+                println!("{}", "Cancelled.".dimmed());
+                return Ok(());
+            }
+        }
+    };
+
+    generate::run(
+        GenerateArgs {
+            prompt,
+            aspect_ratio: Some(aspect_ratio),
+            size: None,
+            model: args.model,
+            seed: None,
+            num_images: None,
+            temperature: None,
+            top_p: None,
+            top_k: None,
+            people: None,
+            modalities: Vec::new(),
+            output: args.output,
+            no_download: false,
+            tmp: false,
+            json_progress: false,
+            open: false,
+            vars: Vec::new(),
+            gen_config: Vec::new(),
+            project: None,
+            no_watermark: false,
+            keep_data: false,
+            format: OutputFormat::Text,
+        },
+        config,
+        db,
+    )
+    .await
+}
+
+/// Ask one question, re-asking required ones until answered
+fn ask(input: &mut impl BufRead, question: &Question) -> Result<Option<String>> {
+    println!("{}", question.hint.dimmed());
+    loop {
+        let answer = read_line(input, &format!("{}: ", question.label.cyan().bold()))?;
+        if !answer.is_empty() {
+            return Ok(Some(answer));
+        }
+        if !question.required {
+            return Ok(None);
+        }
+    }
+}
+
+/// Pick an aspect ratio by number or value
+fn ask_aspect_ratio(input: &mut impl BufRead, default: &str) -> Result<String> {
+    let ratios = Config::aspect_ratios();
+    let choices: Vec<String> = ratios
+        .iter()
+        .enumerate()
+        .map(|(i, r)| format!("{}) {}", i + 1, r))
+        .collect();
+    println!("{}", choices.join("  ").dimmed());
+
+    loop {
+        let answer = read_line(
+            input,
+            &format!("{} [{}]: ", "Aspect ratio".cyan().bold(), default),
+        )?;
+        if answer.is_empty() {
+            return Ok(default.to_string());
+        }
+        if let Some(ratio) = answer
+            .parse::<usize>()
+            .ok()
+            .and_then(|n| n.checked_sub(1))
+            .and_then(|i| ratios.get(i))
+        {
+            return Ok(ratio.to_string());
+        }
+        if ratios.contains(&answer.as_str()) {
+            return Ok(answer);
+        }
+        println!("{}", "Pick a number from the list or type a ratio like 16:9".dimmed());
+    }
+}
+
+fn read_line(input: &mut impl BufRead, prompt: &str) -> Result<String> {
+    print!("{}", prompt);
+    io::stdout().flush()?;
+
+    let mut line = String::new();
+    if input.read_line(&mut line)? == 0 {
+        anyhow::bail!("Input closed");
+    }
+    Ok(line.trim().to_string())
+}
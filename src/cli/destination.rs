@@ -0,0 +1,94 @@
+use anyhow::{Context, Result};
+use std::io::{self, IsTerminal, Write};
+use std::path::{Path, PathBuf};
+
+use crate::cli::OutputFormat;
+use crate::config::Config;
+use crate::tmp_output;
+
+/// The download flags shared by generate and edit
+#[derive(Debug, Clone, Copy)]
+pub struct OutputFlags<'a> {
+    /// `--output`: a directory, or `-` for stdout
+    pub output: Option<&'a Path>,
+    pub no_download: bool,
+    pub tmp: bool,
+    pub open: bool,
+}
+
+impl OutputFlags<'_> {
+    fn is_stdout(&self) -> bool {
+        self.output == Some(Path::new("-"))
+    }
+
+    /// Reject combinations that can't work before any request is made
+    pub fn check(&self, num_images: u8, format: OutputFormat) -> Result<()> {
+        if !self.is_stdout() {
+            return Ok(());
+        }
+        if self.open {
+            anyhow::bail!("--output - writes the image to stdout; it can't be combined with --open");
+        }
+        if !matches!(format, OutputFormat::Text | OutputFormat::Quiet) {
+            anyhow::bail!("--output - writes the image to stdout; use it with --format text or quiet");
+        }
+        if num_images > 1 {
+            anyhow::bail!("--output - writes a single image; drop -n or pass -n 1");
+        }
+        if io::stdout().is_terminal() {
+            anyhow::bail!("Refusing to write image data to a terminal; redirect or pipe --output -");
+        }
+        Ok(())
+    }
+}
+
+/// Where a finished job's images go.
+///
+/// The first of these that applies wins:
+/// 1. `--no-download`: nowhere; the job keeps the image data
+/// 2. `--output -`: stdout, via a `--tmp` directory so the job still has a file
+/// 3. `--tmp`: a fresh temporary directory
+/// 4. `--output <dir>`: that directory, even when `output.auto_download` is off
+/// 5. `--open`: `output.directory`
+/// 6. `output.auto_download`: `output.directory` if on, nowhere if off
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Destination {
+    None,
+    Stdout(PathBuf),
+    Temp(PathBuf),
+    Dir(PathBuf),
+}
+
+impl Destination {
+    /// Resolve the flags for `job_id`, creating its temporary directory if one is needed
+    pub fn resolve(flags: OutputFlags, config: &Config, job_id: &str) -> Result<Self> {
+        Ok(match flags {
+            OutputFlags { no_download: true, .. } => Destination::None,
+            _ if flags.is_stdout() => Destination::Stdout(tmp_output::create(job_id)?),
+            OutputFlags { tmp: true, .. } => Destination::Temp(tmp_output::create(job_id)?),
+            OutputFlags { output: Some(dir), .. } => Destination::Dir(dir.to_path_buf()),
+            OutputFlags { open: true, .. } => Destination::Dir(config.output.dir()),
+            _ if config.output.auto_download => Destination::Dir(config.output.dir()),
+            _ => Destination::None,
+        })
+    }
+
+    /// Directory images are saved to, if they are saved at all
+    pub fn dir(&self) -> Option<&Path> {
+        match self {
+            Destination::None => None,
+            Destination::Stdout(dir) | Destination::Temp(dir) | Destination::Dir(dir) => Some(dir),
+        }
+    }
+}
+
+/// Copy saved images to stdout, for `--output -`
+pub fn write_stdout(paths: &[String]) -> Result<()> {
+    let mut stdout = io::stdout().lock();
+    for path in paths {
+        let bytes = std::fs::read(path).with_context(|| format!("Failed to read {}", path))?;
+        stdout.write_all(&bytes)?;
+    }
+    stdout.flush()?;
+    Ok(())
+}
@@ -1,6 +1,14 @@
 pub mod commands;
+pub mod destination;
+pub mod output;
+pub mod progress;
+pub mod table;
 
-use clap::{Parser, Subcommand};
+use clap::builder::PossibleValuesParser;
+use clap::{Parser, Subcommand, ValueEnum};
+use std::path::PathBuf;
+
+use crate::config::ProviderKind;
 
 #[derive(Parser)]
 #[command(
@@ -38,6 +46,10 @@ EXAMPLES:
     banana config set defaults.aspect_ratio 16:9
     banana config set api.model gemini-3-pro-image-preview
 
+  Use another provider for one run:
+    banana generate "a paper crane" --provider openai
+    banana generate "a foggy harbor" --provider replicate --model black-forest-labs/flux-dev
+
   Launch interactive TUI:
     banana
 
@@ -45,6 +57,9 @@ OUTPUT FORMATS:
   --format text   Human-readable output (default)
   --format json   Machine-readable JSON for AI agents
   --format quiet  Minimal output, just file paths
+  --format jsonl  One JSON object per line (per job, image, or row)
+  --format template --template '{id}\t{model}'
+                  One line per job, image, or row from a template
 
 For AI agent integration, use --format json for structured output."#,
     after_help = r#"CONFIGURATION:
@@ -65,6 +80,22 @@ MORE INFO:
 pub struct Cli {
     #[command(subcommand)]
     pub command: Option<Commands>,
+
+    /// Save every API response as a fixture in DIR
+    #[arg(long, global = true, value_name = "DIR", conflicts_with = "replay")]
+    pub record: Option<PathBuf>,
+
+    /// Answer API requests from fixtures in DIR instead of the network
+    #[arg(long, global = true, value_name = "DIR")]
+    pub replay: Option<PathBuf>,
+
+    /// Image backend for this run, overriding api.provider
+    #[arg(long, global = true, value_parser = PossibleValuesParser::new(ProviderKind::variants()))]
+    pub provider: Option<String>,
+
+    /// Line printed per record with --format template, e.g. '{id}\t{model}'
+    #[arg(long, global = true)]
+    pub template: Option<String>,
 }
 
 #[derive(Subcommand)]
@@ -85,14 +116,40 @@ pub enum Commands {
   High resolution:
     banana generate "detailed portrait" --size 2K
 
+  Four variations in one request:
+    banana generate "a minimalist fox logo" -n 4
+
   JSON output for AI agents:
     banana generate "abstract art" --format json
 
   Custom output directory:
-    banana generate "logo design" --output ./logos"#
+    banana generate "logo design" --output ./logos
+
+  Write the image to stdout:
+    banana generate "a tiny cactus" --output - > cactus.png
+
+  Fill a prompt template:
+    banana generate "a {animal} in {style} style" --var animal=fox --var style=ukiyo-e
+
+  Set a generationConfig field that has no flag yet:
+    banana generate "a crowded market" --gen-config media_resolution=MEDIA_RESOLUTION_HIGH"#
     )]
     Generate(commands::generate::GenerateArgs),
 
+    /// Build a prompt step by step and generate it
+    ///
+    /// Asks for a subject, style, lighting, composition, and aspect ratio,
+    /// previews the assembled prompt, then generates it.
+    #[command(
+        after_help = r#"EXAMPLES:
+  Start the wizard:
+    banana wizard
+
+  Just assemble the prompt:
+    banana wizard --dry-run"#
+    )]
+    Wizard(commands::wizard::WizardArgs),
+
     /// Edit an existing image using a text prompt
     ///
     /// Modifies images using semantic editing - describe what you want to change
@@ -110,10 +167,100 @@ pub enum Commands {
     banana edit scene.png "change the sky to sunset colors"
 
   Remove elements:
-    banana edit room.jpg "remove the chair in the corner""#
+    banana edit room.jpg "remove the chair in the corner"
+
+  Watch a folder and edit every new image:
+    banana edit --watch ./incoming "apply warm film grade" --output ./graded
+
+  Change the aspect ratio without distortion:
+    banana edit portrait.jpg "extend the background" --ar 16:9 --fit pad
+
+  Refine a previous result, keeping its original prompt as context:
+    banana edit banana-output/bn_abc12345_0.png "make the banana shinier" --with-context"#
     )]
     Edit(commands::edit::EditArgs),
 
+    /// Apply the same edit to many images at once
+    ///
+    /// Expands a glob pattern and runs the edit on every matching file with
+    /// bounded concurrency. Each file gets its own job record.
+    #[command(
+        after_help = r#"EXAMPLES:
+  Edit every PNG in a folder:
+    banana edit-batch "shoot/*.png" "remove the background"
+
+  Limit concurrency and choose an output folder:
+    banana edit-batch "photos/**/*.jpg" "make it black and white" -j 2 --output ./bw
+
+  JSON summary for scripts:
+    banana edit-batch "*.png" "add a vignette" --format json"#
+    )]
+    EditBatch(commands::edit_batch::EditBatchArgs),
+
+    /// Generate one prompt across a matrix of parameters
+    ///
+    /// Expands the cross-product of aspect ratios, sizes, and models into
+    /// jobs that share a batch ID, then prints a results table.
+    #[command(
+        after_help = r#"EXAMPLES:
+  Compare ratios and sizes:
+    banana sweep "a lighthouse at dusk" --ar 1:1,16:9 --size 1K,2K
+
+  Compare models:
+    banana sweep "isometric city block" --model gemini-3-pro-image-preview,gemini-2.5-flash-image
+
+  List the sweep's jobs later:
+    banana jobs --batch sw_abc12345"#
+    )]
+    Sweep(commands::sweep::SweepArgs),
+
+    /// Manage batches run by sweep and edit-batch
+    #[command(
+        after_help = r#"EXAMPLES:
+  Re-run only the failed and unfinished jobs:
+    banana batch resume sw_abc12345
+
+  Resume with lower concurrency:
+    banana batch resume eb_def67890 -j 1"#
+    )]
+    Batch(commands::batch::BatchArgs),
+
+    /// Re-render an image or job result at a higher resolution
+    ///
+    /// Submits the image to a high-resolution capable model. When given a
+    /// job ID, the result is stored as a child job of the original.
+    #[command(
+        after_help = r#"EXAMPLES:
+  Upscale a previous job's image to 4K:
+    banana upscale bn_abc12345 --to 4K
+
+  Upscale a file on disk:
+    banana upscale photo.png --to 2K
+
+  Use a local upscaler configured in [hooks]:
+    banana config set hooks.upscaler "realesrgan -i {in} -o {out}"
+    banana upscale bn_abc12345 --external"#
+    )]
+    Upscale(commands::upscale::UpscaleArgs),
+
+    /// Generate cheap drafts, then re-render the best one at high quality
+    ///
+    /// Renders several 1K drafts with the draft model and lists them by
+    /// number. With --refine-best, the chosen draft is re-rendered with the
+    /// quality model as a child job, keeping its composition.
+    #[command(
+        after_help = r#"EXAMPLES:
+  Four drafts, then pick one to refine:
+    banana draft "a fox in a snowy forest" --refine-best
+
+  Choose without being asked (for scripts):
+    banana draft "a fox in a snowy forest" -n 6 --refine-best --pick 3 --format json
+
+  Refine at 2K with a specific model:
+    banana draft "isometric city block" --refine-best --to 2K --model pro"#
+    )]
+    Draft(commands::draft::DraftArgs),
+
     /// Manage and view job history
     ///
     /// View, inspect, and manage your generation history.
@@ -129,20 +276,152 @@ pub enum Commands {
     banana jobs --status completed
     banana jobs --status failed
 
+  Group by day:
+    banana jobs --limit 100 --group-by day
+
   View job details:
     banana jobs show bn_abc12345
 
-  Delete a job:
+  Follow status changes live:
+    banana jobs watch --batch sw_abc12345
+
+  Delete a job (moves it to the trash):
     banana jobs delete bn_abc12345
 
+  List and restore trashed jobs:
+    banana jobs trash
+    banana jobs restore bn_abc12345
+
+  Protect a job from the history size caps:
+    banana jobs star bn_abc12345
+
+  Delete one image of a multi-image job:
+    banana jobs rm-image bn_abc12345 2
+
+  Link a retouched export to its job:
+    banana jobs attach bn_abc12345 ~/Desktop/final_retouch.psd
+
+  Compare two experiments:
+    banana jobs diff bn_abc12345 bn_def67890 --open
+
+  Run a failed job again (the new job's parent is the original):
+    banana jobs retry bn_abc12345
+
   Clear all history:
     banana jobs clear --force
 
+  Clear failed jobs older than a week, permanently, with their files:
+    banana jobs clear --status failed --older-than 7d --permanent --delete-files --force
+
   JSON output:
     banana jobs --format json"#
     )]
     Jobs(commands::jobs::JobsArgs),
 
+    /// Show the banana metadata embedded in an image file
+    ///
+    /// Downloaded images carry their job ID, prompt, and model. If the job
+    /// is still in the history its full record is printed as well.
+    #[command(
+        after_help = r#"EXAMPLES:
+  Where did this image come from?
+    banana inspect banana-output/bn_abc12345_0.png
+
+  Just the job ID:
+    banana inspect photo.png --format quiet"#
+    )]
+    Inspect(commands::inspect::InspectArgs),
+
+    /// Copy jobs and their images between databases
+    ///
+    /// Useful for consolidating histories from several machines. The source
+    /// and destination can be data directories or jobs.db files.
+    #[command(
+        after_help = r#"EXAMPLES:
+  Import everything from a laptop's synced data dir:
+    banana cp --all --from /mnt/laptop/banana-cli
+
+  Export selected jobs:
+    banana cp bn_abc12345 bn_def67890 --to ./export
+
+  Overwrite jobs that already exist:
+    banana cp --all --from ./backup --force"#
+    )]
+    Cp(commands::cp::CpArgs),
+
+    /// Usage and spend statistics
+    ///
+    /// Reports estimated cost from the token usage stored with each job.
+    /// Estimates use published list prices and may differ from your bill.
+    #[command(
+        after_help = r#"EXAMPLES:
+  Spend per day:
+    banana stats spend
+
+  Spend per project over the last 30 days:
+    banana stats spend --group-by project --days 30
+
+  CSV for invoicing:
+    banana stats spend --group-by project --csv > spend.csv"#
+    )]
+    Stats(commands::stats::StatsArgs),
+
+    /// Count the tokens in a prompt
+    ///
+    /// Uses the countTokens endpoint and compares the result with the
+    /// model's input limit. Generate and edit run the same check
+    /// automatically before sending long prompts.
+    #[command(
+        after_help = r#"EXAMPLES:
+  Count tokens for the default model:
+    banana tokens "a cosmic banana floating in space"
+
+  Check a template-generated prompt:
+    banana tokens "$(cat prompt.txt)" --model imagen-4.0-generate-001
+
+  Just the number:
+    banana tokens "sunset over mountains" --format quiet"#
+    )]
+    Tokens(commands::tokens::TokensArgs),
+
+    /// Compare latency, reliability, and cost across models
+    ///
+    /// Sends the same small prompt to each model several times in turn and
+    /// reports latency percentiles, failure rates, and estimated cost.
+    /// Every run is a real (billed) request.
+    #[command(
+        after_help = r#"EXAMPLES:
+  Benchmark every known model, 3 runs each:
+    banana bench
+
+  Compare flash and pro with more samples:
+    banana bench --model gemini-2.5-flash-image,gemini-3-pro-image-preview -n 10
+
+  Machine-readable results:
+    banana bench --format json"#
+    )]
+    Bench(commands::bench::BenchArgs),
+
+    /// Run a local stand-in for the Gemini API
+    ///
+    /// Answers generate and countTokens requests with placeholder images
+    /// (colored by prompt) and plausible usage, so pipelines can be tried
+    /// without an API key or quota. Prints its URL on the first line.
+    #[command(
+        after_help = r#"EXAMPLES:
+  Start the mock and point the CLI at it:
+    banana mock-server --port 8765 &
+    banana config set api.base_url http://127.0.0.1:8765
+
+  Record fixtures against it, then replay them offline:
+    banana --record ./fixtures generate "a red apple"
+    banana --replay ./fixtures generate "a red apple"
+
+  Simulate a slow API returning four images:
+    banana mock-server -n 4 --delay-ms 3000"#
+    )]
+    MockServer(commands::mock_server::MockServerArgs),
+
     /// View or modify configuration
     ///
     /// Manage API keys, default parameters, and output settings.
@@ -153,14 +432,27 @@ pub enum Commands {
   Show all settings:
     banana config show
 
+  Snapshot to paste into a bug report (secrets masked):
+    banana config show --redacted --format json
+
   Get a specific value:
     banana config get defaults.aspect_ratio
+    banana config get api.max_retries --format json
 
   Set values:
     banana config set api.key YOUR_API_KEY
     banana config set defaults.aspect_ratio 16:9
     banana config set defaults.size 2K
     banana config set output.directory ~/Pictures/banana
+    banana config set defaults.aspect_ratio=16:9 defaults.size=2K
+    banana config set --from-file overrides.toml
+
+  Model aliases:
+    banana config set api.model flash
+    banana config set models.aliases.fast gemini-2.5-flash-image
+
+  List all settings:
+    banana config keys
 
   Show config file path:
     banana config path
@@ -169,15 +461,37 @@ pub enum Commands {
     banana config reset --force
 
 AVAILABLE SETTINGS:
-  api.key              - Gemini API key
-  api.model            - Default model
-  defaults.aspect_ratio - Default aspect ratio
-  defaults.size        - Default image size (1K, 2K, 4K)
-  output.directory     - Where to save images
-  output.auto_download - Auto-download images (true/false)
-  output.display       - Display mode (terminal/viewer/none)
-  tui.show_images      - Show images in TUI (true/false)
-  tui.theme            - TUI theme (dark/light)"#
+  Run `banana config keys` to list every setting with its type, allowed
+  values, default, and where the current value comes from."#
     )]
     Config(commands::config::ConfigArgs),
+
+    /// Show version, enabled features, and data paths
+    ///
+    /// Prints build and environment information useful for bug reports.
+    /// Use --json for a machine-readable snapshot.
+    #[command(
+        after_help = r#"EXAMPLES:
+  Human-readable:
+    banana version
+
+  JSON for bug reports and agents:
+    banana version --json"#
+    )]
+    Version(commands::version::VersionArgs),
+}
+
+/// Output format shared by all commands
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    /// Human-readable output
+    Text,
+    /// Machine-readable JSON
+    Json,
+    /// Minimal output (file paths or IDs only)
+    Quiet,
+    /// One compact JSON object per line (per job, image, or row)
+    Jsonl,
+    /// One line per job, image, or row, filled in from --template
+    Template,
 }
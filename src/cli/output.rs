@@ -0,0 +1,189 @@
+use anyhow::Result;
+use serde::Serialize;
+use serde_json::Value;
+use std::sync::OnceLock;
+
+use crate::cli::OutputFormat;
+
+/// Line printed per record by `--format template` (`--template`)
+static TEMPLATE: OnceLock<String> = OnceLock::new();
+
+/// Set the `--template` line for this process; `\t` and `\n` stand for a tab and a newline
+pub fn set_template(template: Option<String>) {
+    if let Some(template) = template {
+        let _ = TEMPLATE.set(template.replace("\\t", "\t").replace("\\n", "\n"));
+    }
+}
+
+/// A command's result, ready to be written in any output format.
+///
+/// json prints the value as-is; jsonl and template print one line per record,
+/// which are the elements of a list value, the value itself otherwise, or
+/// whatever [`Report::records`] sets.
+pub struct Report<'a> {
+    value: Value,
+    records: Option<Vec<Value>>,
+    quiet: Vec<String>,
+    text: Option<Box<dyn FnOnce() + 'a>>,
+}
+
+impl<'a> Report<'a> {
+    pub fn new(value: &impl Serialize) -> Result<Self> {
+        Ok(Self {
+            value: serde_json::to_value(value)?,
+            records: None,
+            quiet: Vec::new(),
+            text: None,
+        })
+    }
+
+    /// Records for jsonl and template output, when they aren't the value itself
+    pub fn records<T: Serialize>(mut self, records: impl IntoIterator<Item = T>) -> Result<Self> {
+        self.records = Some(records.into_iter().map(serde_json::to_value).collect::<serde_json::Result<_>>()?);
+        Ok(self)
+    }
+
+    /// Lines for quiet output, usually file paths or IDs
+    pub fn quiet<S: ToString>(mut self, lines: impl IntoIterator<Item = S>) -> Self {
+        self.quiet = lines.into_iter().map(|line| line.to_string()).collect();
+        self
+    }
+
+    /// Print the report as text (without this, text output is pretty JSON)
+    pub fn text(mut self, print: impl FnOnce() + 'a) -> Self {
+        self.text = Some(Box::new(print));
+        self
+    }
+
+    fn records_iter(&self) -> Box<dyn Iterator<Item = &Value> + '_> {
+        match (&self.records, &self.value) {
+            (Some(records), _) => Box::new(records.iter()),
+            (None, Value::Array(items)) => Box::new(items.iter()),
+            (None, Value::Null) => Box::new(std::iter::empty()),
+            (None, value) => Box::new(std::iter::once(value)),
+        }
+    }
+}
+
+/// Writes reports in one output format
+pub trait OutputWriter {
+    fn write(&mut self, report: Report) -> Result<()>;
+}
+
+/// The writer for `format`
+pub fn writer(format: OutputFormat) -> Result<Box<dyn OutputWriter>> {
+    Ok(match format {
+        OutputFormat::Text => Box::new(TextWriter),
+        OutputFormat::Json => Box::new(JsonWriter),
+        OutputFormat::Quiet => Box::new(QuietWriter),
+        OutputFormat::Jsonl => Box::new(JsonlWriter),
+        OutputFormat::Template => match TEMPLATE.get() {
+            Some(template) => Box::new(TemplateWriter { template }),
+            None => anyhow::bail!("--format template needs --template, e.g. --template '{{id}}\\t{{model}}'"),
+        },
+    })
+}
+
+/// Fail before any work is done if `format` can't be written
+pub fn check(format: OutputFormat) -> Result<()> {
+    writer(format).map(|_| ())
+}
+
+/// Write a single report in `format`
+pub fn write(format: OutputFormat, report: Report) -> Result<()> {
+    writer(format)?.write(report)
+}
+
+/// Human-readable output
+pub struct TextWriter;
+
+impl OutputWriter for TextWriter {
+    fn write(&mut self, report: Report) -> Result<()> {
+        match report.text {
+            Some(print) => print(),
+            None => println!("{}", serde_json::to_string_pretty(&report.value)?),
+        }
+        Ok(())
+    }
+}
+
+/// The whole value as pretty-printed JSON
+pub struct JsonWriter;
+
+impl OutputWriter for JsonWriter {
+    fn write(&mut self, report: Report) -> Result<()> {
+        println!("{}", serde_json::to_string_pretty(&report.value)?);
+        Ok(())
+    }
+}
+
+/// Paths or IDs, one per line
+pub struct QuietWriter;
+
+impl OutputWriter for QuietWriter {
+    fn write(&mut self, report: Report) -> Result<()> {
+        for line in &report.quiet {
+            println!("{}", line);
+        }
+        Ok(())
+    }
+}
+
+/// One compact JSON object per record
+pub struct JsonlWriter;
+
+impl OutputWriter for JsonlWriter {
+    fn write(&mut self, report: Report) -> Result<()> {
+        for record in report.records_iter() {
+            println!("{}", serde_json::to_string(record)?);
+        }
+        Ok(())
+    }
+}
+
+/// The `--template` line per record, with `{field}` (or `{field.sub}`,
+/// `{artifacts.0.path}`) replaced by the record's value; `{{` and `}}` are
+/// literal braces and unknown fields are empty
+pub struct TemplateWriter {
+    template: &'static str,
+}
+
+impl OutputWriter for TemplateWriter {
+    fn write(&mut self, report: Report) -> Result<()> {
+        for record in report.records_iter() {
+            println!("{}", render_template(self.template, record));
+        }
+        Ok(())
+    }
+}
+
+fn render_template(template: &str, record: &Value) -> String {
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find(['{', '}']) {
+        out.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        if rest[start..].starts_with("{{") || rest[start..].starts_with("}}") {
+            out.push_str(&rest[start..start + 1]);
+            rest = &after[1..];
+            continue;
+        }
+        match (rest.as_bytes()[start], after.find('}')) {
+            (b'{', Some(end)) => {
+                let pointer = format!("/{}", after[..end].trim().replace('.', "/"));
+                match record.pointer(&pointer) {
+                    Some(Value::String(s)) => out.push_str(s),
+                    Some(Value::Null) | None => {}
+                    Some(value) => out.push_str(&value.to_string()),
+                }
+                rest = &after[end + 1..];
+            }
+            _ => {
+                out.push_str(&rest[start..start + 1]);
+                rest = after;
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
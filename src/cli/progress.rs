@@ -0,0 +1,164 @@
+use colored::Colorize;
+use indicatif::{ProgressBar, ProgressStyle};
+use serde_json::{json, Value};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use crate::api::DownloadProgress;
+use crate::cli::OutputFormat;
+
+/// Whether spinners and bars animate (`ui.animations`)
+static ANIMATIONS: AtomicBool = AtomicBool::new(true);
+
+/// Turn animated progress on or off for this process; when off, text output
+/// reports progress as plain lines on stderr instead
+pub fn set_animations(enabled: bool) {
+    ANIMATIONS.store(enabled, Ordering::Relaxed);
+}
+
+fn animations() -> bool {
+    ANIMATIONS.load(Ordering::Relaxed)
+}
+
+/// Progress of a single job: a spinner for text output, JSON lines on
+/// stderr when structured events were requested, or nothing at all.
+pub struct Progress {
+    spinner: Option<ProgressBar>,
+    /// Text output without animations: print start and finish lines instead
+    lines: bool,
+    /// Job ID to tag events with, when emitting them
+    events: Option<String>,
+}
+
+impl Progress {
+    /// Start reporting on `job_id`; `events` only applies to non-text output
+    pub fn start(format: OutputFormat, events: bool, job_id: &str, message: String) -> Self {
+        let lines = format == OutputFormat::Text && !animations();
+        if lines {
+            eprintln!("{}", message);
+        }
+        let spinner = (format == OutputFormat::Text && !lines).then(|| {
+            let pb = ProgressBar::new_spinner();
+            pb.set_style(
+                ProgressStyle::default_spinner()
+                    .template("{spinner:.yellow} {msg}")
+                    .unwrap(),
+            );
+            pb.set_message(message.clone());
+            pb.enable_steady_tick(Duration::from_millis(100));
+            pb
+        });
+        let progress = Self {
+            spinner,
+            lines,
+            events: (events && format != OutputFormat::Text).then(|| job_id.to_string()),
+        };
+        progress.emit("started", json!({ "message": message }));
+        progress
+    }
+
+    /// Report bytes written for one image
+    pub fn saving(&self, progress: &DownloadProgress) {
+        let percent = progress.written as f64 / progress.total.max(1) as f64 * 100.0;
+        if let Some(pb) = &self.spinner {
+            pb.set_message(format!("Saving image {} ({:.0}%)...", progress.index + 1, percent));
+        }
+        self.emit("saving", json!({ "image": progress.index, "percent": percent.round() }));
+    }
+
+    /// Finish successfully
+    pub fn finish(&self, message: String) {
+        if let Some(pb) = &self.spinner {
+            pb.finish_with_message(format!("{} {}", "✓".green(), message));
+        } else if self.lines {
+            eprintln!("{} {}", "✓".green(), message);
+        }
+        self.emit("completed", json!({ "message": message }));
+    }
+
+    /// Finish with an error
+    pub fn fail(&self, message: &str, error: &str) {
+        if let Some(pb) = &self.spinner {
+            pb.finish_with_message(format!("{} {}", "✗".red(), message));
+        } else if self.lines {
+            eprintln!("{} {}", "✗".red(), message);
+        }
+        self.emit("failed", json!({ "message": message, "error": error }));
+    }
+
+    /// Write one event line to stderr, keeping stdout for the result
+    fn emit(&self, event: &str, mut fields: Value) {
+        let Some(job_id) = &self.events else { return };
+        fields["event"] = json!(event);
+        fields["job_id"] = json!(job_id);
+        eprintln!("{}", fields);
+    }
+}
+
+/// Progress over a known number of items (batches, sweeps, drafts): a bar,
+/// or a `[done/total]` line per finished item when animations are off
+pub struct BatchProgress {
+    bar: Option<ProgressBar>,
+    total: u64,
+    done: AtomicU64,
+    message: Mutex<String>,
+}
+
+impl BatchProgress {
+    pub fn start(total: u64, message: String) -> Self {
+        let bar = animations().then(|| {
+            let pb = ProgressBar::new(total);
+            pb.set_style(
+                ProgressStyle::default_bar()
+                    .template("{spinner:.yellow} [{bar:30.yellow}] {pos}/{len} {msg}")
+                    .unwrap(),
+            );
+            pb.set_message(message.clone());
+            pb.enable_steady_tick(Duration::from_millis(100));
+            pb
+        });
+        Self {
+            bar,
+            total,
+            done: AtomicU64::new(0),
+            message: Mutex::new(message),
+        }
+    }
+
+    /// Change the message shown next to the count
+    pub fn set_message(&self, message: String) {
+        if let Some(pb) = &self.bar {
+            pb.set_message(message.clone());
+        }
+        *self.message.lock().unwrap() = message;
+    }
+
+    /// Print a line without disturbing the bar
+    pub fn println(&self, line: String) {
+        match &self.bar {
+            Some(pb) => pb.println(line),
+            None => eprintln!("{}", line),
+        }
+    }
+
+    /// Count one item as finished
+    pub fn inc(&self) {
+        let done = self.done.fetch_add(1, Ordering::Relaxed) + 1;
+        match &self.bar {
+            Some(pb) => pb.inc(1),
+            None => eprintln!(
+                "{} {}",
+                format!("[{}/{}]", done, self.total).dimmed(),
+                self.message.lock().unwrap()
+            ),
+        }
+    }
+
+    /// Remove the bar once all items are done
+    pub fn finish(&self) {
+        if let Some(pb) = &self.bar {
+            pb.finish_and_clear();
+        }
+    }
+}
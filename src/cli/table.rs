@@ -0,0 +1,121 @@
+use colored::{ColoredString, Colorize};
+use std::io::{self, IsTerminal};
+
+use crate::core::text;
+
+/// Spaces between columns
+const COLUMN_GAP: usize = 2;
+
+/// Flexible columns are never shrunk below this many columns
+const MIN_FLEX_WIDTH: usize = 12;
+
+/// Plain-text table whose columns are sized from their contents.
+///
+/// Widths are measured on the visible text, so colored cells stay aligned,
+/// and flexible columns are shortened with an ellipsis when the table would
+/// not fit the terminal.
+pub struct Table {
+    headers: Vec<&'static str>,
+    flexible: Vec<bool>,
+    rows: Vec<Row>,
+}
+
+enum Row {
+    Cells(Vec<ColoredString>),
+    /// Full-width label between groups of rows
+    Separator(String),
+}
+
+impl Table {
+    pub fn new(headers: &[&'static str]) -> Self {
+        Self {
+            headers: headers.to_vec(),
+            flexible: vec![false; headers.len()],
+            rows: Vec::new(),
+        }
+    }
+
+    /// Let column `index` shrink to fit the terminal
+    pub fn flexible(mut self, index: usize) -> Self {
+        self.flexible[index] = true;
+        self
+    }
+
+    pub fn add_row(&mut self, cells: Vec<ColoredString>) {
+        self.rows.push(Row::Cells(cells));
+    }
+
+    /// Start a new group of rows under `label`
+    pub fn add_separator(&mut self, label: impl Into<String>) {
+        self.rows.push(Row::Separator(label.into()));
+    }
+
+    pub fn print(&self) {
+        let widths = self.column_widths(terminal_width());
+        let total = widths.iter().sum::<usize>() + COLUMN_GAP * widths.len().saturating_sub(1);
+
+        let header: Vec<ColoredString> = self.headers.iter().map(|h| h.bold()).collect();
+        println!("{}", render_row(&header, &widths));
+        println!("{}", "-".repeat(total));
+        for (i, row) in self.rows.iter().enumerate() {
+            match row {
+                Row::Cells(cells) => println!("{}", render_row(cells, &widths)),
+                Row::Separator(label) => {
+                    if i > 0 {
+                        println!();
+                    }
+                    println!("{}", text::truncate(label, total).yellow().bold());
+                }
+            }
+        }
+    }
+
+    /// Natural width of each column, shrinking flexible ones to fit `max_total`
+    fn column_widths(&self, max_total: Option<usize>) -> Vec<usize> {
+        let mut widths: Vec<usize> = self.headers.iter().map(|h| text::width(h)).collect();
+        for row in &self.rows {
+            let Row::Cells(cells) = row else { continue };
+            for (width, cell) in widths.iter_mut().zip(cells) {
+                *width = (*width).max(text::width(&cell.input));
+            }
+        }
+
+        if let Some(max_total) = max_total {
+            let total = widths.iter().sum::<usize>() + COLUMN_GAP * widths.len().saturating_sub(1);
+            let mut excess = total.saturating_sub(max_total);
+            for (width, _) in widths.iter_mut().zip(&self.flexible).filter(|(_, flex)| **flex) {
+                let cut = excess.min(width.saturating_sub(MIN_FLEX_WIDTH));
+                *width -= cut;
+                excess -= cut;
+            }
+        }
+        widths
+    }
+}
+
+/// One line of the table; the last column is not padded
+fn render_row(cells: &[ColoredString], widths: &[usize]) -> String {
+    let mut line = String::new();
+    for (i, (cell, width)) in cells.iter().zip(widths).enumerate() {
+        if i > 0 {
+            line.push_str(&" ".repeat(COLUMN_GAP));
+        }
+        // Truncate the plain text and keep the cell's color and style
+        let mut cell = cell.clone();
+        cell.input = text::truncate(&cell.input, *width);
+        let visible = text::width(&cell.input);
+        line.push_str(&cell.to_string());
+        if i + 1 < cells.len() {
+            line.push_str(&" ".repeat(width - visible));
+        }
+    }
+    line
+}
+
+/// Columns available on stdout, if it is a terminal
+fn terminal_width() -> Option<usize> {
+    if !io::stdout().is_terminal() {
+        return None;
+    }
+    crossterm::terminal::size().ok().map(|(cols, _)| cols as usize)
+}
@@ -1,8 +1,20 @@
 use anyhow::{Context, Result};
+use chrono::format::{Item, StrftimeItems};
+use chrono::{DateTime, NaiveDate, TimeZone};
 use directories::ProjectDirs;
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::{mpsc, Mutex};
+
+use crate::core::{GenerateParams, PersonGeneration};
+use crate::history;
+use crate::i18n;
+use crate::platform::{self, FileLock};
+use crate::tui;
 
 /// Main configuration structure
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -15,6 +27,23 @@ pub struct Config {
     pub output: OutputConfig,
     #[serde(default)]
     pub tui: TuiConfig,
+    #[serde(default)]
+    pub ui: UiConfig,
+    #[serde(default)]
+    pub hooks: HooksConfig,
+    #[serde(default)]
+    pub history: HistoryConfig,
+    #[serde(default)]
+    pub models: ModelsConfig,
+    #[serde(default)]
+    pub replicate: ReplicateConfig,
+    #[serde(default)]
+    pub openai: OpenAiConfig,
+    #[serde(default)]
+    pub routing: RoutingConfig,
+    /// Per-model overrides of `[defaults]`, keyed by model name
+    #[serde(default, rename = "model-defaults", skip_serializing_if = "BTreeMap::is_empty")]
+    pub model_defaults: BTreeMap<String, ModelDefaults>,
 
     #[serde(skip)]
     pub config_path: PathBuf,
@@ -28,6 +57,24 @@ pub struct ApiConfig {
     pub model: String,
     #[serde(default = "default_base_url")]
     pub base_url: String,
+    /// Google Cloud project to bill (sent as `x-goog-user-project`)
+    #[serde(default)]
+    pub user_project: Option<String>,
+    /// Extra HTTP headers sent with every API request
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub headers: BTreeMap<String, String>,
+    /// Fail a job whose request runs longer than this many seconds (0 = no limit)
+    #[serde(default)]
+    pub job_timeout_secs: u64,
+    /// Model to retry a timed-out job on, once
+    #[serde(default)]
+    pub timeout_fallback_model: Option<String>,
+    /// Times to retry a request after a connection error or 5xx response
+    #[serde(default)]
+    pub max_retries: u32,
+    /// Backend that generates images
+    #[serde(default)]
+    pub provider: ProviderKind,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -36,6 +83,24 @@ pub struct DefaultsConfig {
     pub aspect_ratio: String,
     #[serde(default = "default_size")]
     pub size: String,
+    /// Person generation policy for jobs that don't pass `--people`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub people: Option<PersonGeneration>,
+}
+
+/// `[defaults]` overrides for one model
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ModelDefaults {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub aspect_ratio: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub size: Option<String>,
+}
+
+impl ModelDefaults {
+    pub fn is_empty(&self) -> bool {
+        self.aspect_ratio.is_none() && self.size.is_none()
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -46,6 +111,180 @@ pub struct OutputConfig {
     pub auto_download: bool,
     #[serde(default = "default_display")]
     pub display: DisplayMode,
+    /// EXIF attribution written into every downloaded image
+    #[serde(default, skip_serializing_if = "ExifConfig::is_empty")]
+    pub exif: ExifConfig,
+    /// Watermark composited onto every downloaded image
+    #[serde(default)]
+    pub watermark: WatermarkConfig,
+    /// Convert downloaded images to sRGB and embed an sRGB ICC profile
+    #[serde(default)]
+    pub srgb: bool,
+    /// Keep the base64 image data in the job record after saving the file
+    #[serde(default)]
+    pub retain_data: bool,
+    /// Date format for tables and job details: a preset or a strftime pattern
+    #[serde(default = "default_date_format")]
+    pub date_format: String,
+}
+
+impl OutputConfig {
+    /// Directory images are saved to, with a leading `~` expanded
+    pub fn dir(&self) -> PathBuf {
+        platform::expand_tilde(&self.directory)
+    }
+
+    /// The configured date format, with presets resolved
+    pub fn date_format(&self) -> DateFormat {
+        DateFormat::new(&self.date_format)
+    }
+}
+
+/// How dates are shown to people (JSON output always uses RFC 3339)
+#[derive(Debug, Clone)]
+pub struct DateFormat {
+    /// strftime pattern for the date part
+    date: String,
+}
+
+impl DateFormat {
+    /// Presets accepted by `output.date_format` besides a strftime pattern
+    pub const PRESETS: &'static [&'static str] = &["iso", "us", "eu", "locale"];
+
+    pub fn new(value: &str) -> Self {
+        let date = match value {
+            "iso" => "%Y-%m-%d",
+            "us" => "%m/%d/%Y",
+            "eu" => "%d.%m.%Y",
+            "locale" => locale_date_pattern(),
+            pattern => pattern,
+        };
+        Self { date: date.to_string() }
+    }
+
+    /// Whether `value` is a preset or a pattern chrono can format with
+    pub fn is_valid(value: &str) -> bool {
+        Self::PRESETS.contains(&value)
+            || (!value.is_empty() && !StrftimeItems::new(value).any(|item| matches!(item, Item::Error)))
+    }
+
+    /// A calendar date
+    pub fn date(&self, day: NaiveDate) -> String {
+        day.format(&self.date).to_string()
+    }
+
+    /// A calendar date with its weekday, for day separators
+    pub fn day(&self, day: NaiveDate) -> String {
+        day.format(&format!("%A, {}", self.date)).to_string()
+    }
+
+    /// Date and time to the minute
+    pub fn datetime<Tz: TimeZone>(&self, time: &DateTime<Tz>) -> String
+    where
+        Tz::Offset: fmt::Display,
+    {
+        time.format(&format!("{} %H:%M", self.date)).to_string()
+    }
+
+    /// Date and time to the second
+    pub fn datetime_secs<Tz: TimeZone>(&self, time: &DateTime<Tz>) -> String
+    where
+        Tz::Offset: fmt::Display,
+    {
+        time.format(&format!("{} %H:%M:%S", self.date)).to_string()
+    }
+}
+
+/// Date pattern for the user's locale (`LC_ALL`, `LC_TIME`, or `LANG`)
+fn locale_date_pattern() -> &'static str {
+    let locale = ["LC_ALL", "LC_TIME", "LANG"]
+        .iter()
+        .filter_map(|var| std::env::var(var).ok())
+        .find(|value| !value.is_empty())
+        .unwrap_or_default();
+    let locale = locale.split('.').next().unwrap_or_default();
+    let language = locale.split('_').next().unwrap_or_default();
+
+    match (language, locale) {
+        ("" | "C" | "POSIX", _) => "%Y-%m-%d",
+        (_, "en_US" | "en_PH" | "es_US") => "%m/%d/%Y",
+        ("de" | "ru" | "pl" | "cs" | "sk" | "fi" | "nb" | "da" | "tr" | "uk" | "ro", _) => "%d.%m.%Y",
+        ("ja" | "zh", _) => "%Y/%m/%d",
+        ("ko" | "sv" | "lt" | "hu", _) => "%Y-%m-%d",
+        ("nl", _) => "%d-%m-%Y",
+        _ => "%d/%m/%Y",
+    }
+}
+
+/// EXIF tags stamped into downloaded images
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ExifConfig {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub artist: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub copyright: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub software: Option<String>,
+}
+
+impl ExifConfig {
+    pub fn is_empty(&self) -> bool {
+        self.artist.is_none() && self.copyright.is_none() && self.software.is_none()
+    }
+}
+
+/// Watermark overlay settings (disabled while `path` is unset)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WatermarkConfig {
+    /// PNG to composite onto images
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    #[serde(default)]
+    pub position: WatermarkPosition,
+    /// 0.0 (invisible) to 1.0 (opaque)
+    #[serde(default = "default_watermark_opacity")]
+    pub opacity: f32,
+    /// Watermark width as a fraction of the image width
+    #[serde(default = "default_watermark_scale")]
+    pub scale: f32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "kebab-case")]
+pub enum WatermarkPosition {
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    #[default]
+    BottomRight,
+    Center,
+}
+
+impl WatermarkPosition {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            WatermarkPosition::TopLeft => "top-left",
+            WatermarkPosition::TopRight => "top-right",
+            WatermarkPosition::BottomLeft => "bottom-left",
+            WatermarkPosition::BottomRight => "bottom-right",
+            WatermarkPosition::Center => "center",
+        }
+    }
+
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s.to_lowercase().as_str() {
+            "top-left" => Some(WatermarkPosition::TopLeft),
+            "top-right" => Some(WatermarkPosition::TopRight),
+            "bottom-left" => Some(WatermarkPosition::BottomLeft),
+            "bottom-right" => Some(WatermarkPosition::BottomRight),
+            "center" => Some(WatermarkPosition::Center),
+            _ => None,
+        }
+    }
+
+    pub const fn variants() -> &'static [&'static str] {
+        &["top-left", "top-right", "bottom-left", "bottom-right", "center"]
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -54,6 +293,117 @@ pub struct TuiConfig {
     pub show_images: bool,
     #[serde(default = "default_theme")]
     pub theme: String,
+    /// Insert a date row before each day's jobs in the job list
+    #[serde(default)]
+    pub group_by_day: bool,
+    /// Rows of a TUI drawn inline in the normal screen buffer; 0 uses the alternate screen
+    #[serde(default)]
+    pub inline_height: u16,
+}
+
+/// Terminal output settings shared by all commands
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UiConfig {
+    /// Language of CLI messages, or "auto" to follow the locale
+    #[serde(default = "default_language")]
+    pub language: String,
+    /// Animate spinners and progress bars; off prints plain progress lines
+    #[serde(default = "default_true")]
+    pub animations: bool,
+}
+
+/// Job history retention
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistoryConfig {
+    /// Days a deleted job stays in the trash before it is purged
+    #[serde(default = "default_trash_days")]
+    pub trash_days: u32,
+    /// Hours a `--tmp` output directory is kept before it is deleted
+    #[serde(default = "default_tmp_hours")]
+    pub tmp_hours: u32,
+    /// Age (e.g. "7d") after which failed jobs are moved to the trash
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub auto_prune_failed_after: Option<String>,
+    /// Most jobs kept, trash included (0 = no limit)
+    #[serde(default)]
+    pub max_jobs: u32,
+    /// Most megabytes the data directory may use (0 = no limit)
+    #[serde(default)]
+    pub max_disk_mb: u64,
+}
+
+/// Replicate-hosted models, used when `api.provider = "replicate"`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReplicateConfig {
+    #[serde(default)]
+    pub token: Option<String>,
+    /// Model slug, `owner/name` or `owner/name:version`
+    #[serde(default = "default_replicate_model")]
+    pub model: String,
+    #[serde(default = "default_replicate_base_url")]
+    pub base_url: String,
+}
+
+/// OpenAI image models, used when `api.provider = "openai"`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OpenAiConfig {
+    #[serde(default)]
+    pub key: Option<String>,
+    #[serde(default = "default_openai_model")]
+    pub model: String,
+    #[serde(default = "default_openai_base_url")]
+    pub base_url: String,
+}
+
+/// `api.model` (or `--model`) value that picks a model per job, see `RoutingConfig`
+pub const AUTO_MODEL: &str = "auto";
+
+/// Shown in place of secrets and personal values
+pub const MASK: &str = "****";
+
+/// Rules `api.model = "auto"` picks a model by
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RoutingConfig {
+    /// Model for drafts: sizes not listed below, without a reference image
+    #[serde(default = "default_draft_model")]
+    pub draft_model: String,
+    /// Model for sizes in `quality_sizes` (and edits, with `quality_edits`)
+    #[serde(default = "default_quality_model")]
+    pub quality_model: String,
+    #[serde(default = "default_quality_sizes")]
+    pub quality_sizes: Vec<String>,
+    /// Send jobs with a reference image (edits, upscales) to the quality model
+    #[serde(default = "default_true")]
+    pub quality_edits: bool,
+}
+
+impl RoutingConfig {
+    /// The model for `params` and why it was picked
+    pub fn route(&self, params: &GenerateParams) -> (String, String) {
+        if self.quality_sizes.contains(&params.size) {
+            (self.quality_model.clone(), format!("{} size", params.size))
+        } else if self.quality_edits && params.reference_image.is_some() {
+            (self.quality_model.clone(), "reference image".to_string())
+        } else {
+            (self.draft_model.clone(), format!("draft at {}", params.size))
+        }
+    }
+}
+
+/// Model name shortcuts
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModelsConfig {
+    /// Short names accepted wherever a model is given, mapped to full model names
+    #[serde(default = "default_model_aliases")]
+    pub aliases: BTreeMap<String, String>,
+}
+
+/// External commands invoked at specific points
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct HooksConfig {
+    /// Local upscaler command; `{in}` and `{out}` are replaced with file paths
+    #[serde(default)]
+    pub upscaler: Option<String>,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
@@ -65,6 +415,801 @@ pub enum DisplayMode {
     None,
 }
 
+/// Image generation backend
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum ProviderKind {
+    #[default]
+    Gemini,
+    Replicate,
+    #[serde(rename = "openai")]
+    OpenAi,
+}
+
+/// How a config value is parsed and validated
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ValueKind {
+    String,
+    Bool,
+    Integer,
+    Float,
+    /// One of `KeyInfo::options`
+    Enum,
+}
+
+impl ValueKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ValueKind::String => "string",
+            ValueKind::Bool => "bool",
+            ValueKind::Integer => "integer",
+            ValueKind::Float => "float",
+            ValueKind::Enum => "enum",
+        }
+    }
+}
+
+/// A settable config key: its documentation plus how to read and write it
+#[derive(Debug, Clone, Copy)]
+pub struct KeyInfo {
+    pub key: &'static str,
+    pub kind: ValueKind,
+    pub description: &'static str,
+    /// Allowed values for enums, suggestions for strings
+    pub options: &'static [&'static str],
+    /// Masked on output and hidden from the TUI settings screen
+    pub secret: bool,
+    get: fn(&Config) -> Option<String>,
+    /// Receives a value already checked against `kind`
+    set: fn(&mut Config, &str) -> Result<()>,
+}
+
+impl KeyInfo {
+    /// Values the TUI can cycle through
+    pub fn choices(&self) -> Option<Vec<&'static str>> {
+        match self.kind {
+            ValueKind::Bool => Some(vec!["true", "false"]),
+            _ if !self.options.is_empty() => Some(self.options.to_vec()),
+            _ => None,
+        }
+    }
+
+    /// Check `value` against the key's type, returning it in canonical form
+    fn check(&self, value: &str) -> Result<String> {
+        match self.kind {
+            ValueKind::String => {}
+            ValueKind::Bool => {
+                value.parse::<bool>().context("Invalid boolean value")?;
+            }
+            ValueKind::Integer => {
+                value.parse::<i64>().context("Invalid integer")?;
+            }
+            ValueKind::Float => {
+                value.parse::<f64>().context("Invalid number")?;
+            }
+            ValueKind::Enum => {
+                return self
+                    .options
+                    .iter()
+                    .find(|option| option.eq_ignore_ascii_case(value))
+                    .map(|option| option.to_string())
+                    .with_context(|| {
+                        format!("Invalid value. Valid values: {}", self.options.join(", "))
+                    });
+            }
+        }
+        Ok(value.to_string())
+    }
+}
+
+fn optional(value: &str) -> Option<String> {
+    if value.is_empty() {
+        None
+    } else {
+        Some(value.to_string())
+    }
+}
+
+/// Every key accepted by `config set` (plus `api.headers.<name>`,
+/// `models.aliases.<alias>`, and `model-defaults.<model>.<setting>`).
+///
+/// `config get/set/keys`, config file validation, and the TUI settings
+/// screen are all driven from this table.
+pub const KEYS: &[KeyInfo] = &[
+    KeyInfo {
+        key: "api.key",
+        kind: ValueKind::String,
+        description: "Gemini API key",
+        options: &[],
+        secret: true,
+        get: |c| c.api.key.as_ref().map(|_| MASK.to_string()),
+        set: |c, v| {
+            c.api.key = Some(v.to_string());
+            Ok(())
+        },
+    },
+    KeyInfo {
+        key: "api.model",
+        kind: ValueKind::String,
+        description: "Default model (\"auto\" picks one per job, see routing.*)",
+        options: Config::models(),
+        secret: false,
+        get: |c| Some(c.api.model.clone()),
+        set: |c, v| {
+            c.api.model = c.resolve_model(v);
+            Ok(())
+        },
+    },
+    KeyInfo {
+        key: "api.base_url",
+        kind: ValueKind::String,
+        description: "API endpoint",
+        options: &[],
+        secret: false,
+        get: |c| Some(c.api.base_url.clone()),
+        set: |c, v| {
+            c.api.base_url = v.to_string();
+            Ok(())
+        },
+    },
+    KeyInfo {
+        key: "api.provider",
+        kind: ValueKind::Enum,
+        description: "Image backend",
+        options: ProviderKind::variants(),
+        secret: false,
+        get: |c| Some(c.api.provider.as_str().to_string()),
+        set: |c, v| {
+            c.api.provider = ProviderKind::from_str(v);
+            Ok(())
+        },
+    },
+    KeyInfo {
+        key: "api.user_project",
+        kind: ValueKind::String,
+        description: "Billing project (x-goog-user-project header)",
+        options: &[],
+        secret: false,
+        get: |c| c.api.user_project.clone(),
+        set: |c, v| {
+            c.api.user_project = optional(v);
+            Ok(())
+        },
+    },
+    KeyInfo {
+        key: "api.job_timeout_secs",
+        kind: ValueKind::Integer,
+        description: "Fail jobs whose request runs longer than this (0 = no limit)",
+        options: &[],
+        secret: false,
+        get: |c| Some(c.api.job_timeout_secs.to_string()),
+        set: |c, v| {
+            c.api.job_timeout_secs = v.parse().context("Invalid number of seconds")?;
+            Ok(())
+        },
+    },
+    KeyInfo {
+        key: "api.timeout_fallback_model",
+        kind: ValueKind::String,
+        description: "Model to retry a timed-out job on, once",
+        options: &[],
+        secret: false,
+        get: |c| c.api.timeout_fallback_model.clone(),
+        set: |c, v| {
+            c.api.timeout_fallback_model = optional(v).map(|model| c.resolve_model(&model));
+            Ok(())
+        },
+    },
+    KeyInfo {
+        key: "api.max_retries",
+        kind: ValueKind::Integer,
+        description: "Retries after a connection error or 5xx response (with backoff)",
+        options: &[],
+        secret: false,
+        get: |c| Some(c.api.max_retries.to_string()),
+        set: |c, v| {
+            c.api.max_retries = v.parse().context("Invalid number of retries")?;
+            Ok(())
+        },
+    },
+    KeyInfo {
+        key: "defaults.aspect_ratio",
+        kind: ValueKind::Enum,
+        description: "Default aspect ratio",
+        options: Config::aspect_ratios(),
+        secret: false,
+        get: |c| Some(c.defaults.aspect_ratio.clone()),
+        set: |c, v| {
+            c.defaults.aspect_ratio = v.to_string();
+            Ok(())
+        },
+    },
+    KeyInfo {
+        key: "defaults.size",
+        kind: ValueKind::Enum,
+        description: "Default image size",
+        options: Config::sizes(),
+        secret: false,
+        get: |c| Some(c.defaults.size.clone()),
+        set: |c, v| {
+            c.defaults.size = v.to_string();
+            Ok(())
+        },
+    },
+    KeyInfo {
+        key: "defaults.people",
+        kind: ValueKind::String,
+        description: "Whether images may show people, unless --people says otherwise (empty = API default)",
+        options: PersonGeneration::variants(),
+        secret: false,
+        get: |c| c.defaults.people.map(|policy| policy.as_str().to_string()),
+        set: |c, v| {
+            c.defaults.people = match optional(v) {
+                Some(v) => Some(PersonGeneration::from_str(&v).with_context(|| {
+                    format!("Invalid value. Valid values: {}", PersonGeneration::variants().join(", "))
+                })?),
+                None => None,
+            };
+            Ok(())
+        },
+    },
+    KeyInfo {
+        key: "output.directory",
+        kind: ValueKind::String,
+        description: "Where to save images",
+        options: &[],
+        secret: false,
+        get: |c| Some(c.output.directory.clone()),
+        set: |c, v| {
+            c.output.directory = v.to_string();
+            Ok(())
+        },
+    },
+    KeyInfo {
+        key: "output.auto_download",
+        kind: ValueKind::Bool,
+        description: "Auto-download images",
+        options: &[],
+        secret: false,
+        get: |c| Some(c.output.auto_download.to_string()),
+        set: |c, v| {
+            c.output.auto_download = v.parse()?;
+            Ok(())
+        },
+    },
+    KeyInfo {
+        key: "output.display",
+        kind: ValueKind::Enum,
+        description: "How to show results",
+        options: DisplayMode::variants(),
+        secret: false,
+        get: |c| Some(c.output.display.as_str().to_string()),
+        set: |c, v| {
+            c.output.display = DisplayMode::from_str(v);
+            Ok(())
+        },
+    },
+    KeyInfo {
+        key: "output.srgb",
+        kind: ValueKind::Bool,
+        description: "Convert downloads to sRGB and embed an sRGB ICC profile",
+        options: &[],
+        secret: false,
+        get: |c| Some(c.output.srgb.to_string()),
+        set: |c, v| {
+            c.output.srgb = v.parse()?;
+            Ok(())
+        },
+    },
+    KeyInfo {
+        key: "output.retain_data",
+        kind: ValueKind::Bool,
+        description: "Keep base64 image data in the job record after download",
+        options: &[],
+        secret: false,
+        get: |c| Some(c.output.retain_data.to_string()),
+        set: |c, v| {
+            c.output.retain_data = v.parse()?;
+            Ok(())
+        },
+    },
+    KeyInfo {
+        key: "output.date_format",
+        kind: ValueKind::String,
+        description: "Date format: iso, us, eu, locale, or a strftime pattern like %d/%m/%Y",
+        options: &[],
+        secret: false,
+        get: |c| Some(c.output.date_format.clone()),
+        set: |c, v| {
+            if !DateFormat::is_valid(v) {
+                anyhow::bail!(
+                    "Invalid date format '{}'. Use {} or a strftime pattern",
+                    v,
+                    DateFormat::PRESETS.join(", ")
+                );
+            }
+            c.output.date_format = v.to_string();
+            Ok(())
+        },
+    },
+    KeyInfo {
+        key: "output.exif.artist",
+        kind: ValueKind::String,
+        description: "EXIF Artist written into downloaded images",
+        options: &[],
+        secret: false,
+        get: |c| c.output.exif.artist.clone(),
+        set: |c, v| {
+            c.output.exif.artist = optional(v);
+            Ok(())
+        },
+    },
+    KeyInfo {
+        key: "output.exif.copyright",
+        kind: ValueKind::String,
+        description: "EXIF Copyright written into downloaded images",
+        options: &[],
+        secret: false,
+        get: |c| c.output.exif.copyright.clone(),
+        set: |c, v| {
+            c.output.exif.copyright = optional(v);
+            Ok(())
+        },
+    },
+    KeyInfo {
+        key: "output.exif.software",
+        kind: ValueKind::String,
+        description: "EXIF Software written into downloaded images",
+        options: &[],
+        secret: false,
+        get: |c| c.output.exif.software.clone(),
+        set: |c, v| {
+            c.output.exif.software = optional(v);
+            Ok(())
+        },
+    },
+    KeyInfo {
+        key: "output.watermark.path",
+        kind: ValueKind::String,
+        description: "Watermark PNG composited onto downloads (empty disables)",
+        options: &[],
+        secret: false,
+        get: |c| c.output.watermark.path.clone(),
+        set: |c, v| {
+            c.output.watermark.path = optional(v);
+            Ok(())
+        },
+    },
+    KeyInfo {
+        key: "output.watermark.position",
+        kind: ValueKind::Enum,
+        description: "Watermark placement",
+        options: WatermarkPosition::variants(),
+        secret: false,
+        get: |c| Some(c.output.watermark.position.as_str().to_string()),
+        set: |c, v| {
+            c.output.watermark.position = WatermarkPosition::from_str(v).context("Invalid position")?;
+            Ok(())
+        },
+    },
+    KeyInfo {
+        key: "output.watermark.opacity",
+        kind: ValueKind::Float,
+        description: "Watermark opacity (0.0-1.0)",
+        options: &[],
+        secret: false,
+        get: |c| Some(c.output.watermark.opacity.to_string()),
+        set: |c, v| {
+            let opacity: f32 = v.parse()?;
+            if !(0.0..=1.0).contains(&opacity) {
+                anyhow::bail!("Opacity must be between 0.0 and 1.0");
+            }
+            c.output.watermark.opacity = opacity;
+            Ok(())
+        },
+    },
+    KeyInfo {
+        key: "output.watermark.scale",
+        kind: ValueKind::Float,
+        description: "Watermark width relative to the image",
+        options: &[],
+        secret: false,
+        get: |c| Some(c.output.watermark.scale.to_string()),
+        set: |c, v| {
+            let scale: f32 = v.parse()?;
+            if !(scale > 0.0 && scale <= 1.0) {
+                anyhow::bail!("Scale must be greater than 0.0 and at most 1.0");
+            }
+            c.output.watermark.scale = scale;
+            Ok(())
+        },
+    },
+    KeyInfo {
+        key: "tui.show_images",
+        kind: ValueKind::Bool,
+        description: "Show images in TUI",
+        options: &[],
+        secret: false,
+        get: |c| Some(c.tui.show_images.to_string()),
+        set: |c, v| {
+            c.tui.show_images = v.parse()?;
+            Ok(())
+        },
+    },
+    KeyInfo {
+        key: "tui.theme",
+        kind: ValueKind::Enum,
+        description: "TUI theme",
+        options: &["dark", "light"],
+        secret: false,
+        get: |c| Some(c.tui.theme.clone()),
+        set: |c, v| {
+            c.tui.theme = v.to_string();
+            Ok(())
+        },
+    },
+    KeyInfo {
+        key: "tui.group_by_day",
+        kind: ValueKind::Bool,
+        description: "Separate the job list by day",
+        options: &[],
+        secret: false,
+        get: |c| Some(c.tui.group_by_day.to_string()),
+        set: |c, v| {
+            c.tui.group_by_day = v.parse()?;
+            Ok(())
+        },
+    },
+    KeyInfo {
+        key: "tui.inline_height",
+        kind: ValueKind::Integer,
+        description: "Draw the TUI inline in this many rows (at least 20), keeping scrollback; 0 for full screen",
+        options: &[],
+        secret: false,
+        get: |c| Some(c.tui.inline_height.to_string()),
+        set: |c, v| {
+            let rows: u16 = v.parse()?;
+            if rows > 0 && rows < tui::MIN_HEIGHT {
+                anyhow::bail!("Inline height must be 0 or at least {} rows", tui::MIN_HEIGHT);
+            }
+            c.tui.inline_height = rows;
+            Ok(())
+        },
+    },
+    KeyInfo {
+        key: "hooks.upscaler",
+        kind: ValueKind::String,
+        description: "External upscaler command ({in}/{out} placeholders)",
+        options: &[],
+        secret: false,
+        get: |c| c.hooks.upscaler.clone(),
+        set: |c, v| {
+            c.hooks.upscaler = optional(v);
+            Ok(())
+        },
+    },
+    KeyInfo {
+        key: "ui.language",
+        kind: ValueKind::Enum,
+        description: "Language of CLI messages (auto follows LANG)",
+        options: i18n::LANGUAGES,
+        secret: false,
+        get: |c| Some(c.ui.language.clone()),
+        set: |c, v| {
+            c.ui.language = v.to_string();
+            Ok(())
+        },
+    },
+    KeyInfo {
+        key: "ui.animations",
+        kind: ValueKind::Bool,
+        description: "Animate spinners and progress bars (false prints plain lines)",
+        options: &[],
+        secret: false,
+        get: |c| Some(c.ui.animations.to_string()),
+        set: |c, v| {
+            c.ui.animations = v.parse()?;
+            Ok(())
+        },
+    },
+    KeyInfo {
+        key: "history.trash_days",
+        kind: ValueKind::Integer,
+        description: "Days deleted jobs stay restorable",
+        options: &[],
+        secret: false,
+        get: |c| Some(c.history.trash_days.to_string()),
+        set: |c, v| {
+            c.history.trash_days = v.parse().context("Invalid number of days")?;
+            Ok(())
+        },
+    },
+    KeyInfo {
+        key: "history.tmp_hours",
+        kind: ValueKind::Integer,
+        description: "Hours --tmp output directories are kept",
+        options: &[],
+        secret: false,
+        get: |c| Some(c.history.tmp_hours.to_string()),
+        set: |c, v| {
+            c.history.tmp_hours = v.parse().context("Invalid number of hours")?;
+            Ok(())
+        },
+    },
+    KeyInfo {
+        key: "history.auto_prune_failed_after",
+        kind: ValueKind::String,
+        description: "Trash failed jobs older than this (e.g. 7d; empty = keep)",
+        options: &[],
+        secret: false,
+        get: |c| c.history.auto_prune_failed_after.clone(),
+        set: |c, v| {
+            if !v.is_empty() {
+                history::parse_age(v)?;
+            }
+            c.history.auto_prune_failed_after = optional(v);
+            Ok(())
+        },
+    },
+    KeyInfo {
+        key: "history.max_jobs",
+        kind: ValueKind::Integer,
+        description: "Evict the oldest unstarred jobs beyond this count (0 = no limit)",
+        options: &[],
+        secret: false,
+        get: |c| Some(c.history.max_jobs.to_string()),
+        set: |c, v| {
+            c.history.max_jobs = v.parse().context("Invalid number of jobs")?;
+            Ok(())
+        },
+    },
+    KeyInfo {
+        key: "history.max_disk_mb",
+        kind: ValueKind::Integer,
+        description: "Evict the oldest unstarred jobs while the data dir exceeds this (0 = no limit)",
+        options: &[],
+        secret: false,
+        get: |c| Some(c.history.max_disk_mb.to_string()),
+        set: |c, v| {
+            c.history.max_disk_mb = v.parse().context("Invalid number of megabytes")?;
+            Ok(())
+        },
+    },
+    KeyInfo {
+        key: "replicate.token",
+        kind: ValueKind::String,
+        description: "Replicate API token",
+        options: &[],
+        secret: true,
+        get: |c| c.replicate.token.as_ref().map(|_| MASK.to_string()),
+        set: |c, v| {
+            c.replicate.token = optional(v);
+            Ok(())
+        },
+    },
+    KeyInfo {
+        key: "replicate.model",
+        kind: ValueKind::String,
+        description: "Replicate model slug (owner/name[:version])",
+        options: &["black-forest-labs/flux-schnell", "black-forest-labs/flux-dev"],
+        secret: false,
+        get: |c| Some(c.replicate.model.clone()),
+        set: |c, v| {
+            if !v.contains('/') {
+                anyhow::bail!("Expected a model slug like owner/name");
+            }
+            c.replicate.model = v.to_string();
+            Ok(())
+        },
+    },
+    KeyInfo {
+        key: "replicate.base_url",
+        kind: ValueKind::String,
+        description: "Replicate API endpoint",
+        options: &[],
+        secret: false,
+        get: |c| Some(c.replicate.base_url.clone()),
+        set: |c, v| {
+            c.replicate.base_url = v.to_string();
+            Ok(())
+        },
+    },
+    KeyInfo {
+        key: "openai.key",
+        kind: ValueKind::String,
+        description: "OpenAI API key",
+        options: &[],
+        secret: true,
+        get: |c| c.openai.key.as_ref().map(|_| MASK.to_string()),
+        set: |c, v| {
+            c.openai.key = optional(v);
+            Ok(())
+        },
+    },
+    KeyInfo {
+        key: "openai.model",
+        kind: ValueKind::String,
+        description: "OpenAI image model",
+        options: &["gpt-image-1", "dall-e-3", "dall-e-2"],
+        secret: false,
+        get: |c| Some(c.openai.model.clone()),
+        set: |c, v| {
+            c.openai.model = c.resolve_model(v);
+            Ok(())
+        },
+    },
+    KeyInfo {
+        key: "openai.base_url",
+        kind: ValueKind::String,
+        description: "OpenAI API endpoint",
+        options: &[],
+        secret: false,
+        get: |c| Some(c.openai.base_url.clone()),
+        set: |c, v| {
+            c.openai.base_url = v.to_string();
+            Ok(())
+        },
+    },
+    KeyInfo {
+        key: "routing.draft_model",
+        kind: ValueKind::String,
+        description: "Model \"auto\" uses for drafts",
+        options: Config::models(),
+        secret: false,
+        get: |c| Some(c.routing.draft_model.clone()),
+        set: |c, v| {
+            c.routing.draft_model = c.resolve_model(v);
+            Ok(())
+        },
+    },
+    KeyInfo {
+        key: "routing.quality_model",
+        kind: ValueKind::String,
+        description: "Model \"auto\" uses for quality sizes and edits",
+        options: Config::models(),
+        secret: false,
+        get: |c| Some(c.routing.quality_model.clone()),
+        set: |c, v| {
+            c.routing.quality_model = c.resolve_model(v);
+            Ok(())
+        },
+    },
+    KeyInfo {
+        key: "routing.quality_sizes",
+        kind: ValueKind::String,
+        description: "Comma-separated sizes \"auto\" renders with the quality model",
+        options: &[],
+        secret: false,
+        get: |c| Some(c.routing.quality_sizes.join(",")),
+        set: |c, v| {
+            let sizes: Vec<String> = v
+                .split(',')
+                .map(str::trim)
+                .filter(|size| !size.is_empty())
+                .map(String::from)
+                .collect();
+            if let Some(size) = sizes.iter().find(|size| !Config::sizes().contains(&size.as_str())) {
+                anyhow::bail!("Invalid size '{}'. Valid sizes: {}", size, Config::sizes().join(", "));
+            }
+            c.routing.quality_sizes = sizes;
+            Ok(())
+        },
+    },
+    KeyInfo {
+        key: "routing.quality_edits",
+        kind: ValueKind::Bool,
+        description: "Whether \"auto\" renders edits with the quality model",
+        options: &[],
+        secret: false,
+        get: |c| Some(c.routing.quality_edits.to_string()),
+        set: |c, v| {
+            c.routing.quality_edits = v.parse()?;
+            Ok(())
+        },
+    },
+];
+
+/// Look up a registered key
+pub fn key_info(key: &str) -> Option<&'static KeyInfo> {
+    KEYS.iter().find(|info| info.key == key)
+}
+
+/// Where a config value comes from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum KeySource {
+    Default,
+    File,
+    Env,
+}
+
+impl KeySource {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            KeySource::Default => "default",
+            KeySource::File => "file",
+            KeySource::Env => "env",
+        }
+    }
+}
+
+/// Looks up where config values come from, see `Config::sources`
+pub struct KeySources {
+    /// The config file as parsed, if it exists and parses
+    table: Option<toml::Table>,
+}
+
+impl KeySources {
+    /// Where the current value of `key` comes from
+    pub fn get(&self, key: &str) -> KeySource {
+        let env = match key {
+            "api.key" => Some("GEMINI_API_KEY"),
+            "replicate.token" => Some("REPLICATE_API_TOKEN"),
+            "openai.key" => Some("OPENAI_API_KEY"),
+            _ => None,
+        };
+        if env.is_some_and(|var| std::env::var(var).is_ok()) {
+            return KeySource::Env;
+        }
+
+        // A key is only from the file if the file sets it, whatever its value
+        let in_file = self.table.as_ref().is_some_and(|table| {
+            let mut parts = key.split('.');
+            let mut value = parts.next().and_then(|part| table.get(part));
+            for part in parts {
+                value = value.and_then(|v| v.get(part));
+            }
+            value.is_some()
+        });
+        if in_file {
+            KeySource::File
+        } else {
+            KeySource::Default
+        }
+    }
+}
+
+/// A problem found while validating the config file
+#[derive(Debug, Clone)]
+pub struct ConfigIssue {
+    /// Dotted key path, e.g. `output.display`
+    pub key: String,
+    pub message: String,
+    /// Whether the config can't be used as-is
+    pub fatal: bool,
+}
+
+impl std::fmt::Display for ConfigIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.key, self.message)
+    }
+}
+
+impl ProviderKind {
+    /// Every provider, in `variants()` order
+    pub const ALL: [ProviderKind; 3] = [ProviderKind::Gemini, ProviderKind::Replicate, ProviderKind::OpenAi];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ProviderKind::Gemini => "gemini",
+            ProviderKind::Replicate => "replicate",
+            ProviderKind::OpenAi => "openai",
+        }
+    }
+
+    pub fn from_str(s: &str) -> Self {
+        match s.to_lowercase().as_str() {
+            "replicate" => ProviderKind::Replicate,
+            "openai" => ProviderKind::OpenAi,
+            _ => ProviderKind::Gemini,
+        }
+    }
+
+    pub const fn variants() -> &'static [&'static str] {
+        &["gemini", "replicate", "openai"]
+    }
+}
+
 impl DisplayMode {
     pub fn as_str(&self) -> &'static str {
         match self {
@@ -83,18 +1228,109 @@ impl DisplayMode {
         }
     }
 
-    pub fn variants() -> &'static [&'static str] {
+    pub const fn variants() -> &'static [&'static str] {
         &["terminal", "viewer", "none"]
     }
 }
 
+/// Flatten nested tables into dotted key paths
+fn collect_leaves<'a>(prefix: &str, table: &'a toml::Table, out: &mut Vec<(String, &'a toml::Value)>) {
+    for (name, value) in table {
+        let key = if prefix.is_empty() {
+            name.clone()
+        } else {
+            format!("{}.{}", prefix, name)
+        };
+        match value {
+            toml::Value::Table(inner) => collect_leaves(&key, inner, out),
+            _ => out.push((key, value)),
+        }
+    }
+}
+
+/// A scalar TOML value (or an array of them, comma-joined) in the form
+/// `config set` takes it
+fn leaf_string(value: &toml::Value) -> Option<String> {
+    match value {
+        toml::Value::String(s) => Some(s.clone()),
+        toml::Value::Integer(i) => Some(i.to_string()),
+        toml::Value::Float(f) => Some(f.to_string()),
+        toml::Value::Boolean(b) => Some(b.to_string()),
+        toml::Value::Array(items) => items
+            .iter()
+            .map(|item| match item {
+                toml::Value::Array(_) => None,
+                item => leaf_string(item),
+            })
+            .collect::<Option<Vec<_>>>()
+            .map(|items| items.join(",")),
+        _ => None,
+    }
+}
+
+/// Flatten a TOML table (e.g. an overrides file) into `(key, value)` pairs for `Config::set`
+pub fn assignments(raw: &toml::Table) -> Result<Vec<(String, String)>> {
+    let mut leaves = Vec::new();
+    collect_leaves("", raw, &mut leaves);
+    leaves
+        .into_iter()
+        .map(|(key, value)| match leaf_string(value) {
+            Some(value) => Ok((key, value)),
+            None => anyhow::bail!("{}: expected a single value", key),
+        })
+        .collect()
+}
+
+/// Closest known key to a misspelled one
+fn suggest_key(key: &str) -> Option<&'static str> {
+    Config::keys()
+        .map(|known| (edit_distance(key, known), known))
+        .filter(|(distance, _)| *distance <= 3)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, known)| known)
+}
+
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut prev = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let cost = if ca == *cb { prev } else { prev + 1 };
+            prev = row[j + 1];
+            row[j + 1] = cost.min(row[j] + 1).min(prev + 1);
+        }
+    }
+    row[b.len()]
+}
+
+/// Why images couldn't be saved to `dir`, checked against its nearest
+/// existing ancestor since the directory is created on first download
+fn directory_problem(dir: &Path) -> Option<String> {
+    let mut path = dir;
+    loop {
+        let probe = if path.as_os_str().is_empty() { Path::new(".") } else { path };
+        match fs::metadata(probe) {
+            Ok(meta) if !meta.is_dir() => {
+                return Some(format!("{} is not a directory", probe.display()))
+            }
+            Ok(meta) if meta.permissions().readonly() => {
+                return Some(format!("{} is not writable", probe.display()))
+            }
+            Ok(_) => return None,
+            Err(_) => path = path.parent()?,
+        }
+    }
+}
+
 // Default value functions
 fn default_model() -> String {
     "gemini-3-pro-image-preview".to_string()
 }
 
 fn default_base_url() -> String {
-    "https://generativelanguage.googleapis.com/v1beta".to_string()
+    crate::api::DEFAULT_BASE_URL.to_string()
 }
 
 fn default_aspect_ratio() -> String {
@@ -109,6 +1345,14 @@ fn default_output_directory() -> String {
     "./banana-output".to_string()
 }
 
+fn default_language() -> String {
+    "auto".to_string()
+}
+
+fn default_date_format() -> String {
+    "iso".to_string()
+}
+
 fn default_true() -> bool {
     true
 }
@@ -117,6 +1361,61 @@ fn default_display() -> DisplayMode {
     DisplayMode::Terminal
 }
 
+fn default_watermark_opacity() -> f32 {
+    0.5
+}
+
+fn default_watermark_scale() -> f32 {
+    0.2
+}
+
+fn default_trash_days() -> u32 {
+    30
+}
+
+fn default_tmp_hours() -> u32 {
+    24
+}
+
+fn default_replicate_model() -> String {
+    "black-forest-labs/flux-schnell".to_string()
+}
+
+fn default_replicate_base_url() -> String {
+    crate::api::replicate::DEFAULT_BASE_URL.to_string()
+}
+
+fn default_openai_model() -> String {
+    "gpt-image-1".to_string()
+}
+
+fn default_openai_base_url() -> String {
+    crate::api::openai::DEFAULT_BASE_URL.to_string()
+}
+
+fn default_draft_model() -> String {
+    "gemini-2.5-flash-image".to_string()
+}
+
+fn default_quality_model() -> String {
+    "gemini-3-pro-image-preview".to_string()
+}
+
+fn default_quality_sizes() -> Vec<String> {
+    vec!["4K".to_string()]
+}
+
+fn default_model_aliases() -> BTreeMap<String, String> {
+    [
+        ("pro", "gemini-3-pro-image-preview"),
+        ("flash", "gemini-2.5-flash-image"),
+        ("imagen", "imagen-4.0-generate-001"),
+    ]
+    .into_iter()
+    .map(|(alias, model)| (alias.to_string(), model.to_string()))
+    .collect()
+}
+
 fn default_theme() -> String {
     "dark".to_string()
 }
@@ -127,6 +1426,43 @@ impl Default for ApiConfig {
             key: None,
             model: default_model(),
             base_url: default_base_url(),
+            user_project: None,
+            headers: BTreeMap::new(),
+            job_timeout_secs: 0,
+            max_retries: 0,
+            timeout_fallback_model: None,
+            provider: ProviderKind::default(),
+        }
+    }
+}
+
+impl Default for RoutingConfig {
+    fn default() -> Self {
+        Self {
+            draft_model: default_draft_model(),
+            quality_model: default_quality_model(),
+            quality_sizes: default_quality_sizes(),
+            quality_edits: true,
+        }
+    }
+}
+
+impl Default for OpenAiConfig {
+    fn default() -> Self {
+        Self {
+            key: None,
+            model: default_openai_model(),
+            base_url: default_openai_base_url(),
+        }
+    }
+}
+
+impl Default for ReplicateConfig {
+    fn default() -> Self {
+        Self {
+            token: None,
+            model: default_replicate_model(),
+            base_url: default_replicate_base_url(),
         }
     }
 }
@@ -136,6 +1472,7 @@ impl Default for DefaultsConfig {
         Self {
             aspect_ratio: default_aspect_ratio(),
             size: default_size(),
+            people: None,
         }
     }
 }
@@ -146,6 +1483,22 @@ impl Default for OutputConfig {
             directory: default_output_directory(),
             auto_download: true,
             display: DisplayMode::Terminal,
+            exif: ExifConfig::default(),
+            watermark: WatermarkConfig::default(),
+            srgb: false,
+            retain_data: false,
+            date_format: default_date_format(),
+        }
+    }
+}
+
+impl Default for WatermarkConfig {
+    fn default() -> Self {
+        Self {
+            path: None,
+            position: WatermarkPosition::default(),
+            opacity: default_watermark_opacity(),
+            scale: default_watermark_scale(),
         }
     }
 }
@@ -155,6 +1508,37 @@ impl Default for TuiConfig {
         Self {
             show_images: true,
             theme: default_theme(),
+            group_by_day: false,
+            inline_height: 0,
+        }
+    }
+}
+
+impl Default for UiConfig {
+    fn default() -> Self {
+        Self {
+            language: default_language(),
+            animations: true,
+        }
+    }
+}
+
+impl Default for HistoryConfig {
+    fn default() -> Self {
+        Self {
+            trash_days: default_trash_days(),
+            tmp_hours: default_tmp_hours(),
+            auto_prune_failed_after: None,
+            max_jobs: 0,
+            max_disk_mb: 0,
+        }
+    }
+}
+
+impl Default for ModelsConfig {
+    fn default() -> Self {
+        Self {
+            aliases: default_model_aliases(),
         }
     }
 }
@@ -166,6 +1550,14 @@ impl Default for Config {
             defaults: DefaultsConfig::default(),
             output: OutputConfig::default(),
             tui: TuiConfig::default(),
+            ui: UiConfig::default(),
+            hooks: HooksConfig::default(),
+            history: HistoryConfig::default(),
+            models: ModelsConfig::default(),
+            replicate: ReplicateConfig::default(),
+            openai: OpenAiConfig::default(),
+            routing: RoutingConfig::default(),
+            model_defaults: BTreeMap::new(),
             config_path: PathBuf::new(),
         }
     }
@@ -192,11 +1584,7 @@ impl Config {
         let env_key = std::env::var("GEMINI_API_KEY").ok();
 
         if config_path.exists() {
-            let content = fs::read_to_string(&config_path)
-                .context("Failed to read config file")?;
-            let mut config: Config = toml::from_str(&content)
-                .context("Failed to parse config file")?;
-            config.config_path = config_path;
+            let mut config = Self::read(&config_path)?;
 
             // Environment variable takes precedence
             if let Some(key) = env_key {
@@ -219,8 +1607,139 @@ impl Config {
         }
     }
 
-    /// Save config to file
+    /// Read and validate the config file at `path`
+    fn read(path: &Path) -> Result<Self> {
+        let content = fs::read_to_string(path)
+            .context("Failed to read config file")?;
+        Self::check(&content, path)?;
+        let mut config: Config = toml::from_str(&content)
+            .context("Failed to parse config file")?;
+        config.config_path = path.to_path_buf();
+        Ok(config)
+    }
+
+    /// Validate config file contents, warning about unknown keys and failing
+    /// with every invalid value at once.
+    fn check(content: &str, path: &Path) -> Result<()> {
+        let raw: toml::Table = toml::from_str(content)
+            .with_context(|| format!("Failed to parse config file {}", path.display()))?;
+
+        let (errors, warnings): (Vec<_>, Vec<_>) =
+            Self::validate(&raw).into_iter().partition(|issue| issue.fatal);
+
+        for issue in &warnings {
+            tracing::warn!("{}: {}", path.display(), issue);
+        }
+
+        if !errors.is_empty() {
+            let list: Vec<String> = errors.iter().map(|issue| format!("  {}", issue)).collect();
+            anyhow::bail!(
+                "Invalid config file {}:\n{}",
+                path.display(),
+                list.join("\n")
+            );
+        }
+        Ok(())
+    }
+
+    /// Check every value in a parsed config file against the known keys and
+    /// the same rules `config set` applies.
+    pub fn validate(raw: &toml::Table) -> Vec<ConfigIssue> {
+        let mut leaves = Vec::new();
+        collect_leaves("", raw, &mut leaves);
+
+        let mut scratch = Config::default();
+        let mut issues = Vec::new();
+
+        for (key, value) in leaves {
+            let dynamic = key.starts_with("api.headers.")
+                || key.starts_with("models.aliases.")
+                || key.starts_with("model-defaults.");
+            if !dynamic && !Self::keys().any(|k| k == key) {
+                let message = match suggest_key(&key) {
+                    Some(known) => format!("unknown key (did you mean `{}`?)", known),
+                    None => "unknown key".to_string(),
+                };
+                issues.push(ConfigIssue { key, message, fatal: false });
+                continue;
+            }
+
+            let Some(value) = leaf_string(value) else {
+                issues.push(ConfigIssue {
+                    key,
+                    message: "expected a single value".to_string(),
+                    fatal: true,
+                });
+                continue;
+            };
+
+            if let Err(e) = scratch.set(&key, &value) {
+                issues.push(ConfigIssue { key, message: e.to_string(), fatal: true });
+            }
+        }
+
+        if let Some(message) = directory_problem(&scratch.output.dir()) {
+            issues.push(ConfigIssue {
+                key: "output.directory".to_string(),
+                message,
+                fatal: false,
+            });
+        }
+
+        issues
+    }
+
+    /// Re-read the config file, keeping the current API key.
+    ///
+    /// Used by long-running modes to pick up edits made while they run.
+    pub fn reload(&mut self) -> Result<()> {
+        let mut config = Self::read(&self.config_path)?;
+        config.api.key = self.api.key.take();
+
+        *self = config;
+        Ok(())
+    }
+
+    /// Exclusive lock on the config file, held while it is read and rewritten
+    fn lock(&self) -> Result<FileLock> {
+        FileLock::acquire(&self.config_path.with_extension("toml.lock"), "Config file")
+    }
+
+    /// Save config to file, replacing whatever is on disk
     pub fn save(&self) -> Result<()> {
+        let _lock = self.lock()?;
+        self.write()
+    }
+
+    /// Apply `change` to the config file as it is on disk now and save it.
+    ///
+    /// The file is re-read under a lock, so settings saved by another
+    /// process since this config was loaded are kept rather than
+    /// overwritten with stale values. `self` becomes the merged config.
+    pub fn update(&mut self, change: impl FnOnce(&mut Config) -> Result<()>) -> Result<()> {
+        let _lock = self.lock()?;
+        let mut config = if self.config_path.exists() {
+            Self::read(&self.config_path)?
+        } else {
+            Config {
+                config_path: self.config_path.clone(),
+                ..Config::default()
+            }
+        };
+        change(&mut config)?;
+        config.write()?;
+
+        // Environment variable still takes precedence in memory
+        if let Ok(key) = std::env::var("GEMINI_API_KEY") {
+            config.api.key = Some(key);
+        }
+        *self = config;
+        Ok(())
+    }
+
+    /// Write the file atomically: readers see either the old or the new
+    /// contents, never a partial write
+    fn write(&self) -> Result<()> {
         if let Some(parent) = self.config_path.parent() {
             fs::create_dir_all(parent)
                 .context("Failed to create config directory")?;
@@ -228,9 +1747,21 @@ impl Config {
 
         let content = toml::to_string_pretty(self)
             .context("Failed to serialize config")?;
-        fs::write(&self.config_path, content)
+        let tmp_path = self
+            .config_path
+            .with_extension(format!("toml.{}.tmp", std::process::id()));
+        fs::write(&tmp_path, &content)
             .context("Failed to write config file")?;
+        // Keep restrictive permissions on a file that may hold the API key
+        if let Ok(metadata) = fs::metadata(&self.config_path) {
+            let _ = fs::set_permissions(&tmp_path, metadata.permissions());
+        }
+        if let Err(e) = fs::rename(&tmp_path, &self.config_path) {
+            let _ = fs::remove_file(&tmp_path);
+            return Err(e).context("Failed to write config file");
+        }
 
+        *LAST_WRITTEN.lock().unwrap_or_else(|e| e.into_inner()) = Some(content);
         Ok(())
     }
 
@@ -241,90 +1772,184 @@ impl Config {
 
     /// Set a config value by key path (e.g., "api.key", "defaults.aspect_ratio")
     pub fn set(&mut self, key: &str, value: &str) -> Result<()> {
-        match key {
-            "api.key" => self.api.key = Some(value.to_string()),
-            "api.model" => self.api.model = value.to_string(),
-            "api.base_url" => self.api.base_url = value.to_string(),
-            "defaults.aspect_ratio" => {
-                // Validate aspect ratio
-                let valid = ["1:1", "2:3", "3:2", "3:4", "4:3", "4:5", "5:4", "9:16", "16:9", "21:9"];
-                if valid.contains(&value) {
-                    self.defaults.aspect_ratio = value.to_string();
-                } else {
-                    anyhow::bail!("Invalid aspect ratio. Valid values: {}", valid.join(", "));
-                }
+        if let Some(name) = key.strip_prefix("api.headers.") {
+            reqwest::header::HeaderName::from_bytes(name.as_bytes())
+                .with_context(|| format!("Invalid header name: {}", name))?;
+            if value.is_empty() {
+                self.api.headers.remove(name);
+            } else {
+                self.api.headers.insert(name.to_string(), value.to_string());
             }
-            "defaults.size" => {
-                let valid = ["1K", "2K", "4K"];
-                if valid.contains(&value) {
-                    self.defaults.size = value.to_string();
-                } else {
-                    anyhow::bail!("Invalid size. Valid values: {}", valid.join(", "));
-                }
+            return Ok(());
+        }
+
+        if let Some(alias) = key.strip_prefix("models.aliases.") {
+            if alias.is_empty() || alias.contains('.') {
+                anyhow::bail!("Invalid alias name: {:?}", alias);
             }
-            "output.directory" => self.output.directory = value.to_string(),
-            "output.auto_download" => {
-                self.output.auto_download = value.parse()
-                    .context("Invalid boolean value")?;
+            if value.is_empty() {
+                self.models.aliases.remove(alias);
+            } else {
+                self.models.aliases.insert(alias.to_string(), value.to_string());
             }
-            "output.display" => {
-                self.output.display = DisplayMode::from_str(value);
+            return Ok(());
+        }
+
+        if let Some(rest) = key.strip_prefix("model-defaults.") {
+            // Model names contain dots, so the setting is the last segment
+            let (model, field) = rest
+                .rsplit_once('.')
+                .with_context(|| format!("Expected model-defaults.<model>.<setting>, got {}", key))?;
+            let model = self.resolve_model(model);
+            let info = key_info(&format!("defaults.{}", field))
+                .with_context(|| format!("Unknown model default: {} (use aspect_ratio or size)", field))?;
+            let value = match value {
+                "" => None,
+                _ => Some(info.check(value)?),
+            };
+
+            let overrides = self.model_defaults.entry(model.clone()).or_default();
+            match field {
+                "aspect_ratio" => overrides.aspect_ratio = value,
+                _ => overrides.size = value,
             }
-            "tui.show_images" => {
-                self.tui.show_images = value.parse()
-                    .context("Invalid boolean value")?;
+            if overrides.is_empty() {
+                self.model_defaults.remove(&model);
             }
-            "tui.theme" => self.tui.theme = value.to_string(),
-            _ => anyhow::bail!("Unknown config key: {}", key),
+            return Ok(());
         }
-        Ok(())
+
+        let info = key_info(key).with_context(|| format!("Unknown config key: {}", key))?;
+        let value = info.check(value)?;
+        (info.set)(self, &value)
     }
 
     /// Get a config value by key path
     pub fn get(&self, key: &str) -> Option<String> {
-        match key {
-            "api.key" => self.api.key.clone().map(|_| "****".to_string()), // Mask API key
-            "api.model" => Some(self.api.model.clone()),
-            "api.base_url" => Some(self.api.base_url.clone()),
-            "defaults.aspect_ratio" => Some(self.defaults.aspect_ratio.clone()),
-            "defaults.size" => Some(self.defaults.size.clone()),
-            "output.directory" => Some(self.output.directory.clone()),
-            "output.auto_download" => Some(self.output.auto_download.to_string()),
-            "output.display" => Some(self.output.display.as_str().to_string()),
-            "tui.show_images" => Some(self.tui.show_images.to_string()),
-            "tui.theme" => Some(self.tui.theme.clone()),
-            _ => None,
+        // Header values often carry credentials
+        if let Some(name) = key.strip_prefix("api.headers.") {
+            return self.api.headers.get(name).map(|_| MASK.to_string());
+        }
+        if let Some(alias) = key.strip_prefix("models.aliases.") {
+            return self.models.aliases.get(alias).cloned();
+        }
+        if let Some((model, field)) = key
+            .strip_prefix("model-defaults.")
+            .and_then(|rest| rest.rsplit_once('.'))
+        {
+            let overrides = self.model_defaults.get(&self.resolve_model(model))?;
+            return match field {
+                "aspect_ratio" => overrides.aspect_ratio.clone(),
+                "size" => overrides.size.clone(),
+                _ => None,
+            };
+        }
+        key_info(key).and_then(|info| (info.get)(self))
+    }
+
+    /// Like `get`, but with secrets (the API key, header values) in plain text
+    pub fn get_revealed(&self, key: &str) -> Option<String> {
+        if let Some(name) = key.strip_prefix("api.headers.") {
+            return self.api.headers.get(name).cloned();
+        }
+        if key == "api.key" {
+            return self.api.key.clone();
+        }
+        if key == "replicate.token" {
+            return self.replicate.token.clone();
+        }
+        if key == "openai.key" {
+            return self.openai.key.clone();
+        }
+        self.get(key)
+    }
+
+    /// Full model name for `model`, which may be an alias from `[models.aliases]`
+    pub fn resolve_model(&self, model: &str) -> String {
+        self.models
+            .aliases
+            .get(model)
+            .cloned()
+            .unwrap_or_else(|| model.to_string())
+    }
+
+    /// The requested model, or the provider's configured one if none was
+    /// given, with aliases resolved
+    pub fn model(&self, requested: Option<&str>) -> String {
+        let default = match self.api.provider {
+            ProviderKind::Gemini => &self.api.model,
+            ProviderKind::Replicate => &self.replicate.model,
+            ProviderKind::OpenAi => &self.openai.model,
+        };
+        self.resolve_model(requested.unwrap_or(default))
+    }
+
+    /// This config pointed at a recorded backend (as stored on a job), so
+    /// reruns go where the original request went
+    pub fn for_backend(&self, provider: &str, base_url: Option<&str>) -> Config {
+        let mut config = self.clone();
+        config.api.provider = ProviderKind::from_str(provider);
+        if let Some(url) = base_url {
+            let target = match config.api.provider {
+                ProviderKind::Gemini => &mut config.api.base_url,
+                ProviderKind::Replicate => &mut config.replicate.base_url,
+                ProviderKind::OpenAi => &mut config.openai.base_url,
+            };
+            *target = url.to_string();
+        }
+        config
+    }
+
+    /// Replicate token (from config or environment)
+    pub fn replicate_token(&self) -> Option<String> {
+        std::env::var("REPLICATE_API_TOKEN").ok().or_else(|| self.replicate.token.clone())
+    }
+
+    /// OpenAI API key (from config or environment)
+    pub fn openai_key(&self) -> Option<String> {
+        std::env::var("OPENAI_API_KEY").ok().or_else(|| self.openai.key.clone())
+    }
+
+    /// `[defaults]` with any `[model-defaults."<model>"]` overrides applied
+    pub fn defaults_for(&self, model: &str) -> DefaultsConfig {
+        let mut defaults = self.defaults.clone();
+        if let Some(overrides) = self.model_defaults.get(model) {
+            if let Some(aspect_ratio) = &overrides.aspect_ratio {
+                defaults.aspect_ratio = aspect_ratio.clone();
+            }
+            if let Some(size) = &overrides.size {
+                defaults.size = size.clone();
+            }
         }
+        defaults
     }
 
     /// Get all config keys
-    pub fn keys() -> &'static [&'static str] {
-        &[
-            "api.key",
-            "api.model",
-            "api.base_url",
-            "defaults.aspect_ratio",
-            "defaults.size",
-            "output.directory",
-            "output.auto_download",
-            "output.display",
-            "tui.show_images",
-            "tui.theme",
-        ]
+    pub fn keys() -> impl Iterator<Item = &'static str> {
+        KEYS.iter().map(|info| info.key)
+    }
+
+    /// Where current values come from, reading the config file once
+    pub fn sources(&self) -> KeySources {
+        KeySources {
+            table: fs::read_to_string(&self.config_path)
+                .ok()
+                .and_then(|content| content.parse().ok()),
+        }
     }
 
     /// Available aspect ratios
-    pub fn aspect_ratios() -> &'static [&'static str] {
+    pub const fn aspect_ratios() -> &'static [&'static str] {
         &["1:1", "2:3", "3:2", "3:4", "4:3", "4:5", "5:4", "9:16", "16:9", "21:9"]
     }
 
     /// Available sizes
-    pub fn sizes() -> &'static [&'static str] {
+    pub const fn sizes() -> &'static [&'static str] {
         &["1K", "2K", "4K"]
     }
 
     /// Available models
-    pub fn models() -> &'static [&'static str] {
+    pub const fn models() -> &'static [&'static str] {
         &[
             "gemini-3-pro-image-preview",
             "gemini-2.5-flash-image",
@@ -332,3 +1957,67 @@ impl Config {
         ]
     }
 }
+
+/// Config file contents this process last wrote, so watchers can tell
+/// its own saves from edits made elsewhere
+static LAST_WRITTEN: Mutex<Option<String>> = Mutex::new(None);
+
+/// Watches config.toml for edits made by hand or by other processes.
+///
+/// The directory is watched rather than the file, since saves replace the
+/// file by renaming over it.
+pub struct ConfigWatcher {
+    path: PathBuf,
+    events: mpsc::Receiver<notify::Result<notify::Event>>,
+    /// Contents at the last check, to skip events that changed nothing
+    seen: Option<String>,
+    _watcher: RecommendedWatcher,
+}
+
+impl ConfigWatcher {
+    pub fn new(config: &Config) -> Result<Self> {
+        let path = config.config_path.clone();
+        let dir = path.parent().context("Config path has no directory")?;
+        let (tx, events) = mpsc::channel();
+        let mut watcher = notify::recommended_watcher(tx).context("Failed to start config watcher")?;
+        watcher
+            .watch(dir, RecursiveMode::NonRecursive)
+            .with_context(|| format!("Failed to watch {}", dir.display()))?;
+
+        Ok(Self {
+            seen: fs::read_to_string(&path).ok(),
+            path,
+            events,
+            _watcher: watcher,
+        })
+    }
+
+    /// Whether the file was changed by someone else since the last check.
+    ///
+    /// Saves made by this process are not reported.
+    pub fn changed(&mut self) -> bool {
+        let name = self.path.file_name();
+        let touched = self
+            .events
+            .try_iter()
+            .filter(|event| match event {
+                Ok(event) => event.paths.iter().any(|p| p.file_name() == name),
+                Err(_) => false,
+            })
+            .count()
+            > 0;
+        if !touched {
+            return false;
+        }
+
+        let Ok(content) = fs::read_to_string(&self.path) else {
+            return false;
+        };
+        if self.seen.as_deref() == Some(content.as_str()) {
+            return false;
+        }
+        let own = LAST_WRITTEN.lock().unwrap_or_else(|e| e.into_inner()).as_deref() == Some(content.as_str());
+        self.seen = Some(content);
+        !own
+    }
+}
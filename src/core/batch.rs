@@ -0,0 +1,112 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use super::job::{Job, JobAction};
+use super::params::FitMode;
+
+/// The command that created a batch
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum BatchKind {
+    Sweep,
+    EditBatch,
+}
+
+impl BatchKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BatchKind::Sweep => "sweep",
+            BatchKind::EditBatch => "edit-batch",
+        }
+    }
+
+    /// Prefix of IDs for batches of this kind
+    fn id_prefix(&self) -> &'static str {
+        match self {
+            BatchKind::Sweep => "sw",
+            BatchKind::EditBatch => "eb",
+        }
+    }
+}
+
+/// One job a batch is expected to produce
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct BatchMember {
+    pub model: String,
+    pub aspect_ratio: String,
+    pub size: String,
+    /// Source image for edit members
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source_image: Option<String>,
+}
+
+impl BatchMember {
+    /// Whether `job` was run for this member
+    pub fn matches(&self, job: &Job) -> bool {
+        let source_image = match &job.action {
+            JobAction::Edit { source_image } => Some(source_image),
+            _ => None,
+        };
+        job.params.model == self.model
+            && job.params.aspect_ratio == self.aspect_ratio
+            && job.params.size == self.size
+            && source_image == self.source_image.as_ref()
+    }
+
+    /// Short description for progress and result tables
+    pub fn label(&self) -> String {
+        match &self.source_image {
+            Some(source) => source.clone(),
+            None => format!("{:<6} {:<4} {}", self.aspect_ratio, self.size, self.model),
+        }
+    }
+}
+
+/// A group of jobs run by one command, recorded so it can be resumed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Batch {
+    /// Unique batch ID (e.g., "sw_abc12345")
+    pub id: String,
+
+    pub kind: BatchKind,
+
+    /// Rendered prompt shared by every member
+    pub prompt: String,
+
+    /// Every job the batch should produce, in display order
+    pub members: Vec<BatchMember>,
+
+    /// How edit sources were reshaped before upload
+    #[serde(default)]
+    pub fit: Option<FitMode>,
+
+    /// Project the jobs are billed to
+    #[serde(default)]
+    pub project: Option<String>,
+
+    /// Where images are downloaded (None when downloads were disabled)
+    pub output_dir: Option<String>,
+
+    /// Whether the configured watermark is applied
+    pub watermark: bool,
+
+    pub created_at: DateTime<Utc>,
+}
+
+impl Batch {
+    pub fn new(kind: BatchKind, prompt: impl Into<String>, members: Vec<BatchMember>) -> Self {
+        let uuid = Uuid::new_v4();
+        Self {
+            id: format!("{}_{}", kind.id_prefix(), &uuid.to_string()[..8]),
+            kind,
+            prompt: prompt.into(),
+            members,
+            fit: None,
+            project: None,
+            output_dir: None,
+            watermark: true,
+            created_at: Utc::now(),
+        }
+    }
+}
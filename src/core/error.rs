@@ -1,15 +1,59 @@
 use thiserror::Error;
 
+const MB: f64 = 1024.0 * 1024.0;
+
+/// Result type for the API client, database, and other library-level code
+pub type Result<T, E = BananaError> = std::result::Result<T, E>;
+
 #[derive(Error, Debug)]
 pub enum BananaError {
     #[error("API key not configured. Set GEMINI_API_KEY environment variable or run: banana config set api.key <your-key>")]
     MissingApiKey,
 
     #[error("API error: {message}")]
-    ApiError {
+    ApiError { message: String },
+
+    #[error("API key rejected: {message}. Check GEMINI_API_KEY or run: banana config set api.key <your-key>")]
+    Unauthorized { message: String },
+
+    #[error("Blocked by safety filters ({reason}{}): {message}. Try rephrasing the prompt: describe the scene neutrally and avoid real people, violence, or explicit content", if categories.is_empty() { String::new() } else { format!("; {}", categories.join(", ")) })]
+    SafetyBlocked {
+        /// API block or finish reason, e.g. "SAFETY" or "PROHIBITED_CONTENT"
+        reason: String,
+        /// Harm categories that were flagged (e.g. "dangerous content")
+        categories: Vec<String>,
+        message: String,
+    },
+
+    #[error("Network error: {0}")]
+    Network(#[source] reqwest::Error),
+
+    #[error("Quota exceeded: {message}{}. Wait and retry, lower concurrency (-j), or try another --model", retry_after.map(|s| format!(" (resets in {}s)", s)).unwrap_or_default())]
+    RateLimited {
         message: String,
-        #[source]
-        source: Option<reqwest::Error>,
+        /// Seconds until the quota resets, if known
+        retry_after: Option<u64>,
+    },
+
+    #[error("Model not available: {model} ({message}). Pick another with --model or: banana config set api.model <model>")]
+    ModelNotFound { model: String, message: String },
+
+    #[error("Gemini API unavailable: {message}. Retry shortly, or set api.max_retries to retry automatically")]
+    ServiceUnavailable { message: String },
+
+    #[error("Prompt too long: {tokens} tokens exceeds the {limit}-token limit of {model}")]
+    PromptTooLong {
+        model: String,
+        tokens: u32,
+        limit: u32,
+    },
+
+    #[error("Request too large: {what} is {:.1} MB, over the {:.0} MB API request limit", *size as f64 / MB, *limit as f64 / MB)]
+    PayloadTooLarge {
+        /// The offending part of the request (e.g. a reference image path)
+        what: String,
+        size: usize,
+        limit: usize,
     },
 
     #[error("Invalid API response: {0}")]
@@ -18,6 +62,15 @@ pub enum BananaError {
     #[error("Job not found: {0}")]
     JobNotFound(String),
 
+    #[error("Batch not found: {0}")]
+    BatchNotFound(String),
+
+    #[error("Job ID {0} is already in use")]
+    DuplicateJobId(String),
+
+    #[error("Job {0} is in the destination's trash; restore or purge it there before copying it again")]
+    JobInTrash(String),
+
     #[error("Invalid parameter: {0}")]
     InvalidParameter(String),
 
@@ -33,19 +86,22 @@ pub enum BananaError {
     #[error("IO error: {0}")]
     IoError(#[from] std::io::Error),
 
+    #[error("JSON error: {0}")]
+    Serialization(#[from] serde_json::Error),
+
+    #[error("Fixture error: {0}")]
+    Fixture(String),
+
     #[error("Generation failed: {0}")]
     GenerationFailed(String),
 
-    #[error("Request timeout")]
-    Timeout,
+    #[error("Job timed out after {0}s (api.job_timeout_secs)")]
+    Timeout(u64),
 }
 
 impl From<reqwest::Error> for BananaError {
     fn from(err: reqwest::Error) -> Self {
-        BananaError::ApiError {
-            message: err.to_string(),
-            source: Some(err),
-        }
+        BananaError::Network(err)
     }
 }
 
@@ -1,8 +1,17 @@
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, Local, NaiveDate, Utc};
 use serde::{Deserialize, Serialize};
+use std::path::Path;
 use uuid::Uuid;
 
 use super::params::GenerateParams;
+use super::text;
+use super::usage::{self, TokenUsage};
+
+/// How often a running job's record is refreshed while its request is in flight
+pub const HEARTBEAT_INTERVAL: std::time::Duration = std::time::Duration::from_secs(5);
+
+/// Running jobs not refreshed for this long are presumed hung
+const STALE_AFTER_SECS: i64 = 30;
 
 /// Represents a single generated image
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -15,6 +24,22 @@ pub struct JobImage {
     pub path: Option<String>,
     /// Mime type
     pub mime_type: String,
+    /// Pixel width, read from the file when it was saved
+    #[serde(default)]
+    pub width: Option<u32>,
+    /// Pixel height, read from the file when it was saved
+    #[serde(default)]
+    pub height: Option<u32>,
+    /// Promoted to stand for the job in place of the first image
+    #[serde(default)]
+    pub primary: bool,
+}
+
+impl JobImage {
+    /// Dimensions as "WIDTHxHEIGHT", if known
+    pub fn dimensions(&self) -> Option<String> {
+        Some(format!("{}x{}", self.width?, self.height?))
+    }
 }
 
 /// The type of action performed
@@ -28,6 +53,11 @@ pub enum JobAction {
         /// Path to source image
         source_image: String,
     },
+    /// Re-render an existing image at a higher resolution
+    Upscale {
+        /// Path to source image
+        source_image: String,
+    },
 }
 
 impl std::fmt::Display for JobAction {
@@ -35,6 +65,7 @@ impl std::fmt::Display for JobAction {
         match self {
             JobAction::Generate => write!(f, "generate"),
             JobAction::Edit { .. } => write!(f, "edit"),
+            JobAction::Upscale { .. } => write!(f, "upscale"),
         }
     }
 }
@@ -112,17 +143,87 @@ pub struct Job {
 
     /// Parent job ID (for variations/edits)
     pub parent_id: Option<String>,
+
+    /// Token usage reported by the API
+    #[serde(default)]
+    pub usage: Option<TokenUsage>,
+
+    /// Project the job is billed to
+    #[serde(default)]
+    pub project: Option<String>,
+
+    /// Files derived from this job outside banana (e.g. retouched exports)
+    #[serde(default)]
+    pub attachments: Vec<JobAttachment>,
+
+    /// Batch this job was run as part of (e.g. a sweep)
+    #[serde(default)]
+    pub batch_id: Option<String>,
+
+    /// Backend the job was sent to (e.g. "gemini", "replicate")
+    #[serde(default)]
+    pub provider: Option<String>,
+
+    /// API root the request went to
+    #[serde(default)]
+    pub base_url: Option<String>,
+
+    /// Why the model was picked, when the job asked for "auto"
+    #[serde(default)]
+    pub route: Option<String>,
+
+    /// Starred jobs are never evicted by the history size caps
+    #[serde(default)]
+    pub starred: bool,
+}
+
+/// A downloaded image file, described for JSON output
+#[derive(Debug, Clone, Serialize)]
+pub struct Artifact {
+    /// Index of the image in the generation
+    pub index: u8,
+    /// Absolute file path
+    pub path: String,
+    /// File size in bytes
+    pub bytes: u64,
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    pub mime_type: String,
+}
+
+/// A job with its artifacts, as printed by `--format json`
+#[derive(Debug, Serialize)]
+pub struct JobWithArtifacts<'a> {
+    #[serde(flatten)]
+    pub job: &'a Job,
+    pub artifacts: Vec<Artifact>,
+    /// Flags that were set but had no effect on this model
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub params_ignored: Vec<&'static str>,
+}
+
+/// An external file linked to a job
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JobAttachment {
+    /// File path
+    pub path: String,
+
+    /// When the file was attached
+    pub added_at: DateTime<Utc>,
+}
+
+/// Random job ID such as "bn_abc12345"
+fn new_id() -> String {
+    format!("bn_{}", &Uuid::new_v4().simple().to_string()[..8])
 }
 
 impl Job {
     /// Create a new generation job
     pub fn new_generate(params: GenerateParams) -> Self {
-        let uuid = Uuid::new_v4();
-        let id = format!("bn_{}", &uuid.to_string()[..8]);
         let now = Utc::now();
 
         Self {
-            id,
+            id: new_id(),
             action: JobAction::Generate,
             model: params.model.clone(),
             params,
@@ -131,17 +232,23 @@ impl Job {
             created_at: now,
             updated_at: now,
             parent_id: None,
+            usage: None,
+            project: None,
+            attachments: Vec::new(),
+            batch_id: None,
+            provider: None,
+            base_url: None,
+            route: None,
+            starred: false,
         }
     }
 
     /// Create a new edit job
     pub fn new_edit(params: GenerateParams, source_image: String) -> Self {
-        let uuid = Uuid::new_v4();
-        let id = format!("bn_{}", &uuid.to_string()[..8]);
         let now = Utc::now();
 
         Self {
-            id,
+            id: new_id(),
             action: JobAction::Edit { source_image },
             model: params.model.clone(),
             params,
@@ -150,9 +257,30 @@ impl Job {
             created_at: now,
             updated_at: now,
             parent_id: None,
+            usage: None,
+            project: None,
+            attachments: Vec::new(),
+            batch_id: None,
+            provider: None,
+            base_url: None,
+            route: None,
+            starred: false,
         }
     }
 
+    /// Create a new upscale job as a child of `parent_id` (if known)
+    pub fn new_upscale(params: GenerateParams, source_image: String, parent_id: Option<String>) -> Self {
+        let mut job = Self::new_edit(params, source_image.clone());
+        job.action = JobAction::Upscale { source_image };
+        job.parent_id = parent_id;
+        job
+    }
+
+    /// Give the job a fresh ID (after colliding with an existing one)
+    pub fn regenerate_id(&mut self) {
+        self.id = new_id();
+    }
+
     /// Set job as running with progress
     pub fn set_running(&mut self, progress: u8) {
         self.status = JobStatus::Running { progress: progress.min(100) };
@@ -184,16 +312,108 @@ impl Job {
             data: Some(data),
             path: None,
             mime_type,
+            width: None,
+            height: None,
+            primary: false,
         });
         self.updated_at = Utc::now();
     }
 
-    /// Get the prompt (truncated for display)
+    /// The downloaded image that stands for the job: the promoted one, or
+    /// else the first
+    pub fn primary_image(&self) -> Option<&JobImage> {
+        let mut downloaded = self.images.iter().filter(|img| img.path.is_some());
+        downloaded
+            .clone()
+            .find(|img| img.primary)
+            .or_else(|| downloaded.next())
+    }
+
+    /// Promote image `index` to primary (false if there is no such image)
+    pub fn set_primary_image(&mut self, index: u8) -> bool {
+        if !self.images.iter().any(|img| img.index == index) {
+            return false;
+        }
+        for img in &mut self.images {
+            img.primary = img.index == index;
+        }
+        self.updated_at = Utc::now();
+        true
+    }
+
+    /// Remove image `index` from the job (its file is left alone).
+    ///
+    /// Returns the removed image, or None if the job has no such image.
+    pub fn remove_image(&mut self, index: u8) -> Option<JobImage> {
+        let position = self.images.iter().position(|img| img.index == index)?;
+        let image = self.images.remove(position);
+        self.updated_at = Utc::now();
+        Some(image)
+    }
+
+    /// Local calendar day the job was created on
+    pub fn created_day(&self) -> NaiveDate {
+        self.created_at.with_timezone(&Local).date_naive()
+    }
+
+    /// Get the prompt on one line, shortened to `max_len` columns with an ellipsis
     pub fn prompt_preview(&self, max_len: usize) -> String {
-        if self.params.prompt.len() <= max_len {
-            self.params.prompt.clone()
+        // Keep multi-line prompts on one row in lists and spinners
+        let single_line: String = self
+            .params
+            .prompt
+            .chars()
+            .map(|c| if c.is_control() { ' ' } else { c })
+            .collect();
+        text::truncate(&single_line, max_len)
+    }
+
+    /// Estimated cost in USD based on recorded usage
+    pub fn estimated_cost(&self) -> Option<f64> {
+        usage::estimate_cost(&self.model, self.usage.as_ref(), self.images.len())
+    }
+
+    /// Downloaded images that still exist on disk, with the dimensions
+    /// recorded when they were saved
+    pub fn artifacts(&self) -> Vec<Artifact> {
+        self.images
+            .iter()
+            .filter_map(|img| {
+                let path = std::path::absolute(Path::new(img.path.as_ref()?)).ok()?;
+                let bytes = std::fs::metadata(&path).ok()?.len();
+                Some(Artifact {
+                    index: img.index,
+                    path: path.to_string_lossy().into_owned(),
+                    bytes,
+                    width: img.width,
+                    height: img.height,
+                    mime_type: img.mime_type.clone(),
+                })
+            })
+            .collect()
+    }
+
+    /// This job plus its artifacts, for JSON output
+    pub fn with_artifacts(&self) -> JobWithArtifacts<'_> {
+        JobWithArtifacts {
+            job: self,
+            artifacts: self.artifacts(),
+            params_ignored: self.params.ignored_params(),
+        }
+    }
+
+    /// Whether the job claims to be running but its process stopped sending heartbeats
+    pub fn is_stale(&self) -> bool {
+        matches!(self.status, JobStatus::Running { .. })
+            && (Utc::now() - self.updated_at).num_seconds() > STALE_AFTER_SECS
+    }
+
+    /// Status name for display, with hung running jobs shown as "stalled"
+    pub fn status_label(&self) -> &'static str {
+        if self.is_stale() {
+            "stalled"
         } else {
-            format!("{}...", &self.params.prompt[..max_len.saturating_sub(3)])
+            self.status_name()
         }
     }
 
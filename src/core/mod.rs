@@ -1,7 +1,13 @@
+pub mod batch;
 pub mod error;
 pub mod job;
 pub mod params;
+pub mod prompt;
+pub mod text;
+pub mod usage;
 
-pub use error::BananaError;
-pub use job::{Job, JobAction, JobStatus, JobImage};
-pub use params::GenerateParams;
+pub use batch::{Batch, BatchKind, BatchMember};
+pub use error::{BananaError, Result};
+pub use job::{Job, JobAction, JobAttachment, JobStatus, JobImage};
+pub use params::{ContextTurn, FitMode, GenerateParams, Modality, PersonGeneration};
+pub use usage::TokenUsage;
@@ -1,4 +1,9 @@
+use clap::ValueEnum;
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+use super::error::BananaError;
 
 /// Parameters for image generation
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -25,6 +30,26 @@ pub struct GenerateParams {
     /// Optional seed for reproducibility
     pub seed: Option<i64>,
 
+    /// Sampling temperature (0.0-2.0; lower sticks closer to the prompt)
+    #[serde(default)]
+    pub temperature: Option<f64>,
+
+    /// Nucleus sampling: only tokens within this probability mass (0.0-1.0)
+    #[serde(default)]
+    pub top_p: Option<f64>,
+
+    /// Sample only from the K most likely tokens
+    #[serde(default)]
+    pub top_k: Option<u32>,
+
+    /// Whether the images may show people (unset: the API's default)
+    #[serde(default)]
+    pub person_generation: Option<PersonGeneration>,
+
+    /// Response modalities to ask for (empty: text and image)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub modalities: Vec<Modality>,
+
     /// Optional negative prompt (what to avoid)
     pub negative_prompt: Option<String>,
 
@@ -33,6 +58,110 @@ pub struct GenerateParams {
 
     /// Reference image mime type
     pub reference_mime_type: Option<String>,
+
+    /// Extra `generationConfig` fields from `--gen-config`, by snake_case
+    /// name (dots for nested fields); Gemini models only
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub generation_config: BTreeMap<String, Value>,
+
+    /// Prior conversation turns sent before the prompt, stored like the
+    /// reference image so re-runs send the same conversation
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub context: Vec<ContextTurn>,
+}
+
+/// How to reshape a source image to the requested aspect ratio before upload
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FitMode {
+    /// Center-crop to the target ratio
+    Crop,
+    /// Letterbox onto a white canvas of the target ratio
+    Pad,
+    /// Resize without preserving proportions
+    Stretch,
+}
+
+/// Person generation policy (`--people`, `defaults.people`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+#[value(rename_all = "snake_case")]
+pub enum PersonGeneration {
+    /// Adults, but no children
+    AllowAdult,
+    /// Adults and children
+    AllowAll,
+    /// No people at all
+    DontAllow,
+}
+
+impl PersonGeneration {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PersonGeneration::AllowAdult => "allow_adult",
+            PersonGeneration::AllowAll => "allow_all",
+            PersonGeneration::DontAllow => "dont_allow",
+        }
+    }
+
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s.to_lowercase().replace('-', "_").as_str() {
+            "allow_adult" => Some(PersonGeneration::AllowAdult),
+            "allow_all" => Some(PersonGeneration::AllowAll),
+            "dont_allow" => Some(PersonGeneration::DontAllow),
+            _ => None,
+        }
+    }
+
+    pub const fn variants() -> &'static [&'static str] {
+        &["allow_adult", "allow_all", "dont_allow"]
+    }
+
+    /// The value `image_config.person_generation` takes
+    pub fn api_value(&self) -> &'static str {
+        match self {
+            PersonGeneration::AllowAdult => "ALLOW_ADULT",
+            PersonGeneration::AllowAll => "ALLOW_ALL",
+            PersonGeneration::DontAllow => "DONT_ALLOW",
+        }
+    }
+}
+
+/// A kind of output the model may respond with (`--modalities`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Modality {
+    Text,
+    Image,
+}
+
+impl Modality {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Modality::Text => "text",
+            Modality::Image => "image",
+        }
+    }
+
+    /// The value `response_modalities` takes
+    pub fn api_value(&self) -> &'static str {
+        match self {
+            Modality::Text => "TEXT",
+            Modality::Image => "IMAGE",
+        }
+    }
+}
+
+/// A prior turn of the conversation, used to give edits generation context
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ContextTurn {
+    /// "user" or "model"
+    pub role: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+    /// Inline image as (base64 data, mime type)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub image: Option<(String, String)>,
 }
 
 fn default_aspect_ratio() -> String {
@@ -60,9 +189,16 @@ impl Default for GenerateParams {
             model: default_model(),
             num_images: 1,
             seed: None,
+            temperature: None,
+            top_p: None,
+            top_k: None,
+            person_generation: None,
+            modalities: Vec::new(),
             negative_prompt: None,
             reference_image: None,
             reference_mime_type: None,
+            generation_config: BTreeMap::new(),
+            context: Vec::new(),
         }
     }
 }
@@ -100,6 +236,39 @@ impl GenerateParams {
         self
     }
 
+    /// Set whichever of the sampling controls are given
+    pub fn with_sampling(mut self, temperature: Option<f64>, top_p: Option<f64>, top_k: Option<u32>) -> Self {
+        self.temperature = temperature.or(self.temperature);
+        self.top_p = top_p.or(self.top_p);
+        self.top_k = top_k.or(self.top_k);
+        self
+    }
+
+    pub fn with_person_generation(mut self, policy: PersonGeneration) -> Self {
+        self.person_generation = Some(policy);
+        self
+    }
+
+    /// Ask for these modalities only, in the given order (duplicates dropped)
+    pub fn with_modalities(mut self, modalities: &[Modality]) -> Self {
+        self.modalities.clear();
+        for modality in modalities {
+            if !self.modalities.contains(modality) {
+                self.modalities.push(*modality);
+            }
+        }
+        self
+    }
+
+    /// Modalities sent as `response_modalities`: text and image, unless set
+    /// and taken by the model
+    pub fn response_modalities(&self) -> Vec<String> {
+        if self.modalities.is_empty() || self.ignored_params().contains(&"modalities") {
+            return vec!["TEXT".to_string(), "IMAGE".to_string()];
+        }
+        self.modalities.iter().map(|m| m.api_value().to_string()).collect()
+    }
+
     pub fn with_negative_prompt(mut self, neg: impl Into<String>) -> Self {
         self.negative_prompt = Some(neg.into());
         self
@@ -111,8 +280,118 @@ impl GenerateParams {
         self
     }
 
+    pub fn with_generation_config(mut self, fields: impl IntoIterator<Item = (String, Value)>) -> Self {
+        self.generation_config.extend(fields);
+        self
+    }
+
+    pub fn with_context(mut self, turns: Vec<ContextTurn>) -> Self {
+        self.context = turns;
+        self
+    }
+
+    /// Flags set on these params that the model won't honor.
+    ///
+    /// Gemini models take a count (one request per image), a sampling seed, sampling
+    /// controls, a person generation policy, response modalities, and other
+    /// fields through `generationConfig`; Imagen models take none of them.
+    /// Replicate models (`owner/name` slugs) take a count and seed as model
+    /// inputs; OpenAI models take a count (except DALL·E 3) but no seed.
+    pub fn ignored_params(&self) -> Vec<&'static str> {
+        let replicate = self.model.contains('/');
+        let gemini = self.model.starts_with("gemini-");
+        let takes_count =
+            replicate || gemini || self.model.starts_with("gpt-image-") || self.model == "dall-e-2";
+        let takes_seed = replicate || gemini;
+        let takes_gen_config = gemini;
+
+        let mut ignored = Vec::new();
+        if self.num_images > 1 && !takes_count {
+            ignored.push("num-images");
+        }
+        if self.seed.is_some() && !takes_seed {
+            ignored.push("seed");
+        }
+        if !takes_gen_config {
+            let sampling = [
+                ("temperature", self.temperature.is_some()),
+                ("top-p", self.top_p.is_some()),
+                ("top-k", self.top_k.is_some()),
+                ("people", self.person_generation.is_some()),
+                ("modalities", !self.modalities.is_empty()),
+            ];
+            ignored.extend(sampling.into_iter().filter(|(_, set)| *set).map(|(flag, _)| flag));
+        }
+        if !self.generation_config.is_empty() && !takes_gen_config {
+            ignored.push("gen-config");
+        }
+        ignored
+    }
+
+    /// Whether the model takes an output size (only Gemini 3 Pro does;
+    /// other models render at their native size, about 1K)
+    pub fn supports_size(&self) -> bool {
+        self.model.starts_with("gemini-3-pro-image")
+    }
+
     /// Check if this is an edit request (has reference image)
     pub fn is_edit(&self) -> bool {
         self.reference_image.is_some()
     }
 }
+
+/// Parse a `--gen-config KEY=VALUE` flag.
+///
+/// The value is read as JSON when it is valid JSON (numbers, booleans,
+/// arrays, quoted strings) and as a plain string otherwise. camelCase keys
+/// are converted to the snake_case the rest of the request uses.
+pub fn parse_gen_config(s: &str) -> Result<(String, Value), String> {
+    let (key, value) = s
+        .split_once('=')
+        .ok_or_else(|| format!("expected key=value, got '{}'", s))?;
+    let key = key.trim();
+    if key.is_empty() || key.split('.').any(|part| part.is_empty()) {
+        return Err(format!("invalid field name '{}'", key));
+    }
+    let key = key
+        .chars()
+        .fold(String::with_capacity(key.len()), |mut snake, c| {
+            if c.is_ascii_uppercase() {
+                snake.push('_');
+                snake.push(c.to_ascii_lowercase());
+            } else {
+                snake.push(c);
+            }
+            snake
+        });
+    let value = serde_json::from_str(value).unwrap_or_else(|_| Value::from(value));
+    Ok((key, value))
+}
+
+/// Parse a `--temperature` value (0.0-2.0)
+pub fn parse_temperature(s: &str) -> Result<f64, String> {
+    parse_in_range(s, 0.0, 2.0)
+}
+
+/// Parse a `--top-p` value (0.0-1.0)
+pub fn parse_top_p(s: &str) -> Result<f64, String> {
+    parse_in_range(s, 0.0, 1.0)
+}
+
+fn parse_in_range(s: &str, min: f64, max: f64) -> Result<f64, String> {
+    let value: f64 = s.trim().parse().map_err(|_| format!("'{}' is not a number", s))?;
+    if !(min..=max).contains(&value) {
+        return Err(format!("{} is not between {} and {}", value, min, max));
+    }
+    Ok(value)
+}
+
+/// Reject `--modalities` lists that can't produce an image
+pub fn check_modalities(modalities: &[Modality]) -> Result<(), BananaError> {
+    if !modalities.is_empty() && !modalities.contains(&Modality::Image) {
+        return Err(BananaError::InvalidParameter(
+            "--modalities must include image (e.g. --modalities image or text,image)".to_string(),
+        ));
+    }
+    Ok(())
+}
@@ -0,0 +1,150 @@
+use std::collections::BTreeMap;
+
+use super::BananaError;
+
+/// Parse a `key=value` pair given to `--var`
+pub fn parse_var(s: &str) -> Result<(String, String), String> {
+    let (key, value) = s
+        .split_once('=')
+        .ok_or_else(|| format!("expected key=value, got '{}'", s))?;
+    let key = key.trim();
+    if key.is_empty() || !key.chars().all(is_placeholder_char) {
+        return Err(format!("invalid variable name '{}'", key));
+    }
+    Ok((key.to_string(), value.to_string()))
+}
+
+/// Substitute `{key}` placeholders and lint the result.
+///
+/// `{{` and `}}` stand for literal braces. Fails if any placeholder is left
+/// unresolved or the prompt contains control characters, so templated
+/// prompts never reach the API half-filled.
+pub fn render(template: &str, vars: &[(String, String)]) -> Result<String, BananaError> {
+    let vars: BTreeMap<&str, &str> = vars
+        .iter()
+        .map(|(k, v)| (k.as_str(), v.as_str()))
+        .collect();
+
+    let (prompt, unresolved) = substitute(template, &vars);
+    let mut issues: Vec<String> = unresolved
+        .iter()
+        .map(|name| format!("unresolved placeholder {{{}}} (pass --var {}=...)", name, name))
+        .collect();
+    issues.extend(lint(&prompt));
+    if !issues.is_empty() {
+        return Err(BananaError::InvalidParameter(format!(
+            "prompt failed lint:\n  {}",
+            issues.join("\n  ")
+        )));
+    }
+    Ok(prompt)
+}
+
+/// Replace known `{key}` placeholders and turn `{{`/`}}` into literal
+/// braces, returning the names of placeholders no variable matched (which
+/// are left untouched)
+fn substitute<'a>(template: &'a str, vars: &BTreeMap<&str, &str>) -> (String, Vec<&'a str>) {
+    let mut out = String::with_capacity(template.len());
+    let mut unresolved = Vec::new();
+    let mut rest = template;
+
+    while let Some(start) = rest.find(['{', '}']) {
+        out.push_str(&rest[..start]);
+        let brace = &rest[start..start + 1];
+        let after = &rest[start + 1..];
+        if let Some(unescaped) = after.strip_prefix(brace) {
+            out.push_str(brace);
+            rest = unescaped;
+            continue;
+        }
+        match placeholder_at(after).filter(|_| brace == "{") {
+            Some(name) => {
+                match vars.get(name) {
+                    Some(value) => out.push_str(value),
+                    None => {
+                        out.push_str(&rest[start..start + name.len() + 2]);
+                        unresolved.push(name);
+                    }
+                }
+                rest = &after[name.len() + 1..];
+            }
+            None => {
+                out.push_str(brace);
+                rest = after;
+            }
+        }
+    }
+
+    out.push_str(rest);
+    (out, unresolved)
+}
+
+/// Describe characters that make a prompt unsafe to send
+pub fn lint(prompt: &str) -> Vec<String> {
+    let mut issues = Vec::new();
+
+    for (i, c) in prompt.chars().enumerate() {
+        if c.is_control() && !matches!(c, '\n' | '\t' | '\r') {
+            issues.push(format!("control character {:?} at position {}", c, i));
+        }
+    }
+
+    issues
+}
+
+/// Byte range of the first `{placeholder}` at or after `from`, braces
+/// included (escaped `{{` braces are skipped)
+pub fn next_placeholder(prompt: &str, from: usize) -> Option<std::ops::Range<usize>> {
+    let mut offset = from.min(prompt.len());
+    while let Some(start) = prompt.get(offset..)?.find('{') {
+        let start = offset + start;
+        let after = &prompt[start + 1..];
+        if after.starts_with('{') {
+            offset = start + 2;
+            continue;
+        }
+        if let Some(name) = placeholder_at(after) {
+            return Some(start..start + name.len() + 2);
+        }
+        offset = start + 1;
+    }
+    None
+}
+
+/// Name of the placeholder starting right after a `{`, if it is one
+fn placeholder_at(s: &str) -> Option<&str> {
+    let end = s.find('}')?;
+    let name = &s[..end];
+    (!name.is_empty() && name.chars().all(is_placeholder_char)).then_some(name)
+}
+
+fn is_placeholder_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_' || c == '-' || c == '.'
+}
+
+/// A file-name-safe slug of the prompt's first words, at most `max_len` chars
+pub fn slug(prompt: &str, max_len: usize) -> String {
+    let mut slug = String::new();
+    for word in prompt
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|w| !w.is_empty())
+    {
+        let sep = usize::from(!slug.is_empty());
+        if slug.len() + sep + word.len() > max_len {
+            if slug.is_empty() {
+                slug.push_str(&word[..max_len].to_ascii_lowercase());
+            }
+            break;
+        }
+        if sep == 1 {
+            slug.push('-');
+        }
+        slug.push_str(&word.to_ascii_lowercase());
+    }
+
+    if slug.is_empty() {
+        "image".to_string()
+    } else {
+        slug
+    }
+}
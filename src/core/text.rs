@@ -0,0 +1,37 @@
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+/// Appended to text that was shortened
+pub const ELLIPSIS: &str = "…";
+
+/// Terminal columns taken up by `s`
+pub fn width(s: &str) -> usize {
+    UnicodeWidthStr::width(s)
+}
+
+/// Shorten `s` to at most `max_width` columns, ending in an ellipsis if anything was cut.
+///
+/// Cuts only between grapheme clusters, so multibyte characters, emoji, and
+/// combining marks are never split.
+pub fn truncate(s: &str, max_width: usize) -> String {
+    if width(s) <= max_width {
+        return s.to_string();
+    }
+    if max_width == 0 {
+        return String::new();
+    }
+    let budget = max_width - width(ELLIPSIS);
+
+    let mut out = String::new();
+    let mut used = 0;
+    for grapheme in s.graphemes(true) {
+        let w = width(grapheme);
+        if used + w > budget {
+            break;
+        }
+        out.push_str(grapheme);
+        used += w;
+    }
+    out.push_str(ELLIPSIS);
+    out
+}
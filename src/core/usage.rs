@@ -0,0 +1,90 @@
+use serde::{Deserialize, Serialize};
+
+/// Token usage reported by the API for a job
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TokenUsage {
+    /// Tokens in the prompt (text and reference images)
+    pub prompt_tokens: Option<i32>,
+    /// Tokens in the generated candidates (including images)
+    pub candidates_tokens: Option<i32>,
+    /// Total tokens billed
+    pub total_tokens: Option<i32>,
+}
+
+impl TokenUsage {
+    /// Add another request's usage to this one
+    pub fn add(&mut self, other: &TokenUsage) {
+        let sum = |a: Option<i32>, b: Option<i32>| match (a, b) {
+            (None, None) => None,
+            (a, b) => Some(a.unwrap_or(0) + b.unwrap_or(0)),
+        };
+        self.prompt_tokens = sum(self.prompt_tokens, other.prompt_tokens);
+        self.candidates_tokens = sum(self.candidates_tokens, other.candidates_tokens);
+        self.total_tokens = sum(self.total_tokens, other.total_tokens);
+    }
+}
+
+/// Published list prices for a model, in USD
+struct ModelPricing {
+    /// Per million input tokens
+    input_per_million: f64,
+    /// Per million output tokens
+    output_per_million: f64,
+    /// Flat price per image for models not billed by tokens
+    per_image: Option<f64>,
+}
+
+/// Look up pricing by model name prefix
+fn pricing_for(model: &str) -> Option<ModelPricing> {
+    if model.starts_with("gemini-3-pro-image") {
+        Some(ModelPricing {
+            input_per_million: 2.0,
+            output_per_million: 120.0,
+            per_image: None,
+        })
+    } else if model.starts_with("gemini-2.5-flash-image") {
+        Some(ModelPricing {
+            input_per_million: 0.30,
+            output_per_million: 30.0,
+            per_image: None,
+        })
+    } else if model.starts_with("imagen-4.0") {
+        Some(ModelPricing {
+            input_per_million: 0.0,
+            output_per_million: 0.0,
+            per_image: Some(0.04),
+        })
+    } else {
+        None
+    }
+}
+
+/// Estimate the cost of a request in USD, or `None` for unknown models or missing usage
+pub fn estimate_cost(model: &str, usage: Option<&TokenUsage>, num_images: usize) -> Option<f64> {
+    let pricing = pricing_for(model)?;
+
+    if let Some(per_image) = pricing.per_image {
+        return Some(per_image * num_images as f64);
+    }
+
+    let usage = usage?;
+    let input = usage.prompt_tokens.unwrap_or(0) as f64;
+    let output = usage.candidates_tokens.unwrap_or(0) as f64;
+    Some(
+        input / 1_000_000.0 * pricing.input_per_million
+            + output / 1_000_000.0 * pricing.output_per_million,
+    )
+}
+
+/// Maximum prompt length in tokens accepted by a model, by name prefix
+pub fn input_token_limit(model: &str) -> Option<u32> {
+    if model.starts_with("gemini-3-pro-image") {
+        Some(65_536)
+    } else if model.starts_with("gemini-2.5-flash-image") {
+        Some(32_768)
+    } else if model.starts_with("imagen-4.0") {
+        Some(480)
+    } else {
+        None
+    }
+}
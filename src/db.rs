@@ -1,11 +1,48 @@
-use anyhow::{Context, Result};
 use chrono::{DateTime, Utc};
 use directories::ProjectDirs;
-use rusqlite::{params, Connection, OptionalExtension};
-use std::path::PathBuf;
+use rusqlite::{params, params_from_iter, Connection, OptionalExtension, TransactionBehavior};
+use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex};
 
-use crate::core::Job;
+use crate::core::{BananaError, Batch, Job, JobStatus, Result};
+use crate::platform;
+
+/// Current database schema version (stored in `PRAGMA user_version`)
+pub const SCHEMA_VERSION: u32 = 9;
+
+/// Fresh IDs tried by `insert_new_job` before giving up
+const MAX_ID_ATTEMPTS: u32 = 5;
+
+/// Columns selected when loading a job, in `row_to_job` order
+const JOB_COLUMNS: &str = "id, action_json, params_json, status_json, images_json, model, created_at, updated_at, parent_id, usage_json, project, attachments_json, batch_id, provider, base_url, route, starred";
+
+/// Criteria for bulk job operations
+#[derive(Debug, Clone, Default)]
+pub struct JobFilter {
+    /// Only jobs with this status name
+    pub status: Option<String>,
+    /// Only jobs created before this time
+    pub created_before: Option<DateTime<Utc>>,
+}
+
+impl JobFilter {
+    /// Build the SQL condition (for live jobs) and its parameters
+    fn where_clause(&self) -> (String, Vec<String>) {
+        let mut conditions = vec!["deleted_at IS NULL".to_string()];
+        let mut values = Vec::new();
+
+        if let Some(status) = &self.status {
+            values.push(format!("%\"status\":\"{}%", status));
+            conditions.push(format!("status_json LIKE ?{}", values.len()));
+        }
+        if let Some(before) = &self.created_before {
+            values.push(before.to_rfc3339());
+            conditions.push(format!("created_at < ?{}", values.len()));
+        }
+
+        (conditions.join(" AND "), values)
+    }
+}
 
 /// Database for job persistence
 pub struct Database {
@@ -16,7 +53,7 @@ impl Database {
     /// Get the database file path
     pub fn db_path() -> Result<PathBuf> {
         let proj_dirs = ProjectDirs::from("com", "nanobanan", "banana-cli")
-            .context("Failed to determine data directory")?;
+            .ok_or_else(|| BananaError::ConfigError("Failed to determine data directory".to_string()))?;
         let data_dir = proj_dirs.data_dir();
         std::fs::create_dir_all(data_dir)?;
         Ok(data_dir.join("jobs.db"))
@@ -24,8 +61,12 @@ impl Database {
 
     /// Open or create the database
     pub fn open() -> Result<Self> {
-        let path = Self::db_path()?;
-        let conn = Connection::open(&path)?;
+        Self::open_at(&Self::db_path()?)
+    }
+
+    /// Open or create a database at a specific path
+    pub fn open_at(path: &Path) -> Result<Self> {
+        let conn = Connection::open(path)?;
 
         let db = Self {
             conn: Arc::new(Mutex::new(conn)),
@@ -37,8 +78,11 @@ impl Database {
 
     /// Initialize database schema
     fn init_schema(&self) -> Result<()> {
-        let conn = self.conn.lock().unwrap();
-        conn.execute_batch(
+        let mut conn = self.conn.lock().unwrap();
+        // One immediate transaction, so an interrupted upgrade rolls back whole
+        // and a second process waits instead of racing through the ALTERs
+        let tx = conn.transaction_with_behavior(TransactionBehavior::Immediate)?;
+        tx.execute_batch(
             r#"
             CREATE TABLE IF NOT EXISTS jobs (
                 id TEXT PRIMARY KEY,
@@ -56,16 +100,81 @@ impl Database {
             CREATE INDEX IF NOT EXISTS idx_jobs_status ON jobs(status_json);
             "#,
         )?;
+
+        let version: u32 = tx.pragma_query_value(None, "user_version", |row| row.get(0))?;
+        if version < 2 {
+            tx.execute_batch(
+                r#"
+                ALTER TABLE jobs ADD COLUMN usage_json TEXT;
+                ALTER TABLE jobs ADD COLUMN project TEXT;
+                "#,
+            )?;
+        }
+
+        if version < 3 {
+            tx.execute_batch(
+                r#"
+                ALTER TABLE jobs ADD COLUMN deleted_at TEXT;
+                CREATE INDEX IF NOT EXISTS idx_jobs_deleted_at ON jobs(deleted_at);
+                "#,
+            )?;
+        }
+
+        if version < 4 {
+            tx.execute("ALTER TABLE jobs ADD COLUMN attachments_json TEXT", [])?;
+        }
+
+        if version < 5 {
+            tx.execute_batch(
+                r#"
+                ALTER TABLE jobs ADD COLUMN batch_id TEXT;
+                CREATE INDEX IF NOT EXISTS idx_jobs_batch_id ON jobs(batch_id);
+                "#,
+            )?;
+        }
+
+        if version < 6 {
+            tx.execute_batch(
+                r#"
+                CREATE TABLE IF NOT EXISTS batches (
+                    id TEXT PRIMARY KEY,
+                    kind TEXT NOT NULL,
+                    spec_json TEXT NOT NULL,
+                    created_at TEXT NOT NULL
+                );
+                "#,
+            )?;
+        }
+
+        if version < 7 {
+            tx.execute_batch(
+                r#"
+                ALTER TABLE jobs ADD COLUMN provider TEXT;
+                ALTER TABLE jobs ADD COLUMN base_url TEXT;
+                "#,
+            )?;
+        }
+
+        if version < 8 {
+            tx.execute("ALTER TABLE jobs ADD COLUMN route TEXT", [])?;
+        }
+
+        if version < 9 {
+            tx.execute("ALTER TABLE jobs ADD COLUMN starred INTEGER NOT NULL DEFAULT 0", [])?;
+        }
+
+        tx.pragma_update(None, "user_version", SCHEMA_VERSION)?;
+        tx.commit()?;
         Ok(())
     }
 
     /// Insert a new job
     pub fn insert_job(&self, job: &Job) -> Result<()> {
         let conn = self.conn.lock().unwrap();
-        conn.execute(
+        let inserted = conn.execute(
             r#"
-            INSERT INTO jobs (id, action_json, params_json, status_json, images_json, model, created_at, updated_at, parent_id)
-            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)
+            INSERT INTO jobs (id, action_json, params_json, status_json, images_json, model, created_at, updated_at, parent_id, usage_json, project, attachments_json, batch_id, provider, base_url, route, starred)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17)
             "#,
             params![
                 job.id,
@@ -77,9 +186,50 @@ impl Database {
                 job.created_at.to_rfc3339(),
                 job.updated_at.to_rfc3339(),
                 job.parent_id,
+                job.usage.as_ref().map(serde_json::to_string).transpose()?,
+                job.project,
+                serde_json::to_string(&job.attachments)?,
+                job.batch_id,
+                job.provider,
+                job.base_url,
+                job.route,
+                job.starred,
             ],
+        );
+        match inserted {
+            Err(rusqlite::Error::SqliteFailure(e, _))
+                if e.extended_code == rusqlite::ffi::SQLITE_CONSTRAINT_PRIMARYKEY =>
+            {
+                Err(BananaError::DuplicateJobId(job.id.clone()))
+            }
+            other => other.map(|_| ()).map_err(Into::into),
+        }
+    }
+
+    /// Whether a job with this ID is in the trash
+    pub fn is_trashed(&self, id: &str) -> Result<bool> {
+        let conn = self.conn.lock().unwrap();
+        let count: i64 = conn.query_row(
+            "SELECT COUNT(*) FROM jobs WHERE id = ?1 AND deleted_at IS NOT NULL",
+            params![id],
+            |row| row.get(0),
         )?;
-        Ok(())
+        Ok(count > 0)
+    }
+
+    /// Insert a newly created job, giving it a fresh ID if its random one is taken
+    pub fn insert_new_job(&self, job: &mut Job) -> Result<()> {
+        let mut attempts = 1;
+        loop {
+            match self.insert_job(job) {
+                Err(BananaError::DuplicateJobId(id)) if attempts < MAX_ID_ATTEMPTS => {
+                    tracing::warn!("Job ID {} already exists, generating a new one", id);
+                    job.regenerate_id();
+                    attempts += 1;
+                }
+                result => return result,
+            }
+        }
     }
 
     /// Update an existing job
@@ -94,7 +244,15 @@ impl Database {
                 images_json = ?5,
                 model = ?6,
                 updated_at = ?7,
-                parent_id = ?8
+                parent_id = ?8,
+                usage_json = ?9,
+                project = ?10,
+                attachments_json = ?11,
+                batch_id = ?12,
+                provider = ?13,
+                base_url = ?14,
+                route = ?15,
+                starred = ?16
             WHERE id = ?1
             "#,
             params![
@@ -106,6 +264,31 @@ impl Database {
                 job.model,
                 job.updated_at.to_rfc3339(),
                 job.parent_id,
+                job.usage.as_ref().map(serde_json::to_string).transpose()?,
+                job.project,
+                serde_json::to_string(&job.attachments)?,
+                job.batch_id,
+                job.provider,
+                job.base_url,
+                job.route,
+                job.starred,
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Refresh a running job's progress and updated_at (no-op once it has finished)
+    pub fn heartbeat(&self, id: &str, progress: u8) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            r#"
+            UPDATE jobs SET status_json = ?2, updated_at = ?3
+            WHERE id = ?1 AND status_json LIKE '%"status":"Running"%'
+            "#,
+            params![
+                id,
+                serde_json::to_string(&JobStatus::Running { progress })?,
+                Utc::now().to_rfc3339(),
             ],
         )?;
         Ok(())
@@ -114,9 +297,7 @@ impl Database {
     /// Get a job by ID
     pub fn get_job(&self, id: &str) -> Result<Option<Job>> {
         let conn = self.conn.lock().unwrap();
-        let mut stmt = conn.prepare(
-            "SELECT id, action_json, params_json, status_json, images_json, model, created_at, updated_at, parent_id FROM jobs WHERE id = ?1"
-        )?;
+        let mut stmt = conn.prepare(&format!("SELECT {} FROM jobs WHERE id = ?1 AND deleted_at IS NULL", JOB_COLUMNS))?;
 
         stmt.query_row(params![id], |row| {
             Ok(self.row_to_job(row))
@@ -125,6 +306,37 @@ impl Database {
         .transpose()
     }
 
+    /// Find the job that produced (or has attached) an image file.
+    ///
+    /// Recorded paths may be relative to where banana ran, so both sides are
+    /// compared canonicalized.
+    pub fn find_job_by_image_path(&self, path: &Path) -> Result<Option<Job>> {
+        let conn = self.conn.lock().unwrap();
+        let query = format!(
+            "SELECT {} FROM jobs WHERE deleted_at IS NULL AND (images_json LIKE ?1 OR attachments_json LIKE ?1) ORDER BY created_at DESC",
+            JOB_COLUMNS
+        );
+        let mut stmt = conn.prepare(&query)?;
+        // LIKE on the file name narrows candidates; the exact match is checked
+        // on the decoded images
+        let Some(name) = path.file_name() else {
+            return Ok(None);
+        };
+        let pattern = format!("%{}%", serde_json::to_string(&name.to_string_lossy())?.trim_matches('"'));
+        let rows = stmt.query_map(params![pattern], |row| Ok(self.row_to_job(row)))?;
+
+        let target = platform::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+        let matches = |recorded: &str| platform::canonicalize(Path::new(recorded)).is_ok_and(|p| p == target);
+        let job = rows
+            .flatten()
+            .flatten()
+            .find(|job| {
+                job.images.iter().any(|img| img.path.as_deref().is_some_and(matches))
+                    || job.attachments.iter().any(|a| matches(&a.path))
+            });
+        Ok(job)
+    }
+
     /// List jobs with optional filters
     pub fn list_jobs(&self, limit: u32, status_filter: Option<&str>) -> Result<Vec<Job>> {
         let conn = self.conn.lock().unwrap();
@@ -132,69 +344,227 @@ impl Database {
         let mut jobs = Vec::new();
 
         if let Some(status) = status_filter {
-            let query = "SELECT id, action_json, params_json, status_json, images_json, model, created_at, updated_at, parent_id FROM jobs WHERE status_json LIKE ?1 ORDER BY created_at DESC LIMIT ?2";
-            let mut stmt = conn.prepare(query)?;
+            let query = format!("SELECT {} FROM jobs WHERE deleted_at IS NULL AND status_json LIKE ?1 ORDER BY created_at DESC LIMIT ?2", JOB_COLUMNS);
+            let mut stmt = conn.prepare(&query)?;
             let pattern = format!("%\"status\":\"{}%", status);
-            let rows = stmt.query_map(params![pattern, limit], |row| {
-                Ok((
-                    row.get::<_, String>(0)?,
-                    row.get::<_, String>(1)?,
-                    row.get::<_, String>(2)?,
-                    row.get::<_, String>(3)?,
-                    row.get::<_, String>(4)?,
-                    row.get::<_, String>(5)?,
-                    row.get::<_, String>(6)?,
-                    row.get::<_, String>(7)?,
-                    row.get::<_, Option<String>>(8)?,
-                ))
-            })?;
-
-            for row in rows.flatten() {
-                if let Ok(job) = self.tuple_to_job(row) {
-                    jobs.push(job);
-                }
+            let rows = stmt.query_map(params![pattern, limit], |row| Ok(self.row_to_job(row)))?;
+
+            for job in rows.flatten().flatten() {
+                jobs.push(job);
             }
         } else {
-            let query = "SELECT id, action_json, params_json, status_json, images_json, model, created_at, updated_at, parent_id FROM jobs ORDER BY created_at DESC LIMIT ?1";
-            let mut stmt = conn.prepare(query)?;
-            let rows = stmt.query_map(params![limit], |row| {
-                Ok((
-                    row.get::<_, String>(0)?,
-                    row.get::<_, String>(1)?,
-                    row.get::<_, String>(2)?,
-                    row.get::<_, String>(3)?,
-                    row.get::<_, String>(4)?,
-                    row.get::<_, String>(5)?,
-                    row.get::<_, String>(6)?,
-                    row.get::<_, String>(7)?,
-                    row.get::<_, Option<String>>(8)?,
-                ))
-            })?;
-
-            for row in rows.flatten() {
-                if let Ok(job) = self.tuple_to_job(row) {
-                    jobs.push(job);
-                }
+            let query = format!("SELECT {} FROM jobs WHERE deleted_at IS NULL ORDER BY created_at DESC LIMIT ?1", JOB_COLUMNS);
+            let mut stmt = conn.prepare(&query)?;
+            let rows = stmt.query_map(params![limit], |row| Ok(self.row_to_job(row)))?;
+
+            for job in rows.flatten().flatten() {
+                jobs.push(job);
             }
         }
 
         Ok(jobs)
     }
 
-    /// Delete a job
+    /// Live jobs created at or after `since`, newest first
+    pub fn list_jobs_since(&self, since: DateTime<Utc>) -> Result<Vec<Job>> {
+        let conn = self.conn.lock().unwrap();
+        let query = format!(
+            "SELECT {} FROM jobs WHERE deleted_at IS NULL AND created_at >= ?1 ORDER BY created_at DESC",
+            JOB_COLUMNS
+        );
+        let mut stmt = conn.prepare(&query)?;
+        let rows = stmt.query_map(params![since.to_rfc3339()], |row| Ok(self.row_to_job(row)))?;
+
+        Ok(rows.flatten().flatten().collect())
+    }
+
+    /// Move a job to the trash (soft delete)
     pub fn delete_job(&self, id: &str) -> Result<bool> {
         let conn = self.conn.lock().unwrap();
-        let deleted = conn.execute("DELETE FROM jobs WHERE id = ?1", params![id])?;
+        let deleted = conn.execute(
+            "UPDATE jobs SET deleted_at = ?2 WHERE id = ?1 AND deleted_at IS NULL",
+            params![id, Utc::now().to_rfc3339()],
+        )?;
         Ok(deleted > 0)
     }
 
-    /// Get job count
-    pub fn count_jobs(&self) -> Result<i64> {
+    /// Restore a job from the trash
+    pub fn restore_job(&self, id: &str) -> Result<bool> {
+        let conn = self.conn.lock().unwrap();
+        let restored = conn.execute(
+            "UPDATE jobs SET deleted_at = NULL WHERE id = ?1 AND deleted_at IS NOT NULL",
+            params![id],
+        )?;
+        Ok(restored > 0)
+    }
+
+    /// List trashed jobs with the time they were deleted, most recent first
+    pub fn list_deleted_jobs(&self) -> Result<Vec<(Job, DateTime<Utc>)>> {
+        let conn = self.conn.lock().unwrap();
+        let query = format!(
+            "SELECT {}, deleted_at FROM jobs WHERE deleted_at IS NOT NULL ORDER BY deleted_at DESC",
+            JOB_COLUMNS
+        );
+        let mut stmt = conn.prepare(&query)?;
+        let rows = stmt.query_map([], |row| {
+            let deleted_at: String = row.get("deleted_at")?;
+            Ok(self.row_to_job(row).and_then(|job| {
+                Ok((job, parse_time(&deleted_at)?))
+            }))
+        })?;
+
+        Ok(rows.flatten().flatten().collect())
+    }
+
+    /// Permanently delete jobs trashed before `cutoff`, returning their IDs
+    pub fn purge_deleted(&self, cutoff: DateTime<Utc>) -> Result<Vec<String>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare("SELECT id FROM jobs WHERE deleted_at IS NOT NULL AND deleted_at < ?1")?;
+        let ids: Vec<String> = stmt
+            .query_map(params![cutoff.to_rfc3339()], |row| row.get(0))?
+            .flatten()
+            .collect();
+
+        conn.execute(
+            "DELETE FROM jobs WHERE deleted_at IS NOT NULL AND deleted_at < ?1",
+            params![cutoff.to_rfc3339()],
+        )?;
+        Ok(ids)
+    }
+
+    /// Unstarred jobs in the order the history caps evict them: trashed
+    /// jobs first, then the least recently updated
+    pub fn eviction_candidates(&self) -> Result<Vec<Job>> {
+        let conn = self.conn.lock().unwrap();
+        let query = format!(
+            "SELECT {} FROM jobs WHERE starred = 0 ORDER BY deleted_at IS NULL, updated_at ASC",
+            JOB_COLUMNS
+        );
+        let mut stmt = conn.prepare(&query)?;
+        let rows = stmt.query_map([], |row| Ok(self.row_to_job(row)))?;
+
+        Ok(rows.flatten().flatten().collect())
+    }
+
+    /// Count every stored job, including the trash
+    pub fn count_stored_jobs(&self) -> Result<i64> {
         let conn = self.conn.lock().unwrap();
         let count: i64 = conn.query_row("SELECT COUNT(*) FROM jobs", [], |row| row.get(0))?;
         Ok(count)
     }
 
+    /// Permanently delete the given jobs, live or trashed
+    pub fn purge_jobs(&self, ids: &[String]) -> Result<()> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        for id in ids {
+            tx.execute("DELETE FROM jobs WHERE id = ?1", params![id])?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Bytes of the database file in use, not counting pages freed by
+    /// deletions (SQLite reuses those before growing the file)
+    pub fn used_bytes(&self) -> Result<u64> {
+        let conn = self.conn.lock().unwrap();
+        let pragma = |name: &str| -> rusqlite::Result<u64> {
+            conn.query_row(&format!("PRAGMA {}", name), [], |row| row.get(0))
+        };
+        Ok((pragma("page_count")? - pragma("freelist_count")?) * pragma("page_size")?)
+    }
+
+    /// Count live jobs matching a filter
+    pub fn count_matching(&self, filter: &JobFilter) -> Result<i64> {
+        let conn = self.conn.lock().unwrap();
+        let (condition, values) = filter.where_clause();
+        let count: i64 = conn.query_row(
+            &format!("SELECT COUNT(*) FROM jobs WHERE {}", condition),
+            params_from_iter(&values),
+            |row| row.get(0),
+        )?;
+        Ok(count)
+    }
+
+    /// Remove all jobs matching a filter in a single transaction.
+    ///
+    /// Jobs are moved to the trash unless `permanent` is set. Returns the
+    /// affected jobs so callers can clean up their files.
+    pub fn clear_jobs(&self, filter: &JobFilter, permanent: bool) -> Result<Vec<Job>> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        let (condition, mut values) = filter.where_clause();
+
+        let jobs: Vec<Job> = {
+            let mut stmt = tx.prepare(&format!("SELECT {} FROM jobs WHERE {}", JOB_COLUMNS, condition))?;
+            let rows = stmt.query_map(params_from_iter(&values), |row| Ok(self.row_to_job(row)))?;
+            rows.flatten().flatten().collect()
+        };
+
+        if permanent {
+            tx.execute(&format!("DELETE FROM jobs WHERE {}", condition), params_from_iter(&values))?;
+        } else {
+            values.push(Utc::now().to_rfc3339());
+            tx.execute(
+                &format!("UPDATE jobs SET deleted_at = ?{} WHERE {}", values.len(), condition),
+                params_from_iter(&values),
+            )?;
+        }
+
+        tx.commit()?;
+        Ok(jobs)
+    }
+
+    /// List the jobs of a batch in creation order
+    pub fn list_batch_jobs(&self, batch_id: &str) -> Result<Vec<Job>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM jobs WHERE batch_id = ?1 AND deleted_at IS NULL ORDER BY created_at ASC",
+            JOB_COLUMNS
+        ))?;
+
+        let rows = stmt.query_map(params![batch_id], |row| Ok(self.row_to_job(row)))?;
+
+        let mut jobs = Vec::new();
+        for job in rows.flatten().flatten() {
+            jobs.push(job);
+        }
+        Ok(jobs)
+    }
+
+    /// Record a batch before its jobs are run
+    pub fn insert_batch(&self, batch: &Batch) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT INTO batches (id, kind, spec_json, created_at) VALUES (?1, ?2, ?3, ?4)",
+            params![
+                batch.id,
+                batch.kind.as_str(),
+                serde_json::to_string(batch)?,
+                batch.created_at.to_rfc3339(),
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Get a batch by ID
+    pub fn get_batch(&self, id: &str) -> Result<Option<Batch>> {
+        let conn = self.conn.lock().unwrap();
+        let spec_json: Option<String> = conn
+            .query_row("SELECT spec_json FROM batches WHERE id = ?1", params![id], |row| row.get(0))
+            .optional()?;
+
+        let batch = spec_json.map(|s| serde_json::from_str(&s)).transpose()?;
+        Ok(batch)
+    }
+
+    /// Get job count (excluding trashed jobs)
+    pub fn count_jobs(&self) -> Result<i64> {
+        let conn = self.conn.lock().unwrap();
+        let count: i64 = conn.query_row("SELECT COUNT(*) FROM jobs WHERE deleted_at IS NULL", [], |row| row.get(0))?;
+        Ok(count)
+    }
+
     /// Convert a database row to a Job
     fn row_to_job(&self, row: &rusqlite::Row) -> Result<Job> {
         let action_json: String = row.get(1)?;
@@ -203,6 +573,8 @@ impl Database {
         let images_json: String = row.get(4)?;
         let created_at_str: String = row.get(6)?;
         let updated_at_str: String = row.get(7)?;
+        let usage_json: Option<String> = row.get(9)?;
+        let attachments_json: Option<String> = row.get(11)?;
 
         Ok(Job {
             id: row.get(0)?,
@@ -211,26 +583,29 @@ impl Database {
             status: serde_json::from_str(&status_json)?,
             images: serde_json::from_str(&images_json)?,
             model: row.get(5)?,
-            created_at: DateTime::parse_from_rfc3339(&created_at_str)?.with_timezone(&Utc),
-            updated_at: DateTime::parse_from_rfc3339(&updated_at_str)?.with_timezone(&Utc),
+            created_at: parse_time(&created_at_str)?,
+            updated_at: parse_time(&updated_at_str)?,
             parent_id: row.get(8)?,
+            usage: usage_json.map(|u| serde_json::from_str(&u)).transpose()?,
+            project: row.get(10)?,
+            attachments: attachments_json
+                .map(|a| serde_json::from_str(&a))
+                .transpose()?
+                .unwrap_or_default(),
+            batch_id: row.get(12)?,
+            provider: row.get(13)?,
+            base_url: row.get(14)?,
+            route: row.get(15)?,
+            starred: row.get(16)?,
         })
     }
+}
 
-    /// Convert a tuple to a Job
-    fn tuple_to_job(&self, row: (String, String, String, String, String, String, String, String, Option<String>)) -> Result<Job> {
-        Ok(Job {
-            id: row.0,
-            action: serde_json::from_str(&row.1)?,
-            params: serde_json::from_str(&row.2)?,
-            status: serde_json::from_str(&row.3)?,
-            images: serde_json::from_str(&row.4)?,
-            model: row.5,
-            created_at: DateTime::parse_from_rfc3339(&row.6)?.with_timezone(&Utc),
-            updated_at: DateTime::parse_from_rfc3339(&row.7)?.with_timezone(&Utc),
-            parent_id: row.8,
-        })
-    }
+/// Parse a stored RFC 3339 timestamp
+fn parse_time(value: &str) -> Result<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(value)
+        .map(|t| t.with_timezone(&Utc))
+        .map_err(|e| BananaError::DatabaseError(format!("Invalid timestamp '{}': {}", value, e)))
 }
 
 impl Clone for Database {
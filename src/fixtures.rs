@@ -0,0 +1,85 @@
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use crate::core::{BananaError, Result};
+
+/// How API exchanges are captured or served for this process
+#[derive(Debug, Clone)]
+pub enum FixtureMode {
+    /// Send requests normally and save each response under the directory
+    Record(PathBuf),
+    /// Answer requests from saved responses without touching the network
+    Replay(PathBuf),
+}
+
+static MODE: OnceLock<FixtureMode> = OnceLock::new();
+
+/// Set the fixture mode (from `--record`/`--replay`); only the first call takes effect
+pub fn init(mode: FixtureMode) {
+    let _ = MODE.set(mode);
+}
+
+/// The active fixture mode, if any
+pub fn mode() -> Option<&'static FixtureMode> {
+    MODE.get()
+}
+
+/// Whether responses come from fixtures instead of the API
+pub fn is_replaying() -> bool {
+    matches!(mode(), Some(FixtureMode::Replay(_)))
+}
+
+/// A saved API response
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Fixture {
+    /// API method, e.g. "gemini-2.5-flash-image:generateContent"
+    pub method: String,
+    pub status: u16,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retry_after: Option<u64>,
+    pub body: String,
+}
+
+/// Fixture file for a request.
+///
+/// Named after the method and a hash of the request body, so the same
+/// request always maps to the same file and any change to it (prompt,
+/// parameters, reference image) needs a new recording. The API key is sent
+/// in the URL and never becomes part of a fixture.
+pub fn path_for(dir: &Path, method: &str, body: &[u8]) -> PathBuf {
+    dir.join(format!("{}-{:016x}.json", method.replace(':', "."), fnv1a(body)))
+}
+
+/// Load the fixture recorded for a request
+pub fn load(dir: &Path, method: &str, body: &[u8]) -> Result<Fixture> {
+    let path = path_for(dir, method, body);
+    let content = std::fs::read_to_string(&path).map_err(|_| {
+        BananaError::Fixture(format!(
+            "No fixture for this {} request (expected {}); record one with --record {}",
+            method,
+            path.display(),
+            dir.display()
+        ))
+    })?;
+    serde_json::from_str(&content)
+        .map_err(|e| BananaError::Fixture(format!("Invalid fixture {}: {}", path.display(), e)))
+}
+
+/// Save a response to the fixture file from `path_for`
+pub fn save(path: &Path, fixture: &Fixture) -> Result<()> {
+    let failed = |e: std::io::Error| BananaError::Fixture(format!("Failed to write {}: {}", path.display(), e));
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).map_err(failed)?;
+    }
+    std::fs::write(path, serde_json::to_string_pretty(fixture)?).map_err(failed)?;
+    tracing::debug!("Recorded fixture {}", path.display());
+    Ok(())
+}
+
+/// 64-bit FNV-1a, stable across platforms and Rust versions
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ *byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
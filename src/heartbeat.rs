@@ -0,0 +1,30 @@
+use std::future::Future;
+use tokio::time;
+
+use crate::core::job::HEARTBEAT_INTERVAL;
+use crate::db::Database;
+
+/// Progress reported while waiting; the API gives no real progress, so the
+/// value only creeps toward this and never claims completion
+const MAX_PROGRESS: u8 = 90;
+
+/// Drive `fut` to completion, refreshing the running job's record every
+/// [`HEARTBEAT_INTERVAL`] so other processes can tell it's still alive.
+pub async fn run<F: Future>(db: &Database, job_id: &str, fut: F) -> F::Output {
+    tokio::pin!(fut);
+    let mut ticker = time::interval(HEARTBEAT_INTERVAL);
+    ticker.tick().await;
+
+    let mut progress = 0u8;
+    loop {
+        tokio::select! {
+            output = &mut fut => return output,
+            _ = ticker.tick() => {
+                progress += (MAX_PROGRESS - progress).div_ceil(5);
+                if let Err(e) = db.heartbeat(job_id, progress) {
+                    tracing::debug!("Failed to record heartbeat for {}: {}", job_id, e);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,195 @@
+use anyhow::Result;
+use chrono::{DateTime, Duration, Utc};
+use std::collections::HashMap;
+
+use crate::config::Config;
+use crate::core::{Job, JobImage};
+use crate::db::{Database, JobFilter};
+use crate::thumbnails;
+use crate::tmp_output;
+
+/// Apply the configured history retention policies (run at startup).
+///
+/// Each policy runs even if an earlier one fails; failures are logged.
+pub fn enforce_retention(config: &Config, db: &Database) {
+    if let Err(e) = prune_failed(config, db) {
+        tracing::warn!("Failed to prune failed jobs: {}", e);
+    }
+    if let Err(e) = purge_trash(config, db) {
+        tracing::warn!("Failed to purge the trash: {}", e);
+    }
+    if let Err(e) = purge_tmp_output(config) {
+        tracing::warn!("Failed to remove temporary output: {}", e);
+    }
+    if let Err(e) = enforce_caps(config, db) {
+        tracing::warn!("Failed to enforce the history caps: {}", e);
+    }
+}
+
+/// Remove image `index` from a job, deleting its file and cached thumbnail.
+///
+/// Returns the removed image, or None if the job has no such image.
+pub fn delete_image(job: &mut Job, index: u8) -> Result<Option<JobImage>> {
+    let Some(image) = job.remove_image(index) else {
+        return Ok(None);
+    };
+    if let Some(path) = &image.path {
+        match std::fs::remove_file(path) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(e.into()),
+            _ => {}
+        }
+    }
+    if let Err(e) = thumbnails::remove(&job.id, index) {
+        tracing::warn!("Failed to remove thumbnail: {}", e);
+    }
+    Ok(Some(image))
+}
+
+/// Move failed jobs older than `history.auto_prune_failed_after` to the trash,
+/// returning how many were moved
+pub fn prune_failed(config: &Config, db: &Database) -> Result<usize> {
+    let Some(age) = &config.history.auto_prune_failed_after else {
+        return Ok(0);
+    };
+    let filter = JobFilter {
+        status: Some("failed".to_string()),
+        created_before: Some(cutoff(parse_age(age)?)?),
+    };
+    let pruned = db.clear_jobs(&filter, false)?;
+
+    if !pruned.is_empty() {
+        tracing::info!("Moved {} failed job(s) older than {} to the trash", pruned.len(), age);
+    }
+    Ok(pruned.len())
+}
+
+/// Permanently remove jobs that have been in the trash longer than `history.trash_days`
+fn purge_trash(config: &Config, db: &Database) -> Result<()> {
+    let cutoff = Utc::now() - Duration::days(config.history.trash_days as i64);
+    let purged = db.purge_deleted(cutoff)?;
+
+    for id in &purged {
+        if let Err(e) = thumbnails::remove_for_job(id) {
+            tracing::warn!("Failed to remove thumbnails for {}: {}", id, e);
+        }
+    }
+
+    if !purged.is_empty() {
+        tracing::info!("Purged {} job(s) from the trash", purged.len());
+    }
+    Ok(())
+}
+
+/// Delete `--tmp` output directories older than `history.tmp_hours`
+fn purge_tmp_output(config: &Config) -> Result<()> {
+    let removed = tmp_output::sweep(Duration::hours(config.history.tmp_hours as i64))?;
+    if removed > 0 {
+        tracing::info!("Removed {} temporary output director(ies)", removed);
+    }
+    Ok(())
+}
+
+/// Permanently delete the oldest unstarred jobs (trashed ones first) while
+/// the history exceeds `history.max_jobs` or `history.max_disk_mb`.
+///
+/// Only what eviction can free counts towards `max_disk_mb`: the database
+/// pages in use and the cached thumbnails. Evicted jobs lose their record
+/// and thumbnails; their image files in the output directory are left alone.
+fn enforce_caps(config: &Config, db: &Database) -> Result<()> {
+    let max_jobs = config.history.max_jobs as i64;
+    let max_bytes = config.history.max_disk_mb * 1024 * 1024;
+    if max_jobs == 0 && max_bytes == 0 {
+        return Ok(());
+    }
+
+    // Jobs still generating are never evicted
+    let mut candidates = db
+        .eviction_candidates()?
+        .into_iter()
+        .filter(|job| job.status.is_terminal());
+    let mut thumbs = thumbnails::sizes_by_job()?;
+    let mut evicted = 0;
+
+    if max_jobs > 0 {
+        let excess = (db.count_stored_jobs()? - max_jobs).max(0) as usize;
+        let ids: Vec<String> = candidates.by_ref().take(excess).map(|job| job.id).collect();
+        evict(db, &ids, &mut thumbs)?;
+        evicted += ids.len();
+    }
+
+    if max_bytes > 0 {
+        let mut used = db.used_bytes()? + thumbs.values().sum::<u64>();
+        while used > max_bytes {
+            // Pick enough jobs to get under the cap by estimate (a row takes
+            // roughly the size of its JSON), then measure what was really freed
+            let mut ids = Vec::new();
+            let mut estimate = used;
+            for job in candidates.by_ref() {
+                let row = serde_json::to_vec(&job).map(|json| json.len() as u64).unwrap_or(0);
+                let thumb = thumbs.get(&job.id).copied().unwrap_or(0);
+                estimate = estimate.saturating_sub(row + thumb);
+                ids.push(job.id);
+                if estimate <= max_bytes {
+                    break;
+                }
+            }
+            if ids.is_empty() {
+                break;
+            }
+            evict(db, &ids, &mut thumbs)?;
+            evicted += ids.len();
+
+            let now = db.used_bytes()? + thumbs.values().sum::<u64>();
+            if now >= used {
+                break;
+            }
+            used = now;
+        }
+    }
+
+    if evicted > 0 {
+        tracing::info!("Evicted {} job(s) to stay within the history caps", evicted);
+    }
+    Ok(())
+}
+
+/// Permanently delete jobs and their thumbnails, keeping `thumbs` in step
+fn evict(db: &Database, ids: &[String], thumbs: &mut HashMap<String, u64>) -> Result<()> {
+    if ids.is_empty() {
+        return Ok(());
+    }
+    db.purge_jobs(ids)?;
+    for id in ids {
+        thumbs.remove(id);
+        if let Err(e) = thumbnails::remove_for_job(id) {
+            tracing::warn!("Failed to remove thumbnails for {}: {}", id, e);
+        }
+    }
+    Ok(())
+}
+
+/// Parse an age such as "30m", "12h", "7d", or "2w"
+pub fn parse_age(s: &str) -> Result<Duration> {
+    let s = s.trim();
+    let split = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+    let (num, unit) = s.split_at(split);
+    let n: i64 = num
+        .parse()
+        .map_err(|_| anyhow::anyhow!("Invalid age '{}'. Use e.g. 30m, 12h, 7d, 2w", s))?;
+
+    let age = match unit {
+        "m" => Duration::try_minutes(n),
+        "h" => Duration::try_hours(n),
+        "d" | "" => Duration::try_days(n),
+        "w" => Duration::try_weeks(n),
+        _ => anyhow::bail!("Invalid age unit '{}'. Use m, h, d, or w", unit),
+    };
+    age.ok_or_else(|| anyhow::anyhow!("Age '{}' is too large", s))
+}
+
+/// The moment `age` before now
+pub fn cutoff(age: Duration) -> Result<DateTime<Utc>> {
+    Utc::now()
+        .checked_sub_signed(age)
+        .ok_or_else(|| anyhow::anyhow!("Age of {} days is too large", age.num_days()))
+}
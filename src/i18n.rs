@@ -0,0 +1,110 @@
+use std::sync::OnceLock;
+
+/// Languages user-facing CLI messages are available in.
+///
+/// Messages are looked up by their English text, so a string missing from a
+/// catalog simply stays English. Tracing and log output is never translated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Language {
+    En,
+    De,
+}
+
+/// Values accepted by `ui.language`
+pub const LANGUAGES: &[&str] = &["auto", "en", "de"];
+
+static LANGUAGE: OnceLock<Language> = OnceLock::new();
+
+impl Language {
+    /// Resolve a `ui.language` value; "auto" follows `LC_ALL`, `LC_MESSAGES`, or `LANG`
+    pub fn from_setting(value: &str) -> Self {
+        match value {
+            "de" => Language::De,
+            "auto" => {
+                let locale = ["LC_ALL", "LC_MESSAGES", "LANG"]
+                    .iter()
+                    .filter_map(|var| std::env::var(var).ok())
+                    .find(|value| !value.is_empty())
+                    .unwrap_or_default();
+                if locale.starts_with("de") {
+                    Language::De
+                } else {
+                    Language::En
+                }
+            }
+            _ => Language::En,
+        }
+    }
+}
+
+/// Set the language for this process (first call wins)
+pub fn init(setting: &str) {
+    let _ = LANGUAGE.set(Language::from_setting(setting));
+}
+
+/// Translate a message, given by its English text.
+///
+/// Placeholders such as `{n}` are kept as-is for the caller to fill in.
+pub fn t(message: &'static str) -> &'static str {
+    let catalog = match LANGUAGE.get().copied().unwrap_or(Language::En) {
+        Language::En => return message,
+        Language::De => DE,
+    };
+    catalog
+        .iter()
+        .find(|(en, _)| *en == message)
+        .map(|(_, translated)| *translated)
+        .unwrap_or(message)
+}
+
+/// German messages
+const DE: &[(&str, &str)] = &[
+    ("Error", "Fehler"),
+    ("Warning", "Warnung"),
+    ("Job ID", "Job-ID"),
+    ("Action", "Aktion"),
+    ("Status", "Status"),
+    ("Model", "Modell"),
+    ("Provider", "Anbieter"),
+    ("Batch", "Stapel"),
+    ("Created", "Erstellt"),
+    ("Updated", "Aktualisiert"),
+    ("Prompt", "Prompt"),
+    ("Parameters", "Parameter"),
+    ("Aspect Ratio", "Seitenverhältnis"),
+    ("Images", "Bilder"),
+    ("Attachments", "Anhänge"),
+    ("Parent Job", "Ursprungsjob"),
+    ("Source", "Quelle"),
+    ("Temporary", "Temporär"),
+    ("completed", "abgeschlossen"),
+    ("Generated Images", "Erzeugte Bilder"),
+    ("Edit", "Bearbeitung"),
+    ("Edited Image", "Bearbeitetes Bild"),
+    ("Generating image: {prompt}...", "Erzeuge Bild: {prompt}..."),
+    ("Editing image: {prompt}...", "Bearbeite Bild: {prompt}..."),
+    ("Generated {n} image(s)", "{n} Bild(er) erzeugt"),
+    ("Generated {n} image(s) (not downloaded)", "{n} Bild(er) erzeugt (nicht heruntergeladen)"),
+    ("Edited image saved", "Bearbeitetes Bild gespeichert"),
+    ("Edit complete (not downloaded)", "Bearbeitung abgeschlossen (nicht heruntergeladen)"),
+    ("Generation failed", "Erzeugung fehlgeschlagen"),
+    ("Edit failed", "Bearbeitung fehlgeschlagen"),
+    ("deleted after {n}h", "wird nach {n} h gelöscht"),
+    ("(stalled: no heartbeat since last update)", "(hängt: kein Lebenszeichen seit der letzten Aktualisierung)"),
+    ("(base64 data, not downloaded)", "(Base64-Daten, nicht heruntergeladen)"),
+    ("(primary)", "(Hauptbild)"),
+    ("ID", "ID"),
+    ("ACTION", "AKTION"),
+    ("STATUS", "STATUS"),
+    ("PROMPT", "PROMPT"),
+    ("CREATED", "ERSTELLT"),
+    ("DELETED", "GELÖSCHT"),
+    ("No jobs found.", "Keine Jobs gefunden."),
+    ("Trash is empty.", "Der Papierkorb ist leer."),
+    ("Trashed jobs are purged after {n} days.", "Jobs im Papierkorb werden nach {n} Tagen endgültig gelöscht."),
+    ("Job '{id}' not found", "Job '{id}' nicht gefunden"),
+    ("Job '{id}' is not in the trash", "Job '{id}' ist nicht im Papierkorb"),
+    ("Moved job to trash: {id}", "Job in den Papierkorb verschoben: {id}"),
+    ("Undo with: banana jobs restore {id}", "Rückgängig mit: banana jobs restore {id}"),
+    ("Restored job: {id}", "Job wiederhergestellt: {id}"),
+];
@@ -0,0 +1,137 @@
+use anyhow::{anyhow, Result};
+use image::{DynamicImage, RgbaImage};
+use moxcms::{ColorProfile, Layout, TransformOptions};
+
+/// D50 illuminant (PCS white point)
+const D50: [f64; 3] = [0.9642, 1.0, 0.8249];
+
+/// sRGB primaries, Bradford-adapted to D50
+const RED: [f64; 3] = [0.4360747, 0.2225045, 0.0139322];
+const GREEN: [f64; 3] = [0.3850649, 0.7168786, 0.0971045];
+const BLUE: [f64; 3] = [0.1430804, 0.0606169, 0.7141733];
+
+/// Entries in the tone curve lookup table
+const CURVE_POINTS: usize = 1024;
+
+const DESCRIPTION: &str = "sRGB (banana)";
+const COPYRIGHT: &str = "No copyright, use freely";
+
+/// Build a minimal ICC v2 sRGB display profile for embedding in PNG or JPEG
+/// files (generated rather than vendored as a binary blob)
+pub fn srgb_profile() -> Vec<u8> {
+    let trc = curve_tag();
+    let tags: Vec<([u8; 4], Vec<u8>)> = vec![
+        (*b"desc", desc_tag(DESCRIPTION)),
+        (*b"cprt", text_tag(COPYRIGHT)),
+        (*b"wtpt", xyz_tag(D50)),
+        (*b"rXYZ", xyz_tag(RED)),
+        (*b"gXYZ", xyz_tag(GREEN)),
+        (*b"bXYZ", xyz_tag(BLUE)),
+        (*b"rTRC", trc.clone()),
+        (*b"gTRC", trc.clone()),
+        (*b"bTRC", trc),
+    ];
+
+    let table_len = 4 + tags.len() * 12;
+    let mut offset = 128 + table_len;
+    let mut table = Vec::with_capacity(table_len);
+    let mut data = Vec::new();
+
+    table.extend_from_slice(&(tags.len() as u32).to_be_bytes());
+    for (signature, mut tag) in tags {
+        table.extend_from_slice(&signature);
+        table.extend_from_slice(&(offset as u32).to_be_bytes());
+        table.extend_from_slice(&(tag.len() as u32).to_be_bytes());
+        // Tag data is 4-byte aligned
+        while tag.len() % 4 != 0 {
+            tag.push(0);
+        }
+        offset += tag.len();
+        data.extend_from_slice(&tag);
+    }
+
+    let size = 128 + table.len() + data.len();
+    let mut profile = Vec::with_capacity(size);
+    profile.extend_from_slice(&(size as u32).to_be_bytes());
+    profile.extend_from_slice(&[0; 4]); // preferred CMM
+    profile.extend_from_slice(&0x0210_0000u32.to_be_bytes()); // version 2.1
+    profile.extend_from_slice(b"mntr");
+    profile.extend_from_slice(b"RGB ");
+    profile.extend_from_slice(b"XYZ ");
+    for part in [2025u16, 1, 1, 0, 0, 0] {
+        profile.extend_from_slice(&part.to_be_bytes());
+    }
+    profile.extend_from_slice(b"acsp");
+    profile.extend_from_slice(&[0; 24]); // platform, flags, manufacturer, model, attributes
+    profile.extend_from_slice(&0u32.to_be_bytes()); // perceptual intent
+    for value in D50 {
+        profile.extend_from_slice(&s15_fixed16(value));
+    }
+    profile.resize(128, 0); // creator, profile ID, reserved
+
+    profile.extend_from_slice(&table);
+    profile.extend_from_slice(&data);
+    profile
+}
+
+/// Convert an image's pixels from the color space `profile` describes to sRGB
+pub fn to_srgb(img: &DynamicImage, profile: &[u8]) -> Result<DynamicImage> {
+    let source = ColorProfile::new_from_slice(profile).map_err(|e| anyhow!("Unsupported ICC profile: {:?}", e))?;
+    let transform = source
+        .create_transform_8bit(Layout::Rgba, &ColorProfile::new_srgb(), Layout::Rgba, TransformOptions::default())
+        .map_err(|e| anyhow!("Cannot convert from this ICC profile: {:?}", e))?;
+
+    let pixels = img.to_rgba8();
+    let mut converted = RgbaImage::new(pixels.width(), pixels.height());
+    transform
+        .transform(pixels.as_raw(), &mut converted)
+        .map_err(|e| anyhow!("Color conversion failed: {:?}", e))?;
+    Ok(DynamicImage::ImageRgba8(converted))
+}
+
+fn s15_fixed16(value: f64) -> [u8; 4] {
+    ((value * 65536.0).round() as i32).to_be_bytes()
+}
+
+fn xyz_tag(xyz: [f64; 3]) -> Vec<u8> {
+    let mut tag = b"XYZ \0\0\0\0".to_vec();
+    for value in xyz {
+        tag.extend_from_slice(&s15_fixed16(value));
+    }
+    tag
+}
+
+/// Sampled sRGB transfer function
+fn curve_tag() -> Vec<u8> {
+    let mut tag = b"curv\0\0\0\0".to_vec();
+    tag.extend_from_slice(&(CURVE_POINTS as u32).to_be_bytes());
+    for i in 0..CURVE_POINTS {
+        let v = i as f64 / (CURVE_POINTS - 1) as f64;
+        let linear = if v <= 0.04045 {
+            v / 12.92
+        } else {
+            ((v + 0.055) / 1.055).powf(2.4)
+        };
+        tag.extend_from_slice(&((linear * 65535.0).round() as u16).to_be_bytes());
+    }
+    tag
+}
+
+fn text_tag(text: &str) -> Vec<u8> {
+    let mut tag = b"text\0\0\0\0".to_vec();
+    tag.extend_from_slice(text.as_bytes());
+    tag.push(0);
+    tag
+}
+
+/// v2 textDescriptionType: ASCII description with empty Unicode and ScriptCode parts
+fn desc_tag(text: &str) -> Vec<u8> {
+    let mut tag = b"desc\0\0\0\0".to_vec();
+    tag.extend_from_slice(&(text.len() as u32 + 1).to_be_bytes());
+    tag.extend_from_slice(text.as_bytes());
+    tag.push(0);
+    tag.extend_from_slice(&[0; 8]); // Unicode language code and count
+    tag.extend_from_slice(&[0; 3]); // ScriptCode code and count
+    tag.extend_from_slice(&[0; 67]); // ScriptCode description
+    tag
+}
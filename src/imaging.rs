@@ -0,0 +1,359 @@
+use anyhow::{Context, Result};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use image::codecs::gif::GifDecoder;
+use image::codecs::jpeg::JpegEncoder;
+use image::codecs::webp::WebPDecoder;
+use image::{imageops, AnimationDecoder, DynamicImage, GenericImageView, ImageFormat, ImageOutputFormat, Rgba, RgbaImage};
+use img_parts::jpeg::Jpeg;
+use img_parts::png::Png;
+use img_parts::{Bytes, ImageICC};
+use std::io::Cursor;
+use std::path::Path;
+
+use crate::api::{load_image_base64, MAX_REQUEST_BYTES};
+use crate::core::{BananaError, FitMode};
+use crate::config::{WatermarkConfig, WatermarkPosition};
+use crate::icc;
+use crate::platform;
+
+/// Ratios closer than this are treated as equal
+const RATIO_TOLERANCE: f64 = 0.01;
+
+/// Quality used when re-encoding JPEG outputs after post-processing
+const JPEG_QUALITY: u8 = 95;
+
+/// Largest base64 reference image sent; leaves room for the prompt and context
+const MAX_INLINE_IMAGE_BYTES: usize = MAX_REQUEST_BYTES - 2 * 1024 * 1024;
+
+/// Parse an aspect ratio like "16:9" into width / height
+pub fn parse_ratio(ratio: &str) -> Option<f64> {
+    let (w, h) = ratio.split_once(':')?;
+    let (w, h): (f64, f64) = (w.trim().parse().ok()?, h.trim().parse().ok()?);
+    (w > 0.0 && h > 0.0).then_some(w / h)
+}
+
+/// Width / height of an image file, read from its header
+pub fn image_ratio(path: &Path) -> Result<f64> {
+    let (w, h) = image::image_dimensions(path)
+        .with_context(|| format!("Failed to read dimensions of {}", path.display()))?;
+    Ok(w as f64 / h.max(1) as f64)
+}
+
+/// Whether two ratios differ enough to distort an edit
+pub fn ratios_differ(a: f64, b: f64) -> bool {
+    (a / b - 1.0).abs() > RATIO_TOLERANCE
+}
+
+/// Reshape an image to `ratio` and return it as base64 PNG with its mime type.
+///
+/// Returns `None` when the image already has the requested ratio.
+pub fn fit_to_ratio(path: &Path, ratio: &str, mode: FitMode, frame: Option<usize>) -> Result<Option<(String, String)>> {
+    let target = parse_ratio(ratio).with_context(|| format!("Invalid aspect ratio '{}'", ratio))?;
+    let img = match animated_frame(path, frame)? {
+        Some(img) => img,
+        None => image::open(path).with_context(|| format!("Failed to decode {}", path.display()))?,
+    };
+    let (w, h) = img.dimensions();
+    let current = w as f64 / h as f64;
+
+    if !ratios_differ(current, target) {
+        return Ok(None);
+    }
+
+    let fitted = match mode {
+        FitMode::Crop => {
+            let (cw, ch) = if current > target {
+                ((h as f64 * target).round() as u32, h)
+            } else {
+                (w, (w as f64 / target).round() as u32)
+            };
+            img.crop_imm((w - cw) / 2, (h - ch) / 2, cw.max(1), ch.max(1))
+        }
+        FitMode::Pad => {
+            let (pw, ph) = if current > target {
+                (w, (w as f64 / target).round() as u32)
+            } else {
+                ((h as f64 * target).round() as u32, h)
+            };
+            let mut canvas = RgbaImage::from_pixel(pw, ph, Rgba([255, 255, 255, 255]));
+            imageops::overlay(&mut canvas, &img.to_rgba8(), ((pw - w) / 2) as i64, ((ph - h) / 2) as i64);
+            DynamicImage::ImageRgba8(canvas)
+        }
+        FitMode::Stretch => {
+            // Keep the pixel count roughly the same
+            let area = w as f64 * h as f64;
+            let sh = (area / target).sqrt().round() as u32;
+            let sw = (sh as f64 * target).round() as u32;
+            img.resize_exact(sw.max(1), sh.max(1), imageops::FilterType::Lanczos3)
+        }
+    };
+
+    encode_png(&fitted).map(Some)
+}
+
+pub fn encode_png(img: &DynamicImage) -> Result<(String, String)> {
+    let mut buf = Vec::new();
+    img.write_to(&mut Cursor::new(&mut buf), ImageOutputFormat::Png)
+        .context("Failed to encode image")?;
+    Ok((BASE64.encode(&buf), "image/png".to_string()))
+}
+
+/// Decode one frame of an animated GIF or WebP (the first unless `frame` is given).
+///
+/// Returns `None` for still images, which can be uploaded as they are.
+pub fn animated_frame(path: &Path, frame: Option<usize>) -> Result<Option<DynamicImage>> {
+    let data = std::fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    let index = frame.unwrap_or(0);
+    let not_animated = || {
+        if index > 0 {
+            anyhow::bail!("{} is not animated; --frame {} is out of range", path.display(), index);
+        }
+        Ok(None)
+    };
+
+    let frames = match image::guess_format(&data) {
+        Ok(ImageFormat::Gif) => GifDecoder::new(Cursor::new(&data))?.into_frames(),
+        Ok(ImageFormat::WebP) => {
+            let decoder = WebPDecoder::new(Cursor::new(&data))?;
+            if !decoder.has_animation() {
+                return not_animated();
+            }
+            decoder.into_frames()
+        }
+        _ => return not_animated(),
+    };
+
+    // Stop once the frame is found and we know there's more than one
+    let (mut selected, mut count) = (None, 0);
+    for (i, decoded) in frames.enumerate() {
+        let decoded = decoded.with_context(|| format!("Failed to decode frame {} of {}", i, path.display()))?;
+        count = i + 1;
+        if i == index {
+            selected = Some(decoded.into_buffer());
+        }
+        if selected.is_some() && count > 1 {
+            break;
+        }
+    }
+
+    match selected {
+        None => anyhow::bail!(
+            "{} has {} frame(s); --frame {} is out of range",
+            path.display(),
+            count,
+            index
+        ),
+        Some(_) if count < 2 => not_animated(),
+        Some(buffer) => {
+            if frame.is_none() {
+                tracing::warn!(
+                    "{} is animated; sending its first frame (choose another with --frame)",
+                    path.display()
+                );
+            }
+            Ok(Some(DynamicImage::ImageRgba8(buffer)))
+        }
+    }
+}
+
+/// Load an image for upload as base64, reshaping it to `ratio` first if a fit mode is given
+///
+/// Animated GIF/WebP sources are reduced to a single frame (`frame`, default the first).
+pub async fn load_for_edit(
+    path: &Path,
+    ratio: &str,
+    fit: Option<FitMode>,
+    frame: Option<usize>,
+) -> Result<(String, String)> {
+    if let Some(mode) = fit {
+        let (path_buf, ratio_owned) = (path.to_path_buf(), ratio.to_string());
+        let fitted =
+            tokio::task::spawn_blocking(move || fit_to_ratio(&path_buf, &ratio_owned, mode, frame)).await??;
+        if let Some(fitted) = fitted {
+            return fit_request_limit(path, fitted).await;
+        }
+    }
+
+    let path_buf = path.to_path_buf();
+    let still = tokio::task::spawn_blocking(move || {
+        animated_frame(&path_buf, frame)?.map(|img| encode_png(&img)).transpose()
+    })
+    .await??;
+    if let Some(still) = still {
+        return fit_request_limit(path, still).await;
+    }
+
+    load_reference(path).await
+}
+
+/// Load an image for upload as base64, downscaling it if it's too big to send
+pub async fn load_reference(path: &Path) -> Result<(String, String)> {
+    let loaded = load_image_base64(path).await.context("Failed to load image file")?;
+    fit_request_limit(path, loaded).await
+}
+
+/// Shrink an encoded image that wouldn't fit in a request, or fail naming `path`
+async fn fit_request_limit(path: &Path, (data, mime_type): (String, String)) -> Result<(String, String)> {
+    if data.len() <= MAX_INLINE_IMAGE_BYTES {
+        return Ok((data, mime_type));
+    }
+
+    let size = data.len();
+    let shrunk = tokio::task::spawn_blocking(move || shrink_to_fit(&data, MAX_INLINE_IMAGE_BYTES)).await?;
+    match shrunk {
+        Ok(Some(shrunk)) => {
+            tracing::warn!(
+                "{} is {:.1} MB encoded; downscaled to {}x{} to fit the API request limit",
+                path.display(),
+                size as f64 / (1024.0 * 1024.0),
+                shrunk.width,
+                shrunk.height
+            );
+            Ok((shrunk.data, shrunk.mime_type))
+        }
+        Ok(None) | Err(_) => Err(BananaError::PayloadTooLarge {
+            what: format!("reference image {}", path.display()),
+            size,
+            limit: MAX_REQUEST_BYTES,
+        }
+        .into()),
+    }
+}
+
+/// Decode base64 image data
+pub fn decode_base64(data: &str) -> Result<DynamicImage> {
+    let bytes = BASE64.decode(data).context("Invalid base64 image data")?;
+    image::load_from_memory(&bytes).context("Failed to decode image")
+}
+
+/// A reference image re-encoded by [`shrink_to_fit`]
+struct Shrunk {
+    data: String,
+    mime_type: String,
+    width: u32,
+    height: u32,
+}
+
+/// Re-encode base64 image data as JPEG, downscaling until it fits in `limit` base64 bytes.
+///
+/// Images with transparent pixels stay PNG, since JPEG would drop their
+/// alpha. Returns `None` if no attempt fit.
+fn shrink_to_fit(data: &str, limit: usize) -> Result<Option<Shrunk>> {
+    let img = image::load_from_memory(&BASE64.decode(data)?)?;
+    let (w, h) = img.dimensions();
+    let transparent = img.color().has_alpha() && img.to_rgba8().pixels().any(|p| p[3] < u8::MAX);
+
+    // Encoded size scales roughly with pixel count
+    let mut scale = (limit as f64 / data.len() as f64).sqrt().min(1.0);
+    for _ in 0..5 {
+        let (sw, sh) = ((w as f64 * scale) as u32, (h as f64 * scale) as u32);
+        let resized = img.resize(sw.max(1), sh.max(1), imageops::FilterType::Lanczos3);
+
+        let (data, mime_type) = if transparent {
+            encode_png(&resized)?
+        } else {
+            let mut buf = Vec::new();
+            JpegEncoder::new_with_quality(&mut buf, JPEG_QUALITY).encode_image(&resized.to_rgb8())?;
+            (BASE64.encode(&buf), "image/jpeg".to_string())
+        };
+        if data.len() <= limit {
+            let (width, height) = resized.dimensions();
+            return Ok(Some(Shrunk { data, mime_type, width, height }));
+        }
+        scale *= 0.8;
+    }
+    Ok(None)
+}
+
+/// Composite the configured watermark onto an image file in place
+pub fn apply_watermark(path: &Path, config: &WatermarkConfig) -> Result<()> {
+    let Some(mark_path) = &config.path else {
+        return Ok(());
+    };
+
+    let mut img = image::open(path)
+        .with_context(|| format!("Failed to decode {}", path.display()))?
+        .to_rgba8();
+    let mark = image::open(mark_path)
+        .with_context(|| format!("Failed to load watermark {}", mark_path))?;
+
+    // Size the watermark relative to the image so it looks the same at 1K and 4K
+    let (w, h) = img.dimensions();
+    let target_w = ((w as f32 * config.scale).round() as u32).clamp(1, w);
+    let target_h = ((mark.height() as f32 * target_w as f32 / mark.width().max(1) as f32).round() as u32).clamp(1, h);
+    let mut mark = mark
+        .resize_exact(target_w, target_h, imageops::FilterType::Lanczos3)
+        .to_rgba8();
+
+    let opacity = config.opacity.clamp(0.0, 1.0);
+    for pixel in mark.pixels_mut() {
+        pixel[3] = (pixel[3] as f32 * opacity).round() as u8;
+    }
+
+    let margin = (w.min(h) / 40) as i64;
+    let (max_x, max_y) = ((w - target_w) as i64, (h - target_h) as i64);
+    let (x, y) = match config.position {
+        WatermarkPosition::TopLeft => (margin, margin),
+        WatermarkPosition::TopRight => (max_x - margin, margin),
+        WatermarkPosition::BottomLeft => (margin, max_y - margin),
+        WatermarkPosition::BottomRight => (max_x - margin, max_y - margin),
+        WatermarkPosition::Center => (max_x / 2, max_y / 2),
+    };
+    imageops::overlay(&mut img, &mark, x.max(0), y.max(0));
+
+    save_as_source_format(DynamicImage::ImageRgba8(img), path)
+}
+
+/// Convert a PNG or JPEG tagged with a non-sRGB ICC profile to sRGB in place.
+///
+/// The file is re-encoded without a profile, ready to be tagged as sRGB.
+/// Untagged images (API outputs are untagged sRGB) and images already
+/// carrying banana's sRGB profile are left alone. Returns whether the file
+/// was converted.
+pub fn convert_to_srgb(path: &Path) -> Result<bool> {
+    let data = Bytes::from(std::fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?);
+    let profile = if let Ok(png) = Png::from_bytes(data.clone()) {
+        png.icc_profile()
+    } else if let Ok(jpeg) = Jpeg::from_bytes(data.clone()) {
+        jpeg.icc_profile()
+    } else {
+        None
+    };
+    let Some(profile) = profile.filter(|p| **p != icc::srgb_profile()) else {
+        return Ok(false);
+    };
+
+    let img = image::load_from_memory(&data).with_context(|| format!("Failed to decode {}", path.display()))?;
+    let converted = icc::to_srgb(&img, &profile)?;
+    // Keep RGB images RGB, so JPEGs and opaque PNGs don't gain alpha
+    let converted = if img.color().has_alpha() {
+        converted
+    } else {
+        DynamicImage::ImageRgb8(converted.to_rgb8())
+    };
+    save_as_source_format(converted, path)?;
+    Ok(true)
+}
+
+/// Re-encode an image over `path` in the format its extension names.
+///
+/// Fails, leaving the file untouched, if that format can't be encoded.
+fn save_as_source_format(img: DynamicImage, path: &Path) -> Result<()> {
+    let format = ImageFormat::from_path(path)
+        .with_context(|| format!("Unknown image format for {}", path.display()))?;
+
+    platform::replace_file(path, |file| {
+        match format {
+            ImageFormat::Jpeg => {
+                JpegEncoder::new_with_quality(file, JPEG_QUALITY)
+                    .encode_image(&img.to_rgb8())
+                    .context("Failed to encode JPEG")?;
+            }
+            _ => {
+                img.write_to(file, ImageOutputFormat::from(format))
+                    .with_context(|| format!("Failed to encode {:?}", format))?;
+            }
+        }
+        Ok(())
+    })
+}
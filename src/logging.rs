@@ -0,0 +1,189 @@
+use chrono::{DateTime, Local};
+use directories::ProjectDirs;
+use std::collections::VecDeque;
+use std::fmt;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use tracing::field::{Field, Visit};
+use tracing::{Event, Level, Subscriber};
+use tracing_subscriber::layer::{Context, Layer, SubscriberExt};
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::EnvFilter;
+
+/// Number of warnings/errors kept for the TUI log view
+const CAPACITY: usize = 200;
+
+static RECENT: Mutex<VecDeque<LogEntry>> = Mutex::new(VecDeque::new());
+
+/// Set while the TUI owns the terminal; log lines then go to a file instead
+static CAPTURING: AtomicBool = AtomicBool::new(false);
+
+/// Size at which the log file is rotated to `tui.log.1`
+const MAX_LOG_BYTES: u64 = 1024 * 1024;
+
+/// The log file, open while capturing, and its current length
+static LOG_FILE: Mutex<Option<(File, u64)>> = Mutex::new(None);
+
+/// Install the global subscriber.
+///
+/// Output goes to stderr normally and to `tui.log` in the data directory
+/// while a [`CaptureGuard`] is alive, so it can't corrupt the TUI frame.
+pub fn init() {
+    tracing_subscriber::registry()
+        .with(EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("warn")))
+        .with(
+            tracing_subscriber::fmt::layer()
+                .with_target(false)
+                .with_writer(|| -> Box<dyn Write> {
+                    if CAPTURING.load(Ordering::Relaxed) {
+                        Box::new(io::sink())
+                    } else {
+                        Box::new(io::stderr())
+                    }
+                }),
+        )
+        .with(
+            tracing_subscriber::fmt::layer()
+                .with_target(false)
+                .with_ansi(false)
+                .with_writer(|| LogFileWriter),
+        )
+        .with(RecentLayer)
+        .init();
+}
+
+/// Redirects log output to the log file until dropped
+pub struct CaptureGuard(());
+
+impl Drop for CaptureGuard {
+    fn drop(&mut self) {
+        CAPTURING.store(false, Ordering::Relaxed);
+        *LOG_FILE.lock().unwrap_or_else(|e| e.into_inner()) = None;
+    }
+}
+
+/// Stop writing log lines to the terminal (see [`init`])
+pub fn capture() -> CaptureGuard {
+    *LOG_FILE.lock().unwrap_or_else(|e| e.into_inner()) = open_log_file();
+    CAPTURING.store(true, Ordering::Relaxed);
+    CaptureGuard(())
+}
+
+/// Get the TUI log file path
+pub fn log_path() -> Option<PathBuf> {
+    let proj_dirs = ProjectDirs::from("com", "nanobanan", "banana-cli")?;
+    Some(proj_dirs.data_dir().join("tui.log"))
+}
+
+/// Open the log file for appending, rotating it first if it is full
+fn open_log_file() -> Option<(File, u64)> {
+    let path = log_path()?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).ok()?;
+    }
+    let len = fs::metadata(&path).map(|m| m.len()).unwrap_or(0);
+    if len >= MAX_LOG_BYTES {
+        return rotate_log_file();
+    }
+    let file = OpenOptions::new().create(true).append(true).open(path).ok()?;
+    Some((file, len))
+}
+
+/// Move the log file to `tui.log.1` (replacing the previous one) and start a new one
+fn rotate_log_file() -> Option<(File, u64)> {
+    let path = log_path()?;
+    fs::rename(&path, path.with_extension("log.1")).ok()?;
+    let file = OpenOptions::new().create(true).append(true).open(path).ok()?;
+    Some((file, 0))
+}
+
+/// Appends to the open log file, rotating it when it reaches
+/// [`MAX_LOG_BYTES`]; discards output while not capturing
+struct LogFileWriter;
+
+impl Write for LogFileWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let mut log = LOG_FILE.lock().unwrap_or_else(|e| e.into_inner());
+        if log.as_ref().is_some_and(|(_, len)| len + buf.len() as u64 > MAX_LOG_BYTES) {
+            // Closed first: an open file can't be renamed on Windows
+            *log = None;
+            *log = rotate_log_file().or_else(open_log_file);
+        }
+        let Some((file, len)) = log.as_mut() else {
+            return Ok(buf.len());
+        };
+        let written = file.write(buf)?;
+        *len += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match LOG_FILE.lock().unwrap_or_else(|e| e.into_inner()).as_mut() {
+            Some((file, _)) => file.flush(),
+            None => Ok(()),
+        }
+    }
+}
+
+/// A captured warning or error
+#[derive(Debug, Clone)]
+pub struct LogEntry {
+    pub time: DateTime<Local>,
+    pub level: Level,
+    pub message: String,
+}
+
+/// Tracing layer that keeps the most recent warnings and errors in memory
+pub struct RecentLayer;
+
+impl<S: Subscriber> Layer<S> for RecentLayer {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        let level = *event.metadata().level();
+        if level > Level::WARN {
+            return;
+        }
+
+        let mut visitor = MessageVisitor(String::new());
+        event.record(&mut visitor);
+
+        let entry = LogEntry {
+            time: Local::now(),
+            level,
+            message: visitor.0,
+        };
+
+        if let Ok(mut recent) = RECENT.lock() {
+            if recent.len() == CAPACITY {
+                recent.pop_front();
+            }
+            recent.push_back(entry);
+        }
+    }
+}
+
+/// Collects the `message` field plus any other fields as `key=value`
+struct MessageVisitor(String);
+
+impl Visit for MessageVisitor {
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        if !self.0.is_empty() {
+            self.0.push(' ');
+        }
+        if field.name() == "message" {
+            self.0.push_str(&format!("{:?}", value));
+        } else {
+            self.0.push_str(&format!("{}={:?}", field.name(), value));
+        }
+    }
+}
+
+/// Captured warnings and errors, newest first
+pub fn recent() -> Vec<LogEntry> {
+    RECENT
+        .lock()
+        .map(|recent| recent.iter().rev().cloned().collect())
+        .unwrap_or_default()
+}
@@ -1,48 +1,111 @@
 use anyhow::Result;
 use clap::Parser;
-use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, EnvFilter};
 
 mod api;
 mod cli;
 mod config;
 mod core;
 mod db;
+mod fixtures;
+mod heartbeat;
+mod history;
 mod http_client;
+mod i18n;
+mod icc;
+mod imaging;
+mod logging;
+mod metadata;
+mod platform;
+mod quota;
+mod templates;
+mod thumbnails;
+mod tmp_output;
 mod tui;
 
 use cli::{Cli, Commands};
-use config::Config;
+use config::{Config, ProviderKind};
 use db::Database;
 
 #[tokio::main]
 async fn main() -> Result<()> {
     // Initialize tracing
-    tracing_subscriber::registry()
-        .with(EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("warn")))
-        .with(tracing_subscriber::fmt::layer().with_target(false))
-        .init();
+    logging::init();
 
     let cli = Cli::parse();
 
+    if let Some(dir) = cli.record.clone() {
+        fixtures::init(fixtures::FixtureMode::Record(dir));
+    } else if let Some(dir) = cli.replay.clone() {
+        fixtures::init(fixtures::FixtureMode::Replay(dir));
+    }
+
     // Load or create config
     let mut config = Config::load_or_create()?;
+    if let Some(provider) = &cli.provider {
+        config.api.provider = ProviderKind::from_str(provider);
+    }
+
+    i18n::init(&config.ui.language);
+    cli::progress::set_animations(config.ui.animations);
+    cli::output::set_template(cli.template.clone());
 
     // Initialize database
     let db = Database::open()?;
 
+    history::enforce_retention(&config, &db);
+
     match cli.command {
         Some(Commands::Generate(args)) => {
             cli::commands::generate::run(args, &config, &db).await?;
         }
+        Some(Commands::Wizard(args)) => {
+            cli::commands::wizard::run(args, &config, &db).await?;
+        }
         Some(Commands::Edit(args)) => {
             cli::commands::edit::run(args, &config, &db).await?;
         }
+        Some(Commands::EditBatch(args)) => {
+            cli::commands::edit_batch::run(args, &config, &db).await?;
+        }
+        Some(Commands::Batch(args)) => {
+            cli::commands::batch::run(args, &config, &db, cli.provider.is_some()).await?;
+        }
+        Some(Commands::Sweep(args)) => {
+            cli::commands::sweep::run(args, &config, &db).await?;
+        }
+        Some(Commands::Draft(args)) => {
+            cli::commands::draft::run(args, &config, &db).await?;
+        }
+        Some(Commands::Upscale(args)) => {
+            cli::commands::upscale::run(args, &config, &db).await?;
+        }
         Some(Commands::Jobs(args)) => {
-            cli::commands::jobs::run(args, &db)?;
+            cli::commands::jobs::run(args, &config, &db, cli.provider.is_some()).await?;
+        }
+        Some(Commands::Inspect(args)) => {
+            cli::commands::inspect::run(args, &config, &db)?;
+        }
+        Some(Commands::Cp(args)) => {
+            cli::commands::cp::run(args)?;
+        }
+        Some(Commands::Stats(args)) => {
+            cli::commands::stats::run(args, &config, &db)?;
+        }
+        Some(Commands::Tokens(args)) => {
+            cli::commands::tokens::run(args, &config).await?;
+        }
+        Some(Commands::Bench(args)) => {
+            cli::commands::bench::run(args, &config, &db).await?;
+        }
+        Some(Commands::MockServer(args)) => {
+            cli::commands::mock_server::run(args).await?;
         }
         Some(Commands::Config(args)) => {
             cli::commands::config::run(args, &mut config)?;
         }
+        Some(Commands::Version(args)) => {
+            cli::commands::version::run(args, &config)?;
+        }
         None => {
             // Launch TUI
             tui::run(&mut config, &db).await?;
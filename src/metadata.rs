@@ -0,0 +1,221 @@
+use anyhow::{Context, Result};
+use exif::experimental::Writer as ExifWriter;
+use exif::{Field, In, Tag, Value};
+use img_parts::jpeg::{markers, Jpeg, JpegSegment};
+use img_parts::png::{Png, PngChunk};
+use img_parts::{Bytes, ImageEXIF, ImageICC};
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::{BufReader, Cursor, Read};
+use std::path::Path;
+
+use crate::config::ExifConfig;
+use crate::core::Job;
+use crate::icc;
+use crate::imaging;
+use crate::platform;
+
+/// Keyword of the PNG iTXt chunk and prefix of the JPEG comment holding banana metadata
+const METADATA_KEY: &str = "banana";
+
+/// Generation details embedded into every downloaded image
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImageMetadata {
+    pub job_id: String,
+    pub prompt: String,
+    pub model: String,
+    pub aspect_ratio: String,
+    pub size: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub seed: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parent_id: Option<String>,
+    /// banana version that wrote the file
+    pub generator: String,
+}
+
+impl ImageMetadata {
+    pub fn for_job(job: &Job) -> Self {
+        Self {
+            job_id: job.id.clone(),
+            prompt: job.params.prompt.clone(),
+            model: job.model.clone(),
+            aspect_ratio: job.params.aspect_ratio.clone(),
+            size: job.params.size.clone(),
+            seed: job.params.seed,
+            parent_id: job.parent_id.clone(),
+            generator: format!("banana {}", env!("CARGO_PKG_VERSION")),
+        }
+    }
+}
+
+/// Embed metadata into a PNG (iTXt chunk) or JPEG (comment segment) in place,
+/// along with any configured EXIF attribution. If `srgb` is set, images in
+/// another color space are converted to sRGB first, and all are tagged with
+/// an sRGB ICC profile.
+///
+/// Other formats are left untouched.
+pub fn embed(path: &Path, metadata: &ImageMetadata, exif: &ExifConfig, srgb: bool) -> Result<()> {
+    let json = serde_json::to_string(metadata)?;
+    let exif = exif_blob(exif)?;
+    if srgb && imaging::convert_to_srgb(path)? {
+        tracing::debug!("Converted {} to sRGB", path.display());
+    }
+    let mut data = Vec::new();
+    BufReader::new(File::open(path)?).read_to_end(&mut data)?;
+    let data = Bytes::from(data);
+
+    // Written to a temp file renamed over the original, so a failed write
+    // never leaves a truncated image
+    let written = if let Ok(mut png) = Png::from_bytes(data.clone()) {
+        png.chunks_mut()
+            .retain(|chunk| !is_banana_itxt(chunk));
+        // iTXt must come before IEND
+        let at = png.chunks().len().saturating_sub(1);
+        png.chunks_mut().insert(at, PngChunk::new(*b"iTXt", itxt_contents(&json)));
+        if exif.is_some() {
+            png.set_exif(exif);
+        }
+        if srgb {
+            tag_srgb(&mut png);
+        }
+        platform::replace_file(path, |file| {
+            png.encoder().write_to(file)?;
+            Ok(())
+        })
+    } else if let Ok(mut jpeg) = Jpeg::from_bytes(data) {
+        let prefix = format!("{}:", METADATA_KEY);
+        jpeg.segments_mut().retain(|segment| {
+            !(segment.marker() == markers::COM && segment.contents().starts_with(prefix.as_bytes()))
+        });
+        // Keep APPn segments (JFIF, EXIF) first
+        let at = jpeg
+            .segments()
+            .iter()
+            .position(|segment| !(markers::APP0..=markers::APP15).contains(&segment.marker()))
+            .unwrap_or(0);
+        let comment = Bytes::from(format!("{}{}", prefix, json));
+        jpeg.segments_mut()
+            .insert(at, JpegSegment::new_with_contents(markers::COM, comment));
+        if exif.is_some() {
+            jpeg.set_exif(exif);
+        }
+        if srgb {
+            tag_srgb(&mut jpeg);
+        }
+        platform::replace_file(path, |file| {
+            jpeg.encoder().write_to(file)?;
+            Ok(())
+        })
+    } else {
+        return Ok(());
+    };
+
+    written.with_context(|| format!("Failed to write metadata to {}", path.display()))
+}
+
+/// Read banana metadata from an image, if it has any
+pub fn read(path: &Path) -> Result<Option<ImageMetadata>> {
+    let data = Bytes::from(
+        std::fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?,
+    );
+
+    let json = if let Ok(png) = Png::from_bytes(data.clone()) {
+        png.chunks()
+            .iter()
+            .find(|chunk| is_banana_itxt(chunk))
+            .and_then(|chunk| itxt_text(chunk.contents()))
+    } else if let Ok(jpeg) = Jpeg::from_bytes(data) {
+        let prefix = format!("{}:", METADATA_KEY);
+        jpeg.segments_by_marker(markers::COM)
+            .find_map(|segment| segment.contents().strip_prefix(prefix.as_bytes()))
+            .and_then(|text| std::str::from_utf8(text).ok())
+            .map(String::from)
+    } else {
+        None
+    };
+
+    match json {
+        Some(json) => Ok(Some(
+            serde_json::from_str(&json).context("Embedded banana metadata is malformed")?,
+        )),
+        None => Ok(None),
+    }
+}
+
+/// Embed the sRGB profile unless the image already carries one.
+///
+/// API outputs are sRGB but untagged, which color-managed tools may read as
+/// another space. Images in other spaces were converted by
+/// `imaging::convert_to_srgb` and lost their profile, so a profile still
+/// present here is already sRGB.
+fn tag_srgb(image: &mut impl ImageICC) {
+    if image.icc_profile().is_some() {
+        tracing::debug!("Image already has an ICC profile; leaving it as is");
+        return;
+    }
+    image.set_icc_profile(Some(Bytes::from(icc::srgb_profile())));
+}
+
+/// Encode the configured attribution tags as a TIFF/EXIF block
+fn exif_blob(config: &ExifConfig) -> Result<Option<Bytes>> {
+    let fields: Vec<Field> = [
+        (Tag::Artist, &config.artist),
+        (Tag::Copyright, &config.copyright),
+        (Tag::Software, &config.software),
+    ]
+    .into_iter()
+    .filter_map(|(tag, value)| {
+        value.as_ref().map(|v| Field {
+            tag,
+            ifd_num: In::PRIMARY,
+            value: Value::Ascii(vec![v.as_bytes().to_vec()]),
+        })
+    })
+    .collect();
+
+    if fields.is_empty() {
+        return Ok(None);
+    }
+
+    let mut writer = ExifWriter::new();
+    for field in &fields {
+        writer.push_field(field);
+    }
+    let mut buf = Cursor::new(Vec::new());
+    writer
+        .write(&mut buf, false)
+        .context("Failed to encode EXIF data")?;
+    Ok(Some(Bytes::from(buf.into_inner())))
+}
+
+/// iTXt layout: keyword NUL, compression flag, compression method,
+/// language tag NUL, translated keyword NUL, UTF-8 text
+fn itxt_contents(text: &str) -> Bytes {
+    let mut contents = Vec::with_capacity(METADATA_KEY.len() + 5 + text.len());
+    contents.extend_from_slice(METADATA_KEY.as_bytes());
+    contents.extend_from_slice(&[0, 0, 0, 0, 0]);
+    contents.extend_from_slice(text.as_bytes());
+    Bytes::from(contents)
+}
+
+fn is_banana_itxt(chunk: &PngChunk) -> bool {
+    chunk.kind() == *b"iTXt"
+        && chunk.contents().starts_with(METADATA_KEY.as_bytes())
+        && chunk.contents().get(METADATA_KEY.len()) == Some(&0)
+}
+
+/// Extract the uncompressed text of an iTXt chunk
+fn itxt_text(contents: &[u8]) -> Option<String> {
+    let mut fields = contents.splitn(2, |&b| b == 0);
+    fields.next()?;
+    let rest = fields.next()?;
+    // Compressed text isn't written by banana
+    if rest.first() != Some(&0) {
+        return None;
+    }
+    let mut fields = rest.get(2..)?.splitn(3, |&b| b == 0);
+    fields.next()?; // language tag
+    fields.next()?; // translated keyword
+    String::from_utf8(fields.next()?.to_vec()).ok()
+}
@@ -0,0 +1,200 @@
+use anyhow::{Context, Result};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+use tempfile::NamedTempFile;
+
+/// Canonicalize a path, stripping the Windows verbatim prefix (`\\?\`)
+/// so paths stay readable and usable by tools that don't understand it.
+pub fn canonicalize(path: &Path) -> std::io::Result<PathBuf> {
+    let canonical = path.canonicalize()?;
+    Ok(strip_verbatim_prefix(canonical))
+}
+
+/// Remove the `\\?\` (and `\\?\UNC\`) prefix produced by `canonicalize` on Windows
+pub fn strip_verbatim_prefix(path: PathBuf) -> PathBuf {
+    let s = path.to_string_lossy();
+    if let Some(rest) = s.strip_prefix(r"\\?\UNC\") {
+        PathBuf::from(format!(r"\\{}", rest))
+    } else if let Some(rest) = s.strip_prefix(r"\\?\") {
+        PathBuf::from(rest)
+    } else {
+        path
+    }
+}
+
+/// Render a path for display using the platform's native separators
+pub fn display_path(path: &Path) -> String {
+    let s = path.to_string_lossy().to_string();
+    if cfg!(windows) {
+        s.replace('/', "\\")
+    } else {
+        s
+    }
+}
+
+/// Open a file in the platform's default viewer without blocking
+pub fn open_in_viewer(path: &Path) -> Result<()> {
+    let mut command = if cfg!(target_os = "windows") {
+        // `start` is a cmd builtin; the empty string is the window title
+        let mut c = Command::new("cmd");
+        c.args(["/C", "start", ""]).arg(path);
+        c
+    } else if cfg!(target_os = "macos") {
+        let mut c = Command::new("open");
+        c.arg(path);
+        c
+    } else {
+        let mut c = Command::new("xdg-open");
+        c.arg(path);
+        c
+    };
+
+    command
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .with_context(|| format!("Failed to open {} in viewer", path.display()))?;
+
+    Ok(())
+}
+
+/// Replace `path` with what `write` produces.
+///
+/// The new contents go to a temp file in the same directory that is renamed
+/// over `path` once complete, so a failure never leaves a truncated file.
+/// The original file's permissions are kept.
+pub fn replace_file<F>(path: &Path, write: F) -> Result<()>
+where
+    F: FnOnce(&mut BufWriter<NamedTempFile>) -> Result<()>,
+{
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    let temp = NamedTempFile::new_in(dir)
+        .with_context(|| format!("Failed to create a temp file in {}", dir.display()))?;
+    let mut file = BufWriter::new(temp);
+    write(&mut file)?;
+    file.flush()?;
+
+    let temp = file.into_inner().map_err(|e| e.into_error())?;
+    if let Ok(metadata) = std::fs::metadata(path) {
+        std::fs::set_permissions(temp.path(), metadata.permissions())?;
+    }
+    temp.persist(path)
+        .with_context(|| format!("Failed to replace {}", path.display()))?;
+    Ok(())
+}
+
+/// How long to wait for another process to release a lock
+const LOCK_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Lock files older than this were left behind by a process that died
+const LOCK_STALE_AFTER: Duration = Duration::from_secs(30);
+
+/// Exclusive lock held by creating a lock file next to the file it guards.
+/// Released when dropped.
+pub struct FileLock {
+    path: PathBuf,
+}
+
+impl FileLock {
+    /// Wait for and take the lock at `path`; `what` names the guarded file in errors
+    pub fn acquire(path: &Path, what: &str) -> Result<Self> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create {}", parent.display()))?;
+        }
+
+        let started = Instant::now();
+        loop {
+            match std::fs::OpenOptions::new().write(true).create_new(true).open(path) {
+                Ok(_) => return Ok(Self { path: path.to_path_buf() }),
+                Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {
+                    let stale = std::fs::metadata(path)
+                        .and_then(|m| m.modified())
+                        .ok()
+                        .and_then(|mtime| mtime.elapsed().ok())
+                        .is_some_and(|age| age > LOCK_STALE_AFTER);
+                    if stale {
+                        let _ = std::fs::remove_file(path);
+                        continue;
+                    }
+                    if started.elapsed() > LOCK_TIMEOUT {
+                        anyhow::bail!(
+                            "{} is locked by another banana process (remove {} if none is running)",
+                            what,
+                            path.display()
+                        );
+                    }
+                    std::thread::sleep(Duration::from_millis(50));
+                }
+                Err(e) => return Err(e).with_context(|| format!("Failed to lock {}", what.to_lowercase())),
+            }
+        }
+    }
+}
+
+impl Drop for FileLock {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+/// Expand a leading `~` to the home directory, as a shell would
+pub fn expand_tilde(path: &str) -> PathBuf {
+    let rest = match path.strip_prefix('~') {
+        Some(rest) if rest.is_empty() || rest.starts_with(['/', std::path::MAIN_SEPARATOR]) => rest,
+        _ => return PathBuf::from(path),
+    };
+    match directories::BaseDirs::new() {
+        Some(dirs) => dirs.home_dir().join(rest.trim_start_matches(['/', std::path::MAIN_SEPARATOR])),
+        None => PathBuf::from(path),
+    }
+}
+
+/// Complete a partly typed path as a shell would on Tab.
+///
+/// Extends the last component to the longest prefix shared by the entries
+/// that start with it, adding a separator once a single directory matches.
+/// A leading `~` is kept as typed.
+/// Returns `None` if nothing matches or there is nothing to add.
+pub fn complete_path(input: &str) -> Option<String> {
+    let split = input.rfind(['/', std::path::MAIN_SEPARATOR]).map_or(0, |i| i + 1);
+    let (dir, prefix) = input.split_at(split);
+    let read_from = if dir.is_empty() { PathBuf::from(".") } else { expand_tilde(dir) };
+
+    let mut matches: Vec<(String, bool)> = std::fs::read_dir(read_from)
+        .ok()?
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let name = entry.file_name().to_string_lossy().to_string();
+            // Hidden entries only when asked for
+            let visible = !name.starts_with('.') || prefix.starts_with('.');
+            let is_dir = entry.path().is_dir();
+            (visible && name.starts_with(prefix)).then_some((name, is_dir))
+        })
+        .collect();
+    matches.sort();
+
+    let completed = match matches.as_slice() {
+        [] => return None,
+        [(name, true)] => format!("{}{}/", dir, name),
+        [(name, false)] => format!("{}{}", dir, name),
+        [(first, _), rest @ ..] => {
+            let shared = rest.iter().fold(first.clone(), |shared, (name, _)| {
+                shared
+                    .chars()
+                    .zip(name.chars())
+                    .take_while(|(a, b)| a == b)
+                    .map(|(a, _)| a)
+                    .collect()
+            });
+            format!("{}{}", dir, shared)
+        }
+    };
+    (completed != input).then_some(completed)
+}
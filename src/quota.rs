@@ -0,0 +1,63 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use directories::ProjectDirs;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+/// Last known rate-limit state, persisted so other invocations can see it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QuotaState {
+    /// When the rate limit was hit
+    pub limited_at: DateTime<Utc>,
+    /// When the API said the quota resets (if it said)
+    pub resets_at: Option<DateTime<Utc>>,
+    /// Quota identifier reported by the API (e.g. "GenerateRequestsPerMinutePerProjectPerModel")
+    pub quota_id: Option<String>,
+    /// Model the limit applied to
+    pub model: String,
+}
+
+impl QuotaState {
+    /// Seconds until the quota resets, if still limited
+    pub fn seconds_remaining(&self) -> Option<i64> {
+        let remaining = (self.resets_at? - Utc::now()).num_seconds();
+        (remaining > 0).then_some(remaining)
+    }
+}
+
+/// Get the quota state file path
+fn state_path() -> Result<PathBuf> {
+    let proj_dirs = ProjectDirs::from("com", "nanobanan", "banana-cli")
+        .context("Failed to determine data directory")?;
+    Ok(proj_dirs.data_dir().join("quota.json"))
+}
+
+/// Load the last known quota state
+pub fn load() -> Option<QuotaState> {
+    let path = state_path().ok()?;
+    let content = std::fs::read_to_string(path).ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+/// Persist the quota state
+pub fn save(state: &QuotaState) -> Result<()> {
+    let path = state_path()?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(path, serde_json::to_string_pretty(state)?)?;
+    Ok(())
+}
+
+/// Forget the quota state after a successful request
+pub fn clear() {
+    if let Ok(path) = state_path() {
+        let _ = std::fs::remove_file(path);
+    }
+}
+
+/// Parse a protobuf duration string such as "42s" or "1.5s" into whole seconds
+pub fn parse_retry_delay(delay: &str) -> Option<u64> {
+    let secs: f64 = delay.trim().strip_suffix('s')?.parse().ok()?;
+    Some(secs.ceil() as u64)
+}
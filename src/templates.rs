@@ -0,0 +1,105 @@
+use anyhow::Result;
+use std::path::PathBuf;
+
+use crate::config::Config;
+
+/// A reusable prompt with `{placeholders}`
+#[derive(Debug, Clone)]
+pub struct Template {
+    pub name: String,
+    pub body: String,
+}
+
+/// Templates available out of the box
+const BUILTIN: &[(&str, &str)] = &[
+    (
+        "product-shot",
+        "studio product photo of {product} on a {background} background, softbox lighting, 85mm lens",
+    ),
+    (
+        "portrait",
+        "portrait of {subject}, {style} style, shallow depth of field, natural window light",
+    ),
+    (
+        "logo",
+        "minimalist logo for {brand}, {color} palette, flat vector, centered on white",
+    ),
+    (
+        "landscape",
+        "{time_of_day} landscape of {place}, cinematic wide shot, volumetric light",
+    ),
+    (
+        "app-icon",
+        "app icon of {object}, rounded square, {style}, bold shapes, high contrast",
+    ),
+];
+
+/// Directory for user templates (one `<name>.txt` file per template)
+pub fn templates_dir() -> Result<PathBuf> {
+    Ok(Config::config_dir()?.join("templates"))
+}
+
+/// Load built-in and user templates, sorted by name.
+///
+/// User templates override built-ins with the same name.
+pub fn load() -> Vec<Template> {
+    let mut templates: Vec<Template> = BUILTIN
+        .iter()
+        .map(|(name, body)| Template {
+            name: name.to_string(),
+            body: body.to_string(),
+        })
+        .collect();
+
+    let entries = templates_dir()
+        .ok()
+        .and_then(|dir| std::fs::read_dir(dir).ok())
+        .into_iter()
+        .flatten()
+        .flatten();
+    for entry in entries {
+        let path = entry.path();
+        if path.extension().and_then(|e| e.to_str()) != Some("txt") {
+            continue;
+        }
+        let (Some(name), Ok(body)) = (
+            path.file_stem().and_then(|s| s.to_str()),
+            std::fs::read_to_string(&path),
+        ) else {
+            continue;
+        };
+
+        let template = Template {
+            name: name.to_string(),
+            body: body.trim().to_string(),
+        };
+        match templates.iter_mut().find(|t| t.name == template.name) {
+            Some(existing) => *existing = template,
+            None => templates.push(template),
+        }
+    }
+
+    templates.sort_by(|a, b| a.name.cmp(&b.name));
+    templates
+}
+
+/// Score a fuzzy (in-order subsequence) match of `query` against `text`.
+///
+/// Higher is better; consecutive and early matches score more. `None` if
+/// the query doesn't match.
+pub fn fuzzy_score(query: &str, text: &str) -> Option<i64> {
+    let text: Vec<char> = text.to_lowercase().chars().collect();
+    let mut score = 0i64;
+    let mut pos = 0usize;
+    let mut last_match: Option<usize> = None;
+
+    for q in query.to_lowercase().chars().filter(|c| !c.is_whitespace()) {
+        let found = pos + text[pos..].iter().position(|&c| c == q)?;
+        score += if last_match.map(|l| l + 1 == found).unwrap_or(false) { 10 } else { 1 };
+        score -= found as i64 / 10;
+        last_match = Some(found);
+        pos = found + 1;
+    }
+
+    Some(score)
+}
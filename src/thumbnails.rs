@@ -0,0 +1,102 @@
+use anyhow::{Context, Result};
+use directories::ProjectDirs;
+use image::ImageFormat;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+/// Longest edge of a cached thumbnail, in pixels
+pub const THUMBNAIL_SIZE: u32 = 256;
+
+/// Get the thumbnail cache directory (under the data dir)
+pub fn cache_dir() -> Result<PathBuf> {
+    let proj_dirs = ProjectDirs::from("com", "nanobanan", "banana-cli")
+        .context("Failed to determine data directory")?;
+    Ok(proj_dirs.data_dir().join("thumbnails"))
+}
+
+/// Get the cached thumbnail path for a job image.
+///
+/// The name includes the source file's modification time, so an image that
+/// is rewritten in place (upscaled, re-encoded) gets a fresh thumbnail.
+pub fn path_for(job_id: &str, index: u8, source: &Path) -> Result<PathBuf> {
+    let modified = std::fs::metadata(source)
+        .and_then(|m| m.modified())
+        .with_context(|| format!("Failed to read {}", source.display()))?;
+    let stamp = modified.duration_since(UNIX_EPOCH).unwrap_or_default().as_nanos();
+    Ok(cache_dir()?.join(format!("{}_{}_{}.jpg", job_id, index, stamp)))
+}
+
+/// Return the cached thumbnail for a job image, creating it from `source` if missing.
+///
+/// Thumbnails are JPEG: the image crate can't encode WebP without libwebp,
+/// and JPEG keeps 256px previews to a few KB.
+pub fn ensure(job_id: &str, index: u8, source: &Path) -> Result<PathBuf> {
+    let path = path_for(job_id, index, source)?;
+    if path.exists() {
+        return Ok(path);
+    }
+
+    std::fs::create_dir_all(cache_dir()?).context("Failed to create thumbnail directory")?;
+    // Drop thumbnails of earlier versions of the image
+    remove(job_id, index)?;
+
+    let img = image::open(source)
+        .with_context(|| format!("Failed to decode {}", source.display()))?;
+    img.thumbnail(THUMBNAIL_SIZE, THUMBNAIL_SIZE)
+        .to_rgb8()
+        .save_with_format(&path, ImageFormat::Jpeg)
+        .context("Failed to write thumbnail")?;
+
+    Ok(path)
+}
+
+/// Remove the cached thumbnails of one job image, if any
+pub fn remove(job_id: &str, index: u8) -> Result<()> {
+    remove_with_prefix(&format!("{}_{}_", job_id, index))?;
+    // Thumbnails cached before they were keyed on mtime
+    match std::fs::remove_file(cache_dir()?.join(format!("{}_{}.jpg", job_id, index))) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+        _ => Ok(()),
+    }
+}
+
+/// Total size in bytes of the cached thumbnails, by job ID
+pub fn sizes_by_job() -> Result<HashMap<String, u64>> {
+    let dir = cache_dir()?;
+    let mut sizes = HashMap::new();
+    if !dir.exists() {
+        return Ok(sizes);
+    }
+
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy().into_owned();
+        // `{job}_{index}_{mtime}.jpg`; job IDs contain underscores themselves
+        if let Some(job_id) = name.rsplitn(3, '_').nth(2) {
+            *sizes.entry(job_id.to_string()).or_insert(0) += entry.metadata()?.len();
+        }
+    }
+    Ok(sizes)
+}
+
+/// Remove all cached thumbnails for a job
+pub fn remove_for_job(job_id: &str) -> Result<()> {
+    remove_with_prefix(&format!("{}_", job_id))
+}
+
+/// Remove cached thumbnails whose file name starts with `prefix`
+fn remove_with_prefix(prefix: &str) -> Result<()> {
+    let dir = cache_dir()?;
+    if !dir.exists() {
+        return Ok(());
+    }
+
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        if entry.file_name().to_string_lossy().starts_with(prefix) {
+            std::fs::remove_file(entry.path())?;
+        }
+    }
+    Ok(())
+}
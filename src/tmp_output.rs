@@ -0,0 +1,104 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Duration, Utc};
+use directories::ProjectDirs;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+use crate::platform::FileLock;
+
+/// A temporary output directory created by `--tmp`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TmpDir {
+    pub path: PathBuf,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Get the registry file path
+fn registry_path() -> Result<PathBuf> {
+    let proj_dirs = ProjectDirs::from("com", "nanobanan", "banana-cli")
+        .context("Failed to determine data directory")?;
+    Ok(proj_dirs.data_dir().join("tmp_dirs.json"))
+}
+
+/// Lock the registry so concurrent runs don't drop each other's entries
+fn lock() -> Result<FileLock> {
+    FileLock::acquire(&registry_path()?.with_extension("json.lock"), "Temporary directory registry")
+}
+
+/// Load the registered temporary directories
+fn load() -> Vec<TmpDir> {
+    registry_path()
+        .ok()
+        .and_then(|path| std::fs::read_to_string(path).ok())
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+/// Persist the registered temporary directories
+fn save(dirs: &[TmpDir]) -> Result<()> {
+    let path = registry_path()?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(path, serde_json::to_string_pretty(dirs)?)?;
+    Ok(())
+}
+
+/// Create a fresh temporary output directory for a job and register it for cleanup.
+///
+/// The directory gets a random name and is only accessible to the current user.
+pub fn create(job_id: &str) -> Result<PathBuf> {
+    let prefix = format!("banana-{}-", job_id);
+    let mut builder = tempfile::Builder::new();
+    builder.prefix(&prefix);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        builder.permissions(std::fs::Permissions::from_mode(0o700));
+    }
+    let path = builder
+        .tempdir()
+        .context("Failed to create temporary directory")?
+        .keep();
+
+    let _lock = lock()?;
+    let mut dirs = load();
+    dirs.push(TmpDir {
+        path: path.clone(),
+        created_at: Utc::now(),
+    });
+    save(&dirs)?;
+    Ok(path)
+}
+
+/// Delete registered directories older than `max_age`, returning how many were removed
+pub fn sweep(max_age: Duration) -> Result<usize> {
+    let _lock = lock()?;
+    let dirs = load();
+    if dirs.is_empty() {
+        return Ok(0);
+    }
+
+    let cutoff = Utc::now() - max_age;
+    let (expired, kept): (Vec<TmpDir>, Vec<TmpDir>) =
+        dirs.into_iter().partition(|dir| dir.created_at < cutoff);
+    if expired.is_empty() {
+        return Ok(0);
+    }
+
+    for dir in &expired {
+        if let Err(e) = remove(&dir.path) {
+            tracing::warn!("Failed to remove {}: {}", dir.path.display(), e);
+        }
+    }
+    save(&kept)?;
+    Ok(expired.len())
+}
+
+/// Remove a directory, treating one that is already gone as removed
+fn remove(path: &Path) -> std::io::Result<()> {
+    match std::fs::remove_dir_all(path) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e),
+        _ => Ok(()),
+    }
+}
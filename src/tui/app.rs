@@ -1,7 +1,29 @@
-use crate::config::Config;
-use crate::core::Job;
+use crate::api::GeminiClient;
+use crate::config::{Config, ConfigWatcher, KeyInfo, ProviderKind, KEYS};
+use crate::core::{prompt, GenerateParams, Job};
 use crate::db::Database;
+use crate::history;
+use crate::logging::{self, LogEntry};
+use crate::platform;
+use crate::quota::{self, QuotaState};
+use crate::templates::{self, Template};
 use anyhow::Result;
+use chrono::{Local, Utc};
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
+
+/// How long the settings screen waits for the model list before giving up
+const MODELS_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// How often history retention is re-applied while the TUI is open
+const RETENTION_INTERVAL: Duration = Duration::from_secs(600);
+
+/// Columns of the image grid in the job detail view
+pub const IMAGE_GRID_COLUMNS: usize = 2;
 
 /// Application mode
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -14,59 +36,81 @@ pub enum AppMode {
     JobDetail,
     /// Settings screen
     Settings,
+    /// Prompt template picker (opened from input mode)
+    Templates,
+    /// Path input for the image to edit (before its instruction)
+    EditPath,
+    /// Recent warnings and errors
+    Log,
+    /// `:` command line
+    Command,
 }
 
-/// Settings field being edited
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub enum SettingsField {
-    Model,
-    AspectRatio,
-    Size,
-    OutputDirectory,
-    AutoDownload,
-    Display,
-    ShowImages,
-    Theme,
+/// Result of background work, delivered to the event loop
+#[derive(Debug)]
+pub enum AppEvent {
+    /// Model list for the settings screen
+    Models(Vec<String>),
 }
 
+/// Today's activity, shown in the footer of the main view
+#[derive(Debug, Clone, Default)]
+pub struct DayStats {
+    pub completed: usize,
+    pub failed: usize,
+    /// Estimated spend of today's jobs that have usage data
+    pub spend_usd: f64,
+    /// Jobs queued or running now, from any day
+    pub queue_depth: usize,
+}
+
+impl DayStats {
+    fn load(db: &Database) -> Result<Self> {
+        let midnight = Local::now()
+            .date_naive()
+            .and_hms_opt(0, 0, 0)
+            .and_then(|t| t.and_local_timezone(Local).earliest())
+            .map(|t| t.with_timezone(&Utc))
+            .unwrap_or_else(Utc::now);
+
+        let mut stats = Self::default();
+        for job in db.list_jobs_since(midnight)? {
+            match job.status_label() {
+                "completed" => stats.completed += 1,
+                "failed" => stats.failed += 1,
+                _ => {}
+            }
+            stats.spend_usd += job.estimated_cost().unwrap_or(0.0);
+        }
+        for status in ["queued", "running"] {
+            stats.queue_depth += db
+                .list_jobs(u32::MAX, Some(status))?
+                .iter()
+                .filter(|job| !job.is_stale())
+                .count();
+        }
+        Ok(stats)
+    }
+}
+
+/// Settings field being edited (a non-secret registry key)
+#[derive(Debug, Clone, Copy)]
+pub struct SettingsField(&'static KeyInfo);
+
 impl SettingsField {
-    pub fn all() -> &'static [SettingsField] {
-        &[
-            SettingsField::Model,
-            SettingsField::AspectRatio,
-            SettingsField::Size,
-            SettingsField::OutputDirectory,
-            SettingsField::AutoDownload,
-            SettingsField::Display,
-            SettingsField::ShowImages,
-            SettingsField::Theme,
-        ]
+    pub fn all() -> Vec<SettingsField> {
+        KEYS.iter()
+            .filter(|info| !info.secret)
+            .map(SettingsField)
+            .collect()
     }
 
     pub fn label(&self) -> &'static str {
-        match self {
-            SettingsField::Model => "Model",
-            SettingsField::AspectRatio => "Aspect Ratio",
-            SettingsField::Size => "Size",
-            SettingsField::OutputDirectory => "Output Directory",
-            SettingsField::AutoDownload => "Auto Download",
-            SettingsField::Display => "Display Mode",
-            SettingsField::ShowImages => "Show Images in TUI",
-            SettingsField::Theme => "Theme",
-        }
+        self.0.key
     }
 
     pub fn config_key(&self) -> &'static str {
-        match self {
-            SettingsField::Model => "api.model",
-            SettingsField::AspectRatio => "defaults.aspect_ratio",
-            SettingsField::Size => "defaults.size",
-            SettingsField::OutputDirectory => "output.directory",
-            SettingsField::AutoDownload => "output.auto_download",
-            SettingsField::Display => "output.display",
-            SettingsField::ShowImages => "tui.show_images",
-            SettingsField::Theme => "tui.theme",
-        }
+        self.0.key
     }
 }
 
@@ -87,6 +131,9 @@ pub struct App {
     /// Cursor position in input
     pub cursor_pos: usize,
 
+    /// Placeholder selected by Tab; typing replaces it
+    pub input_selection: Option<Range<usize>>,
+
     /// Job list
     pub jobs: Vec<Job>,
 
@@ -96,6 +143,12 @@ pub struct App {
     /// Currently viewing job (for detail view)
     pub current_job: Option<Job>,
 
+    /// Detail view: selected cell of the image grid
+    pub image_selected: usize,
+
+    /// Detail view: the selected image is waiting for a second `x` to be deleted
+    pub image_delete_pending: bool,
+
     /// Status message
     pub status_message: Option<String>,
 
@@ -105,8 +158,8 @@ pub struct App {
     /// Whether to quit
     pub should_quit: bool,
 
-    /// Whether config was changed
-    pub config_changed: bool,
+    /// Settings changed here but not yet saved, by config key
+    pub config_changes: BTreeMap<String, String>,
 
     /// Settings: selected field index
     pub settings_selected: usize,
@@ -117,41 +170,163 @@ pub struct App {
     /// Settings: edit buffer
     pub settings_edit_buffer: String,
 
+    /// Input: Enter was pressed once and the parameters are shown for confirmation
+    pub confirm_pending: bool,
+
+    /// Input: image the prompt edits, instead of generating a new one
+    pub edit_source: Option<PathBuf>,
+
     /// Generation in progress
     pub generating: bool,
+
+    /// Last known API rate-limit state
+    pub quota: Option<QuotaState>,
+
+    /// Most recently trashed job (for undo)
+    pub last_deleted: Option<String>,
+
+    /// Templates: loaded library
+    pub templates: Vec<Template>,
+
+    /// Templates: fuzzy search query
+    pub template_query: String,
+
+    /// Templates: selected index into the filtered list
+    pub template_selected: usize,
+
+    /// Log: captured warnings and errors (newest first)
+    pub log_entries: Vec<LogEntry>,
+
+    /// Log: selected entry
+    pub log_selected: usize,
+
+    /// Reports edits to config.toml made outside the TUI
+    config_watcher: Option<ConfigWatcher>,
+
+    /// When history retention was last applied
+    pub retention_checked_at: Instant,
+
+    /// Today's counts and spend for the footer
+    pub day_stats: DayStats,
+
+    /// Settings: image models offered by the API, fetched when settings open
+    pub models: Option<Vec<String>>,
+
+    /// Settings: whether the model list has been requested
+    pub models_requested: bool,
+
+    /// Background tasks send their results here
+    events: mpsc::UnboundedSender<AppEvent>,
+    event_rx: mpsc::UnboundedReceiver<AppEvent>,
 }
 
 impl App {
     pub fn new(config: Config, db: Database) -> Self {
+        let config_watcher = ConfigWatcher::new(&config)
+            .map_err(|e| tracing::warn!("Config changes won't be picked up live: {:#}", e))
+            .ok();
+        let (events, event_rx) = mpsc::unbounded_channel();
         Self {
             mode: AppMode::Main,
             config,
             db,
             input: String::new(),
             cursor_pos: 0,
+            input_selection: None,
             jobs: Vec::new(),
             selected_job: 0,
             current_job: None,
+            image_selected: 0,
+            image_delete_pending: false,
             status_message: None,
             error_message: None,
             should_quit: false,
-            config_changed: false,
+            config_changes: BTreeMap::new(),
             settings_selected: 0,
             settings_editing: false,
             settings_edit_buffer: String::new(),
-        generating: false,
+            confirm_pending: false,
+            edit_source: None,
+            generating: false,
+            quota: quota::load(),
+            last_deleted: None,
+            templates: templates::load(),
+            template_query: String::new(),
+            template_selected: 0,
+            log_entries: Vec::new(),
+            log_selected: 0,
+            config_watcher,
+            retention_checked_at: Instant::now(),
+            day_stats: DayStats::default(),
+            models: None,
+            models_requested: false,
+            events,
+            event_rx,
+        }
+    }
+
+    /// Apply results delivered by background tasks
+    pub fn handle_app_events(&mut self) {
+        while let Ok(event) = self.event_rx.try_recv() {
+            match event {
+                AppEvent::Models(models) => self.models = Some(models),
+            }
         }
     }
 
     /// Load jobs from database
     pub fn load_jobs(&mut self) -> Result<()> {
         self.jobs = self.db.list_jobs(50, None)?;
+        self.day_stats = DayStats::load(&self.db)?;
         if self.selected_job >= self.jobs.len() && !self.jobs.is_empty() {
             self.selected_job = self.jobs.len() - 1;
         }
         Ok(())
     }
 
+    /// Reload the persisted quota state
+    pub fn refresh_quota(&mut self) {
+        self.quota = quota::load();
+    }
+
+    /// Pick up edits to config.toml made while the TUI is running
+    pub fn reload_config_if_changed(&mut self) {
+        if !self.config_watcher.as_mut().is_some_and(ConfigWatcher::changed) {
+            return;
+        }
+
+        // Settings changed here but not yet saved win over the file
+        let result = self.config.reload().and_then(|()| {
+            self.config_changes
+                .iter()
+                .try_for_each(|(key, value)| self.config.set(key, value))
+        });
+        match result {
+            Ok(()) => self.set_status("Reloaded config.toml"),
+            Err(e) => self.set_error(format!("Failed to reload config: {:#}", e)),
+        }
+    }
+
+    /// Re-apply history retention, which otherwise only runs at startup
+    pub fn enforce_retention_if_due(&mut self) {
+        if self.retention_checked_at.elapsed() < RETENTION_INTERVAL {
+            return;
+        }
+        self.retention_checked_at = Instant::now();
+
+        match history::prune_failed(&self.config, &self.db) {
+            Ok(0) => {}
+            Ok(pruned) => {
+                if let Err(e) = self.load_jobs() {
+                    self.set_error(format!("Failed to reload jobs: {}", e));
+                } else {
+                    self.set_status(format!("Moved {} old failed job(s) to the trash", pruned));
+                }
+            }
+            Err(e) => self.set_error(format!("Failed to prune failed jobs: {:#}", e)),
+        }
+    }
+
     /// Set status message
     pub fn set_status(&mut self, msg: impl Into<String>) {
         self.status_message = Some(msg.into());
@@ -189,38 +364,301 @@ impl App {
         }
     }
 
+    /// Show the selected job in the detail view
+    pub fn open_job_detail(&mut self) {
+        if let Some(job) = self.selected_job().cloned() {
+            self.current_job = Some(job);
+            self.image_selected = 0;
+            self.image_delete_pending = false;
+            self.mode = AppMode::JobDetail;
+        }
+    }
+
+    /// Move the image grid selection by `delta` cells, staying in bounds
+    pub fn move_image_selection(&mut self, delta: isize) {
+        let count = self.current_job.as_ref().map_or(0, |job| job.images.len());
+        let target = self.image_selected as isize + delta;
+        if (0..count as isize).contains(&target) {
+            self.image_selected = target as usize;
+        }
+        self.image_delete_pending = false;
+    }
+
+    /// Open the selected grid image in the default viewer
+    pub fn open_selected_image(&mut self) {
+        let path = self
+            .current_job
+            .as_ref()
+            .and_then(|job| job.images.get(self.image_selected))
+            .and_then(|img| img.path.clone());
+        match path {
+            Some(path) => {
+                if let Err(e) = platform::open_in_viewer(Path::new(&path)) {
+                    self.set_error(e.to_string());
+                }
+            }
+            None => self.set_error("Image was not downloaded"),
+        }
+    }
+
+    /// Make the selected grid image the job's primary image
+    pub fn promote_selected_image(&mut self) -> Result<()> {
+        let Some(job) = self.current_job.as_mut() else {
+            return Ok(());
+        };
+        let Some(index) = job.images.get(self.image_selected).map(|img| img.index) else {
+            return Ok(());
+        };
+        job.set_primary_image(index);
+        self.db.update_job(job)?;
+        self.load_jobs()?;
+        self.set_status(format!("Image {} is now the primary image", index));
+        Ok(())
+    }
+
+    /// Delete the selected grid image and its file, asking for a second
+    /// press first
+    pub fn delete_selected_image(&mut self) -> Result<()> {
+        let Some(job) = self.current_job.as_mut() else {
+            return Ok(());
+        };
+        let Some(index) = job.images.get(self.image_selected).map(|img| img.index) else {
+            return Ok(());
+        };
+
+        if !self.image_delete_pending {
+            self.image_delete_pending = true;
+            self.set_status(format!("Press x again to delete image {} and its file", index));
+            return Ok(());
+        }
+        self.image_delete_pending = false;
+
+        history::delete_image(job, index)?;
+        self.db.update_job(job)?;
+        self.image_selected = self.image_selected.min(job.images.len().saturating_sub(1));
+        self.load_jobs()?;
+        self.set_status(format!("Deleted image {}", index));
+        Ok(())
+    }
+
+    /// Open the log view with a fresh snapshot of captured messages
+    pub fn open_log(&mut self) {
+        self.log_entries = logging::recent();
+        self.log_selected = 0;
+        self.mode = AppMode::Log;
+    }
+
+    /// Open the `:` command line
+    pub fn open_command(&mut self) {
+        self.mode = AppMode::Command;
+        self.input.clear();
+        self.cursor_pos = 0;
+        self.input_selection = None;
+        self.clear_messages();
+    }
+
+    /// Run the typed `:` command
+    pub fn run_command(&mut self) {
+        let command = self.input.trim().to_string();
+        self.input.clear();
+        self.cursor_pos = 0;
+        self.mode = AppMode::Main;
+        match command.as_str() {
+            "log" => self.open_log(),
+            "" => {}
+            other => self.set_error(format!("Unknown command: :{}", other)),
+        }
+    }
+
+    /// Open the template picker
+    pub fn open_templates(&mut self) {
+        self.template_query.clear();
+        self.template_selected = 0;
+        self.mode = AppMode::Templates;
+    }
+
+    /// Templates matching the search query, best match first
+    pub fn filtered_templates(&self) -> Vec<&Template> {
+        let mut matches: Vec<(i64, &Template)> = self
+            .templates
+            .iter()
+            .filter_map(|t| {
+                let score = templates::fuzzy_score(&self.template_query, &t.name)
+                    .map(|s| s * 2)
+                    .or_else(|| templates::fuzzy_score(&self.template_query, &t.body))?;
+                Some((score, t))
+            })
+            .collect();
+        matches.sort_by_key(|(score, _)| Reverse(*score));
+        matches.into_iter().map(|(_, t)| t).collect()
+    }
+
+    /// Insert the selected template at the cursor and select its first placeholder
+    pub fn insert_selected_template(&mut self) {
+        let Some(body) = self
+            .filtered_templates()
+            .get(self.template_selected)
+            .map(|t| t.body.clone())
+        else {
+            return;
+        };
+
+        let start = self.cursor_pos;
+        self.input.insert_str(start, &body);
+        self.cursor_pos = start + body.len();
+        self.input_selection = None;
+        self.mode = AppMode::Input;
+
+        if let Some(range) = prompt::next_placeholder(&self.input, start) {
+            self.select_placeholder(range);
+        }
+    }
+
+    /// Select the next `{placeholder}` after the cursor, wrapping around
+    pub fn select_next_placeholder(&mut self) {
+        let from = self
+            .input_selection
+            .as_ref()
+            .map(|r| r.end)
+            .unwrap_or(self.cursor_pos);
+        let next = prompt::next_placeholder(&self.input, from)
+            .or_else(|| prompt::next_placeholder(&self.input, 0));
+        if let Some(range) = next {
+            self.select_placeholder(range);
+        }
+    }
+
+    fn select_placeholder(&mut self, range: Range<usize>) {
+        self.cursor_pos = range.start;
+        self.input_selection = Some(range);
+    }
+
+    /// Remove the selected placeholder (if any) before editing at the cursor
+    pub fn take_input_selection(&mut self) -> bool {
+        match self.input_selection.take() {
+            Some(range) => {
+                self.cursor_pos = range.start;
+                self.input.replace_range(range, "");
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Parameters a prompt submitted now would be generated with
+    pub fn submit_params(&self, prompt: &str) -> GenerateParams {
+        let model = self.config.model(None);
+        let defaults = self.config.defaults_for(&model);
+        GenerateParams::new(prompt)
+            .with_aspect_ratio(defaults.aspect_ratio)
+            .with_size(defaults.size)
+            .with_model(model)
+    }
+
+    /// One-line summary of `submit_params`, shown before sending
+    pub fn submit_summary(&self) -> String {
+        let params = self.submit_params(&self.input);
+        let summary = format!("{} · {} · {}", params.model, params.aspect_ratio, params.size);
+        match &self.edit_source {
+            Some(source) => {
+                let name = source.file_name().unwrap_or(source.as_os_str()).to_string_lossy();
+                format!("{} · edit {}", summary, name)
+            }
+            None => summary,
+        }
+    }
+
+    /// Start an edit by asking for the source image's path
+    pub fn open_edit_path(&mut self) {
+        self.mode = AppMode::EditPath;
+        self.input.clear();
+        self.cursor_pos = 0;
+        self.input_selection = None;
+        self.edit_source = None;
+        self.clear_messages();
+    }
+
+    /// Complete the typed path to the matching file or directory
+    pub fn complete_edit_path(&mut self) {
+        if let Some(completed) = platform::complete_path(&self.input) {
+            self.input = completed;
+            self.cursor_pos = self.input.len();
+        }
+    }
+
+    /// Accept the typed path as the edit source and ask for the instruction
+    pub fn accept_edit_path(&mut self) {
+        let path = platform::expand_tilde(self.input.trim());
+        if !path.is_file() {
+            self.set_error(format!("Not a file: {}", path.display()));
+            return;
+        }
+        match platform::canonicalize(&path) {
+            Ok(path) => {
+                self.set_status(format!("Editing {}", platform::display_path(&path)));
+                self.edit_source = Some(path);
+                self.input.clear();
+                self.cursor_pos = 0;
+                self.mode = AppMode::Input;
+            }
+            Err(e) => self.set_error(format!("Cannot open {}: {}", path.display(), e)),
+        }
+    }
+
     /// Get current settings value
     pub fn get_settings_value(&self, field: &SettingsField) -> String {
-        match field {
-            SettingsField::Model => self.config.api.model.clone(),
-            SettingsField::AspectRatio => self.config.defaults.aspect_ratio.clone(),
-            SettingsField::Size => self.config.defaults.size.clone(),
-            SettingsField::OutputDirectory => self.config.output.directory.clone(),
-            SettingsField::AutoDownload => self.config.output.auto_download.to_string(),
-            SettingsField::Display => self.config.output.display.as_str().to_string(),
-            SettingsField::ShowImages => self.config.tui.show_images.to_string(),
-            SettingsField::Theme => self.config.tui.theme.clone(),
-        }
+        self.config.get(field.config_key()).unwrap_or_default()
     }
 
     /// Set settings value
     pub fn set_settings_value(&mut self, field: &SettingsField, value: &str) -> Result<()> {
         self.config.set(field.config_key(), value)?;
-        self.config_changed = true;
+        self.config_changes
+            .insert(field.config_key().to_string(), value.to_string());
         Ok(())
     }
 
+    /// Fetch the model list for settings once, in the background.
+    ///
+    /// Settings offer the built-in list until the result arrives, and keep
+    /// it if the fetch fails or times out. Only Gemini lists its models.
+    pub fn load_models(&mut self) {
+        if self.models_requested || self.config.api.provider != ProviderKind::Gemini {
+            return;
+        }
+        self.models_requested = true;
+
+        let client = match GeminiClient::from_config(&self.config) {
+            Ok(client) => client,
+            Err(e) => {
+                tracing::warn!("Failed to fetch model list, using built-in list: {}", e);
+                return;
+            }
+        };
+        let events = self.events.clone();
+        tokio::spawn(async move {
+            match tokio::time::timeout(MODELS_TIMEOUT, client.list_models()).await {
+                Ok(Ok(models)) if !models.is_empty() => {
+                    let _ = events.send(AppEvent::Models(models));
+                }
+                Ok(Ok(_)) => {}
+                Ok(Err(e)) => tracing::warn!("Failed to fetch model list, using built-in list: {}", e),
+                Err(_) => tracing::warn!(
+                    "Model list took over {}s, using built-in list",
+                    MODELS_TIMEOUT.as_secs()
+                ),
+            }
+        });
+    }
+
     /// Get options for a settings field (if applicable)
-    pub fn get_settings_options(&self, field: &SettingsField) -> Option<Vec<&'static str>> {
-        match field {
-            SettingsField::Model => Some(Config::models().to_vec()),
-            SettingsField::AspectRatio => Some(Config::aspect_ratios().to_vec()),
-            SettingsField::Size => Some(Config::sizes().to_vec()),
-            SettingsField::AutoDownload => Some(vec!["true", "false"]),
-            SettingsField::Display => Some(crate::config::DisplayMode::variants().to_vec()),
-            SettingsField::ShowImages => Some(vec!["true", "false"]),
-            SettingsField::Theme => Some(vec!["dark", "light"]),
-            _ => None,
+    pub fn get_settings_options(&self, field: &SettingsField) -> Option<Vec<String>> {
+        match (field.config_key(), &self.models) {
+            ("api.model", Some(models)) => Some(models.clone()),
+            _ => field
+                .0
+                .choices()
+                .map(|options| options.iter().map(|o| o.to_string()).collect()),
         }
     }
 
@@ -228,9 +666,9 @@ impl App {
     pub fn cycle_settings_option(&mut self, field: &SettingsField) -> Result<()> {
         if let Some(options) = self.get_settings_options(field) {
             let current = self.get_settings_value(field);
-            let current_idx = options.iter().position(|&o| o == current).unwrap_or(0);
+            let current_idx = options.iter().position(|o| *o == current).unwrap_or(0);
             let next_idx = (current_idx + 1) % options.len();
-            self.set_settings_value(field, options[next_idx])?;
+            self.set_settings_value(field, &options[next_idx])?;
         }
         Ok(())
     }
@@ -1,10 +1,12 @@
 use anyhow::Result;
-use crossterm::event::{KeyCode, KeyEvent};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
 use std::path::PathBuf;
 
-use super::app::{App, AppMode, SettingsField};
+use super::app::{App, AppMode, SettingsField, IMAGE_GRID_COLUMNS};
 use crate::api::GeminiClient;
-use crate::core::{GenerateParams, Job};
+use crate::core::Job;
+use crate::imaging;
+use crate::platform;
 
 /// Handle input in main mode
 pub async fn handle_main_input(app: &mut App, key: KeyEvent) -> Result<()> {
@@ -26,20 +28,25 @@ pub async fn handle_main_input(app: &mut App, key: KeyEvent) -> Result<()> {
         }
 
         // View job details
-        KeyCode::Enter => {
-            if let Some(job) = app.selected_job().cloned() {
-                app.current_job = Some(job);
-                app.mode = AppMode::JobDetail;
-            }
-        }
+        KeyCode::Enter => app.open_job_detail(),
 
         // Open settings
         KeyCode::Char('s') => {
+            app.load_models();
             app.mode = AppMode::Settings;
             app.settings_selected = 0;
             app.settings_editing = false;
         }
 
+        // Recent warnings and errors
+        KeyCode::Char('e') => app.open_log(),
+
+        // Command line (`:log`)
+        KeyCode::Char(':') => app.open_command(),
+
+        // Edit an image from disk
+        KeyCode::Char('E') => app.open_edit_path(),
+
         // Refresh
         KeyCode::Char('r') => {
             app.load_jobs()?;
@@ -52,7 +59,17 @@ pub async fn handle_main_input(app: &mut App, key: KeyEvent) -> Result<()> {
                 let id = job.id.clone();
                 app.db.delete_job(&id)?;
                 app.load_jobs()?;
-                app.set_status(format!("Deleted job: {}", id));
+                app.set_status(format!("Moved job to trash: {} (u: undo)", id));
+                app.last_deleted = Some(id);
+            }
+        }
+
+        // Undo last delete
+        KeyCode::Char('u') => {
+            if let Some(id) = app.last_deleted.take() {
+                app.db.restore_job(&id)?;
+                app.load_jobs()?;
+                app.set_status(format!("Restored job: {}", id));
             }
         }
 
@@ -68,60 +85,95 @@ pub async fn handle_main_input(app: &mut App, key: KeyEvent) -> Result<()> {
 
 /// Handle input in text input mode
 pub async fn handle_input_mode(app: &mut App, key: KeyEvent) -> Result<()> {
+    // The first Enter shows the parameters; the next key sends, adjusts, or goes back to editing
+    if std::mem::take(&mut app.confirm_pending) {
+        match key.code {
+            KeyCode::Enter => {
+                let prompt = std::mem::take(&mut app.input);
+                app.cursor_pos = 0;
+                app.input_selection = None;
+                app.mode = AppMode::Main;
+
+                match app.edit_source.take() {
+                    Some(source) => edit_image(app, source, prompt).await?,
+                    None => generate_image(app, prompt).await?,
+                }
+                return Ok(());
+            }
+            KeyCode::Char('s') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                app.load_models();
+                app.mode = AppMode::Settings;
+                app.settings_selected = 0;
+                app.settings_editing = false;
+                return Ok(());
+            }
+            KeyCode::Esc => return Ok(()),
+            _ => {}
+        }
+    }
+
     match key.code {
         KeyCode::Esc => {
             app.mode = AppMode::Main;
             app.input.clear();
             app.cursor_pos = 0;
+            app.input_selection = None;
+            app.edit_source = None;
         }
 
         KeyCode::Enter => {
             if !app.input.is_empty() {
-                let prompt = app.input.clone();
-                app.input.clear();
-                app.cursor_pos = 0;
-                app.mode = AppMode::Main;
-
-                // Generate image
-                generate_image(app, prompt).await?;
+                app.confirm_pending = true;
             }
         }
 
+        // Open the template picker
+        KeyCode::Char('t') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+            app.open_templates();
+        }
+
+        // Jump to the next {placeholder}
+        KeyCode::Tab => app.select_next_placeholder(),
+
+        // The cursor is a byte offset, so it moves by whole characters
         KeyCode::Char(c) => {
+            app.take_input_selection();
             app.input.insert(app.cursor_pos, c);
-            app.cursor_pos += 1;
+            app.cursor_pos += c.len_utf8();
         }
 
-        KeyCode::Backspace => {
-            if app.cursor_pos > 0 {
-                app.cursor_pos -= 1;
+        KeyCode::Backspace if !app.take_input_selection() => {
+            if let Some(c) = app.input[..app.cursor_pos].chars().next_back() {
+                app.cursor_pos -= c.len_utf8();
                 app.input.remove(app.cursor_pos);
             }
         }
 
-        KeyCode::Delete => {
-            if app.cursor_pos < app.input.len() {
-                app.input.remove(app.cursor_pos);
-            }
+        KeyCode::Delete if !app.take_input_selection() && app.cursor_pos < app.input.len() => {
+            app.input.remove(app.cursor_pos);
         }
 
         KeyCode::Left => {
-            if app.cursor_pos > 0 {
-                app.cursor_pos -= 1;
+            app.input_selection = None;
+            if let Some(c) = app.input[..app.cursor_pos].chars().next_back() {
+                app.cursor_pos -= c.len_utf8();
             }
         }
 
         KeyCode::Right => {
-            if app.cursor_pos < app.input.len() {
-                app.cursor_pos += 1;
+            app.input_selection = None;
+            if let Some(c) = app.input[app.cursor_pos..].chars().next() {
+                app.cursor_pos += c.len_utf8();
             }
         }
 
         KeyCode::Home => {
+            app.input_selection = None;
             app.cursor_pos = 0;
         }
 
         KeyCode::End => {
+            app.input_selection = None;
             app.cursor_pos = app.input.len();
         }
 
@@ -130,15 +182,140 @@ pub async fn handle_input_mode(app: &mut App, key: KeyEvent) -> Result<()> {
     Ok(())
 }
 
+/// Handle input while choosing the image to edit
+pub fn handle_edit_path_input(app: &mut App, key: KeyEvent) -> Result<()> {
+    match key.code {
+        KeyCode::Esc => {
+            app.mode = AppMode::Main;
+            app.input.clear();
+            app.cursor_pos = 0;
+        }
+        KeyCode::Enter => app.accept_edit_path(),
+        KeyCode::Tab => app.complete_edit_path(),
+        KeyCode::Char(c) => {
+            app.input.insert(app.cursor_pos, c);
+            app.cursor_pos += c.len_utf8();
+        }
+        KeyCode::Backspace => {
+            if let Some(c) = app.input[..app.cursor_pos].chars().next_back() {
+                app.cursor_pos -= c.len_utf8();
+                app.input.remove(app.cursor_pos);
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Handle input on the `:` command line
+pub fn handle_command_input(app: &mut App, key: KeyEvent) -> Result<()> {
+    match key.code {
+        KeyCode::Esc => {
+            app.mode = AppMode::Main;
+            app.input.clear();
+            app.cursor_pos = 0;
+        }
+        KeyCode::Enter => app.run_command(),
+        KeyCode::Char(c) => {
+            app.input.insert(app.cursor_pos, c);
+            app.cursor_pos += c.len_utf8();
+        }
+        KeyCode::Backspace => {
+            if let Some(c) = app.input[..app.cursor_pos].chars().next_back() {
+                app.cursor_pos -= c.len_utf8();
+                app.input.remove(app.cursor_pos);
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Insert pasted text into whichever text field is active.
+///
+/// Line breaks become spaces, since prompts and settings are single-line.
+/// Pastes outside a text field are dropped.
+pub fn handle_paste(app: &mut App, text: &str) {
+    let text = text.replace("\r\n", " ").replace(['\r', '\n'], " ");
+    match app.mode {
+        AppMode::Input | AppMode::EditPath | AppMode::Command => {
+            app.confirm_pending = false;
+            app.take_input_selection();
+            app.input.insert_str(app.cursor_pos, &text);
+            app.cursor_pos += text.len();
+        }
+        AppMode::Settings if app.settings_editing => app.settings_edit_buffer.push_str(&text),
+        AppMode::Templates => {
+            app.template_query.push_str(&text);
+            app.template_selected = 0;
+        }
+        _ => {}
+    }
+}
+
+/// Handle input in the template picker
+pub fn handle_templates_input(app: &mut App, key: KeyEvent) -> Result<()> {
+    match key.code {
+        KeyCode::Esc => app.mode = AppMode::Input,
+        KeyCode::Enter => app.insert_selected_template(),
+        KeyCode::Up => {
+            app.template_selected = app.template_selected.saturating_sub(1);
+        }
+        KeyCode::Down if app.template_selected + 1 < app.filtered_templates().len() => {
+            app.template_selected += 1;
+        }
+        KeyCode::Backspace => {
+            app.template_query.pop();
+            app.template_selected = 0;
+        }
+        KeyCode::Char(c) => {
+            app.template_query.push(c);
+            app.template_selected = 0;
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
 /// Handle input in job detail mode
 pub fn handle_job_detail_input(app: &mut App, key: KeyEvent) -> Result<()> {
+    let has_grid = app.current_job.as_ref().is_some_and(|job| job.images.len() > 1);
+    let columns = IMAGE_GRID_COLUMNS as isize;
+
     match key.code {
         KeyCode::Esc | KeyCode::Char('q') | KeyCode::Backspace => {
             app.mode = AppMode::Main;
             app.current_job = None;
         }
 
-        // Could add download, re-run, etc.
+        // Image grid
+        KeyCode::Left | KeyCode::Char('h') if has_grid => app.move_image_selection(-1),
+        KeyCode::Right | KeyCode::Char('l') if has_grid => app.move_image_selection(1),
+        KeyCode::Up | KeyCode::Char('k') if has_grid => app.move_image_selection(-columns),
+        KeyCode::Down | KeyCode::Char('j') if has_grid => app.move_image_selection(columns),
+        KeyCode::Enter | KeyCode::Char('o') if has_grid => app.open_selected_image(),
+        KeyCode::Char('p') if has_grid => app.promote_selected_image()?,
+        KeyCode::Char('x') if has_grid => return app.delete_selected_image(),
+
+        _ => {}
+    }
+    app.image_delete_pending = false;
+    Ok(())
+}
+
+/// Handle input in the log view
+pub fn handle_log_input(app: &mut App, key: KeyEvent) -> Result<()> {
+    match key.code {
+        KeyCode::Esc | KeyCode::Char('q') | KeyCode::Backspace => {
+            app.mode = AppMode::Main;
+        }
+        KeyCode::Up | KeyCode::Char('k') => {
+            app.log_selected = app.log_selected.saturating_sub(1);
+        }
+        KeyCode::Down | KeyCode::Char('j') if app.log_selected + 1 < app.log_entries.len() => {
+            app.log_selected += 1;
+        }
+        KeyCode::Char('r') => app.open_log(),
         _ => {}
     }
     Ok(())
@@ -208,7 +385,8 @@ pub fn handle_settings_input(app: &mut App, key: KeyEvent) -> Result<()> {
             }
 
             KeyCode::Esc | KeyCode::Char('q') => {
-                app.mode = AppMode::Main;
+                // Back to the prompt if settings were opened to adjust it before sending
+                app.mode = if app.input.is_empty() { AppMode::Main } else { AppMode::Input };
                 app.clear_messages();
             }
 
@@ -221,17 +399,33 @@ pub fn handle_settings_input(app: &mut App, key: KeyEvent) -> Result<()> {
 /// Generate an image from a prompt
 async fn generate_image(app: &mut App, prompt: String) -> Result<()> {
     app.set_status(format!("Generating: {}...", &prompt));
-    app.generating = true;
 
     // Build parameters from config
-    let params = GenerateParams::new(&prompt)
-        .with_aspect_ratio(&app.config.defaults.aspect_ratio)
-        .with_size(&app.config.defaults.size)
-        .with_model(&app.config.api.model);
+    let params = app.submit_params(&prompt);
+    run_job(app, Job::new_generate(params)).await
+}
+
+/// Edit an image on disk with an instruction
+async fn edit_image(app: &mut App, source: PathBuf, prompt: String) -> Result<()> {
+    app.set_status(format!("Editing: {}...", &prompt));
 
-    // Create job
-    let mut job = Job::new_generate(params);
-    app.db.insert_job(&job)?;
+    let params = app.submit_params(&prompt);
+    let (base64_data, mime_type) =
+        match imaging::load_for_edit(&source, &params.aspect_ratio, None, None).await {
+            Ok(loaded) => loaded,
+            Err(e) => {
+                app.set_error(format!("Failed to load {}: {}", source.display(), e));
+                return Ok(());
+            }
+        };
+    let params = params.with_reference_image(base64_data, mime_type);
+    run_job(app, Job::new_edit(params, platform::display_path(&source))).await
+}
+
+/// Record a new job, run it, and download its images
+async fn run_job(app: &mut App, mut job: Job) -> Result<()> {
+    app.generating = true;
+    app.db.insert_new_job(&mut job)?;
 
     // Create client
     let client = match GeminiClient::from_config(&app.config) {
@@ -251,7 +445,7 @@ async fn generate_image(app: &mut App, prompt: String) -> Result<()> {
     app.db.update_job(&job)?;
 
     // Generate
-    match client.generate(&job.params).await {
+    match client.generate_job(&mut job, &app.db).await {
         Ok(response) => {
             if let Err(e) = client.process_response(&mut job, response) {
                 job.set_failed(e.to_string());
@@ -264,7 +458,7 @@ async fn generate_image(app: &mut App, prompt: String) -> Result<()> {
 
             // Download if enabled
             if app.config.output.auto_download {
-                let output_dir = PathBuf::from(&app.config.output.directory);
+                let output_dir = app.config.output.dir();
                 match client.download_images(&mut job, &output_dir).await {
                     Ok(paths) => {
                         app.set_status(format!(
@@ -289,7 +483,41 @@ async fn generate_image(app: &mut App, prompt: String) -> Result<()> {
 
     app.db.update_job(&job)?;
     app.load_jobs()?;
+    app.refresh_quota();
     app.generating = false;
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::db::Database;
+
+    fn press(code: KeyCode) -> KeyEvent {
+        KeyEvent::new(code, KeyModifiers::NONE)
+    }
+
+    #[tokio::test]
+    async fn input_cursor_moves_by_whole_characters() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = Database::open_at(&dir.path().join("jobs.db")).unwrap();
+        let mut app = App::new(Config::default(), db);
+        app.mode = AppMode::Input;
+
+        handle_paste(&mut app, "“x”");
+        assert_eq!((app.input.as_str(), app.cursor_pos), ("“x”", "“x”".len()));
+
+        handle_input_mode(&mut app, press(KeyCode::Backspace)).await.unwrap();
+        assert_eq!((app.input.as_str(), app.cursor_pos), ("“x", "“x".len()));
+
+        handle_input_mode(&mut app, press(KeyCode::Left)).await.unwrap();
+        assert_eq!(app.cursor_pos, "“".len());
+
+        handle_input_mode(&mut app, press(KeyCode::Char('é'))).await.unwrap();
+        handle_input_mode(&mut app, press(KeyCode::Right)).await.unwrap();
+        handle_input_mode(&mut app, press(KeyCode::Delete)).await.unwrap();
+        assert_eq!((app.input.as_str(), app.cursor_pos), ("“éx", "“éx".len()));
+    }
+}
@@ -4,27 +4,58 @@ mod ui;
 
 use anyhow::Result;
 use crossterm::{
-    event::{poll, read, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyModifiers},
+    event::{
+        poll, read, DisableBracketedPaste, DisableMouseCapture, EnableBracketedPaste,
+        EnableMouseCapture, Event, KeyCode, KeyModifiers,
+    },
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
-use ratatui::{backend::CrosstermBackend, Terminal};
+use ratatui::{backend::CrosstermBackend, Terminal, TerminalOptions, Viewport};
 use std::io;
 use std::time::Duration;
 
 use crate::config::Config;
 use crate::db::Database;
+use crate::logging;
 
 pub use app::{App, AppMode};
+pub use ui::MIN_HEIGHT;
 
 /// Run the TUI application
 pub async fn run(config: &mut Config, db: &Database) -> Result<()> {
-    // Setup terminal
+    // Keep log lines off the alternate screen
+    let _log_guard = logging::capture();
+
+    // Setup terminal; inline mode stays in the normal buffer and leaves the
+    // mouse to the terminal, so scrollback and copy-mode keep working
+    let inline_height = config.tui.inline_height;
     enable_raw_mode()?;
     let mut stdout = io::stdout();
-    execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
+    if inline_height > 0 {
+        execute!(stdout, EnableBracketedPaste)?;
+    } else {
+        execute!(
+            stdout,
+            EnterAlternateScreen,
+            EnableMouseCapture,
+            EnableBracketedPaste
+        )?;
+    }
     let backend = CrosstermBackend::new(stdout);
-    let mut terminal = Terminal::new(backend)?;
+    let viewport = match inline_height {
+        0 => Viewport::Fullscreen,
+        rows => Viewport::Inline(rows),
+    };
+    // An inline viewport asks the terminal for the cursor position, which can fail
+    let mut terminal = match Terminal::with_options(backend, TerminalOptions { viewport }) {
+        Ok(terminal) => terminal,
+        Err(e) => {
+            disable_raw_mode()?;
+            execute!(io::stdout(), DisableBracketedPaste)?;
+            return Err(e.into());
+        }
+    };
 
     // Create app state
     let mut app = App::new(config.clone(), db.clone());
@@ -34,17 +65,27 @@ pub async fn run(config: &mut Config, db: &Database) -> Result<()> {
 
     // Restore terminal
     disable_raw_mode()?;
-    execute!(
-        terminal.backend_mut(),
-        LeaveAlternateScreen,
-        DisableMouseCapture
-    )?;
+    if inline_height > 0 {
+        terminal.clear()?;
+        execute!(terminal.backend_mut(), DisableBracketedPaste)?;
+    } else {
+        execute!(
+            terminal.backend_mut(),
+            LeaveAlternateScreen,
+            DisableMouseCapture,
+            DisableBracketedPaste
+        )?;
+    }
     terminal.show_cursor()?;
 
-    // Save config if changed
-    if app.config_changed {
-        *config = app.config.clone();
-        config.save()?;
+    // Save only the settings changed here, so edits made by other
+    // processes while the TUI was open are kept
+    if !app.config_changes.is_empty() {
+        config.update(|config| {
+            app.config_changes
+                .iter()
+                .try_for_each(|(key, value)| config.set(key, value))
+        })?;
     }
 
     result
@@ -55,17 +96,39 @@ async fn run_app<B: ratatui::backend::Backend>(
     app: &mut App,
 ) -> Result<()> {
     loop {
+        app.reload_config_if_changed();
+        app.handle_app_events();
+        app.enforce_retention_if_due();
+
         // Draw UI
         terminal.draw(|f| ui::draw(f, app))?;
 
         // Handle events
         if poll(Duration::from_millis(100))? {
-            if let Event::Key(key) = read()? {
+            let event = read()?;
+            match &event {
+                // Pasted text arrives as one event, so it never triggers keybindings
+                Event::Paste(text) => event_handler::handle_paste(app, text),
+                // Re-lay out from a cleared screen instead of over the old size's frame
+                Event::Resize(..) => terminal.autoresize()?,
+                _ => {}
+            }
+            if let Event::Key(key) = event {
                 // Global quit shortcuts
                 if key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL) {
                     return Ok(());
                 }
-                if key.code == KeyCode::Char('q') && app.mode != AppMode::Input && app.mode != AppMode::Settings {
+                if key.code == KeyCode::Char('q')
+                    && !matches!(
+                        app.mode,
+                        AppMode::Input
+                            | AppMode::Settings
+                            | AppMode::Templates
+                            | AppMode::EditPath
+                            | AppMode::Log
+                            | AppMode::Command
+                    )
+                {
                     return Ok(());
                 }
 
@@ -75,6 +138,10 @@ async fn run_app<B: ratatui::backend::Backend>(
                     AppMode::Input => event_handler::handle_input_mode(app, key).await?,
                     AppMode::JobDetail => event_handler::handle_job_detail_input(app, key)?,
                     AppMode::Settings => event_handler::handle_settings_input(app, key)?,
+                    AppMode::Templates => event_handler::handle_templates_input(app, key)?,
+                    AppMode::EditPath => event_handler::handle_edit_path_input(app, key)?,
+                    AppMode::Log => event_handler::handle_log_input(app, key)?,
+                    AppMode::Command => event_handler::handle_command_input(app, key)?,
                 }
             }
         }
@@ -1,22 +1,62 @@
 use ratatui::{
-    layout::{Constraint, Direction, Layout, Rect},
+    layout::{Alignment, Constraint, Direction, Layout, Rect},
     style::{Color, Modifier, Style},
     text::{Line, Span},
-    widgets::{Block, Borders, Clear, List, ListItem, Paragraph, Wrap},
+    widgets::{Block, Borders, Clear, List, ListItem, ListState, Paragraph, Wrap},
     Frame,
 };
 
-use super::app::{App, AppMode, SettingsField};
+use std::path::Path;
+
+use super::app::{App, AppMode, SettingsField, IMAGE_GRID_COLUMNS};
+use crate::core::{text, Job};
+
+/// Rows taken by one cell of the image grid (border, name, dimensions)
+const IMAGE_CELL_HEIGHT: usize = 4;
+
+/// Smallest terminal the views are laid out for
+const MIN_WIDTH: u16 = 60;
+pub const MIN_HEIGHT: u16 = 20;
 
 /// Main draw function
 pub fn draw(frame: &mut Frame, app: &App) {
+    let area = frame.area();
+    if area.width < MIN_WIDTH || area.height < MIN_HEIGHT {
+        draw_too_small(frame, area);
+        return;
+    }
+
     match app.mode {
-        AppMode::Main | AppMode::Input => draw_main(frame, app),
+        AppMode::Main | AppMode::Input | AppMode::EditPath | AppMode::Command => {
+            draw_main(frame, app)
+        }
         AppMode::JobDetail => draw_job_detail(frame, app),
         AppMode::Settings => draw_settings(frame, app),
+        AppMode::Templates => {
+            draw_main(frame, app);
+            draw_templates(frame, app);
+        }
+        AppMode::Log => draw_log(frame, app),
     }
 }
 
+/// Placeholder for terminals too small to lay the views out in
+fn draw_too_small(frame: &mut Frame, area: Rect) {
+    let lines = vec![
+        Line::from(Span::styled(
+            "Terminal too small",
+            Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD),
+        )),
+        Line::from(format!("{}x{}, need {}x{}", area.width, area.height, MIN_WIDTH, MIN_HEIGHT)),
+    ];
+    let top = area.height.saturating_sub(lines.len() as u16) / 2;
+    let area = Rect { y: area.y + top, height: area.height - top, ..area };
+    let text = Paragraph::new(lines)
+        .alignment(Alignment::Center)
+        .wrap(Wrap { trim: true });
+    frame.render_widget(text, area);
+}
+
 /// Draw main view with job list
 fn draw_main(frame: &mut Frame, app: &App) {
     let chunks = Layout::default()
@@ -25,12 +65,16 @@ fn draw_main(frame: &mut Frame, app: &App) {
             Constraint::Length(3),  // Title/input
             Constraint::Min(10),    // Job list
             Constraint::Length(3),  // Status bar
+            Constraint::Length(1),  // Today's stats
             Constraint::Length(2),  // Help line
         ])
         .split(frame.area());
 
     // Title or input
-    if app.mode == AppMode::Input {
+    if matches!(
+        app.mode,
+        AppMode::Input | AppMode::Templates | AppMode::EditPath | AppMode::Command
+    ) {
         draw_input(frame, app, chunks[0]);
     } else {
         draw_title(frame, chunks[0]);
@@ -42,8 +86,11 @@ fn draw_main(frame: &mut Frame, app: &App) {
     // Status bar
     draw_status(frame, app, chunks[2]);
 
+    // Today's stats
+    draw_day_stats(frame, app, chunks[3]);
+
     // Help line
-    draw_help(frame, app, chunks[3]);
+    draw_help(frame, app, chunks[4]);
 }
 
 fn draw_title(frame: &mut Frame, area: Rect) {
@@ -66,58 +113,92 @@ fn draw_title(frame: &mut Frame, area: Rect) {
 }
 
 fn draw_input(frame: &mut Frame, app: &App, area: Rect) {
-    let input = Paragraph::new(app.input.as_str())
+    let title = if app.confirm_pending {
+        Line::from(Span::styled(
+            format!(" Send with {}? ", app.submit_summary()),
+            Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD),
+        ))
+    } else if app.mode == AppMode::Command {
+        Line::from("Command (log; Enter to run, Esc to cancel)")
+    } else if app.mode == AppMode::EditPath {
+        Line::from("Image to edit (Tab to complete, Enter to continue, Esc to cancel)")
+    } else if app.edit_source.is_some() {
+        Line::from("Edit instruction (Enter to edit, Esc to cancel)")
+    } else {
+        Line::from("Enter prompt (Enter to generate, Esc to cancel)")
+    };
+
+    // Highlight the placeholder selected with Tab
+    let line = match &app.input_selection {
+        Some(range) => Line::from(vec![
+            Span::raw(&app.input[..range.start]),
+            Span::styled(
+                &app.input[range.clone()],
+                Style::default().fg(Color::Black).bg(Color::Yellow),
+            ),
+            Span::raw(&app.input[range.end..]),
+        ]),
+        None => Line::from(app.input.as_str()),
+    };
+
+    let input = Paragraph::new(line)
         .style(Style::default().fg(Color::White))
         .block(
             Block::default()
                 .borders(Borders::ALL)
                 .border_style(Style::default().fg(Color::Cyan))
-                .title("Enter prompt (Enter to generate, Esc to cancel)"),
+                .title(title),
         );
     frame.render_widget(input, area);
 
     // Show cursor
     frame.set_cursor_position((
-        area.x + app.cursor_pos as u16 + 1,
+        area.x + text::width(&app.input[..app.cursor_pos]) as u16 + 1,
         area.y + 1,
     ));
 }
 
 fn draw_job_list(frame: &mut Frame, app: &App, area: Rect) {
-    let items: Vec<ListItem> = app
-        .jobs
-        .iter()
-        .enumerate()
-        .map(|(i, job)| {
-            let status_style = match job.status_name() {
-                "completed" => Style::default().fg(Color::Green),
-                "failed" => Style::default().fg(Color::Red),
-                "running" => Style::default().fg(Color::Yellow),
-                "queued" => Style::default().fg(Color::Blue),
-                _ => Style::default().fg(Color::Gray),
-            };
+    let dates = app.config.output.date_format();
+    let mut items: Vec<ListItem> = Vec::new();
+    let mut current_day = None;
+    for (i, job) in app.jobs.iter().enumerate() {
+        if app.config.tui.group_by_day && current_day != Some(job.created_day()) {
+            current_day = Some(job.created_day());
+            items.push(ListItem::new(Line::from(Span::styled(
+                format!("── {}", dates.day(job.created_day())),
+                Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD),
+            ))));
+        }
 
-            let content = Line::from(vec![
-                Span::styled(
-                    format!("{:<12}", job.id),
-                    if i == app.selected_job {
-                        Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD)
-                    } else {
-                        Style::default().fg(Color::White)
-                    },
-                ),
-                Span::raw(" "),
-                Span::styled(format!("{:<10}", job.status_name()), status_style),
-                Span::raw(" "),
-                Span::styled(
-                    job.prompt_preview(50),
-                    Style::default().fg(Color::White),
-                ),
-            ]);
+        let status_style = match job.status_label() {
+            "completed" => Style::default().fg(Color::Green),
+            "failed" | "stalled" => Style::default().fg(Color::Red),
+            "running" => Style::default().fg(Color::Yellow),
+            "queued" => Style::default().fg(Color::Blue),
+            _ => Style::default().fg(Color::Gray),
+        };
 
-            ListItem::new(content)
-        })
-        .collect();
+        let content = Line::from(vec![
+            Span::styled(
+                format!("{:<12}", job.id),
+                if i == app.selected_job {
+                    Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD)
+                } else {
+                    Style::default().fg(Color::White)
+                },
+            ),
+            Span::raw(" "),
+            Span::styled(format!("{:<10}", job.status_label()), status_style),
+            Span::raw(" "),
+            Span::styled(
+                job.prompt_preview(50),
+                Style::default().fg(Color::White),
+            ),
+        ]);
+
+        items.push(ListItem::new(content));
+    }
 
     let list = List::new(items)
         .block(
@@ -145,16 +226,54 @@ fn draw_status(frame: &mut Frame, app: &App, area: Rect) {
         ("Ready", Style::default().fg(Color::Gray))
     };
 
+    let mut block = Block::default().borders(Borders::ALL).title("Status");
+    if let Some(remaining) = app.quota.as_ref().and_then(|q| q.seconds_remaining()) {
+        block = block.title(
+            Line::from(Span::styled(
+                format!(" Quota resets in {}s ", remaining),
+                Style::default().fg(Color::Red),
+            ))
+            .right_aligned(),
+        );
+    }
+
     let status = Paragraph::new(message)
         .style(style)
-        .block(Block::default().borders(Borders::ALL).title("Status"));
+        .block(block);
     frame.render_widget(status, area);
 }
 
+/// One-line dashboard: today's outcomes and spend, and the current queue
+fn draw_day_stats(frame: &mut Frame, app: &App, area: Rect) {
+    let stats = &app.day_stats;
+    let dim = Style::default().fg(Color::DarkGray);
+    let line = Line::from(vec![
+        Span::styled(" Today: ", dim),
+        Span::styled(format!("{} completed", stats.completed), Style::default().fg(Color::Green)),
+        Span::styled(" · ", dim),
+        Span::styled(
+            format!("{} failed", stats.failed),
+            if stats.failed > 0 { Style::default().fg(Color::Red) } else { dim },
+        ),
+        Span::styled(" · ", dim),
+        Span::styled(format!("${:.4}", stats.spend_usd), Style::default().fg(Color::Yellow)),
+        Span::styled("   Queue: ", dim),
+        Span::styled(
+            stats.queue_depth.to_string(),
+            if stats.queue_depth > 0 { Style::default().fg(Color::Cyan) } else { dim },
+        ),
+    ]);
+    frame.render_widget(Paragraph::new(line), area);
+}
+
 fn draw_help(frame: &mut Frame, app: &App, area: Rect) {
     let help_text = match app.mode {
-        AppMode::Input => "Enter: Generate | Esc: Cancel",
-        AppMode::Main => "i: New prompt | Enter: View | s: Settings | d: Delete | r: Refresh | q: Quit",
+        AppMode::Input if app.confirm_pending => "Enter: Send | Ctrl+S: Adjust settings | Esc: Keep editing",
+        AppMode::Input => "Enter: Generate | Ctrl+T: Templates | Tab: Next placeholder | Esc: Cancel",
+        AppMode::Templates => "Type to search | ↑↓: Navigate | Enter: Insert | Esc: Back",
+        AppMode::EditPath => "Tab: Complete path | Enter: Continue | Esc: Cancel",
+        AppMode::Command => "Enter: Run | Esc: Cancel",
+        AppMode::Main => "i: New prompt | E: Edit image | Enter: View | s: Settings | e: Log | d: Delete | u: Undo | r: Refresh | q: Quit",
         _ => "",
     };
 
@@ -163,6 +282,89 @@ fn draw_help(frame: &mut Frame, app: &App, area: Rect) {
     frame.render_widget(help, area);
 }
 
+/// Draw the template picker as a popup over the main view
+fn draw_templates(frame: &mut Frame, app: &App) {
+    let area = centered_rect(70, 60, frame.area());
+    frame.render_widget(Clear, area);
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(3),  // Search
+            Constraint::Min(3),     // Template list
+        ])
+        .split(area);
+
+    let search = Paragraph::new(app.template_query.as_str())
+        .style(Style::default().fg(Color::White))
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(Color::Cyan))
+                .title("Templates"),
+        );
+    frame.render_widget(search, chunks[0]);
+
+    frame.set_cursor_position((
+        chunks[0].x + text::width(&app.template_query) as u16 + 1,
+        chunks[0].y + 1,
+    ));
+
+    let templates = app.filtered_templates();
+    let items: Vec<ListItem> = templates
+        .iter()
+        .enumerate()
+        .map(|(i, template)| {
+            let content = Line::from(vec![
+                Span::styled(
+                    format!("{:<16}", template.name),
+                    if i == app.template_selected {
+                        Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD)
+                    } else {
+                        Style::default().fg(Color::White)
+                    },
+                ),
+                Span::styled(template.body.clone(), Style::default().fg(Color::Gray)),
+            ]);
+
+            ListItem::new(content)
+        })
+        .collect();
+
+    let list = List::new(items)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(format!("{} match(es)", templates.len())),
+        )
+        .highlight_style(Style::default().bg(Color::DarkGray));
+
+    let mut state = ListState::default();
+    state.select(Some(app.template_selected));
+    frame.render_stateful_widget(list, chunks[1], &mut state);
+}
+
+/// Rect centered in `area`, sized as a percentage of it
+fn centered_rect(percent_x: u16, percent_y: u16, area: Rect) -> Rect {
+    let vertical = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Percentage((100 - percent_y) / 2),
+            Constraint::Percentage(percent_y),
+            Constraint::Percentage((100 - percent_y) / 2),
+        ])
+        .split(area);
+
+    Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([
+            Constraint::Percentage((100 - percent_x) / 2),
+            Constraint::Percentage(percent_x),
+            Constraint::Percentage((100 - percent_x) / 2),
+        ])
+        .split(vertical[1])[1]
+}
+
 /// Draw job detail view
 fn draw_job_detail(frame: &mut Frame, app: &App) {
     let area = frame.area();
@@ -212,7 +414,7 @@ fn draw_job_detail(frame: &mut Frame, app: &App) {
         Line::from(vec![
             Span::styled("Created: ", Style::default().fg(Color::Gray)),
             Span::styled(
-                job.created_at.format("%Y-%m-%d %H:%M:%S").to_string(),
+                app.config.output.date_format().datetime_secs(&job.created_at),
                 Style::default().fg(Color::White),
             ),
         ]),
@@ -241,7 +443,9 @@ fn draw_job_detail(frame: &mut Frame, app: &App) {
         ]),
     ];
 
-    if !job.images.is_empty() {
+    // Several images get their own grid below the details
+    let grid_rows = job.images.len().div_ceil(IMAGE_GRID_COLUMNS);
+    if job.images.len() == 1 {
         lines.push(Line::from(""));
         lines.push(Line::from(vec![
             Span::styled(
@@ -254,8 +458,27 @@ fn draw_job_detail(frame: &mut Frame, app: &App) {
                 .path
                 .as_deref()
                 .unwrap_or("(not downloaded)");
-            lines.push(Line::from(vec![
+            let mut spans = vec![
                 Span::styled(format!("  [{}] {}", img.index, path_text), Style::default().fg(Color::White)),
+            ];
+            if let Some(dimensions) = img.dimensions() {
+                spans.push(Span::styled(format!(" ({})", dimensions), Style::default().fg(Color::Gray)));
+            }
+            lines.push(Line::from(spans));
+        }
+    }
+
+    if !job.attachments.is_empty() {
+        lines.push(Line::from(""));
+        lines.push(Line::from(vec![
+            Span::styled(
+                format!("Attachments ({}):", job.attachments.len()),
+                Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD),
+            ),
+        ]));
+        for attachment in &job.attachments {
+            lines.push(Line::from(vec![
+                Span::styled(format!("  {}", attachment.path), Style::default().fg(Color::White)),
             ]));
         }
     }
@@ -263,10 +486,151 @@ fn draw_job_detail(frame: &mut Frame, app: &App) {
     let details = Paragraph::new(lines)
         .block(Block::default().borders(Borders::ALL).title("Details"))
         .wrap(Wrap { trim: true });
-    frame.render_widget(details, chunks[1]);
+    let help_text = if job.images.len() > 1 {
+        let body = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Min(8),                                              // Details
+                Constraint::Length((grid_rows * IMAGE_CELL_HEIGHT) as u16 + 2),  // Image grid
+            ])
+            .split(chunks[1]);
+        frame.render_widget(details, body[0]);
+        draw_image_grid(frame, app, job, body[1]);
+        "←→↑↓: Select image | o: Open | p: Make primary | x: Delete image | Esc: Back"
+    } else {
+        frame.render_widget(details, chunks[1]);
+        "Esc/q: Back"
+    };
 
     // Help
-    let help = Paragraph::new("Esc/q: Back")
+    let help = Paragraph::new(help_text)
+        .style(Style::default().fg(Color::DarkGray));
+    frame.render_widget(help, chunks[2]);
+}
+
+/// Draw a job's images as a grid of selectable cells
+fn draw_image_grid(frame: &mut Frame, app: &App, job: &Job, area: Rect) {
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(format!("Images ({})", job.images.len()));
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints(vec![Constraint::Length(IMAGE_CELL_HEIGHT as u16); job.images.len().div_ceil(IMAGE_GRID_COLUMNS)])
+        .split(inner);
+    for (row, images) in rows.iter().zip(job.images.chunks(IMAGE_GRID_COLUMNS)) {
+        let cells = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints(vec![Constraint::Ratio(1, IMAGE_GRID_COLUMNS as u32); IMAGE_GRID_COLUMNS])
+            .split(*row);
+
+        for (cell, img) in cells.iter().zip(images) {
+            let selected = job.images.get(app.image_selected).is_some_and(|s| s.index == img.index);
+            let border_style = if selected {
+                Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)
+            } else {
+                Style::default().fg(Color::DarkGray)
+            };
+            let title = if img.primary {
+                format!("[{}] ★ primary", img.index)
+            } else {
+                format!("[{}]", img.index)
+            };
+
+            let name = img
+                .path
+                .as_deref()
+                .map(|path| {
+                    Path::new(path)
+                        .file_name()
+                        .map_or(path.to_string(), |name| name.to_string_lossy().into_owned())
+                })
+                .unwrap_or_else(|| "(not downloaded)".to_string());
+            let lines = vec![
+                Line::from(Span::styled(name, Style::default().fg(Color::White))),
+                Line::from(Span::styled(
+                    img.dimensions().unwrap_or_else(|| img.mime_type.clone()),
+                    Style::default().fg(Color::Gray),
+                )),
+            ];
+
+            let widget = Paragraph::new(lines).block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .border_style(border_style)
+                    .title(title),
+            );
+            frame.render_widget(widget, *cell);
+        }
+    }
+}
+
+/// Draw recent warnings and errors
+fn draw_log(frame: &mut Frame, app: &App) {
+    let area = frame.area();
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Min(10),    // Entries
+            Constraint::Length(5),  // Selected message
+            Constraint::Length(2),  // Help
+        ])
+        .split(area);
+
+    let items: Vec<ListItem> = app
+        .log_entries
+        .iter()
+        .map(|entry| {
+            let level_style = if entry.level == tracing::Level::ERROR {
+                Style::default().fg(Color::Red)
+            } else {
+                Style::default().fg(Color::Yellow)
+            };
+
+            let content = Line::from(vec![
+                Span::styled(
+                    entry.time.format("%H:%M:%S").to_string(),
+                    Style::default().fg(Color::DarkGray),
+                ),
+                Span::raw(" "),
+                Span::styled(format!("{:<5}", entry.level), level_style),
+                Span::raw(" "),
+                Span::styled(entry.message.clone(), Style::default().fg(Color::White)),
+            ]);
+
+            ListItem::new(content)
+        })
+        .collect();
+
+    let list = List::new(items)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(format!("Recent warnings and errors ({})", app.log_entries.len())),
+        )
+        .highlight_style(Style::default().bg(Color::DarkGray));
+
+    let mut state = ListState::default();
+    if !app.log_entries.is_empty() {
+        state.select(Some(app.log_selected));
+    }
+    frame.render_stateful_widget(list, chunks[0], &mut state);
+
+    // Full text of the selected entry, which may be cut off in the list
+    let message = app
+        .log_entries
+        .get(app.log_selected)
+        .map(|entry| entry.message.as_str())
+        .unwrap_or("Nothing logged this session");
+    let detail = Paragraph::new(message)
+        .wrap(Wrap { trim: false })
+        .block(Block::default().borders(Borders::ALL));
+    frame.render_widget(detail, chunks[1]);
+
+    let help = Paragraph::new("↑↓: Navigate | r: Refresh | Esc/q: Back")
         .style(Style::default().fg(Color::DarkGray));
     frame.render_widget(help, chunks[2]);
 }
@@ -309,7 +673,7 @@ fn draw_settings(frame: &mut Frame, app: &App) {
 
             let content = Line::from(vec![
                 Span::styled(
-                    format!("{:<20}", field.label()),
+                    format!("{:<28}", field.label()),
                     if is_selected {
                         Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD)
                     } else {
@@ -0,0 +1,90 @@
+// Shared harness for the end-to-end tests: a sandboxed HOME and a
+// `banana mock-server` to point it at.
+
+use serde_json::Value;
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Output, Stdio};
+
+const BIN: &str = env!("CARGO_BIN_EXE_banana");
+
+/// An isolated home directory, removed when dropped
+pub struct Sandbox {
+    pub home: PathBuf,
+}
+
+impl Sandbox {
+    pub fn new(name: &str) -> Self {
+        let home = std::env::temp_dir().join(format!("banana-test-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&home);
+        std::fs::create_dir_all(&home).unwrap();
+        Self { home }
+    }
+
+    pub fn command(&self) -> Command {
+        let mut cmd = Command::new(BIN);
+        cmd.current_dir(&self.home)
+            .env("HOME", &self.home)
+            .env_remove("XDG_CONFIG_HOME")
+            .env_remove("XDG_DATA_HOME")
+            .env_remove("GEMINI_API_KEY")
+            .env("NO_COLOR", "1");
+        cmd
+    }
+
+    /// Run banana with `args`, panicking with its stderr if it fails
+    pub fn run(&self, args: &[&str]) -> Output {
+        let output = self.command().args(args).output().unwrap();
+        assert!(
+            output.status.success(),
+            "banana {:?} failed:\n{}",
+            args,
+            String::from_utf8_lossy(&output.stderr)
+        );
+        output
+    }
+
+    pub fn run_json(&self, args: &[&str]) -> Value {
+        serde_json::from_slice(&self.run(args).stdout).unwrap()
+    }
+
+    /// Start a mock server and point the config at it
+    pub fn start_mock(&self) -> MockServer {
+        let mut child = self
+            .command()
+            .args(["mock-server", "--port", "0"])
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .unwrap();
+
+        let mut url = String::new();
+        BufReader::new(child.stdout.take().unwrap()).read_line(&mut url).unwrap();
+        let server = MockServer { child };
+
+        self.run(&["config", "set", "api.base_url", url.trim()]);
+        self.run(&["config", "set", "api.key", "test-key"]);
+        server
+    }
+}
+
+impl Drop for Sandbox {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.home);
+    }
+}
+
+pub struct MockServer {
+    child: Child,
+}
+
+impl Drop for MockServer {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+pub fn artifact_path(job: &Value) -> &Path {
+    Path::new(job["artifacts"][0]["path"].as_str().expect("job has an artifact"))
+}
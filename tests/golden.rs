@@ -0,0 +1,99 @@
+// End-to-end tests of the generate/edit/jobs flows against `banana mock-server`.
+//
+// Each test runs the real binary with its own HOME, so config, database, and
+// output never touch the user's files.
+
+mod common;
+
+use common::{artifact_path, Sandbox};
+
+#[test]
+fn generate_edit_and_list_jobs() {
+    let sandbox = Sandbox::new("flows");
+    let _mock = sandbox.start_mock();
+
+    let generated = sandbox.run_json(&["generate", "a red apple", "--ar", "16:9", "--format", "json"]);
+    assert_eq!(generated["status"]["status"], "Completed");
+    assert_eq!(generated["params"]["prompt"], "a red apple");
+    assert_eq!(generated["artifacts"].as_array().unwrap().len(), 1);
+    assert_eq!(generated["artifacts"][0]["width"], 64);
+    assert_eq!(generated["artifacts"][0]["height"], 36);
+    assert_eq!(generated["artifacts"][0]["mime_type"], "image/png");
+    assert!(generated["usage"]["total_tokens"].as_i64().unwrap() > 0);
+    assert!(artifact_path(&generated).is_file());
+
+    let source = artifact_path(&generated).display().to_string();
+    let edited = sandbox.run_json(&["edit", &source, "make it green", "--format", "json"]);
+    assert_eq!(edited["status"]["status"], "Completed");
+    assert_eq!(edited["action"]["type"], "Edit");
+    assert!(artifact_path(&edited).is_file());
+
+    let listing = sandbox.run_json(&["jobs", "--format", "json"]);
+    let ids: Vec<&str> = listing["jobs"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|job| job["id"].as_str().unwrap())
+        .collect();
+    assert_eq!(ids.len(), 2);
+    assert!(ids.contains(&generated["id"].as_str().unwrap()));
+    assert!(ids.contains(&edited["id"].as_str().unwrap()));
+    assert_eq!(listing["summary"]["total"], 2);
+    assert_eq!(listing["summary"]["completed"], 2);
+
+    let shown = sandbox.run_json(&["jobs", "show", generated["id"].as_str().unwrap(), "--format", "json"]);
+    assert_eq!(shown["params"], generated["params"]);
+}
+
+#[test]
+fn edit_with_context_continues_the_source_job() {
+    let sandbox = Sandbox::new("context");
+    let _mock = sandbox.start_mock();
+
+    // Default output dir, so the image is addressed relative to the working directory
+    let generated = sandbox.run_json(&["generate", "a red apple", "--format", "json"]);
+    let name = artifact_path(&generated).file_name().unwrap().to_str().unwrap().to_string();
+    let relative = format!("banana-output/{}", name);
+
+    let edited = sandbox.run_json(&["edit", &relative, "make it green", "--with-context", "--format", "json"]);
+    assert_eq!(edited["status"]["status"], "Completed");
+    assert_eq!(edited["parent_id"], generated["id"]);
+    assert_eq!(edited["params"]["context"].as_array().map(Vec::len), Some(2));
+
+    // Without embedded metadata, inspect falls back to the recorded path
+    let path = sandbox.home.join(&relative);
+    image::open(&path).unwrap().save(&path).unwrap();
+    let inspected = sandbox.run_json(&["inspect", &relative, "--format", "json"]);
+    assert!(inspected["metadata"].is_null());
+    assert_eq!(inspected["job"]["id"], generated["id"]);
+}
+
+#[test]
+fn replay_serves_recorded_responses_offline() {
+    let sandbox = Sandbox::new("replay");
+    let fixtures = sandbox.home.join("fixtures");
+    let fixtures = fixtures.to_str().unwrap();
+
+    let recorded = {
+        let _mock = sandbox.start_mock();
+        sandbox.run_json(&["--record", fixtures, "generate", "a blue pear", "--format", "json"])
+    };
+    assert_eq!(recorded["status"]["status"], "Completed");
+
+    // The mock is gone and the key removed; only the fixture can answer
+    sandbox.run(&["config", "set", "api.key", ""]);
+    let replayed = sandbox.run_json(&["--replay", fixtures, "generate", "a blue pear", "--format", "json"]);
+    assert_eq!(replayed["status"]["status"], "Completed");
+    assert_eq!(replayed["usage"], recorded["usage"]);
+    assert_eq!(replayed["artifacts"][0]["width"], recorded["artifacts"][0]["width"]);
+    assert_eq!(replayed["artifacts"][0]["height"], recorded["artifacts"][0]["height"]);
+    assert!(artifact_path(&replayed).is_file());
+
+    let missing = sandbox
+        .command()
+        .args(["--replay", fixtures, "generate", "a different prompt", "--format", "quiet"])
+        .output()
+        .unwrap();
+    assert!(!missing.status.success());
+    assert!(String::from_utf8_lossy(&missing.stderr).contains("No fixture"));
+}
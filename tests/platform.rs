@@ -0,0 +1,90 @@
+// End-to-end tests of how paths are reported and images displayed on each
+// platform: absolute native paths, no `\\?\` prefixes, and the viewer mode.
+
+mod common;
+
+use common::{artifact_path, Sandbox};
+use std::path::Path;
+
+/// Assert that `path` is absolute, free of the verbatim prefix, and uses the
+/// platform's separators
+fn assert_native(path: &str) {
+    assert!(Path::new(path).is_absolute(), "{} is not absolute", path);
+    assert!(!path.starts_with(r"\\?\"), "{} has a verbatim prefix", path);
+    if cfg!(windows) {
+        assert!(!path.contains('/'), "{} has forward slashes", path);
+    }
+}
+
+#[test]
+fn relative_paths_are_stored_absolute_and_native() {
+    let sandbox = Sandbox::new("paths");
+    let _mock = sandbox.start_mock();
+
+    let generated = sandbox.run_json(&["generate", "a red apple", "-o", "out", "--format", "json"]);
+    let output = artifact_path(&generated);
+    assert_native(output.to_str().unwrap());
+    assert!(output.is_file());
+    assert_eq!(output.parent().unwrap().file_name().unwrap(), "out");
+
+    let relative = Path::new("out").join(output.file_name().unwrap());
+    let edited = sandbox.run_json(&["edit", relative.to_str().unwrap(), "make it green", "--format", "json"]);
+    let source = edited["action"]["source_image"].as_str().unwrap();
+    assert_native(source);
+    assert_eq!(
+        std::fs::canonicalize(source).unwrap(),
+        std::fs::canonicalize(output).unwrap()
+    );
+}
+
+#[test]
+fn config_path_points_at_the_config_file() {
+    let sandbox = Sandbox::new("config-path");
+    sandbox.run(&["config", "set", "api.key", "test-key"]);
+
+    let output = sandbox.run(&["config", "path"]);
+    let path = String::from_utf8(output.stdout).unwrap();
+    let path = path.trim();
+    assert_native(path);
+    assert!(Path::new(path).is_file());
+    assert_eq!(Path::new(path).file_name().unwrap(), "config.toml");
+}
+
+#[cfg(unix)]
+#[test]
+fn viewer_display_opens_the_image() {
+    use std::os::unix::fs::PermissionsExt;
+    use std::time::{Duration, Instant};
+
+    let sandbox = Sandbox::new("viewer");
+    let _mock = sandbox.start_mock();
+    sandbox.run(&["config", "set", "output.display", "viewer"]);
+
+    // Stand in for the platform viewer, recording what it was asked to open
+    let bin = sandbox.home.join("bin");
+    let opened = sandbox.home.join("opened");
+    std::fs::create_dir_all(&bin).unwrap();
+    let viewer = bin.join(if cfg!(target_os = "macos") { "open" } else { "xdg-open" });
+    let script = format!("#!/bin/sh\nprintf '%s' \"$1\" > '{0}.tmp' && mv '{0}.tmp' '{0}'\n", opened.display());
+    std::fs::write(&viewer, script).unwrap();
+    std::fs::set_permissions(&viewer, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+    let path = format!("{}:{}", bin.display(), std::env::var("PATH").unwrap_or_default());
+    let output = sandbox
+        .command()
+        .env("PATH", path)
+        .args(["generate", "a red apple", "--format", "text"])
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+
+    // The viewer is spawned without waiting for it
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while !opened.exists() && Instant::now() < deadline {
+        std::thread::sleep(Duration::from_millis(50));
+    }
+    let image = std::fs::read_to_string(&opened).expect("viewer was not opened");
+    assert_native(&image);
+    assert!(Path::new(&image).is_file());
+    assert!(String::from_utf8_lossy(&output.stdout).contains(&image));
+}